@@ -0,0 +1,239 @@
+//! Builds batched vote transactions.
+//!
+//! Voters lock their tokens with the vote-intent lock and sign a vote intent for each token cell. The
+//! aggregator collects the intents, spends all token cells and updates the Vote Cells once. See the batched
+//! vote section of the README for the transaction structure.
+
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_hash::blake2b_256;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_voting_layout::{INTENT_SIGNATURE_BYTESIZE, INTENT_SIGNATURE_OFFSET};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    UnknownVoteOption,
+    DuplicateIntent,
+    NoIntents,
+    AmountOverflow,
+    InsufficientCapacity,
+    Signing,
+}
+
+/// A voter's signed intent to vote with all tokens of one cell.
+#[derive(Clone)]
+pub struct VoteIntent {
+    pub vote_option_type: u8,
+    pub amount: u128,
+    pub out_point: OutPoint,
+    /// Lock hash receipts for the tokens are issued to when the vote holds them in escrow.
+    pub owner_lock_hash: [u8; 32],
+    pub signature: [u8; INTENT_SIGNATURE_BYTESIZE],
+}
+
+impl VoteIntent {
+    pub fn new(
+        vote_option_type: u8,
+        amount: u128,
+        out_point: OutPoint,
+        owner_lock_hash: [u8; 32],
+    ) -> Self {
+        VoteIntent {
+            vote_option_type,
+            amount,
+            out_point,
+            owner_lock_hash,
+            signature: [0u8; INTENT_SIGNATURE_BYTESIZE],
+        }
+    }
+
+    fn message_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(INTENT_SIGNATURE_OFFSET);
+        bytes.push(self.vote_option_type);
+        bytes.extend_from_slice(&self.amount.to_le_bytes());
+        bytes.extend_from_slice(self.out_point.as_slice());
+        bytes.extend_from_slice(&self.owner_lock_hash);
+        bytes
+    }
+
+    /// The hash the voter signs.
+    pub fn message(&self) -> [u8; 32] {
+        blake2b_256(self.message_bytes())
+    }
+
+    pub fn sign(mut self, privkey: &Privkey) -> Result<Self, Error> {
+        let signature = privkey
+            .sign_recoverable(&self.message().into())
+            .map_err(|_| Error::Signing)?;
+        self.signature.copy_from_slice(&signature.serialize());
+        Ok(self)
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut bytes = self.message_bytes();
+        bytes.extend_from_slice(&self.signature);
+        Bytes::from(bytes)
+    }
+}
+
+/// A live Vote Cell of the vote.
+#[derive(Clone)]
+pub struct VoteCellInfo {
+    pub out_point: OutPoint,
+    pub output: CellOutput,
+    pub vote_option_type: u8,
+    pub shard_index: u8,
+    pub total_votes_collected: u128,
+}
+
+impl VoteCellInfo {
+    pub fn data(&self) -> Bytes {
+        let mut data = vec![self.vote_option_type];
+        data.extend_from_slice(&self.total_votes_collected.to_le_bytes());
+        data.push(self.shard_index);
+        Bytes::from(data)
+    }
+}
+
+pub struct Aggregator {
+    vote_cells: Vec<VoteCellInfo>,
+    intents: Vec<(VoteIntent, CellOutput)>,
+    cell_deps: Vec<CellDep>,
+    header_deps: Vec<Byte32>,
+    change: Option<(Script, u64)>,
+}
+
+impl Aggregator {
+    /// The votes of every option are added to the first of the given Vote Cells with that option.
+    pub fn new(vote_cells: Vec<VoteCellInfo>) -> Self {
+        Aggregator {
+            vote_cells,
+            intents: Vec::new(),
+            cell_deps: Vec::new(),
+            header_deps: Vec::new(),
+            change: None,
+        }
+    }
+
+    pub fn add_cell_dep(&mut self, cell_dep: CellDep) {
+        self.cell_deps.push(cell_dep);
+    }
+
+    /// Once the vote has a voting end, the headers of the blocks the Vote Cells were created in are needed.
+    pub fn add_header_dep(&mut self, block_hash: Byte32) {
+        self.header_deps.push(block_hash);
+    }
+
+    /// Return the capacity of the spent token cells, minus the fee, to the given lock.
+    pub fn set_change(&mut self, lock: Script, fee: u64) {
+        self.change = Some((lock, fee));
+    }
+
+    /// Add a signed intent along with the token cell it spends.
+    pub fn add_intent(
+        &mut self,
+        intent: VoteIntent,
+        token_output: CellOutput,
+    ) -> Result<(), Error> {
+        if !self
+            .vote_cells
+            .iter()
+            .any(|vote_cell| vote_cell.vote_option_type == intent.vote_option_type)
+        {
+            return Err(Error::UnknownVoteOption);
+        }
+
+        if self
+            .intents
+            .iter()
+            .any(|(other, _)| other.out_point == intent.out_point)
+        {
+            return Err(Error::DuplicateIntent);
+        }
+
+        self.intents.push((intent, token_output));
+        Ok(())
+    }
+
+    pub fn build(&self) -> Result<TransactionView, Error> {
+        if self.intents.is_empty() {
+            return Err(Error::NoIntents);
+        }
+
+        let mut vote_cells = self.vote_cells.clone();
+        for (intent, _) in &self.intents {
+            let vote_cell = vote_cells
+                .iter_mut()
+                .find(|vote_cell| vote_cell.vote_option_type == intent.vote_option_type)
+                .ok_or(Error::UnknownVoteOption)?;
+            vote_cell.total_votes_collected = vote_cell
+                .total_votes_collected
+                .checked_add(intent.amount)
+                .ok_or(Error::AmountOverflow)?;
+        }
+
+        // Vote Cells come first, so the intents are passed in the witness of the first Vote Cell.
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        let mut outputs_data = Vec::new();
+        for vote_cell in &vote_cells {
+            inputs.push(
+                CellInput::new_builder()
+                    .previous_output(vote_cell.out_point.clone())
+                    .build(),
+            );
+            outputs.push(vote_cell.output.clone());
+            outputs_data.push(vote_cell.data());
+        }
+
+        let mut intents = Vec::new();
+        let mut token_capacity: u64 = 0;
+        for (intent, token_output) in &self.intents {
+            inputs.push(
+                CellInput::new_builder()
+                    .previous_output(intent.out_point.clone())
+                    .build(),
+            );
+            intents.extend_from_slice(&intent.to_bytes());
+
+            let capacity: Capacity = token_output.capacity().unpack();
+            token_capacity = token_capacity
+                .checked_add(capacity.as_u64())
+                .ok_or(Error::AmountOverflow)?;
+        }
+
+        if let Some((lock, fee)) = &self.change {
+            let capacity = token_capacity
+                .checked_sub(*fee)
+                .ok_or(Error::InsufficientCapacity)?;
+            outputs.push(
+                CellOutput::new_builder()
+                    .capacity(capacity.pack())
+                    .lock(lock.clone())
+                    .build(),
+            );
+            outputs_data.push(Bytes::new());
+        }
+
+        let witness_args = WitnessArgs::new_builder()
+            .input_type(
+                BytesOpt::new_builder()
+                    .set(Some(Bytes::from(intents).pack()))
+                    .build(),
+            )
+            .build();
+
+        Ok(TransactionBuilder::default()
+            .inputs(inputs)
+            .outputs(outputs)
+            .outputs_data(outputs_data.pack())
+            .cell_deps(self.cell_deps.clone())
+            .header_deps(self.header_deps.clone())
+            .witness(witness_args.as_bytes().pack())
+            .build())
+    }
+}
@@ -0,0 +1,136 @@
+//! Access to a CKB node over RPC.
+
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{BlockView, HeaderView, TransactionView},
+    packed::{Byte32, OutPoint},
+    prelude::*,
+    H256,
+};
+use ckb_tool::rpc_client::RpcClient;
+use ckb_voting_sdk::audit::BlockSource;
+use ckb_voting_sdk::report::HeaderSource;
+use ckb_voting_sdk::LiveCell;
+
+use crate::Error;
+
+/// Parse an out point written as `<tx hash>:<index>`.
+pub fn parse_out_point(value: &str) -> Result<OutPoint, Error> {
+    let invalid = || Error::InvalidArgument(format!("invalid out point {}", value));
+
+    let mut parts = value.splitn(2, ':');
+    let tx_hash = parts.next().ok_or_else(invalid)?;
+    let index = parts.next().ok_or_else(invalid)?;
+    let tx_hash = parse_hash(tx_hash).map_err(|_| invalid())?;
+    let index: u32 = index.parse().map_err(|_| invalid())?;
+
+    Ok(OutPoint::new(tx_hash.pack(), index))
+}
+
+pub fn parse_hash(value: &str) -> Result<H256, Error> {
+    let value = value.trim_start_matches("0x");
+    value
+        .parse()
+        .map_err(|_| Error::InvalidArgument(format!("invalid hash {}", value)))
+}
+
+pub fn format_out_point(out_point: &OutPoint) -> String {
+    let tx_hash: H256 = out_point.tx_hash().unpack();
+    let index: u32 = out_point.index().unpack();
+
+    format!("{:#x}:{}", tx_hash, index)
+}
+
+pub struct Chain {
+    rpc: RpcClient,
+}
+
+impl Chain {
+    pub fn new(url: &str) -> Self {
+        Chain {
+            rpc: RpcClient::new(url),
+        }
+    }
+
+    pub fn live_cell(&self, out_point: &OutPoint) -> Result<LiveCell, Error> {
+        let cell = self
+            .rpc
+            .inner()
+            .get_live_cell(out_point.clone().into(), true)
+            .map_err(|err| Error::Rpc(format!("{:?}", err)))?
+            .cell
+            .ok_or_else(|| Error::CellNotLive(format_out_point(out_point)))?;
+        let data: Bytes = cell
+            .data
+            .map(|data| data.content.into_bytes())
+            .unwrap_or_default();
+
+        Ok(LiveCell::new(out_point.clone(), cell.output.into(), data))
+    }
+
+    /// Header of the block the cell was created in.
+    pub fn header(&self, out_point: &OutPoint) -> Result<HeaderView, Error> {
+        let tx_hash: H256 = out_point.tx_hash().unpack();
+        let block_hash = self
+            .rpc
+            .inner()
+            .get_transaction(tx_hash)
+            .map_err(|err| Error::Rpc(format!("{:?}", err)))?
+            .and_then(|tx| tx.tx_status.block_hash)
+            .ok_or_else(|| Error::CellNotLive(format_out_point(out_point)))?;
+
+        self.rpc
+            .inner()
+            .get_header(block_hash)
+            .map_err(|err| Error::Rpc(format!("{:?}", err)))?
+            .map(Into::into)
+            .ok_or_else(|| Error::Rpc(String::from("block header not found")))
+    }
+
+    pub fn tip_header(&self) -> Result<HeaderView, Error> {
+        self.rpc
+            .inner()
+            .get_tip_header()
+            .map(Into::into)
+            .map_err(|err| Error::Rpc(format!("{:?}", err)))
+    }
+
+    pub fn send_transaction(&self, tx: &TransactionView) -> Result<Byte32, Error> {
+        let tx: json_types::Transaction = tx.data().into();
+
+        self.rpc
+            .inner()
+            .send_transaction(tx)
+            .map(|tx_hash| tx_hash.pack())
+            .map_err(|err| Error::Rpc(format!("{:?}", err)))
+    }
+}
+
+impl BlockSource for Chain {
+    fn tip_block_number(&self) -> Result<u64, ckb_voting_sdk::Error> {
+        self.rpc
+            .inner()
+            .get_tip_block_number()
+            .map(|number| number.value())
+            .map_err(|err| ckb_voting_sdk::Error::BlockSource(format!("{:?}", err)))
+    }
+
+    fn block(&self, number: u64) -> Result<Option<BlockView>, ckb_voting_sdk::Error> {
+        self.rpc
+            .inner()
+            .get_block_by_number(number.into())
+            .map(|block| block.map(Into::into))
+            .map_err(|err| ckb_voting_sdk::Error::BlockSource(format!("{:?}", err)))
+    }
+}
+
+impl HeaderSource for Chain {
+    fn header(&self, block_hash: &Byte32) -> Result<Option<HeaderView>, ckb_voting_sdk::Error> {
+        self.rpc
+            .inner()
+            .get_header(block_hash.unpack())
+            .map(|header| header.map(Into::into))
+            .map_err(|err| ckb_voting_sdk::Error::BlockSource(format!("{:?}", err)))
+    }
+}
@@ -0,0 +1,894 @@
+//! Commands of the command-line tool. Every command builds a transaction of the vote lifecycle, which is written
+//! as JSON, or signed with a local key and sent to a CKB node. `status`, `audit`, `explain`, `report` and
+//! `verify-report` only read the chain.
+
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{DepType, TransactionView},
+    packed::{self, CellDep, OutPoint, Script},
+    prelude::*,
+};
+use ckb_voting_sdk::audit::{audit_vote, AuditReport, ChainSnapshot};
+use ckb_voting_sdk::capacity::{balance_tx, DEFAULT_FEE_RATE};
+use ckb_voting_sdk::explain::explain_tx;
+use ckb_voting_sdk::report::VoteReport;
+use ckb_voting_sdk::{
+    sign_as_organizer, CastVote, CoreCellData, CreateVote, FinishVote, LiveCell, MintTokens,
+    VoteCellData, VOTE_OPTION_NO, VOTE_OPTION_YES, VOTING_STATUS_CANCELLED, VOTING_STATUS_EXECUTED,
+    VOTING_STATUS_FINISHED, VOTING_WEIGHT_MODE_SNAPSHOT,
+};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde::Deserialize;
+
+use crate::chain::{format_out_point, parse_hash, parse_out_point, Chain};
+use crate::deployment::Deployment;
+use crate::wallet::{load_key, lock_script, sign_tx};
+use crate::Error;
+
+const DAO_CORE: &str = "dao-core";
+const SUDT: &str = "sudt";
+const VOTE_ESCROW: &str = "vote-escrow";
+
+/// A voter or recipient of tokens, as listed in the voters file.
+#[derive(Deserialize)]
+struct Recipient {
+    lock: json_types::Script,
+    amount: json_types::Uint128,
+}
+
+struct Context {
+    chain: Chain,
+    deployment: Deployment,
+    cell_deps: Vec<CellDep>,
+}
+
+fn tx_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("capacity-cell")
+            .long("capacity-cell")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Cell paying for the new cells and the fee, as <tx hash>:<index>"),
+        Arg::with_name("fee-rate")
+            .long("fee-rate")
+            .takes_value(true)
+            .help("Fee rate in shannons per 1,000 bytes. Defaults to 1,000"),
+        Arg::with_name("cell-dep")
+            .long("cell-dep")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Additional code cell dep, as <tx hash>:<index>"),
+        Arg::with_name("lock-dep")
+            .long("lock-dep")
+            .takes_value(true)
+            .help("Dep group of the lock of the key, as <tx hash>:<index>"),
+        Arg::with_name("key").long("key").takes_value(true).help(
+            "File with the private key to sign with. The signed transaction is sent to the node",
+        ),
+        Arg::with_name("output")
+            .long("output")
+            .takes_value(true)
+            .help("File the unsigned transaction is written to. Defaults to stdout"),
+    ]
+}
+
+fn out_point_arg<'a, 'b>(name: &'a str, help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .long(name)
+        .takes_value(true)
+        .value_name("OUT_POINT")
+        .help(help)
+}
+
+/// Arguments of all commands.
+pub fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("ckb-voting")
+        .about("Create, vote on and finish votes on CKB")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("rpc")
+                .long("rpc")
+                .takes_value(true)
+                .global(true)
+                .default_value("http://127.0.0.1:8114")
+                .help("URL of the CKB node"),
+        )
+        .arg(
+            Arg::with_name("deployment")
+                .long("deployment")
+                .takes_value(true)
+                .global(true)
+                .default_value("deployment.toml")
+                .help("Deployment config of the scripts"),
+        )
+        .arg(
+            Arg::with_name("migration")
+                .long("migration")
+                .takes_value(true)
+                .global(true)
+                .help("Migration file written by capsule deploy"),
+        )
+        .subcommand(
+            SubCommand::with_name("create")
+                .about("Create a vote and distribute its tokens to the voters")
+                .arg(out_point_arg("seed", "Cell whose lock owns the token of the vote").required(true))
+                .arg(
+                    Arg::with_name("voters")
+                        .long("voters")
+                        .takes_value(true)
+                        .required(true)
+                        .help("JSON file listing the lock and amount of tokens of every voter"),
+                )
+                .arg(Arg::with_name("title").long("title").takes_value(true))
+                .arg(
+                    Arg::with_name("voting-end")
+                        .long("voting-end")
+                        .takes_value(true)
+                        .help("Block number after which the vote can be finished"),
+                )
+                .arg(
+                    Arg::with_name("vote-shards")
+                        .long("vote-shards")
+                        .takes_value(true)
+                        .help("Number of Vote Cells per option"),
+                )
+                .args(&tx_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("vote")
+                .about("Cast votes with tokens, which are burned, escrowed or returned depending on the vote")
+                .arg(out_point_arg("core", "Core Cell of the vote").required(true))
+                .arg(out_point_arg("vote-cell", "Vote Cell of the option").required(true))
+                .arg(
+                    out_point_arg("token-cell", "Token cell to vote with")
+                        .required(true)
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("votes")
+                        .long("votes")
+                        .takes_value(true)
+                        .help("Number of votes. Defaults to all tokens of the token cells"),
+                )
+                .args(&tx_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("finish")
+                .about("Finish a vote by collecting all of its Vote Cells")
+                .arg(out_point_arg("core", "Core Cell of the vote").required(true))
+                .arg(
+                    out_point_arg("vote-cell", "Vote Cell of the vote")
+                        .required(true)
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("casting-vote")
+                        .long("casting-vote")
+                        .takes_value(true)
+                        .possible_values(&["yes", "no"])
+                        .help("Result when no option has a majority. Needs the organizer's authorization"),
+                )
+                .arg(out_point_arg(
+                    "organizer-cell",
+                    "Cell with the organizer lock, spent and recreated to authorize the organizer",
+                ))
+                .arg(
+                    Arg::with_name("organizer-multisig")
+                        .long("organizer-multisig")
+                        .takes_value(true)
+                        .help("Multisig config of the organizer, as hex, for votes in multisig mode"),
+                )
+                .arg(
+                    Arg::with_name("organizer-key")
+                        .long("organizer-key")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .requires("organizer-multisig")
+                        .help("File with the key of a member of the organizer multisig to sign with"),
+                )
+                .args(&tx_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("status")
+                .about("Print the settings and tallies of a vote")
+                .arg(out_point_arg("core", "Core Cell of the vote").required(true))
+                .arg(
+                    out_point_arg("vote-cell", "Vote Cell of the vote")
+                        .multiple(true)
+                        .number_of_values(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("audit")
+                .about("Check the token supply of a vote for tokens minted after its creation")
+                .arg(out_point_arg("core", "Core Cell of the vote").required(true))
+                .arg(
+                    Arg::with_name("snapshot")
+                        .long("snapshot")
+                        .takes_value(true)
+                        .help("JSON file with the blocks of a local chain, used instead of the node"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("Describe the cells of a transaction and predict the errors of the voting scripts")
+                .arg(
+                    Arg::with_name("tx")
+                        .long("tx")
+                        .takes_value(true)
+                        .required(true)
+                        .help("JSON file with the transaction, as written by --output"),
+                )
+                .arg(
+                    Arg::with_name("snapshot")
+                        .long("snapshot")
+                        .takes_value(true)
+                        .help("JSON file with the blocks of a local chain, used instead of the node"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Write the report of a finished vote, with proofs of its transactions")
+                .arg(
+                    Arg::with_name("settlement")
+                        .long("settlement")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Hash of the transaction finishing the vote"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "markdown"])
+                        .default_value("json"),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .takes_value(true)
+                        .help("File with the secp256k1 key signing the report"),
+                )
+                .arg(
+                    Arg::with_name("snapshot")
+                        .long("snapshot")
+                        .takes_value(true)
+                        .help("JSON file with the blocks of a local chain, used instead of the node"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify-report")
+                .about("Check a report against the headers of the chain")
+                .arg(
+                    Arg::with_name("report")
+                        .long("report")
+                        .takes_value(true)
+                        .required(true)
+                        .help("JSON file with the report, as written by report"),
+                )
+                .arg(
+                    Arg::with_name("snapshot")
+                        .long("snapshot")
+                        .takes_value(true)
+                        .help("JSON file with the blocks of a local chain, used instead of the node"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("mint")
+                .about("Mint tokens owned by the lock of the owner cell")
+                .arg(out_point_arg("owner-cell", "Cell whose lock owns the token").required(true))
+                .arg(
+                    Arg::with_name("recipients")
+                        .long("recipients")
+                        .takes_value(true)
+                        .required(true)
+                        .help("JSON file listing the lock and amount of tokens of every recipient"),
+                )
+                .args(&tx_args()),
+        )
+}
+
+fn parse_number<T: std::str::FromStr>(
+    matches: &ArgMatches,
+    name: &str,
+) -> Result<Option<T>, Error> {
+    match matches.value_of(name) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::InvalidArgument(format!("invalid {} {}", name, value))),
+        None => Ok(None),
+    }
+}
+
+fn parse_hex(matches: &ArgMatches, name: &str) -> Result<Option<Bytes>, Error> {
+    let value = match matches.value_of(name) {
+        Some(value) => value.trim_start_matches("0x"),
+        None => return Ok(None),
+    };
+    let invalid = || Error::InvalidArgument(format!("invalid {} {}", name, value));
+    if !value.is_ascii() || value.len() % 2 != 0 {
+        return Err(invalid());
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&value[index..index + 2], 16).map_err(|_| invalid()))
+        .collect::<Result<Vec<u8>, _>>()
+        .map(|data| Some(Bytes::from(data)))
+}
+
+fn parse_out_points(matches: &ArgMatches, name: &str) -> Result<Vec<OutPoint>, Error> {
+    matches
+        .values_of(name)
+        .map(|values| values.map(parse_out_point).collect())
+        .unwrap_or_else(|| Ok(vec![]))
+}
+
+fn load_recipients(path: &str) -> Result<Vec<(Script, u128)>, Error> {
+    let content = fs::read_to_string(path)?;
+    let recipients: Vec<Recipient> = serde_json::from_str(&content)
+        .map_err(|err| Error::InvalidArgument(format!("invalid recipients: {}", err)))?;
+
+    Ok(recipients
+        .into_iter()
+        .map(|recipient| (recipient.lock.into(), recipient.amount.value()))
+        .collect())
+}
+
+fn load_snapshot(matches: &ArgMatches) -> Result<Option<ChainSnapshot>, Error> {
+    match matches.value_of("snapshot") {
+        Some(snapshot) => Ok(Some(ChainSnapshot::from_json(&fs::read_to_string(
+            snapshot,
+        )?)?)),
+        None => Ok(None),
+    }
+}
+
+/// Load a cell from the snapshot, or from the node. Spent cells are only found in a snapshot, the node only
+/// returns live cells.
+fn load_cell(
+    chain: &Chain,
+    snapshot: &Option<ChainSnapshot>,
+    out_point: &OutPoint,
+) -> Result<LiveCell, Error> {
+    match snapshot {
+        Some(snapshot) => snapshot
+            .cell(out_point)
+            .ok_or_else(|| Error::CellNotLive(format_out_point(out_point))),
+        None => chain.live_cell(out_point),
+    }
+}
+
+fn load_deployment(
+    matches: &ArgMatches,
+    load_cell: impl Fn(&OutPoint) -> Result<LiveCell, Error>,
+) -> Result<Deployment, Error> {
+    let mut deployment = Deployment::load(
+        Path::new(matches.value_of("deployment").unwrap_or_default()),
+        matches.value_of("migration").map(Path::new),
+    )?;
+    deployment.resolve_on_chain_cells(load_cell)?;
+
+    Ok(deployment)
+}
+
+fn load_context(matches: &ArgMatches) -> Result<Context, Error> {
+    let chain = Chain::new(matches.value_of("rpc").unwrap_or_default());
+    let deployment = load_deployment(matches, |out_point| chain.live_cell(out_point))?;
+
+    let mut cell_deps: Vec<CellDep> = parse_out_points(matches, "cell-dep")?
+        .into_iter()
+        .map(|out_point| CellDep::new_builder().out_point(out_point).build())
+        .collect();
+    if let Some(lock_dep) = matches.value_of("lock-dep") {
+        cell_deps.push(
+            CellDep::new_builder()
+                .out_point(parse_out_point(lock_dep)?)
+                .dep_type(DepType::DepGroup.into())
+                .build(),
+        );
+    }
+
+    Ok(Context {
+        chain,
+        deployment,
+        cell_deps,
+    })
+}
+
+fn signer_lock(matches: &ArgMatches, context: &Context) -> Result<Option<Script>, Error> {
+    match matches.value_of("key") {
+        Some(key) => {
+            let key = load_key(Path::new(key))?;
+            Ok(Some(lock_script(context.deployment.lock.as_ref(), &key)?))
+        }
+        None => Ok(None),
+    }
+}
+
+fn load_capacity_cells(matches: &ArgMatches, context: &Context) -> Result<Vec<LiveCell>, Error> {
+    parse_out_points(matches, "capacity-cell")?
+        .iter()
+        .map(|out_point| context.chain.live_cell(out_point))
+        .collect()
+}
+
+/// The lock of the signer, or of the first capacity cell for unsigned transactions.
+fn change_lock(
+    matches: &ArgMatches,
+    context: &Context,
+    capacity_cells: &[LiveCell],
+) -> Result<Option<Script>, Error> {
+    Ok(signer_lock(matches, context)?
+        .or_else(|| capacity_cells.first().map(|cell| cell.output.lock())))
+}
+
+/// All cells the transaction spends. Capacity cells are only spent until the outputs and the fee are covered.
+fn spent_cells(
+    tx: &TransactionView,
+    input_cells: &[LiveCell],
+    capacity_cells: &[LiveCell],
+) -> Vec<LiveCell> {
+    input_cells
+        .iter()
+        .chain(
+            capacity_cells
+                .iter()
+                .take(tx.inputs().len() - input_cells.len()),
+        )
+        .cloned()
+        .collect()
+}
+
+/// Balance the transaction with the capacity cells. Returns the balanced transaction and all cells it spends.
+fn balance(
+    matches: &ArgMatches,
+    context: &Context,
+    tx: TransactionView,
+    input_cells: &[LiveCell],
+) -> Result<(TransactionView, Vec<LiveCell>), Error> {
+    let capacity_cells = load_capacity_cells(matches, context)?;
+    let fee_rate = parse_number(matches, "fee-rate")?.unwrap_or(DEFAULT_FEE_RATE);
+    let change_lock = change_lock(matches, context, &capacity_cells)?
+        .or_else(|| input_cells.first().map(|cell| cell.output.lock()))
+        .ok_or(Error::InsufficientCapacity)?;
+
+    // The cell deps are added first, they count towards the size paid for.
+    let tx = tx
+        .as_advanced_builder()
+        .cell_deps(context.cell_deps.clone())
+        .build();
+    let tx = balance_tx(tx, input_cells, &capacity_cells, &change_lock, fee_rate)?;
+
+    let spent_cells = spent_cells(&tx, input_cells, &capacity_cells);
+    Ok((tx, spent_cells))
+}
+
+/// Either sign the balanced transaction and send it, or write it unsigned.
+fn send_tx(
+    matches: &ArgMatches,
+    context: &Context,
+    tx: TransactionView,
+    spent_cells: &[LiveCell],
+) -> Result<(), Error> {
+    match (matches.value_of("key"), signer_lock(matches, context)?) {
+        (Some(key), Some(signer_lock)) => {
+            let key = load_key(Path::new(key))?;
+            let input_locks: Vec<Script> =
+                spent_cells.iter().map(|cell| cell.output.lock()).collect();
+            let tx = sign_tx(tx, &input_locks, &signer_lock, &key)?;
+            let tx_hash = context.chain.send_transaction(&tx)?;
+            println!("{:#x}", tx_hash);
+        }
+        _ => {
+            let tx = json_types::TransactionView::from(tx);
+            let json = serde_json::to_string_pretty(&tx)
+                .map_err(|err| Error::InvalidArgument(err.to_string()))?;
+            match matches.value_of("output") {
+                Some(output) => fs::write(output, json)?,
+                None => println!("{}", json),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Balance the transaction with the capacity cells, then either sign and send it, or write it unsigned.
+fn complete_tx(
+    matches: &ArgMatches,
+    context: &Context,
+    tx: TransactionView,
+    input_cells: &[LiveCell],
+) -> Result<(), Error> {
+    let (tx, spent_cells) = balance(matches, context, tx, input_cells)?;
+    send_tx(matches, context, tx, &spent_cells)
+}
+
+fn create(matches: &ArgMatches) -> Result<(), Error> {
+    let context = load_context(matches)?;
+    let seed_cell = context.chain.live_cell(&parse_out_point(
+        matches.value_of("seed").unwrap_or_default(),
+    )?)?;
+
+    // The organizer is the signer, or the owner of the seed cell for unsigned transactions.
+    let organizer_lock = signer_lock(matches, &context)?.unwrap_or_else(|| seed_cell.output.lock());
+    let mut core_data = CoreCellData::new([0u8; 32], 0);
+    core_data.organizer_lock_hash = organizer_lock.calc_script_hash().unpack();
+    if let Some(title) = matches.value_of("title") {
+        core_data.vote_title = title.to_string();
+    }
+    if let Some(voting_end) = parse_number(matches, "voting-end")? {
+        core_data.voting_end_block_number = voting_end;
+    }
+    if let Some(vote_shard_count) = parse_number(matches, "vote-shards")? {
+        core_data.vote_shard_count = vote_shard_count;
+    }
+
+    let mut builder = CreateVote::new(
+        &context.deployment.script(DAO_CORE)?,
+        &context.deployment.script(SUDT)?,
+        seed_cell.clone(),
+    )
+    .core_data(core_data);
+    for (lock, amount) in load_recipients(matches.value_of("voters").unwrap_or_default())? {
+        builder = builder.voter(lock, amount);
+    }
+
+    complete_tx(matches, &context, builder.build()?, &[seed_cell])
+}
+
+fn vote(matches: &ArgMatches) -> Result<(), Error> {
+    let context = load_context(matches)?;
+    let core_cell = context.chain.live_cell(&parse_out_point(
+        matches.value_of("core").unwrap_or_default(),
+    )?)?;
+    let core_data = CoreCellData::from_slice(&core_cell.data)
+        .ok_or_else(|| Error::InvalidArgument(String::from("not a Core Cell")))?;
+    let is_snapshot = core_data.voting_weight_mode == VOTING_WEIGHT_MODE_SNAPSHOT;
+    let vote_out_point = parse_out_point(matches.value_of("vote-cell").unwrap_or_default())?;
+    let vote_cell = context.chain.live_cell(&vote_out_point)?;
+
+    // The header of the Vote Cell proves it was created before the voting end.
+    let mut input_cells = vec![vote_cell.clone()];
+    let mut builder = CastVote::new(
+        &context.deployment.script(DAO_CORE)?,
+        &context.deployment.script(SUDT)?,
+        core_cell,
+        vote_cell,
+    )
+    .header_dep(context.chain.header(&vote_out_point)?.hash());
+    if core_data.escrow_lock_hash != [0u8; 32] && !is_snapshot {
+        builder = builder.vote_escrow(&context.deployment.script(VOTE_ESCROW)?);
+    }
+    for out_point in parse_out_points(matches, "token-cell")? {
+        let token_cell = context.chain.live_cell(&out_point)?;
+        input_cells.push(token_cell.clone());
+        builder = builder.token_cell(token_cell);
+        // Snapshot votes prove the token cells were created before the snapshot.
+        if is_snapshot {
+            builder = builder.header_dep(context.chain.header(&out_point)?.hash());
+        }
+    }
+    if is_snapshot {
+        builder = builder.header_dep(context.chain.tip_header()?.hash());
+    }
+    if let Some(votes) = parse_number(matches, "votes")? {
+        builder = builder.votes(votes);
+    }
+
+    // CastVote returns the capacity freed by the token cells and pays the fee, with the capacity cells if needed.
+    let capacity_cells = load_capacity_cells(matches, &context)?;
+    for capacity_cell in &capacity_cells {
+        builder = builder.capacity_cell(capacity_cell.clone());
+    }
+    if let Some(change_lock) = change_lock(matches, &context, &capacity_cells)? {
+        builder = builder.change_lock(change_lock);
+    }
+    if let Some(fee_rate) = parse_number(matches, "fee-rate")? {
+        builder = builder.fee_rate(fee_rate);
+    }
+    for cell_dep in &context.cell_deps {
+        builder = builder.cell_dep(cell_dep.clone());
+    }
+
+    let tx = builder.build()?;
+    let spent_cells = spent_cells(&tx, &input_cells, &capacity_cells);
+    send_tx(matches, &context, tx, &spent_cells)
+}
+
+fn finish(matches: &ArgMatches) -> Result<(), Error> {
+    let context = load_context(matches)?;
+    let core_out_point = parse_out_point(matches.value_of("core").unwrap_or_default())?;
+    let core_cell = context.chain.live_cell(&core_out_point)?;
+    let core_data = CoreCellData::from_slice(&core_cell.data)
+        .ok_or_else(|| Error::InvalidArgument(String::from("not a Core Cell")))?;
+
+    let mut input_cells = vec![core_cell.clone()];
+    let mut builder = FinishVote::new(
+        &context.deployment.script(DAO_CORE)?,
+        core_cell,
+        &context.chain.header(&core_out_point)?,
+    );
+    for out_point in parse_out_points(matches, "vote-cell")? {
+        let vote_cell = context.chain.live_cell(&out_point)?;
+        input_cells.push(vote_cell.clone());
+        builder = builder.vote_cell(vote_cell, &context.chain.header(&out_point)?);
+    }
+    match matches.value_of("casting-vote") {
+        Some("yes") => builder = builder.casting_vote(VOTE_OPTION_YES),
+        Some(_) => builder = builder.casting_vote(VOTE_OPTION_NO),
+        None => {}
+    }
+
+    if let Some(out_point) = matches.value_of("organizer-cell") {
+        let organizer_cell = context.chain.live_cell(&parse_out_point(out_point)?)?;
+        input_cells.push(organizer_cell.clone());
+        builder = builder.organizer_input(organizer_cell);
+    }
+    if let Some(multisig_config) = parse_hex(matches, "organizer-multisig")? {
+        builder = builder.organizer_multisig(multisig_config);
+    }
+
+    // The tip proves that the voting end was reached.
+    if core_data.voting_end_block_number > 0 {
+        builder = builder.header_dep(context.chain.tip_header()?.hash());
+    }
+
+    // The organizer signs the balanced transaction, before the inputs are signed.
+    let (mut tx, spent_cells) = balance(matches, &context, builder.build()?, &input_cells)?;
+    for key in matches.values_of("organizer-key").into_iter().flatten() {
+        tx = sign_as_organizer(tx, &load_key(Path::new(key))?)?;
+    }
+    send_tx(matches, &context, tx, &spent_cells)
+}
+
+fn option_name(vote_option_type: u8) -> &'static str {
+    match vote_option_type {
+        VOTE_OPTION_YES => "yes",
+        _ => "no",
+    }
+}
+
+fn status(matches: &ArgMatches) -> Result<(), Error> {
+    let chain = Chain::new(matches.value_of("rpc").unwrap_or_default());
+    let core_cell = chain.live_cell(&parse_out_point(
+        matches.value_of("core").unwrap_or_default(),
+    )?)?;
+    let core_data = CoreCellData::from_slice(&core_cell.data)
+        .ok_or_else(|| Error::InvalidArgument(String::from("not a Core Cell")))?;
+
+    let status = match core_data.is_voting_finished {
+        VOTING_STATUS_FINISHED => format!(
+            "finished with {} at block {}",
+            option_name(core_data.vote_result_option_type),
+            core_data.settlement_block_number
+        ),
+        VOTING_STATUS_CANCELLED => String::from("cancelled"),
+        VOTING_STATUS_EXECUTED => format!(
+            "executed, finished with {} at block {}",
+            option_name(core_data.vote_result_option_type),
+            core_data.settlement_block_number
+        ),
+        _ => String::from("open"),
+    };
+    println!("title: {}", core_data.vote_title);
+    println!("status: {}", status);
+    println!("distributed tokens: {}", core_data.total_distributed_tokens);
+    if core_data.voting_end_block_number > 0 {
+        println!("voting end: {}", core_data.voting_end_block_number);
+    }
+    println!("vote shards: {}", core_data.vote_shard_count);
+
+    for out_point in parse_out_points(matches, "vote-cell")? {
+        let vote_cell = chain.live_cell(&out_point)?;
+        let vote_data = VoteCellData::from_slice(&vote_cell.data)
+            .ok_or_else(|| Error::InvalidArgument(String::from("not a Vote Cell")))?;
+        println!(
+            "{} (shard {}): {} votes in {}",
+            option_name(vote_data.vote_option_type),
+            vote_data.shard_index,
+            vote_data.total_votes_collected,
+            format_out_point(&out_point)
+        );
+    }
+
+    Ok(())
+}
+
+fn print_audit_report(report: &AuditReport) {
+    println!(
+        "created in block {} by {:#x}",
+        report.creation_block_number, report.creation_tx_hash
+    );
+    println!("distributed tokens: {}", report.total_distributed_tokens);
+    println!(
+        "issued tokens: {} ({} live, {} burned)",
+        report.issued_tokens(),
+        report.live_tokens,
+        report.burned_tokens
+    );
+    for mint in &report.mints {
+        println!(
+            "minted {} in block {} by {:#x}",
+            mint.amount, mint.block_number, mint.tx_hash
+        );
+    }
+
+    if !report.mints.is_empty() {
+        println!("warning: tokens were minted after the vote was created");
+    }
+    if report.issued_tokens() != report.total_distributed_tokens {
+        println!("warning: issued tokens don't match the distributed tokens");
+    }
+}
+
+fn audit(matches: &ArgMatches) -> Result<(), Error> {
+    let chain = Chain::new(matches.value_of("rpc").unwrap_or_default());
+    let core_out_point = parse_out_point(matches.value_of("core").unwrap_or_default())?;
+
+    let report = match matches.value_of("snapshot") {
+        Some(snapshot) => {
+            let snapshot = ChainSnapshot::from_json(&fs::read_to_string(snapshot)?)?;
+            let core_cell = snapshot
+                .cell(&core_out_point)
+                .ok_or_else(|| Error::CellNotLive(format_out_point(&core_out_point)))?;
+            audit_vote(&snapshot, &core_cell)?
+        }
+        None => audit_vote(&chain, &chain.live_cell(&core_out_point)?)?,
+    };
+    print_audit_report(&report);
+
+    if !report.is_supply_consistent() {
+        process::exit(2);
+    }
+
+    Ok(())
+}
+
+/// Load a transaction, with or without its hash.
+fn load_tx(path: &str) -> Result<TransactionView, Error> {
+    let content = fs::read_to_string(path)?;
+    let tx = serde_json::from_str::<json_types::TransactionView>(&content)
+        .map(|tx| tx.inner)
+        .or_else(|_| serde_json::from_str::<json_types::Transaction>(&content))
+        .map_err(|err| Error::InvalidArgument(format!("invalid transaction: {}", err)))?;
+
+    Ok(packed::Transaction::from(tx).into_view())
+}
+
+fn explain(matches: &ArgMatches) -> Result<(), Error> {
+    let chain = Chain::new(matches.value_of("rpc").unwrap_or_default());
+    let snapshot = load_snapshot(matches)?;
+    let deployment = load_deployment(matches, |out_point| load_cell(&chain, &snapshot, out_point))?;
+
+    let tx = load_tx(matches.value_of("tx").unwrap_or_default())?;
+
+    let explanation = explain_tx(
+        &tx,
+        &deployment.script(DAO_CORE)?,
+        &deployment.script(SUDT)?,
+        |out_point| load_cell(&chain, &snapshot, out_point).ok(),
+    );
+    print!("{}", explanation);
+
+    if explanation.predicted_error().is_some() {
+        process::exit(2);
+    }
+
+    Ok(())
+}
+
+fn report(matches: &ArgMatches) -> Result<(), Error> {
+    let chain = Chain::new(matches.value_of("rpc").unwrap_or_default());
+    let settlement_tx_hash = parse_hash(matches.value_of("settlement").unwrap_or_default())?.pack();
+    let snapshot = load_snapshot(matches)?;
+    let dao_core = load_deployment(matches, |out_point| load_cell(&chain, &snapshot, out_point))?
+        .script(DAO_CORE)?;
+
+    let mut report = match &snapshot {
+        Some(snapshot) => VoteReport::generate(snapshot, &settlement_tx_hash, &dao_core)?,
+        None => VoteReport::generate(&chain, &settlement_tx_hash, &dao_core)?,
+    };
+    if let Some(key) = matches.value_of("key") {
+        report.sign(&load_key(Path::new(key))?)?;
+    }
+
+    match matches.value_of("format") {
+        Some("markdown") => print!("{}", report.to_markdown()),
+        _ => println!("{}", report.to_json()),
+    }
+
+    Ok(())
+}
+
+fn verify_report(matches: &ArgMatches) -> Result<(), Error> {
+    let chain = Chain::new(matches.value_of("rpc").unwrap_or_default());
+    let report = VoteReport::from_json(&fs::read_to_string(
+        matches.value_of("report").unwrap_or_default(),
+    )?)?;
+
+    let snapshot = load_snapshot(matches)?;
+    let dao_core = load_deployment(matches, |out_point| load_cell(&chain, &snapshot, out_point))?
+        .script(DAO_CORE)?;
+
+    let result = match &snapshot {
+        Some(snapshot) => report.verify(snapshot, &dao_core),
+        None => report.verify(&chain, &dao_core),
+    };
+    let signer = match result {
+        Ok(signer) => signer,
+        Err(ckb_voting_sdk::Error::InvalidReport(reason)) => {
+            println!("invalid report: {}", reason);
+            process::exit(2);
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    println!("title: {}", report.vote_title);
+    for tally in &report.tallies {
+        println!(
+            "{}: {} votes",
+            option_name(tally.vote_option_type),
+            tally.votes
+        );
+    }
+    println!(
+        "result: {} at block {}",
+        option_name(report.vote_result_option_type),
+        report.settlement_block_number
+    );
+    match signer {
+        Some(signer) => println!(
+            "signed by: 0x{}",
+            signer
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        ),
+        None => println!("not signed"),
+    }
+
+    Ok(())
+}
+
+fn mint(matches: &ArgMatches) -> Result<(), Error> {
+    let context = load_context(matches)?;
+    let owner_cell = context.chain.live_cell(&parse_out_point(
+        matches.value_of("owner-cell").unwrap_or_default(),
+    )?)?;
+
+    let mut builder = MintTokens::new(&context.deployment.script(SUDT)?, owner_cell.clone());
+    for (lock, amount) in load_recipients(matches.value_of("recipients").unwrap_or_default())? {
+        builder = builder.recipient(lock, amount);
+    }
+
+    complete_tx(matches, &context, builder.build()?, &[owner_cell])
+}
+
+/// Run the command of the parsed arguments.
+pub fn run(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        ("create", Some(matches)) => create(matches),
+        ("vote", Some(matches)) => vote(matches),
+        ("finish", Some(matches)) => finish(matches),
+        ("status", Some(matches)) => status(matches),
+        ("audit", Some(matches)) => audit(matches),
+        ("explain", Some(matches)) => explain(matches),
+        ("report", Some(matches)) => report(matches),
+        ("verify-report", Some(matches)) => verify_report(matches),
+        ("mint", Some(matches)) => mint(matches),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,150 @@
+//! Resolves the deployed scripts from `deployment.toml` and the migration file written by `capsule deploy`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    core::ScriptHashType,
+    packed::{OutPoint, Script},
+    prelude::*,
+    H256,
+};
+use ckb_voting_sdk::{DeployedScript, LiveCell};
+use serde::Deserialize;
+
+use crate::Error;
+
+#[derive(Deserialize)]
+struct DeploymentConfig {
+    #[serde(default)]
+    cells: Vec<CellConfig>,
+    lock: Option<json_types::Script>,
+}
+
+#[derive(Deserialize)]
+struct CellConfig {
+    name: String,
+    location: CellLocation,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CellLocation {
+    OnChain { tx_hash: H256, index: u32 },
+    File { file: String },
+}
+
+#[derive(Deserialize)]
+struct Migration {
+    cell_recipes: Vec<CellRecipe>,
+}
+
+#[derive(Deserialize)]
+struct CellRecipe {
+    name: String,
+    tx_hash: H256,
+    index: u32,
+    data_hash: H256,
+    type_id: Option<H256>,
+}
+
+pub struct Deployment {
+    scripts: HashMap<String, DeployedScript>,
+    on_chain_cells: HashMap<String, OutPoint>,
+    // Cells deployed from a file missing in the migration, by the file.
+    undeployed_cells: HashMap<String, String>,
+    /// Lock of the deployed cells. Its code hash and hash type are used for the signing key's lock.
+    pub lock: Option<Script>,
+}
+
+impl Deployment {
+    /// Cells deployed from a file are looked up in the migration by name. Cells referencing an on-chain
+    /// location are resolved with `resolve_on_chain_cells`.
+    pub fn from_str(deployment: &str, migration: Option<&str>) -> Result<Self, Error> {
+        let config: DeploymentConfig =
+            toml::from_str(deployment).map_err(|err| Error::InvalidDeployment(err.to_string()))?;
+        let recipes = match migration {
+            Some(migration) => {
+                serde_json::from_str::<Migration>(migration)
+                    .map_err(|err| Error::InvalidDeployment(err.to_string()))?
+                    .cell_recipes
+            }
+            None => vec![],
+        };
+
+        let mut scripts = HashMap::new();
+        let mut on_chain_cells = HashMap::new();
+        let mut undeployed_cells = HashMap::new();
+
+        for cell in config.cells {
+            if let Some(recipe) = recipes.iter().find(|recipe| recipe.name == cell.name) {
+                let out_point = OutPoint::new(recipe.tx_hash.pack(), recipe.index);
+                let script = match &recipe.type_id {
+                    Some(type_id) => {
+                        DeployedScript::new(out_point, type_id.pack(), ScriptHashType::Type)
+                    }
+                    None => DeployedScript::new(
+                        out_point,
+                        recipe.data_hash.pack(),
+                        ScriptHashType::Data,
+                    ),
+                };
+                scripts.insert(cell.name, script);
+                continue;
+            }
+
+            match cell.location {
+                CellLocation::OnChain { tx_hash, index } => {
+                    on_chain_cells.insert(cell.name, OutPoint::new(tx_hash.pack(), index));
+                }
+                CellLocation::File { file } => {
+                    undeployed_cells.insert(cell.name, file);
+                }
+            }
+        }
+
+        Ok(Deployment {
+            scripts,
+            on_chain_cells,
+            undeployed_cells,
+            lock: config.lock.map(Into::into),
+        })
+    }
+
+    pub fn load(path: &Path, migration_path: Option<&Path>) -> Result<Self, Error> {
+        let deployment = fs::read_to_string(path)?;
+        let migration = match migration_path {
+            Some(migration_path) => Some(fs::read_to_string(migration_path)?),
+            None => None,
+        };
+
+        Deployment::from_str(&deployment, migration.as_deref())
+    }
+
+    /// Reference the cells at an on-chain location by the hash of their data.
+    pub fn resolve_on_chain_cells(
+        &mut self,
+        load_cell: impl Fn(&OutPoint) -> Result<LiveCell, Error>,
+    ) -> Result<(), Error> {
+        for (name, out_point) in self.on_chain_cells.drain() {
+            let cell = load_cell(&out_point)?;
+            self.scripts
+                .insert(name, DeployedScript::from_code(out_point, &cell.data));
+        }
+
+        Ok(())
+    }
+
+    pub fn script(&self, name: &str) -> Result<DeployedScript, Error> {
+        if let Some(script) = self.scripts.get(name) {
+            return Ok(script.clone());
+        }
+
+        match self.undeployed_cells.get(name) {
+            Some(file) => Err(Error::NotDeployed(format!("{} ({})", name, file))),
+            None => Err(Error::NotDeployed(name.to_string())),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    InvalidDeployment(String),
+    NotDeployed(String),
+    InvalidArgument(String),
+    Rpc(String),
+    CellNotLive(String),
+    Sdk(ckb_voting_sdk::Error),
+    InvalidKey,
+    InsufficientCapacity,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::InvalidDeployment(reason) => write!(f, "invalid deployment: {}", reason),
+            Error::NotDeployed(name) => write!(f, "script {} is not deployed", name),
+            Error::InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+            Error::Rpc(reason) => write!(f, "rpc error: {}", reason),
+            Error::CellNotLive(out_point) => write!(f, "cell {} is not live", out_point),
+            Error::Sdk(err) => write!(f, "could not build transaction: {:?}", err),
+            Error::InvalidKey => write!(f, "invalid private key"),
+            Error::InsufficientCapacity => write!(f, "inputs don't cover the outputs and fee"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<ckb_voting_sdk::Error> for Error {
+    fn from(err: ckb_voting_sdk::Error) -> Self {
+        Error::Sdk(err)
+    }
+}
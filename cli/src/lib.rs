@@ -0,0 +1,10 @@
+//! Library of the ckb-voting command-line tool, which builds, signs and sends the transactions of the vote
+//! lifecycle. See `commands.rs` for the commands.
+
+pub mod chain;
+pub mod commands;
+pub mod deployment;
+mod error;
+pub mod wallet;
+
+pub use error::Error;
@@ -0,0 +1,14 @@
+//! Command-line tool for organizers and voters. See `commands.rs` for the commands.
+
+use std::process;
+
+use ckb_voting::commands::{app, run};
+
+fn main() {
+    let matches = app().get_matches();
+
+    if let Err(err) = run(&matches) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
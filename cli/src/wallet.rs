@@ -0,0 +1,128 @@
+//! Signing transactions with a local key.
+
+use std::fs;
+use std::path::Path;
+
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_hash::{blake2b_256, new_blake2b};
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{ScriptHashType, TransactionView},
+    packed::{self, Script, WitnessArgs},
+    prelude::*,
+    H256,
+};
+
+use crate::Error;
+
+// Type hash of the default secp256k1 lock, the same on mainnet and testnet.
+pub const SECP256K1_BLAKE160_SIGHASH_ALL_TYPE_HASH: [u8; 32] = [
+    0x9b, 0xd7, 0xe0, 0x6f, 0x3e, 0xcf, 0x4b, 0xe0, 0xf2, 0xfc, 0xd2, 0x18, 0x8b, 0x23, 0xf1, 0xb9,
+    0xfc, 0xc8, 0x8e, 0x5d, 0x4b, 0x65, 0xa8, 0x63, 0x7b, 0x17, 0x72, 0x3b, 0xbd, 0xa3, 0xcc, 0xe8,
+];
+const SIGNATURE_BYTESIZE: usize = 65;
+
+/// Load a private key stored as hex in the first line of the file, like the keys exported by ckb-cli.
+pub fn load_key(path: &Path) -> Result<Privkey, Error> {
+    let content = fs::read_to_string(path)?;
+    let line = content.lines().next().ok_or(Error::InvalidKey)?;
+    let key: H256 = line
+        .trim()
+        .trim_start_matches("0x")
+        .parse()
+        .map_err(|_| Error::InvalidKey)?;
+
+    Ok(Privkey::from(key))
+}
+
+/// The lock of the key. Code hash and hash type are taken from the template, which defaults to the secp256k1
+/// lock.
+pub fn lock_script(template: Option<&Script>, key: &Privkey) -> Result<Script, Error> {
+    let pubkey = key.pubkey().map_err(|_| Error::InvalidKey)?;
+    let pubkey_hash = blake2b_256(pubkey.serialize());
+    let template = match template {
+        Some(template) => template.clone(),
+        None => Script::new_builder()
+            .code_hash(SECP256K1_BLAKE160_SIGHASH_ALL_TYPE_HASH.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .build(),
+    };
+
+    Ok(template
+        .as_builder()
+        .args(Bytes::from(pubkey_hash[..20].to_vec()).pack())
+        .build())
+}
+
+/// Sign all inputs locked with the specified lock, using the secp256k1 sighash all scheme. The input locks
+/// have to be in the order of the inputs of the transaction.
+pub fn sign_tx(
+    tx: TransactionView,
+    input_locks: &[Script],
+    lock: &Script,
+    key: &Privkey,
+) -> Result<TransactionView, Error> {
+    let group: Vec<usize> = input_locks
+        .iter()
+        .enumerate()
+        .filter(|(_, input_lock)| *input_lock == lock)
+        .map(|(i, _)| i)
+        .collect();
+    let first_index = match group.first() {
+        Some(first_index) => *first_index,
+        None => return Ok(tx),
+    };
+
+    let mut witnesses: Vec<Bytes> = tx.witnesses().into_iter().map(|w| w.unpack()).collect();
+    if witnesses.len() < input_locks.len() {
+        witnesses.resize(input_locks.len(), Bytes::new());
+    }
+
+    let witness_args = match witnesses[first_index].is_empty() {
+        true => WitnessArgs::default(),
+        false => WitnessArgs::from_slice(&witnesses[first_index])
+            .map_err(|_| Error::InvalidArgument(String::from("invalid witness")))?,
+    };
+    let zero_lock = Bytes::from(vec![0u8; SIGNATURE_BYTESIZE]);
+    let witness_for_digest = witness_args
+        .clone()
+        .as_builder()
+        .lock(Some(zero_lock).pack())
+        .build()
+        .as_bytes();
+
+    let mut blake2b = new_blake2b();
+    blake2b.update(&tx.hash().raw_data());
+    blake2b.update(&(witness_for_digest.len() as u64).to_le_bytes());
+    blake2b.update(&witness_for_digest);
+    for witness in group[1..]
+        .iter()
+        .map(|i| &witnesses[*i])
+        .chain(witnesses[input_locks.len()..].iter())
+    {
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(witness);
+    }
+
+    let mut message = [0u8; 32];
+    blake2b.finalize(&mut message);
+    let signature = key
+        .sign_recoverable(&H256::from(message))
+        .map_err(|_| Error::InvalidKey)?;
+
+    witnesses[first_index] = witness_args
+        .as_builder()
+        .lock(Some(Bytes::from(signature.serialize())).pack())
+        .build()
+        .as_bytes();
+
+    Ok(tx
+        .as_advanced_builder()
+        .set_witnesses(
+            witnesses
+                .into_iter()
+                .map(|witness| witness.pack())
+                .collect::<Vec<packed::Bytes>>(),
+        )
+        .build())
+}
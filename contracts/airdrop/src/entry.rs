@@ -0,0 +1,297 @@
+// Import from `core` instead of from `std` since we are in no-std mode.
+use core::result::Result;
+
+// Import CKB syscalls and structures.
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use alloc::vec::Vec;
+use blake2b_ref::Blake2bBuilder;
+use ckb_std::ckb_types::{bytes::Bytes, prelude::*};
+use ckb_std::high_level::{
+    load_cell_capacity, load_cell_data, load_cell_lock_hash, load_cell_type_hash, load_script,
+    load_script_hash, load_witness_args, QueryIter,
+};
+use ckb_std::{ckb_constants::Source, debug};
+
+// Import our local error codes.
+use crate::error::Error;
+
+// Constants
+const BLAKE2B256_HASH_BYTESIZE: usize = 32; // Number of bytes for a Blake2b-256 hash.
+const LOCK_HASH_LEN: usize = BLAKE2B256_HASH_BYTESIZE; // Number of bytes for a lock hash.
+const U32_BYTESIZE: usize = 4;
+const U128_BYTESIZE: usize = 16;
+
+// Airdrop Cell data layout. The data starts with the sUDT amount so the cell is a regular sUDT cell.
+const AMOUNT_OFFSET: usize = 0;
+const MERKLE_ROOT_OFFSET: usize = AMOUNT_OFFSET + U128_BYTESIZE;
+const CLAIMED_BITMAP_OFFSET: usize = MERKLE_ROOT_OFFSET + BLAKE2B256_HASH_BYTESIZE; // Followed by one bit for every leaf.
+
+// Claim witness layout, stored in the lock field of the WitnessArgs.
+const CLAIM_INDEX_OFFSET: usize = 0;
+const CLAIM_LOCK_HASH_OFFSET: usize = CLAIM_INDEX_OFFSET + U32_BYTESIZE;
+const CLAIM_AMOUNT_OFFSET: usize = CLAIM_LOCK_HASH_OFFSET + LOCK_HASH_LEN;
+const CLAIM_PROOF_OFFSET: usize = CLAIM_AMOUNT_OFFSET + U128_BYTESIZE; // Followed by the sibling hashes, from the leaf up.
+
+// Domain separation of Merkle tree leaves and nodes, so a node can't be passed off as a leaf.
+const MERKLE_LEAF_PREFIX: u8 = 0;
+const MERKLE_NODE_PREFIX: u8 = 1;
+
+struct AirdropCell {
+    amount: u128,
+    merkle_root: [u8; BLAKE2B256_HASH_BYTESIZE],
+    claimed_bitmap: Vec<u8>,
+}
+
+struct Claim {
+    index: u32,
+    lock_hash: [u8; LOCK_HASH_LEN],
+    amount: u128,
+    proof: Vec<[u8; BLAKE2B256_HASH_BYTESIZE]>,
+}
+
+impl AirdropCell {
+    fn from_data(data: &[u8]) -> Result<Self, Error> {
+        if data.len() <= CLAIMED_BITMAP_OFFSET {
+            return Err(Error::InvalidCellData);
+        }
+
+        let mut merkle_root = [0u8; BLAKE2B256_HASH_BYTESIZE];
+        merkle_root.copy_from_slice(&data[MERKLE_ROOT_OFFSET..CLAIMED_BITMAP_OFFSET]);
+
+        Ok(AirdropCell {
+            amount: read_u128(data, AMOUNT_OFFSET),
+            merkle_root,
+            claimed_bitmap: data[CLAIMED_BITMAP_OFFSET..].to_vec(),
+        })
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        data.extend_from_slice(&self.merkle_root);
+        data.extend_from_slice(&self.claimed_bitmap);
+
+        data
+    }
+
+    fn is_claimed(&self, index: u32) -> bool {
+        self.claimed_bitmap[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    fn set_claimed(&mut self, index: u32) {
+        self.claimed_bitmap[index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+impl Claim {
+    fn from_witness(witness: &[u8]) -> Result<Self, Error> {
+        if witness.len() < CLAIM_PROOF_OFFSET
+            || (witness.len() - CLAIM_PROOF_OFFSET) % BLAKE2B256_HASH_BYTESIZE != 0
+        {
+            return Err(Error::InvalidWitness);
+        }
+
+        let mut index = [0u8; U32_BYTESIZE];
+        index.copy_from_slice(&witness[CLAIM_INDEX_OFFSET..CLAIM_LOCK_HASH_OFFSET]);
+
+        let mut lock_hash = [0u8; LOCK_HASH_LEN];
+        lock_hash.copy_from_slice(&witness[CLAIM_LOCK_HASH_OFFSET..CLAIM_AMOUNT_OFFSET]);
+
+        let proof = witness[CLAIM_PROOF_OFFSET..]
+            .chunks(BLAKE2B256_HASH_BYTESIZE)
+            .map(|chunk| {
+                let mut sibling = [0u8; BLAKE2B256_HASH_BYTESIZE];
+                sibling.copy_from_slice(chunk);
+                sibling
+            })
+            .collect();
+
+        Ok(Claim {
+            index: u32::from_le_bytes(index),
+            lock_hash,
+            amount: read_u128(witness, CLAIM_AMOUNT_OFFSET),
+            proof,
+        })
+    }
+
+    // The leaf commits to the position in the tree as well, so every leaf can only be claimed once.
+    fn leaf_hash(&self) -> [u8; BLAKE2B256_HASH_BYTESIZE] {
+        hash(&[
+            &[MERKLE_LEAF_PREFIX],
+            &self.index.to_le_bytes(),
+            &self.lock_hash,
+            &self.amount.to_le_bytes(),
+        ])
+    }
+
+    // Calculate the Merkle root from the leaf and its proof. The bits of the index determine
+    // whether the sibling on each level is on the left or on the right.
+    fn merkle_root(&self) -> Result<[u8; BLAKE2B256_HASH_BYTESIZE], Error> {
+        let mut node = self.leaf_hash();
+        let mut position = self.index;
+
+        for sibling in self.proof.iter() {
+            node = match position & 1 {
+                0 => hash(&[&[MERKLE_NODE_PREFIX], &node, sibling]),
+                _ => hash(&[&[MERKLE_NODE_PREFIX], sibling, &node]),
+            };
+            position >>= 1;
+        }
+
+        // An index beyond the depth of the tree doesn't point to a leaf.
+        if position != 0 {
+            return Err(Error::InvalidProof);
+        }
+
+        Ok(node)
+    }
+}
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    let mut buffer = [0u8; U128_BYTESIZE];
+    buffer.copy_from_slice(&data[offset..offset + U128_BYTESIZE]);
+    u128::from_le_bytes(buffer)
+}
+
+fn hash(parts: &[&[u8]]) -> [u8; BLAKE2B256_HASH_BYTESIZE] {
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_BYTESIZE)
+        .personal(b"ckb-default-hash")
+        .build();
+
+    for part in parts {
+        blake2b.update(part);
+    }
+
+    let mut hash: [u8; BLAKE2B256_HASH_BYTESIZE] = [0; BLAKE2B256_HASH_BYTESIZE];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+/// Determine if owner mode is enabled.
+fn check_owner_mode(args: &Bytes) -> bool {
+    // Compare the Lock Script Hash from the script args with the Lock Scripts
+    // of each input cell to determine if a match exists.
+    QueryIter::new(load_cell_lock_hash, Source::Input).any(|lock_hash| args[..] == lock_hash[..])
+}
+
+/// Count the number of tokens held by a lock in the specified source. Source should be either Input or Output.
+fn determine_token_amount(
+    lock_hash: &[u8; LOCK_HASH_LEN],
+    token_type_hash: &Option<[u8; BLAKE2B256_HASH_BYTESIZE]>,
+    source: Source,
+) -> Result<u128, Error> {
+    let mut token_amount: u128 = 0;
+
+    for (i, cell_lock_hash) in QueryIter::new(load_cell_lock_hash, source).enumerate() {
+        if cell_lock_hash != *lock_hash || load_cell_type_hash(i, source)? != *token_type_hash {
+            continue;
+        }
+
+        let data = load_cell_data(i, source)?;
+        if data.len() < U128_BYTESIZE {
+            return Err(Error::Encoding);
+        }
+
+        token_amount = token_amount
+            .checked_add(read_u128(&data, 0))
+            .ok_or(Error::AmountOverflow)?;
+    }
+
+    Ok(token_amount)
+}
+
+/// Verify a claim of one leaf of the airdrop. The claimed amount has to be moved to the claimant's lock,
+/// and the rest of the airdrop has to stay in an updated Airdrop Cell with the leaf marked as claimed.
+/// Non-transferable sUDT rejects this move outside of owner mode, so those tokens can't be airdropped.
+fn validate_claim() -> Result<(), Error> {
+    // Only one Airdrop Cell can be claimed from per transaction.
+    if QueryIter::new(load_cell_capacity, Source::GroupInput).count() != 1 {
+        return Err(Error::InvalidTransactionStructure);
+    }
+
+    let mut airdrop_cell = AirdropCell::from_data(&load_cell_data(0, Source::GroupInput)?)?;
+    let token_type_hash = load_cell_type_hash(0, Source::GroupInput)?;
+
+    let witness_args = load_witness_args(0, Source::GroupInput)?;
+    let witness: Bytes = witness_args
+        .lock()
+        .to_opt()
+        .ok_or(Error::InvalidWitness)?
+        .unpack();
+    let claim = Claim::from_witness(&witness)?;
+
+    if claim.index as usize >= airdrop_cell.claimed_bitmap.len() * 8 {
+        return Err(Error::InvalidClaimIndex);
+    }
+
+    if claim.merkle_root()? != airdrop_cell.merkle_root {
+        return Err(Error::InvalidProof);
+    }
+
+    if airdrop_cell.is_claimed(claim.index) {
+        return Err(Error::AlreadyClaimed);
+    }
+
+    debug!(
+        "Claiming leaf {:?}, amount: {:?}",
+        claim.index, claim.amount
+    );
+
+    // The remaining tokens and capacity go back to the same lock, with the leaf marked as claimed.
+    let script_hash = load_script_hash()?;
+    let output_indexes: Vec<usize> = QueryIter::new(load_cell_lock_hash, Source::Output)
+        .enumerate()
+        .filter(|(_, lock_hash)| *lock_hash == script_hash)
+        .map(|(i, _)| i)
+        .collect();
+
+    if output_indexes.len() != 1 {
+        return Err(Error::InvalidOutputCell);
+    }
+
+    let output_index = output_indexes[0];
+    airdrop_cell.amount = airdrop_cell
+        .amount
+        .checked_sub(claim.amount)
+        .ok_or(Error::ClaimAmountMismatch)?;
+    airdrop_cell.set_claimed(claim.index);
+
+    if load_cell_type_hash(output_index, Source::Output)? != token_type_hash
+        || load_cell_data(output_index, Source::Output)? != airdrop_cell.to_data()
+        || load_cell_capacity(output_index, Source::Output)?
+            < load_cell_capacity(0, Source::GroupInput)?
+    {
+        return Err(Error::InvalidOutputCell);
+    }
+
+    // The claimant has to end up with at least the claimed amount more than they put in.
+    let input_amount = determine_token_amount(&claim.lock_hash, &token_type_hash, Source::Input)?;
+    let output_amount = determine_token_amount(&claim.lock_hash, &token_type_hash, Source::Output)?;
+
+    if output_amount.saturating_sub(input_amount) < claim.amount {
+        return Err(Error::ClaimAmountMismatch);
+    }
+
+    Ok(())
+}
+
+// Main entry point.
+pub fn main() -> Result<(), Error> {
+    // Load the currently executing script and get the args.
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+
+    // Args are the lock hash of the organizer, who can take back the unclaimed tokens.
+    if args.len() != LOCK_HASH_LEN {
+        return Err(Error::InvalidArgsLength);
+    }
+
+    if check_owner_mode(&args) {
+        return Ok(());
+    }
+
+    validate_claim()?;
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+// Import from `core` instead of from `std` since we are in no-std mode.
+use core::result::Result;
+
+// Import CKB syscalls and structures.
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use alloc::vec::Vec;
+use ckb_std::ckb_types::{bytes::Bytes, prelude::*};
+use ckb_std::high_level::{
+    load_cell_capacity, load_cell_data, load_cell_lock_hash, load_cell_type_hash, load_script,
+    load_script_hash, QueryIter,
+};
+use ckb_std::{ckb_constants::Source, debug};
+
+// Import our local error codes.
+use crate::error::Error;
+
+// Constants
+const LOCK_HASH_LEN: usize = 32; // Number of bytes for a lock hash. (Blake2b 256-bit 32 bytes)
+const MINIMUM_AMOUNT_EXPONENT_LEN: usize = 1; // Each optional minimum amount is stored as a single byte exponent.
+const UDT_DATA_LEN: usize = 16; // UDT uses a u128, which is 16 bytes.
+
+// Minimum amounts a payment has to add to an ACP cell. None means the asset can't be used to satisfy the minimum.
+struct MinimumAmounts {
+    ckb: Option<u128>,
+    udt: Option<u128>,
+}
+
+// Total capacity and UDT amount of all ACP cells with the same type script.
+struct Balance {
+    type_hash: Option<[u8; 32]>,
+    capacity: u128,
+    udt_amount: u128,
+}
+
+/// Determine if owner mode is enabled.
+fn check_owner_mode(args: &Bytes) -> bool {
+    // Compare the Lock Script Hash from the script args with the Lock Scripts
+    // of each input cell to determine if a match exists.
+    QueryIter::new(load_cell_lock_hash, Source::Input)
+        .any(|lock_hash| args[0..LOCK_HASH_LEN] == lock_hash[..])
+}
+
+/// Decode a minimum amount exponent. The minimum amount is 10^exponent, in shannons for CKB and in base units for UDT.
+fn parse_minimum_amount(exponent: Option<&u8>) -> Result<Option<u128>, Error> {
+    match exponent {
+        Some(exponent) => 10u128
+            .checked_pow(*exponent as u32)
+            .map(Some)
+            .ok_or(Error::InvalidMinimumAmount),
+        None => Ok(None),
+    }
+}
+
+/// Parse the optional minimum amounts following the owner lock hash in the args.
+fn parse_minimum_amounts(args: &Bytes) -> Result<MinimumAmounts, Error> {
+    let exponents = &args[LOCK_HASH_LEN..];
+
+    Ok(MinimumAmounts {
+        ckb: parse_minimum_amount(exponents.first())?,
+        udt: parse_minimum_amount(exponents.get(1))?,
+    })
+}
+
+/// Sum the capacity and UDT amount of cells in the specified source, grouped by type script.
+/// Only cells for which `filter` returns true are counted.
+fn determine_balances<F>(source: Source, filter: F) -> Result<Vec<Balance>, Error>
+where
+    F: Fn(usize) -> Result<bool, Error>,
+{
+    let mut balances: Vec<Balance> = Vec::new();
+
+    for (i, type_hash) in QueryIter::new(load_cell_type_hash, source).enumerate() {
+        if !filter(i)? {
+            continue;
+        }
+
+        let capacity = load_cell_capacity(i, source)? as u128;
+
+        // Cells with a type script are expected to hold a UDT.
+        let udt_amount = match type_hash {
+            Some(_) => {
+                let data = load_cell_data(i, source)?;
+                if data.len() < UDT_DATA_LEN {
+                    return Err(Error::InvalidUdtCellData);
+                }
+
+                let mut buffer = [0u8; UDT_DATA_LEN];
+                buffer.copy_from_slice(&data[0..UDT_DATA_LEN]);
+                u128::from_le_bytes(buffer)
+            }
+            None => 0,
+        };
+
+        match balances
+            .iter_mut()
+            .find(|balance| balance.type_hash == type_hash)
+        {
+            Some(balance) => {
+                balance.capacity = balance
+                    .capacity
+                    .checked_add(capacity)
+                    .ok_or(Error::AmountOverflow)?;
+                balance.udt_amount = balance
+                    .udt_amount
+                    .checked_add(udt_amount)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+            None => balances.push(Balance {
+                type_hash,
+                capacity,
+                udt_amount,
+            }),
+        }
+    }
+
+    Ok(balances)
+}
+
+/// Verify that every ACP cell spent is paid back to the same lock with at least the same amounts,
+/// and that the payment reaches the minimum amount for at least one of the assets.
+fn validate_payment(args: &Bytes) -> Result<(), Error> {
+    let minimum_amounts = parse_minimum_amounts(args)?;
+    let script_hash = load_script_hash()?;
+
+    let input_balances = determine_balances(Source::GroupInput, |_| Ok(true))?;
+    let output_balances = determine_balances(Source::Output, |i| {
+        Ok(load_cell_lock_hash(i, Source::Output)? == script_hash)
+    })?;
+
+    for input_balance in input_balances {
+        let output_balance = output_balances
+            .iter()
+            .find(|balance| balance.type_hash == input_balance.type_hash)
+            .ok_or(Error::OutputCellMissing)?;
+
+        if output_balance.capacity < input_balance.capacity
+            || output_balance.udt_amount < input_balance.udt_amount
+        {
+            return Err(Error::AmountDecreased);
+        }
+
+        let capacity_paid = output_balance.capacity - input_balance.capacity;
+        let udt_paid = output_balance.udt_amount - input_balance.udt_amount;
+
+        debug!("CKB paid: {:?}, UDT paid: {:?}", capacity_paid, udt_paid);
+
+        // Without any minimum amounts, any payment is accepted.
+        if minimum_amounts.ckb.is_none() && minimum_amounts.udt.is_none() {
+            continue;
+        }
+
+        let ckb_minimum_reached = minimum_amounts
+            .ckb
+            .map(|minimum| capacity_paid >= minimum)
+            .unwrap_or(false);
+        let udt_minimum_reached = input_balance.type_hash.is_some()
+            && minimum_amounts
+                .udt
+                .map(|minimum| udt_paid >= minimum)
+                .unwrap_or(false);
+
+        if !ckb_minimum_reached && !udt_minimum_reached {
+            return Err(Error::MinimumAmountNotReached);
+        }
+    }
+
+    Ok(())
+}
+
+// Main entry point.
+pub fn main() -> Result<(), Error> {
+    // Load the currently executing script and get the args.
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+
+    // Args are the owner lock hash, optionally followed by the CKB and UDT minimum amount exponents.
+    if args.len() < LOCK_HASH_LEN || args.len() > LOCK_HASH_LEN + 2 * MINIMUM_AMOUNT_EXPONENT_LEN {
+        return Err(Error::InvalidArgsLength);
+    }
+
+    // The owner can spend the cells freely.
+    if check_owner_mode(&args) {
+        return Ok(());
+    }
+
+    // Anyone else can only pay into the cells.
+    validate_payment(&args)?;
+
+    Ok(())
+}
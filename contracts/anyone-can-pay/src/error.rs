@@ -0,0 +1,2 @@
+/// Error, defined in the shared registry of error codes.
+pub use ckb_voting_errors::AcpError as Error;
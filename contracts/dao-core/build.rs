@@ -0,0 +1,54 @@
+//! Pins the code hashes of the vote escrow lock, the sUDT type and the treasury lock, so dao-core can check the
+//! escrow lock and the token family of a new vote, and the treasury release of a passed one. All of them are listed
+//! before dao-core in capsule.toml, so their binaries are built first.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use blake2b_ref::Blake2bBuilder;
+
+// The pinned contracts, along with the name of the constant holding their code hash.
+const PINNED_CONTRACTS: &[(&str, &str)] = &[
+    ("vote-escrow", "VOTE_ESCROW_CODE_HASH"),
+    ("sudt", "SUDT_CODE_HASH"),
+    ("treasury", "TREASURY_CODE_HASH"),
+];
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR");
+    let profile = env::var("PROFILE").expect("PROFILE");
+
+    let mut code_hashes = String::new();
+    for (name, constant) in PINNED_CONTRACTS {
+        let binary_path = Path::new(&manifest_dir)
+            .join("../../build")
+            .join(&profile)
+            .join(name);
+        println!("cargo:rerun-if-changed={}", binary_path.display());
+
+        let binary = fs::read(&binary_path).unwrap_or_else(|_| {
+            panic!(
+                "{} is missing, {} has to be built before dao-core",
+                binary_path.display(),
+                name
+            )
+        });
+
+        // The code hash of a script referenced by data hash.
+        let mut blake2b = Blake2bBuilder::new(32)
+            .personal(b"ckb-default-hash")
+            .build();
+        blake2b.update(&binary);
+        let mut code_hash = [0u8; 32];
+        blake2b.finalize(&mut code_hash);
+
+        code_hashes.push_str(&format!(
+            "pub const {}: [u8; 32] = {:?};\n",
+            constant, code_hash
+        ));
+    }
+
+    let out_path = Path::new(&env::var("OUT_DIR").expect("OUT_DIR")).join("code_hashes.rs");
+    fs::write(out_path, code_hashes).expect("write code hashes");
+}
@@ -0,0 +1,76 @@
+// Import from `core` instead of from `std` since we are in no-std mode.
+use core::result::Result;
+
+use ckb_std::ckb_constants::Source;
+use ckb_std::ckb_types::{bytes::Bytes, prelude::*};
+use ckb_std::high_level::load_witness_args;
+
+use alloc::vec::Vec;
+use ckb_voting_layout::{
+    INTENT_AMOUNT_BYTESIZE, INTENT_AMOUNT_OFFSET, INTENT_BYTESIZE, INTENT_OUT_POINT_BYTESIZE,
+    INTENT_OUT_POINT_OFFSET, INTENT_OWNER_LOCK_HASH_BYTESIZE, INTENT_OWNER_LOCK_HASH_OFFSET,
+    INTENT_SIGNATURE_OFFSET, INTENT_VOTE_OPTION_TYPE_OFFSET,
+};
+
+// Import local modules.
+use crate::cells::VOTE_OPTIONS;
+use crate::error::Error;
+
+// Every voter signs an intent, which is checked by the vote intent lock of the voter's token cell.
+pub struct VoteIntent {
+    pub vote_option_type: u8,
+    pub amount: u128,
+    pub out_point: [u8; INTENT_OUT_POINT_BYTESIZE],
+    pub owner_lock_hash: [u8; INTENT_OWNER_LOCK_HASH_BYTESIZE],
+}
+
+// Load the vote intents of a batched vote. They are passed in the input_type field of the witness of the first
+// Vote Cell. Returns None when the votes are not batched.
+pub fn load_vote_intents() -> Result<Option<Vec<VoteIntent>>, Error> {
+    let witness = match load_witness_args(0, Source::GroupInput) {
+        Ok(witness_args) => witness_args.input_type().to_opt(),
+        Err(_) => None,
+    };
+    let witness: Bytes = match witness {
+        Some(witness) => witness.unpack(),
+        None => return Ok(None),
+    };
+
+    if witness.is_empty() || witness.len() % INTENT_BYTESIZE != 0 {
+        return Err(Error::InvalidVoteIntent);
+    }
+
+    let mut vote_intents: Vec<VoteIntent> = Vec::new();
+
+    for intent in witness.chunks(INTENT_BYTESIZE) {
+        let vote_option_type = intent[INTENT_VOTE_OPTION_TYPE_OFFSET];
+        if !VOTE_OPTIONS.contains(&vote_option_type) {
+            return Err(Error::InvalidVoteIntent);
+        }
+
+        let mut amount = [0u8; INTENT_AMOUNT_BYTESIZE];
+        amount.copy_from_slice(&intent[INTENT_AMOUNT_OFFSET..INTENT_OUT_POINT_OFFSET]);
+        let mut out_point = [0u8; INTENT_OUT_POINT_BYTESIZE];
+        out_point.copy_from_slice(&intent[INTENT_OUT_POINT_OFFSET..INTENT_OWNER_LOCK_HASH_OFFSET]);
+        let mut owner_lock_hash = [0u8; INTENT_OWNER_LOCK_HASH_BYTESIZE];
+        owner_lock_hash
+            .copy_from_slice(&intent[INTENT_OWNER_LOCK_HASH_OFFSET..INTENT_SIGNATURE_OFFSET]);
+
+        // Every token cell can only be counted once.
+        if vote_intents
+            .iter()
+            .any(|vote_intent| vote_intent.out_point == out_point)
+        {
+            return Err(Error::InvalidVoteIntent);
+        }
+
+        vote_intents.push(VoteIntent {
+            vote_option_type,
+            amount: u128::from_le_bytes(amount),
+            out_point,
+            owner_lock_hash,
+        });
+    }
+
+    Ok(Some(vote_intents))
+}
@@ -0,0 +1,275 @@
+// Import from `core` instead of from `std` since we are in no-std mode.
+use core::result::Result;
+
+use ckb_std::ckb_constants::Source;
+use ckb_std::high_level::load_cell_data;
+
+use alloc::vec::Vec;
+
+// Import local modules.
+use crate::error::Error;
+
+// Cell data layout, shared with the locks and host code.
+use ckb_voting_layout::*;
+pub use ckb_voting_layout::{
+    BLAKE2B256_HASH_BYTESIZE, CORE_DATA_LEN, RECEIPT_DATA_LEN, VOTE_DATA_LEN, VOTE_OPTIONS,
+    VOTE_OPTION_YES, VOTING_STATUS_CANCELLED, VOTING_STATUS_EXECUTED, VOTING_STATUS_FINISHED,
+    VOTING_STATUS_OPEN,
+};
+
+// How the voting weight of a voter is determined.
+#[derive(Clone, Copy, PartialEq)]
+pub enum VotingWeightMode {
+    // Voting sUDT is distributed to the voters and burned when voting.
+    Distributed = 0,
+    // Balance of an existing token held at the snapshot block, proved through header deps.
+    Snapshot = 1,
+}
+
+// How cells holding the voting token are identified.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TokenIdMode {
+    // TOKEN_CODE_HASH is the full type script hash of the token, which matches a single token.
+    TypeHash = 0,
+    // TOKEN_CODE_HASH is the code hash of the token's type script, used along with TOKEN_HASH_TYPE.
+    // This matches all tokens of one contract family, regardless of their args.
+    CodeHash = 1,
+}
+
+// How the organizer authorizes admin transitions.
+#[derive(Clone, Copy, PartialEq)]
+pub enum OrganizerMode {
+    // ORGANIZER_LOCK_HASH is the lock hash of the organizer, who has to include an input with that lock.
+    Lock = 0,
+    // ORGANIZER_LOCK_HASH is the hash of an M-of-N multisig config, with the signatures in the witness.
+    Multisig = 1,
+}
+
+// Script hash types.
+const HASH_TYPE_DATA: u8 = 0;
+const HASH_TYPE_TYPE: u8 = 1;
+
+// Core Cells, Vote Cells and Receipt Cells share the same type script. They are told apart by their data length.
+pub enum Cell {
+    Core(CoreCell),
+    Vote(VoteCell),
+    Receipt(ReceiptCell),
+}
+
+pub struct CoreCell {
+    pub token_code_hash: [u8; CODE_HASH_BYTESIZE],
+    pub total_distributed_tokens: u128,
+    pub is_voting_finished: u8,
+    pub vote_result_option_type: u8,
+    pub voting_weight_mode: VotingWeightMode,
+    pub snapshot_block_number: u64,
+    pub token_id_mode: TokenIdMode,
+    pub token_hash_type: u8,
+    pub settlement_block_number: u64,
+    pub organizer_lock_hash: [u8; BLAKE2B256_HASH_BYTESIZE],
+    pub organizer_mode: OrganizerMode,
+    pub escrow_lock_hash: [u8; BLAKE2B256_HASH_BYTESIZE],
+    pub voting_end_block_number: u64,
+    pub max_extension: u64,
+    pub total_extension: u64,
+    pub vote_shard_count: u8,
+}
+
+pub struct VoteCell {
+    pub vote_option_type: u8,
+    pub total_votes_collected: u128,
+    pub shard_index: u8,
+}
+
+// Created for every vote cast with escrowed tokens, so the voter can reclaim them if the vote is cancelled.
+pub struct ReceiptCell {
+    pub vote_option_type: u8,
+    pub amount: u128,
+    pub owner_lock_hash: [u8; BLAKE2B256_HASH_BYTESIZE],
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut buffer = [0u8; U64_BYTESIZE];
+    buffer.copy_from_slice(&data[offset..offset + U64_BYTESIZE]);
+    u64::from_le_bytes(buffer)
+}
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    let mut buffer = [0u8; U128_BYTESIZE];
+    buffer.copy_from_slice(&data[offset..offset + U128_BYTESIZE]);
+    u128::from_le_bytes(buffer)
+}
+
+impl CoreCell {
+    pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+        if data.len() != CORE_DATA_LEN {
+            return Err(Error::InvalidDataBytesize);
+        }
+
+        let mut token_code_hash = [0u8; CODE_HASH_BYTESIZE];
+        token_code_hash.copy_from_slice(
+            &data[TOKEN_CODE_HASH_OFFSET..TOKEN_CODE_HASH_OFFSET + CODE_HASH_BYTESIZE],
+        );
+
+        let voting_weight_mode = match data[VOTING_WEIGHT_MODE_OFFSET] {
+            0 => VotingWeightMode::Distributed,
+            1 => VotingWeightMode::Snapshot,
+            _ => return Err(Error::InvalidVotingWeightMode),
+        };
+
+        let token_id_mode = match data[TOKEN_ID_MODE_OFFSET] {
+            0 => TokenIdMode::TypeHash,
+            1 => TokenIdMode::CodeHash,
+            _ => return Err(Error::InvalidTokenIdMode),
+        };
+
+        let token_hash_type = data[TOKEN_HASH_TYPE_OFFSET];
+        if token_hash_type != HASH_TYPE_DATA && token_hash_type != HASH_TYPE_TYPE {
+            return Err(Error::InvalidTokenHashType);
+        }
+
+        let mut organizer_lock_hash = [0u8; BLAKE2B256_HASH_BYTESIZE];
+        organizer_lock_hash
+            .copy_from_slice(&data[ORGANIZER_LOCK_HASH_OFFSET..ORGANIZER_MODE_OFFSET]);
+
+        let organizer_mode = match data[ORGANIZER_MODE_OFFSET] {
+            0 => OrganizerMode::Lock,
+            1 => OrganizerMode::Multisig,
+            _ => return Err(Error::InvalidOrganizerMode),
+        };
+
+        let mut escrow_lock_hash = [0u8; BLAKE2B256_HASH_BYTESIZE];
+        escrow_lock_hash
+            .copy_from_slice(&data[ESCROW_LOCK_HASH_OFFSET..VOTING_END_BLOCK_NUMBER_OFFSET]);
+
+        Ok(CoreCell {
+            token_code_hash,
+            total_distributed_tokens: read_u128(data, TOTAL_DISTRIBUTED_TOKENS_OFFSET),
+            is_voting_finished: data[IS_VOTING_FINISHED_OFFSET],
+            vote_result_option_type: data[VOTE_RESULT_OPTION_TYPE_OFFSET],
+            voting_weight_mode,
+            snapshot_block_number: read_u64(data, SNAPSHOT_BLOCK_NUMBER_OFFSET),
+            token_id_mode,
+            token_hash_type,
+            settlement_block_number: read_u64(data, SETTLEMENT_BLOCK_NUMBER_OFFSET),
+            organizer_lock_hash,
+            organizer_mode,
+            escrow_lock_hash,
+            voting_end_block_number: read_u64(data, VOTING_END_BLOCK_NUMBER_OFFSET),
+            max_extension: read_u64(data, MAX_EXTENSION_OFFSET),
+            total_extension: read_u64(data, TOTAL_EXTENSION_OFFSET),
+            vote_shard_count: data[VOTE_SHARD_COUNT_OFFSET],
+        })
+    }
+
+    // Voted tokens are held in escrow instead of being burned.
+    pub fn has_escrow(&self) -> bool {
+        self.escrow_lock_hash != [0u8; BLAKE2B256_HASH_BYTESIZE]
+    }
+}
+
+// Build the data of a Core Cell after voting was finished with the specified result at the settlement block.
+// All other fields stay the same.
+pub fn finished_core_data(
+    data: &[u8],
+    vote_result_option_type: u8,
+    settlement_block_number: u64,
+) -> Vec<u8> {
+    let mut finished_data = data.to_vec();
+    finished_data[IS_VOTING_FINISHED_OFFSET] = VOTING_STATUS_FINISHED;
+    finished_data[VOTE_RESULT_OPTION_TYPE_OFFSET] = vote_result_option_type;
+    finished_data[SETTLEMENT_BLOCK_NUMBER_OFFSET
+        ..SETTLEMENT_BLOCK_NUMBER_OFFSET + SETTLEMENT_BLOCK_NUMBER_BYTESIZE]
+        .copy_from_slice(&settlement_block_number.to_le_bytes());
+
+    finished_data
+}
+
+// Build the data of a Core Cell after the vote was cancelled. All other fields stay the same.
+pub fn cancelled_core_data(data: &[u8]) -> Vec<u8> {
+    let mut cancelled_data = data.to_vec();
+    cancelled_data[IS_VOTING_FINISHED_OFFSET] = VOTING_STATUS_CANCELLED;
+
+    cancelled_data
+}
+
+// Build the data of a Core Cell after the treasury released the funds of the vote. All other fields stay the same.
+pub fn executed_core_data(data: &[u8]) -> Vec<u8> {
+    let mut executed_data = data.to_vec();
+    executed_data[IS_VOTING_FINISHED_OFFSET] = VOTING_STATUS_EXECUTED;
+
+    executed_data
+}
+
+// Build the data of a Core Cell after the voting end was extended. All other fields stay the same.
+pub fn extended_core_data(
+    data: &[u8],
+    voting_end_block_number: u64,
+    total_extension: u64,
+) -> Vec<u8> {
+    let mut extended_data = data.to_vec();
+    extended_data[VOTING_END_BLOCK_NUMBER_OFFSET..MAX_EXTENSION_OFFSET]
+        .copy_from_slice(&voting_end_block_number.to_le_bytes());
+    extended_data[TOTAL_EXTENSION_OFFSET..VOTE_SHARD_COUNT_OFFSET]
+        .copy_from_slice(&total_extension.to_le_bytes());
+
+    extended_data
+}
+
+impl VoteCell {
+    pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+        if data.len() != VOTE_DATA_LEN {
+            return Err(Error::InvalidDataBytesize);
+        }
+
+        let vote_option_type = data[VOTE_OPTION_TYPE_OFFSET];
+        if !VOTE_OPTIONS.contains(&vote_option_type) {
+            return Err(Error::InvalidVoteOption);
+        }
+
+        Ok(VoteCell {
+            vote_option_type,
+            total_votes_collected: read_u128(data, TOTAL_VOTES_COLLECTED_OFFSET),
+            shard_index: data[SHARD_INDEX_OFFSET],
+        })
+    }
+}
+
+impl ReceiptCell {
+    pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+        if data.len() != RECEIPT_DATA_LEN {
+            return Err(Error::InvalidDataBytesize);
+        }
+
+        let vote_option_type = data[RECEIPT_VOTE_OPTION_TYPE_OFFSET];
+        if !VOTE_OPTIONS.contains(&vote_option_type) {
+            return Err(Error::InvalidVoteOption);
+        }
+
+        let mut owner_lock_hash = [0u8; BLAKE2B256_HASH_BYTESIZE];
+        owner_lock_hash.copy_from_slice(&data[RECEIPT_OWNER_LOCK_HASH_OFFSET..RECEIPT_DATA_LEN]);
+
+        Ok(ReceiptCell {
+            vote_option_type,
+            amount: read_u128(data, RECEIPT_AMOUNT_OFFSET),
+            owner_lock_hash,
+        })
+    }
+}
+
+impl Cell {
+    pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+        match data.len() {
+            CORE_DATA_LEN => Ok(Cell::Core(CoreCell::from_data(data)?)),
+            VOTE_DATA_LEN => Ok(Cell::Vote(VoteCell::from_data(data)?)),
+            RECEIPT_DATA_LEN => Ok(Cell::Receipt(ReceiptCell::from_data(data)?)),
+            _ => Err(Error::InvalidDataBytesize),
+        }
+    }
+
+    pub fn load(index: usize, source: Source) -> Result<Self, Error> {
+        let data = load_cell_data(index, source)?;
+
+        Cell::from_data(&data)
+    }
+}
@@ -1,63 +1,137 @@
 // Import from `core` instead of from `std` since we are in no-std mode.
-use core::{ops::Add, result::Result};
+use core::result::Result;
 
 use ckb_std::{
     ckb_constants::Source,
-    ckb_types::packed::OutPoint,
-    high_level::{load_cell_type_hash, load_input},
+    ckb_types::packed::{Byte, OutPoint, Script},
+    high_level::{
+        load_cell_data_hash, load_cell_lock, load_cell_lock_hash, load_cell_type_hash, load_header,
+        load_input, load_script_hash,
+    },
 };
 use ckb_std::{ckb_types::bytes::Bytes, high_level::load_cell_type};
-use ckb_std::{ckb_types::packed::Byte, high_level::load_script};
+use ckb_std::high_level::load_script;
 use ckb_std::{ckb_types::prelude::*, debug};
 
 // Import CKB syscalls and structures
 // https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
-use ckb_std::high_level::load_cell_lock;
-use ckb_std::high_level::{load_cell, load_cell_data, QueryIter};
+use ckb_std::high_level::{load_cell_data, QueryIter};
 
+use alloc::vec::Vec;
 use blake2b_ref::Blake2bBuilder;
 
 // Import local modules.
+use crate::batch::{load_vote_intents, VoteIntent};
+use crate::cells::{
+    cancelled_core_data, executed_core_data, extended_core_data, finished_core_data, Cell,
+    CoreCell, ReceiptCell, TokenIdMode, VoteCell, VotingWeightMode, BLAKE2B256_HASH_BYTESIZE,
+    CORE_DATA_LEN, VOTE_OPTIONS, VOTE_OPTION_YES, VOTING_STATUS_CANCELLED, VOTING_STATUS_EXECUTED,
+    VOTING_STATUS_FINISHED, VOTING_STATUS_OPEN,
+};
 use crate::error::Error;
+use crate::organizer::is_organizer_authorized;
 
 // The modes of operation for the script.
 enum Mode {
     Burn,
     Create,
-    Transfer,
+    Vote,
+    Finish,
+    Cancel,
+    Extend,
+    Execute,
+    Refund,
 }
 
+// Cells of this type found in a source, along with their index in the source.
+type CoreCellList = Vec<(usize, CoreCell)>;
+type VoteCellList = Vec<(usize, VoteCell)>;
+type ReceiptCellList = Vec<(usize, ReceiptCell)>;
+
 // Constants
-const BLAKE2B256_HASH_BYTESIZE: usize = 32;
-const CODE_HASH_BYTESIZE: usize = 32;
 const U128_BYTESIZE: usize = 16;
 const ARGS_BYTESIZE: usize = BLAKE2B256_HASH_BYTESIZE;
+const MAJORITY_PERCENTAGE: u128 = 51; // Percentage of all tokens a vote option needs to win.
+const HASH_TYPE_DATA: u8 = 0;
+const SUDT_ARGS_BYTESIZE: usize = BLAKE2B256_HASH_BYTESIZE + 1; // Owner lock hash, followed by the flags.
+const SUDT_FLAG_NON_TRANSFERABLE: u8 = 0b0000_0001;
+
+// VOTE_ESCROW_CODE_HASH, SUDT_CODE_HASH and TREASURY_CODE_HASH, pinned by build.rs.
+include!(concat!(env!("OUT_DIR"), "/code_hashes.rs"));
+
+// Load all Core Cells, Vote Cells and Receipt Cells from the specified source. Source should be either GroupInput or GroupOutput.
+fn load_cells(
+    source: Source,
+) -> Result<(usize, CoreCellList, VoteCellList, ReceiptCellList), Error> {
+    let mut core_cells = CoreCellList::new();
+    let mut vote_cells = VoteCellList::new();
+    let mut receipt_cells = ReceiptCellList::new();
+
+    for (i, data) in QueryIter::new(load_cell_data, source).enumerate() {
+        match Cell::from_data(&data)? {
+            Cell::Core(core_cell) => core_cells.push((i, core_cell)),
+            Cell::Vote(vote_cell) => vote_cells.push((i, vote_cell)),
+            Cell::Receipt(receipt_cell) => receipt_cells.push((i, receipt_cell)),
+        }
+    }
 
-const VOTE_TITLE_BYTESIZE: usize = 32;
-const TOTAL_DISTRIBUTED_TOKENS_BYTESIZE: usize = 16;
-const IS_VOTING_FINISHED_BYTESIZE: usize = 1;
-const VOTE_RESULT_OPTION_TYPE: usize = 1;
-const DATA_LEN: usize = CODE_HASH_BYTESIZE
-    + VOTE_TITLE_BYTESIZE
-    + TOTAL_DISTRIBUTED_TOKENS_BYTESIZE
-    + IS_VOTING_FINISHED_BYTESIZE
-    + VOTE_RESULT_OPTION_TYPE; // Number of bytes required for args. (82 bytes)
+    Ok((
+        core_cells.len() + vote_cells.len() + receipt_cells.len(),
+        core_cells,
+        vote_cells,
+        receipt_cells,
+    ))
+}
 
 // Determines the mode of operation for the currently executing script.
 fn determine_mode() -> Result<Mode, Error> {
-    // Gather counts on the number of group input and groupt output cells.
-    let group_input_count = QueryIter::new(load_cell, Source::GroupInput).count();
-    let group_output_count = QueryIter::new(load_cell, Source::GroupOutput).count();
+    // Gather counts on the number of Core Cells, Vote Cells and Receipt Cells in group inputs and group outputs.
+    let (group_input_count, input_core_cells, input_vote_cells, input_receipt_cells) =
+        load_cells(Source::GroupInput)?;
+    let (group_output_count, output_core_cells, output_vote_cells, _) =
+        load_cells(Source::GroupOutput)?;
 
-    // Detect the operation based on the cell count.
-    if group_input_count == 1 && group_output_count == 0 {
+    // Detect the operation based on the cell count. Vote Cells can only be burned along with their Core Cell.
+    if input_core_cells.len() > 0 && group_output_count == 0 {
         return Ok(Mode::Burn);
     }
-    if group_input_count == 0 && group_output_count == 1 {
+    if group_input_count == 0 && output_core_cells.len() == 1 {
         return Ok(Mode::Create);
     }
-    if group_input_count == 1 && group_output_count == 1 {
-        return Ok(Mode::Transfer);
+    if input_core_cells.len() == 1
+        && input_vote_cells.len() > 0
+        && output_core_cells.len() == 1
+        && group_output_count == 1
+    {
+        return Ok(Mode::Finish);
+    }
+    // Cancelling, extending and executing the vote only update the Core Cell. They are told apart by the output status.
+    if input_core_cells.len() == 1
+        && group_input_count == 1
+        && output_core_cells.len() == 1
+        && group_output_count == 1
+    {
+        match output_core_cells[0].1.is_voting_finished {
+            VOTING_STATUS_CANCELLED => return Ok(Mode::Cancel),
+            VOTING_STATUS_EXECUTED => return Ok(Mode::Execute),
+            _ => {}
+        }
+
+        return Ok(Mode::Extend);
+    }
+    if input_core_cells.len() == 0
+        && output_core_cells.len() == 0
+        && input_vote_cells.len() > 0
+        && group_input_count == input_vote_cells.len()
+        && group_input_count == output_vote_cells.len()
+    {
+        return Ok(Mode::Vote);
+    }
+    if input_receipt_cells.len() > 0
+        && group_input_count == input_receipt_cells.len()
+        && group_output_count == 0
+    {
+        return Ok(Mode::Refund);
     }
 
     // If no known code structure was used, return an error.
@@ -86,23 +160,342 @@ fn calculate_instance_id(
     hash
 }
 
+// Calculate the lock hash of the vote escrow lock holding the tokens of the vote with the specified type hash.
+fn calculate_escrow_lock_hash(
+    core_type_hash: &[u8; BLAKE2B256_HASH_BYTESIZE],
+) -> [u8; BLAKE2B256_HASH_BYTESIZE] {
+    let escrow_lock_script = Script::new_builder()
+        .code_hash(VOTE_ESCROW_CODE_HASH.pack())
+        .hash_type(Byte::new(HASH_TYPE_DATA))
+        .args(core_type_hash[..].pack())
+        .build();
+
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_BYTESIZE)
+        .personal(b"ckb-default-hash")
+        .build();
+    blake2b.update(escrow_lock_script.as_slice());
+
+    let mut hash: [u8; BLAKE2B256_HASH_BYTESIZE] = [0; BLAKE2B256_HASH_BYTESIZE];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+// Check whether a Treasury Cell of this vote is spent in the transaction. Its lock only releases funds when the
+// finished Core Cell is spent along with it, so the treasury lock itself checks the vote result and the delay.
+fn is_treasury_released() -> Result<bool, Error> {
+    let core_type_hash = load_script_hash()?;
+
+    Ok(QueryIter::new(load_cell_lock, Source::Input).any(|lock| {
+        let args: Bytes = lock.args().unpack();
+
+        lock.code_hash().as_slice() == TREASURY_CODE_HASH
+            && lock.hash_type().as_slice()[0] == HASH_TYPE_DATA
+            && args.len() >= BLAKE2B256_HASH_BYTESIZE
+            && args[..BLAKE2B256_HASH_BYTESIZE] == core_type_hash
+    }))
+}
+
+// Check that the token family counted in CODE_HASH mode can't be minted by voters. Anyone can issue an instance of
+// the standard sUDT with their own lock as the owner, which would count as voting weight. Code referenced by type
+// is found in the cell deps, so its data hash can be compared too.
+fn validate_token_family(core_cell: &CoreCell) -> Result<(), Error> {
+    if core_cell.token_id_mode != TokenIdMode::CodeHash {
+        return Ok(());
+    }
+
+    let code_data_hash = if core_cell.token_hash_type == HASH_TYPE_DATA {
+        core_cell.token_code_hash
+    } else {
+        let index = QueryIter::new(load_cell_type_hash, Source::CellDep)
+            .position(|cell_type_hash| cell_type_hash == Some(core_cell.token_code_hash))
+            .ok_or(Error::MintableTokenFamily)?;
+        load_cell_data_hash(index, Source::CellDep)?
+    };
+
+    if code_data_hash == SUDT_CODE_HASH {
+        return Err(Error::MintableTokenFamily);
+    }
+
+    Ok(())
+}
+
+// Determine if a token cell holds sUDT issued in non-transferable mode. Such tokens can only be burned or returned
+// to the same lock, so they can't be moved to the vote escrow lock. Code referenced by type is found in the cell
+// deps, like in validate_token_family.
+fn is_non_transferable_sudt(index: usize, source: Source) -> Result<bool, Error> {
+    let script = match load_cell_type(index, source)? {
+        Some(script) => script,
+        None => return Ok(false),
+    };
+
+    let code_data_hash = if script.hash_type().as_slice()[0] == HASH_TYPE_DATA {
+        script.code_hash().unpack()
+    } else {
+        match QueryIter::new(load_cell_type_hash, Source::CellDep)
+            .position(|cell_type_hash| cell_type_hash == Some(script.code_hash().unpack()))
+        {
+            Some(index) => load_cell_data_hash(index, Source::CellDep)?,
+            None => return Ok(false),
+        }
+    };
+
+    let args: Bytes = script.args().unpack();
+
+    Ok(code_data_hash == SUDT_CODE_HASH
+        && args.len() == SUDT_ARGS_BYTESIZE
+        && args[BLAKE2B256_HASH_BYTESIZE] & SUDT_FLAG_NON_TRANSFERABLE != 0)
+}
+
+// Decode the amount of tokens stored in a sUDT cell.
+fn parse_token_amount(data: &[u8]) -> Result<u128, Error> {
+    if data.len() < U128_BYTESIZE {
+        return Err(Error::InvalidTokenCellData);
+    }
+
+    let mut buffer = [0u8; U128_BYTESIZE];
+    buffer.copy_from_slice(&data[0..U128_BYTESIZE]);
+
+    Ok(u128::from_le_bytes(buffer))
+}
+
+// Find the indexes of all cells holding the voting token in the specified source.
+fn find_token_cells(core_cell: &CoreCell, source: Source) -> Vec<usize> {
+    match core_cell.token_id_mode {
+        TokenIdMode::TypeHash => QueryIter::new(load_cell_type_hash, source)
+            .enumerate()
+            .filter(|(_, cell_type_hash)| *cell_type_hash == Some(core_cell.token_code_hash))
+            .map(|(i, _)| i)
+            .collect(),
+        TokenIdMode::CodeHash => QueryIter::new(load_cell_type, source)
+            .enumerate()
+            .filter(|(_, cell_type)| match cell_type {
+                Some(script) => {
+                    script.code_hash().as_slice() == core_cell.token_code_hash
+                        && script.hash_type().as_slice()[0] == core_cell.token_hash_type
+                }
+                None => false,
+            })
+            .map(|(i, _)| i)
+            .collect(),
+    }
+}
+
+// Count the number of voting tokens in the specified source. Source should be either Input or Output.
+fn determine_token_amount(core_cell: &CoreCell, source: Source) -> Result<u128, Error> {
+    let mut token_amount: u128 = 0;
+
+    for i in find_token_cells(core_cell, source) {
+        let data = load_cell_data(i, source)?;
+        token_amount = token_amount
+            .checked_add(parse_token_amount(&data)?)
+            .ok_or(Error::AmountOverflow)?;
+    }
+
+    Ok(token_amount)
+}
+
+// Count the number of voting tokens in the specified source held by cells with the specified lock.
+fn determine_locked_token_amount(
+    core_cell: &CoreCell,
+    source: Source,
+    lock_hash: &[u8; BLAKE2B256_HASH_BYTESIZE],
+) -> Result<u128, Error> {
+    let mut token_amount: u128 = 0;
+
+    for i in find_token_cells(core_cell, source) {
+        if load_cell_lock_hash(i, source)? != *lock_hash {
+            continue;
+        }
+
+        let data = load_cell_data(i, source)?;
+        token_amount = token_amount
+            .checked_add(parse_token_amount(&data)?)
+            .ok_or(Error::AmountOverflow)?;
+    }
+
+    Ok(token_amount)
+}
+
+// Add up the votes for every option from the vote intents of a batched vote. Every intent has to spend a whole
+// token cell of the voting token, whose lock checks the signature of the voter.
+fn determine_intent_votes(
+    core_cell: &CoreCell,
+    vote_intents: &[VoteIntent],
+) -> Result<[u128; VOTE_OPTIONS.len()], Error> {
+    // With snapshot voting weight the tokens are not spent, so they can't back an intent.
+    if core_cell.voting_weight_mode != VotingWeightMode::Distributed {
+        return Err(Error::InvalidVoteIntent);
+    }
+
+    let token_cells = find_token_cells(core_cell, Source::Input);
+    let mut intent_votes = [0u128; VOTE_OPTIONS.len()];
+
+    for vote_intent in vote_intents {
+        let index = QueryIter::new(load_input, Source::Input)
+            .position(|input| input.previous_output().as_slice() == &vote_intent.out_point[..])
+            .ok_or(Error::InvalidVoteIntent)?;
+
+        if !token_cells.contains(&index) {
+            return Err(Error::InvalidVoteIntent);
+        }
+
+        let data = load_cell_data(index, Source::Input)?;
+        if parse_token_amount(&data)? != vote_intent.amount {
+            return Err(Error::InvalidVoteIntent);
+        }
+
+        let option_index = VOTE_OPTIONS
+            .iter()
+            .position(|option| *option == vote_intent.vote_option_type)
+            .ok_or(Error::InvalidVoteIntent)?;
+        intent_votes[option_index] = intent_votes[option_index]
+            .checked_add(vote_intent.amount)
+            .ok_or(Error::AmountOverflow)?;
+    }
+
+    Ok(intent_votes)
+}
+
+// Check that every receipt is issued to the lock of the token cells it accounts for. The receipts of every owner
+// have to add up to the tokens its lock spent, less any tokens returned to it.
+fn validate_receipt_owners(
+    core_cell: &CoreCell,
+    receipt_cells: &ReceiptCellList,
+) -> Result<(), Error> {
+    let mut owners: Vec<[u8; BLAKE2B256_HASH_BYTESIZE]> = Vec::new();
+
+    for (_, receipt_cell) in receipt_cells.iter() {
+        if !owners.contains(&receipt_cell.owner_lock_hash) {
+            owners.push(receipt_cell.owner_lock_hash);
+        }
+    }
+
+    for owner_lock_hash in owners.iter() {
+        let mut receipt_amount: u128 = 0;
+
+        for (_, receipt_cell) in receipt_cells.iter() {
+            if receipt_cell.owner_lock_hash == *owner_lock_hash {
+                receipt_amount = receipt_amount
+                    .checked_add(receipt_cell.amount)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+        }
+
+        let input_token_amount =
+            determine_locked_token_amount(core_cell, Source::Input, owner_lock_hash)?;
+        let output_token_amount =
+            determine_locked_token_amount(core_cell, Source::Output, owner_lock_hash)?;
+
+        if output_token_amount > input_token_amount
+            || input_token_amount - output_token_amount != receipt_amount
+        {
+            return Err(Error::ReceiptMismatch);
+        }
+    }
+
+    Ok(())
+}
+
+// Check that every receipt of a batched vote is issued to an owner signed in the intents. The receipts of every
+// owner have to add up to the amounts of its intents, so the aggregator can't redirect them.
+fn validate_intent_receipt_owners(
+    vote_intents: &[VoteIntent],
+    receipt_cells: &ReceiptCellList,
+) -> Result<(), Error> {
+    for (_, receipt_cell) in receipt_cells.iter() {
+        let mut receipt_amount: u128 = 0;
+        let mut intent_amount: u128 = 0;
+
+        for (_, other_receipt_cell) in receipt_cells.iter() {
+            if other_receipt_cell.owner_lock_hash == receipt_cell.owner_lock_hash {
+                receipt_amount = receipt_amount
+                    .checked_add(other_receipt_cell.amount)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+        }
+
+        for vote_intent in vote_intents {
+            if vote_intent.owner_lock_hash == receipt_cell.owner_lock_hash {
+                intent_amount = intent_amount
+                    .checked_add(vote_intent.amount)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+        }
+
+        if receipt_amount != intent_amount {
+            return Err(Error::ReceiptMismatch);
+        }
+    }
+
+    Ok(())
+}
+
+// Find the Core Cell the currently executing Vote Cells belong to. It has to be passed as a cell dep.
+fn load_core_cell_dep() -> Result<CoreCell, Error> {
+    let script_hash = load_script_hash()?;
+
+    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, Source::CellDep).enumerate() {
+        if cell_type_hash != Some(script_hash) {
+            continue;
+        }
+
+        if let Cell::Core(core_cell) = Cell::load(i, Source::CellDep)? {
+            return Ok(core_cell);
+        }
+    }
+
+    Err(Error::CoreCellMissing)
+}
+
+// Find the index of the Core Cell of the currently executing script among all outputs of the transaction.
+fn find_core_cell_output_index() -> Result<usize, Error> {
+    let script_hash = load_script_hash()?;
+
+    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, Source::Output).enumerate() {
+        if cell_type_hash == Some(script_hash)
+            && load_cell_data(i, Source::Output)?.len() == CORE_DATA_LEN
+        {
+            return Ok(i);
+        }
+    }
+
+    Err(Error::CoreCellMissing)
+}
+
+// Check that the Vote Cells hold exactly one cell for every shard of every option, so none can be left out.
+fn validate_vote_shards(vote_cells: &VoteCellList, vote_shard_count: u8) -> Result<(), Error> {
+    if vote_cells.len() != VOTE_OPTIONS.len() * vote_shard_count as usize {
+        return Err(Error::InvalidVoteCellCount);
+    }
+
+    for option in VOTE_OPTIONS.iter() {
+        for shard_index in 0..vote_shard_count {
+            if !vote_cells.iter().any(|(_, vote_cell)| {
+                vote_cell.vote_option_type == *option && vote_cell.shard_index == shard_index
+            }) {
+                return Err(Error::InvalidVoteCellCount);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 // Validate a transaction to create a cell.
 fn validate_create() -> Result<(), Error> {
-    // Load the output cell data and verify that the value is 0u64.
-    let cell_data = load_cell_data(0, Source::GroupOutput)?;
-    let cell_type = load_cell_type(0, Source::GroupOutput)?.unwrap();
-    let cell_type_args: Bytes = cell_type.args().unpack();
-    let cell_lock = load_cell_lock(0, Source::GroupOutput)?;
-    let cell_lock_args: Bytes = cell_lock.args().unpack();
+    let (_, core_cells, vote_cells, receipt_cells) = load_cells(Source::GroupOutput)?;
+    let (core_cell_index, core_cell) = &core_cells[0];
 
-    if cell_lock_args.len() != 0 {
-        return Err(Error::LockArgsOtherThanZero);
-    }
+    let cell_type = load_cell_type(*core_cell_index, Source::GroupOutput)?.unwrap();
+    let cell_type_args: Bytes = cell_type.args().unpack();
 
-    // Determine the Seed Cell Outpoint.
+    // Determine the Seed Cell Outpoint. Several votes can be created from the same Seed Cell, since the
+    // instance id also commits to the output index of the Core Cell.
     let seed_cell_outpoint = load_input(0, Source::Input)?.previous_output();
+    let output_index = find_core_cell_output_index()?;
 
-    let instance_id = calculate_instance_id(&seed_cell_outpoint, 0);
+    let instance_id = calculate_instance_id(&seed_cell_outpoint, output_index);
     // debug!("Output Instance ID: {:?}", output_nft_data.instance_id);
     // debug!("Calculated Instance ID: {:?}", instance_id);
 
@@ -110,53 +503,532 @@ fn validate_create() -> Result<(), Error> {
         return Err(Error::InvalidInstanceId);
     }
 
-    if cell_data.len() != DATA_LEN {
-        debug!("LEN IS: {:?}", cell_data.len());
-        return Err(Error::InvalidDataBytesize);
+    // A vote can't be created with a result already in place.
+    if core_cell.is_voting_finished != VOTING_STATUS_OPEN
+        || core_cell.vote_result_option_type != 0
+        || core_cell.settlement_block_number != 0
+        || core_cell.total_extension != 0
+    {
+        return Err(Error::InvalidOutputCellData);
     }
 
-    // Token Code Hash
-    let mut buffer = [0u8; 32];
-    buffer.copy_from_slice(&cell_data[0..32]);
-    let token_code_hash = Bytes::from(buffer.to_vec());
+    // Receipts are only issued for votes cast.
+    if receipt_cells.len() > 0 {
+        return Err(Error::ReceiptMismatch);
+    }
 
-    // Vote title
-    // let mut buffer = [0u8; 32];
-    // buffer.copy_from_slice(&cell_data[32..64]);
+    // Escrowed tokens can only be held by the vote escrow lock of this vote, which refunds them if it's cancelled.
+    if core_cell.has_escrow()
+        && core_cell.escrow_lock_hash != calculate_escrow_lock_hash(&load_script_hash()?)
+    {
+        return Err(Error::InvalidEscrowLock);
+    }
 
-    // Total distributed tokens
-    let mut buffer = [0u8; 16];
-    buffer.copy_from_slice(&cell_data[64..80]);
-    let expected_total_tokens_distributed = u128::from_le_bytes(buffer);
+    // Votes would have to move the tokens to the escrow lock, which non-transferable sUDT rejects.
+    if core_cell.has_escrow() {
+        for i in find_token_cells(core_cell, Source::Output) {
+            if is_non_transferable_sudt(i, Source::Output)? {
+                return Err(Error::NonTransferableEscrow);
+            }
+        }
+    }
 
-    let mut tokens_distributed: u128 = 0;
+    validate_token_family(core_cell)?;
 
-    // Load each cell from the outputs.
-    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, Source::Output).enumerate() {
-        if cell_type_hash.is_none() {
-            continue;
+    if core_cell.vote_shard_count == 0 {
+        return Err(Error::InvalidShardCount);
+    }
+
+    // Exactly one empty Vote Cell has to be created for every shard of every option.
+    validate_vote_shards(&vote_cells, core_cell.vote_shard_count)?;
+
+    if vote_cells
+        .iter()
+        .any(|(_, vote_cell)| vote_cell.total_votes_collected != 0)
+    {
+        return Err(Error::InvalidVoteTally);
+    }
+
+    // With snapshot voting weight no tokens are distributed. TOTAL_DISTRIBUTED_TOKENS is then the total
+    // supply of the token at the snapshot block, which the organizer declares.
+    if core_cell.voting_weight_mode == VotingWeightMode::Snapshot {
+        return Ok(());
+    }
+
+    // Tokens held by an Airdrop Cell count as distributed too, since its data starts with the sUDT amount.
+    let tokens_distributed = determine_token_amount(core_cell, Source::Output)?;
+
+    debug!("Total tokens distributed: {:?}", tokens_distributed);
+
+    if core_cell.total_distributed_tokens != tokens_distributed {
+        return Err(Error::TokenDistributionMismatch);
+    }
+
+    Ok(())
+}
+
+// Determine the voting weight proven by a balance snapshot. Every token cell spent has to have been created
+// no later than the snapshot block, which is proven by including the header of its block in the header deps.
+// Since the cell is still live, it was live at the snapshot too. Spending it makes sure it's counted only once.
+fn determine_snapshot_weight(core_cell: &CoreCell) -> Result<u128, Error> {
+    // The snapshot block has to be part of the chain already, otherwise the cells
+    // returned to the voter could be used to vote again.
+    let snapshot_reached = QueryIter::new(load_header, Source::HeaderDep).any(|header| {
+        let block_number: u64 = header.raw().number().unpack();
+        block_number >= core_cell.snapshot_block_number
+    });
+
+    if !snapshot_reached {
+        return Err(Error::SnapshotNotReached);
+    }
+
+    let mut weight: u128 = 0;
+
+    for i in find_token_cells(core_cell, Source::Input) {
+        let header = load_header(i, Source::Input).map_err(|_| Error::CellNotLiveAtSnapshot)?;
+        let block_number: u64 = header.raw().number().unpack();
+
+        if block_number > core_cell.snapshot_block_number {
+            return Err(Error::CellNotLiveAtSnapshot);
         }
 
-        // Convert the scripts to bytes and check if they are the same.
-        let cell_type_hash = cell_type_hash.unwrap();
+        let data = load_cell_data(i, Source::Input)?;
+        weight = weight
+            .checked_add(parse_token_amount(&data)?)
+            .ok_or(Error::AmountOverflow)?;
+    }
 
-        let HASH_TYPE_DATA: Byte = 0.into();
+    // The tokens themselves are not spent on voting, all of them have to be returned.
+    if determine_token_amount(core_cell, Source::Output)? < weight {
+        return Err(Error::SnapshotTokensNotReturned);
+    }
 
-        if cell_type_hash == *token_code_hash {
-            let data = load_cell_data(i, Source::Output)?;
+    Ok(weight)
+}
 
-            let mut buffer = [0u8; 16];
-            buffer.copy_from_slice(&data[0..16]);
-            let token_amount = u128::from_le_bytes(buffer);
-            
-            tokens_distributed += token_amount;
+// Validate a transaction casting votes into the Vote Cells.
+fn validate_vote() -> Result<(), Error> {
+    let core_cell = load_core_cell_dep()?;
+
+    if core_cell.is_voting_finished != VOTING_STATUS_OPEN {
+        return Err(Error::VotingFinished);
+    }
+
+    // The block the transaction is committed in isn't known to the script, so votes are bounded by the block
+    // every Vote Cell was created in. Since Vote Cells are recreated on every vote, each shard accepts at most
+    // one more vote after the voting end.
+    if core_cell.voting_end_block_number != 0 {
+        for i in 0..QueryIter::new(load_cell_data, Source::GroupInput).count() {
+            let header = load_header(i, Source::GroupInput).map_err(|_| Error::VotingEnded)?;
+            let block_number: u64 = header.raw().number().unpack();
+
+            if block_number > core_cell.voting_end_block_number {
+                return Err(Error::VotingEnded);
+            }
         }
     }
 
-    debug!("Total tokens distributed: {:?}", tokens_distributed);
+    let (_, _, input_vote_cells, _) = load_cells(Source::GroupInput)?;
+    let (_, _, output_vote_cells, output_receipt_cells) = load_cells(Source::GroupOutput)?;
 
-    if expected_total_tokens_distributed != tokens_distributed {
-        return Err(Error::TokenDistributionMismatch);
+    // Tokens can only be reclaimed if they were held in escrow instead of being burned.
+    let uses_escrow =
+        core_cell.has_escrow() && core_cell.voting_weight_mode == VotingWeightMode::Distributed;
+
+    if !uses_escrow && output_receipt_cells.len() > 0 {
+        return Err(Error::ReceiptMismatch);
+    }
+
+    // Every Vote Cell is updated in place, which can only increase its tally.
+    let mut votes_cast: u128 = 0;
+    let mut option_votes_cast = [0u128; VOTE_OPTIONS.len()];
+
+    for ((_, input_vote_cell), (_, output_vote_cell)) in
+        input_vote_cells.iter().zip(output_vote_cells.iter())
+    {
+        if input_vote_cell.vote_option_type != output_vote_cell.vote_option_type {
+            return Err(Error::InvalidVoteOption);
+        }
+
+        if input_vote_cell.shard_index != output_vote_cell.shard_index {
+            return Err(Error::InvalidVoteShard);
+        }
+
+        if output_vote_cell.total_votes_collected < input_vote_cell.total_votes_collected {
+            return Err(Error::InvalidVoteTally);
+        }
+
+        let shard_votes_cast =
+            output_vote_cell.total_votes_collected - input_vote_cell.total_votes_collected;
+        votes_cast = votes_cast
+            .checked_add(shard_votes_cast)
+            .ok_or(Error::AmountOverflow)?;
+
+        let option_index = VOTE_OPTIONS
+            .iter()
+            .position(|option| *option == output_vote_cell.vote_option_type)
+            .ok_or(Error::InvalidVoteOption)?;
+        option_votes_cast[option_index] = option_votes_cast[option_index]
+            .checked_add(shard_votes_cast)
+            .ok_or(Error::AmountOverflow)?;
+    }
+
+    // The receipts issued for an option have to add up to the votes it received across all shards.
+    if uses_escrow {
+        for (option, option_votes_cast) in VOTE_OPTIONS.iter().zip(option_votes_cast.iter()) {
+            let mut receipt_amount: u128 = 0;
+
+            for (_, receipt_cell) in output_receipt_cells.iter() {
+                if receipt_cell.vote_option_type == *option {
+                    receipt_amount = receipt_amount
+                        .checked_add(receipt_cell.amount)
+                        .ok_or(Error::AmountOverflow)?;
+                }
+            }
+
+            if receipt_amount != *option_votes_cast {
+                return Err(Error::ReceiptMismatch);
+            }
+        }
+    }
+
+    // In a batched vote the votes every option received have to match the intents for it.
+    let vote_intents = load_vote_intents()?;
+
+    if let Some(vote_intents) = &vote_intents {
+        if determine_intent_votes(&core_cell, vote_intents)? != option_votes_cast {
+            return Err(Error::VoteIntentMismatch);
+        }
+    }
+
+    // Receipts are issued to the owners the voters signed in their intents, or otherwise to the locks of the
+    // voters' token cells.
+    if uses_escrow {
+        match &vote_intents {
+            Some(vote_intents) => {
+                validate_intent_receipt_owners(vote_intents, &output_receipt_cells)?
+            }
+            None => validate_receipt_owners(&core_cell, &output_receipt_cells)?,
+        }
+    }
+
+    let weight = match core_cell.voting_weight_mode {
+        VotingWeightMode::Distributed if uses_escrow => {
+            // Voting tokens are moved to the escrow lock when voting.
+            let input_escrow_amount = determine_locked_token_amount(
+                &core_cell,
+                Source::Input,
+                &core_cell.escrow_lock_hash,
+            )?;
+            let output_escrow_amount = determine_locked_token_amount(
+                &core_cell,
+                Source::Output,
+                &core_cell.escrow_lock_hash,
+            )?;
+
+            if output_escrow_amount < input_escrow_amount {
+                return Err(Error::VoteTallyMismatch);
+            }
+
+            output_escrow_amount - input_escrow_amount
+        }
+        VotingWeightMode::Distributed => {
+            // Voting tokens are burned when voting.
+            let input_token_amount = determine_token_amount(&core_cell, Source::Input)?;
+            let output_token_amount = determine_token_amount(&core_cell, Source::Output)?;
+
+            if output_token_amount > input_token_amount {
+                return Err(Error::VoteTallyMismatch);
+            }
+
+            input_token_amount - output_token_amount
+        }
+        VotingWeightMode::Snapshot => determine_snapshot_weight(&core_cell)?,
+    };
+
+    debug!("Votes cast: {:?}, voting weight: {:?}", votes_cast, weight);
+
+    if votes_cast != weight {
+        return Err(Error::VoteTallyMismatch);
+    }
+
+    Ok(())
+}
+
+// Determine the settlement block of a vote being finished. The block the transaction is committed in is not
+// known to the script, so the latest block any of the Core Cell and Vote Cells were created in is used instead.
+// Vote Cells are recreated on every vote, so this is no earlier than the last vote cast. It is only a lower
+// bound of the block the vote finished in, so the treasury measures the execution delay from the block of
+// the finished Core Cell instead.
+fn determine_settlement_block_number() -> Result<u64, Error> {
+    let mut settlement_block_number: u64 = 0;
+
+    // Every cell's header has to be included, so the settlement block can't be moved back.
+    for i in 0..QueryIter::new(load_cell_data, Source::GroupInput).count() {
+        let header =
+            load_header(i, Source::GroupInput).map_err(|_| Error::SettlementHeaderMissing)?;
+        let block_number: u64 = header.raw().number().unpack();
+        settlement_block_number = settlement_block_number.max(block_number);
+    }
+
+    Ok(settlement_block_number)
+}
+
+// Validate a transaction finishing the vote. All Vote Cells are collected and burned, and the result
+// is written to the Core Cell along with the settlement block. The winning option has to hold the
+// majority of all distributed tokens, otherwise the organizer settles the vote with a casting vote.
+fn validate_finish() -> Result<(), Error> {
+    let (_, input_core_cells, input_vote_cells, _) = load_cells(Source::GroupInput)?;
+    let (_, output_core_cells, _, _) = load_cells(Source::GroupOutput)?;
+    let (input_core_cell_index, core_cell) = &input_core_cells[0];
+    let (output_core_cell_index, output_core_cell) = &output_core_cells[0];
+
+    if core_cell.is_voting_finished != VOTING_STATUS_OPEN {
+        return Err(Error::VotingFinished);
+    }
+
+    // Every shard of every option has to be counted, so all Vote Cells are required.
+    validate_vote_shards(&input_vote_cells, core_cell.vote_shard_count)?;
+
+    let mut option_tallies = [0u128; VOTE_OPTIONS.len()];
+
+    for (option, option_tally) in VOTE_OPTIONS.iter().zip(option_tallies.iter_mut()) {
+        for (_, vote_cell) in input_vote_cells.iter() {
+            if vote_cell.vote_option_type == *option {
+                *option_tally = option_tally
+                    .checked_add(vote_cell.total_votes_collected)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+        }
+    }
+
+    let majority_threshold = core_cell
+        .total_distributed_tokens
+        .checked_mul(MAJORITY_PERCENTAGE)
+        .ok_or(Error::AmountOverflow)?;
+    let winning_vote_option_type = match VOTE_OPTIONS
+        .iter()
+        .zip(option_tallies.iter())
+        .find(|(_, option_tally)| option_tally.saturating_mul(100) >= majority_threshold)
+    {
+        Some((winning_vote_option_type, _)) => *winning_vote_option_type,
+        // The casting vote is the result written to the output Core Cell.
+        None if is_organizer_authorized(core_cell, *input_core_cell_index)? => {
+            if !VOTE_OPTIONS.contains(&output_core_cell.vote_result_option_type) {
+                return Err(Error::InvalidOutputCellData);
+            }
+
+            output_core_cell.vote_result_option_type
+        }
+        None => return Err(Error::NoMajority),
+    };
+
+    debug!("Winning vote option: {:?}", winning_vote_option_type);
+
+    let settlement_block_number = determine_settlement_block_number()?;
+    debug!("Settlement block number: {:?}", settlement_block_number);
+
+    // The voting end has to be part of the chain already, which is proven by including a header dep at or after it.
+    if core_cell.voting_end_block_number != 0
+        && !QueryIter::new(load_header, Source::HeaderDep).any(|header| {
+            let block_number: u64 = header.raw().number().unpack();
+            block_number >= core_cell.voting_end_block_number
+        })
+    {
+        return Err(Error::VotingNotEnded);
+    }
+
+    let input_data = load_cell_data(*input_core_cell_index, Source::GroupInput)?;
+    let output_data = load_cell_data(*output_core_cell_index, Source::GroupOutput)?;
+
+    if output_data
+        != finished_core_data(
+            &input_data,
+            winning_vote_option_type,
+            settlement_block_number,
+        )
+    {
+        return Err(Error::InvalidOutputCellData);
+    }
+
+    Ok(())
+}
+
+// Validate a transaction cancelling the vote. Only the organizer can cancel a vote which is still open.
+// The Core Cell is marked as cancelled, which blocks further votes, and all other fields stay the same.
+fn validate_cancel() -> Result<(), Error> {
+    let (_, input_core_cells, _, _) = load_cells(Source::GroupInput)?;
+    let (input_core_cell_index, core_cell) = &input_core_cells[0];
+
+    if core_cell.is_voting_finished != VOTING_STATUS_OPEN {
+        return Err(Error::VotingFinished);
+    }
+
+    if !is_organizer_authorized(core_cell, *input_core_cell_index)? {
+        return Err(Error::Unauthorized);
+    }
+
+    let input_data = load_cell_data(*input_core_cell_index, Source::GroupInput)?;
+    let output_data = load_cell_data(0, Source::GroupOutput)?;
+
+    if output_data != cancelled_core_data(&input_data) {
+        return Err(Error::InvalidOutputCellData);
+    }
+
+    Ok(())
+}
+
+// Validate a transaction extending the voting end. Only the organizer can extend a vote which is still open.
+// The voting end can only be moved forward, by no more than the maximum extension in total.
+fn validate_extend() -> Result<(), Error> {
+    let (_, input_core_cells, _, _) = load_cells(Source::GroupInput)?;
+    let (_, output_core_cells, _, _) = load_cells(Source::GroupOutput)?;
+    let (input_core_cell_index, core_cell) = &input_core_cells[0];
+    let (output_core_cell_index, output_core_cell) = &output_core_cells[0];
+
+    if core_cell.is_voting_finished != VOTING_STATUS_OPEN {
+        return Err(Error::VotingFinished);
+    }
+
+    if !is_organizer_authorized(core_cell, *input_core_cell_index)? {
+        return Err(Error::Unauthorized);
+    }
+
+    if output_core_cell.voting_end_block_number <= core_cell.voting_end_block_number {
+        return Err(Error::InvalidExtension);
+    }
+
+    let total_extension = core_cell
+        .total_extension
+        .checked_add(output_core_cell.voting_end_block_number - core_cell.voting_end_block_number)
+        .ok_or(Error::ExtensionLimitExceeded)?;
+
+    debug!(
+        "Total extension: {:?} of {:?}",
+        total_extension, core_cell.max_extension
+    );
+
+    if total_extension > core_cell.max_extension {
+        return Err(Error::ExtensionLimitExceeded);
+    }
+
+    let input_data = load_cell_data(*input_core_cell_index, Source::GroupInput)?;
+    let output_data = load_cell_data(*output_core_cell_index, Source::GroupOutput)?;
+
+    if output_data
+        != extended_core_data(
+            &input_data,
+            output_core_cell.voting_end_block_number,
+            total_extension,
+        )
+    {
+        return Err(Error::InvalidOutputCellData);
+    }
+
+    Ok(())
+}
+
+// Validate a transaction marking the vote as executed. The treasury only releases funds when the finished
+// Core Cell is spent, so marking it executed lets the funds of the vote be released only once. The Core Cell
+// is protected by its lock, and all other fields stay the same. A Treasury Cell of the vote has to be spent along
+// with it, otherwise the vote could be marked executed without releasing anything.
+fn validate_execute() -> Result<(), Error> {
+    let (_, input_core_cells, _, _) = load_cells(Source::GroupInput)?;
+    let (input_core_cell_index, core_cell) = &input_core_cells[0];
+
+    if core_cell.is_voting_finished != VOTING_STATUS_FINISHED {
+        return Err(Error::VoteNotFinished);
+    }
+
+    let input_data = load_cell_data(*input_core_cell_index, Source::GroupInput)?;
+    let output_data = load_cell_data(0, Source::GroupOutput)?;
+
+    if output_data != executed_core_data(&input_data) {
+        return Err(Error::InvalidOutputCellData);
+    }
+
+    if !is_treasury_released()? {
+        return Err(Error::TreasuryNotReleased);
+    }
+
+    Ok(())
+}
+
+// Validate a transaction burning the vote. Only the organizer can burn the Core Cell, and not while escrowed
+// tokens can still be refunded, since refunds need the Core Cell of the cancelled vote as a cell dep. A passed
+// vote which isn't executed yet can only be burned while releasing its treasury, which can't happen afterwards.
+fn validate_burn() -> Result<(), Error> {
+    let (_, input_core_cells, _, _) = load_cells(Source::GroupInput)?;
+
+    for (input_core_cell_index, core_cell) in input_core_cells.iter() {
+        if !is_organizer_authorized(core_cell, *input_core_cell_index)? {
+            return Err(Error::Unauthorized);
+        }
+
+        if core_cell.is_voting_finished == VOTING_STATUS_FINISHED
+            && core_cell.vote_result_option_type == VOTE_OPTION_YES
+            && !is_treasury_released()?
+        {
+            return Err(Error::TreasuryNotReleased);
+        }
+
+        // An open vote can still be cancelled, so its escrowed tokens count as outstanding as well.
+        let uses_escrow =
+            core_cell.has_escrow() && core_cell.voting_weight_mode == VotingWeightMode::Distributed;
+        if uses_escrow
+            && (core_cell.is_voting_finished == VOTING_STATUS_OPEN
+                || core_cell.is_voting_finished == VOTING_STATUS_CANCELLED)
+        {
+            return Err(Error::EscrowOutstanding);
+        }
+    }
+
+    Ok(())
+}
+
+// Validate a transaction refunding escrowed tokens of a cancelled vote. Receipt Cells are burned, and
+// the owner of every receipt has to receive at least the amount of voting tokens it records.
+fn validate_refund() -> Result<(), Error> {
+    let core_cell = load_core_cell_dep()?;
+
+    if core_cell.is_voting_finished != VOTING_STATUS_CANCELLED {
+        return Err(Error::VoteNotCancelled);
+    }
+
+    let (_, _, _, receipt_cells) = load_cells(Source::GroupInput)?;
+    let mut owners: Vec<[u8; BLAKE2B256_HASH_BYTESIZE]> = Vec::new();
+
+    for (_, receipt_cell) in receipt_cells.iter() {
+        if !owners.contains(&receipt_cell.owner_lock_hash) {
+            owners.push(receipt_cell.owner_lock_hash);
+        }
+    }
+
+    for owner_lock_hash in owners.iter() {
+        let mut refund_amount: u128 = 0;
+
+        for (_, receipt_cell) in receipt_cells.iter() {
+            if receipt_cell.owner_lock_hash == *owner_lock_hash {
+                refund_amount = refund_amount
+                    .checked_add(receipt_cell.amount)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+        }
+
+        let input_token_amount =
+            determine_locked_token_amount(&core_cell, Source::Input, owner_lock_hash)?;
+        let output_token_amount =
+            determine_locked_token_amount(&core_cell, Source::Output, owner_lock_hash)?;
+
+        debug!(
+            "Refund amount: {:?}, tokens received: {:?}",
+            refund_amount,
+            output_token_amount.saturating_sub(input_token_amount)
+        );
+
+        if output_token_amount.saturating_sub(input_token_amount) < refund_amount {
+            return Err(Error::RefundMismatch);
+        }
     }
 
     Ok(())
@@ -171,9 +1043,14 @@ pub fn main() -> Result<(), Error> {
     }
 
     match determine_mode() {
-        Ok(Mode::Burn) => return Ok(()),
+        Ok(Mode::Burn) => validate_burn()?,
         Ok(Mode::Create) => validate_create()?,
-        Ok(Mode::Transfer) => return Err(Error::InvalidTransactionStructure),
+        Ok(Mode::Vote) => validate_vote()?,
+        Ok(Mode::Finish) => validate_finish()?,
+        Ok(Mode::Cancel) => validate_cancel()?,
+        Ok(Mode::Extend) => validate_extend()?,
+        Ok(Mode::Execute) => validate_execute()?,
+        Ok(Mode::Refund) => validate_refund()?,
         Err(e) => return Err(e),
     }
 
@@ -1,37 +1,2 @@
-use ckb_std::error::SysError;
-
-/// Error
-#[repr(i8)]
-pub enum Error {
-    IndexOutOfBound = 1,
-    ItemMissing,
-    LengthNotEnough,
-    Encoding,
-    // Add customized errors here...
-    InvalidCounterValue,
-    InvalidTransactionStructure,
-    InvalidOutputCellData,
-    CounterValueOverflow,
-    Unauthorized,
-    InvalidDataBytesize,
-    WitnessLoadFail,
-    InvalidInputCellData,
-    InvalidArgsLength,
-    LockArgsOtherThanZero,
-    InvalidInstanceId,
-    TokenDistributionMismatch
-}
-
-impl From<SysError> for Error {
-    fn from(err: SysError) -> Self {
-        use SysError::*;
-        match err {
-            IndexOutOfBound => Self::IndexOutOfBound,
-            ItemMissing => Self::ItemMissing,
-            LengthNotEnough(_) => Self::LengthNotEnough,
-            Encoding => Self::Encoding,
-            Unknown(err_code) => panic!("unexpected sys error {}", err_code),
-        }
-    }
-}
-
+/// Error, defined in the shared registry of error codes.
+pub use ckb_voting_errors::DaoCoreError as Error;
@@ -11,8 +11,11 @@
 #![feature(panic_info_message)]
 
 // define modules
+mod batch;
+mod cells;
 mod entry;
 mod error;
+mod organizer;
 
 use ckb_std::{
     default_alloc,
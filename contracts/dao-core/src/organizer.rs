@@ -0,0 +1,144 @@
+// Import from `core` instead of from `std` since we are in no-std mode.
+use core::result::Result;
+
+use ckb_std::ckb_constants::Source;
+use ckb_std::ckb_types::{bytes::Bytes, prelude::*};
+use ckb_std::debug;
+use ckb_std::high_level::{load_cell_lock_hash, load_tx_hash, load_witness_args, QueryIter};
+
+use alloc::vec::Vec;
+use blake2b_ref::Blake2bBuilder;
+
+// Import local modules.
+use crate::cells::{CoreCell, OrganizerMode, BLAKE2B256_HASH_BYTESIZE};
+use crate::error::Error;
+
+// Constants
+const PUBKEY_HASH_BYTESIZE: usize = 20; // Number of bytes for a blake160 hash of a compressed public key.
+const SIGNATURE_BYTESIZE: usize = 65; // Recoverable secp256k1 signature, followed by the recovery id.
+const RECOVERY_ID_OFFSET: usize = 64;
+
+// Multisig config layout, passed in the witness along with the signatures.
+const THRESHOLD_OFFSET: usize = 0;
+const PUBKEY_COUNT_OFFSET: usize = THRESHOLD_OFFSET + 1;
+const PUBKEY_HASHES_OFFSET: usize = PUBKEY_COUNT_OFFSET + 1; // Followed by one pubkey hash for every key.
+
+fn hash(data: &[u8]) -> [u8; BLAKE2B256_HASH_BYTESIZE] {
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_BYTESIZE)
+        .personal(b"ckb-default-hash")
+        .build();
+    blake2b.update(data);
+
+    let mut hash: [u8; BLAKE2B256_HASH_BYTESIZE] = [0; BLAKE2B256_HASH_BYTESIZE];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+// Recover the pubkey hash of the key that signed the message.
+fn recover_pubkey_hash(
+    message: &[u8; BLAKE2B256_HASH_BYTESIZE],
+    signature: &[u8],
+) -> Result<[u8; PUBKEY_HASH_BYTESIZE], Error> {
+    let recovery_id = libsecp256k1::RecoveryId::parse(signature[RECOVERY_ID_OFFSET])
+        .map_err(|_| Error::InvalidOrganizerWitness)?;
+    let signature = libsecp256k1::Signature::parse_standard_slice(&signature[..RECOVERY_ID_OFFSET])
+        .map_err(|_| Error::InvalidOrganizerWitness)?;
+    let pubkey = libsecp256k1::recover(
+        &libsecp256k1::Message::parse(message),
+        &signature,
+        &recovery_id,
+    )
+    .map_err(|_| Error::InvalidOrganizerWitness)?;
+
+    let mut pubkey_hash = [0u8; PUBKEY_HASH_BYTESIZE];
+    pubkey_hash.copy_from_slice(&hash(&pubkey.serialize_compressed())[..PUBKEY_HASH_BYTESIZE]);
+
+    Ok(pubkey_hash)
+}
+
+// Verify the M-of-N signatures in the witness of the Core Cell. The witness holds the multisig config, whose
+// hash is stored in the Core Cell, followed by the signatures of the transaction hash.
+fn verify_multisig(core_cell: &CoreCell, witness: &[u8]) -> Result<(), Error> {
+    if witness.len() < PUBKEY_HASHES_OFFSET {
+        return Err(Error::InvalidOrganizerWitness);
+    }
+
+    let threshold = witness[THRESHOLD_OFFSET] as usize;
+    let pubkey_count = witness[PUBKEY_COUNT_OFFSET] as usize;
+    let signatures_offset = PUBKEY_HASHES_OFFSET + pubkey_count * PUBKEY_HASH_BYTESIZE;
+
+    if threshold == 0
+        || threshold > pubkey_count
+        || witness.len() < signatures_offset
+        || (witness.len() - signatures_offset) % SIGNATURE_BYTESIZE != 0
+    {
+        return Err(Error::InvalidOrganizerWitness);
+    }
+
+    if hash(&witness[..signatures_offset]) != core_cell.organizer_lock_hash {
+        return Err(Error::InvalidOrganizerWitness);
+    }
+
+    let pubkey_hashes: Vec<&[u8]> = witness[PUBKEY_HASHES_OFFSET..signatures_offset]
+        .chunks(PUBKEY_HASH_BYTESIZE)
+        .collect();
+    let message = load_tx_hash()?;
+    let mut signers: Vec<[u8; PUBKEY_HASH_BYTESIZE]> = Vec::new();
+
+    for signature in witness[signatures_offset..].chunks(SIGNATURE_BYTESIZE) {
+        let signer = recover_pubkey_hash(&message, signature)?;
+
+        if !pubkey_hashes.contains(&&signer[..]) {
+            return Err(Error::InvalidOrganizerWitness);
+        }
+
+        // Every key counts only once towards the threshold.
+        if signers.contains(&signer) {
+            return Err(Error::DuplicateOrganizerSigner);
+        }
+
+        signers.push(signer);
+    }
+
+    debug!(
+        "Organizer signatures: {:?} of {:?}",
+        signers.len(),
+        threshold
+    );
+
+    if signers.len() < threshold {
+        return Err(Error::OrganizerThresholdNotMet);
+    }
+
+    Ok(())
+}
+
+// Determine if the organizer of the vote authorized the transaction. With a single organizer lock the
+// transaction has to include an input with that lock. With a multisig organizer the signatures are passed
+// in the input_type field of the witness of the Core Cell, at the specified index in the group inputs.
+pub fn is_organizer_authorized(
+    core_cell: &CoreCell,
+    core_cell_index: usize,
+) -> Result<bool, Error> {
+    match core_cell.organizer_mode {
+        OrganizerMode::Lock => Ok(QueryIter::new(load_cell_lock_hash, Source::Input)
+            .any(|lock_hash| lock_hash == core_cell.organizer_lock_hash)),
+        OrganizerMode::Multisig => {
+            let witness = match load_witness_args(core_cell_index, Source::GroupInput) {
+                Ok(witness_args) => witness_args.input_type().to_opt(),
+                Err(_) => None,
+            };
+
+            match witness {
+                Some(witness) => {
+                    let witness: Bytes = witness.unpack();
+                    verify_multisig(core_cell, &witness)?;
+
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+    }
+}
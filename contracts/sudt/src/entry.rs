@@ -6,6 +6,7 @@ use core::result::Result;
 use ckb_std::ckb_types::{bytes::Bytes, prelude::*};
 use ckb_std::high_level::{load_cell_data, load_cell_lock_hash, load_script, QueryIter};
 use ckb_std::{ckb_constants::Source, debug};
+use alloc::vec::Vec;
 
 // Import our local error codes.
 use crate::error::Error;
@@ -13,24 +14,73 @@ use crate::error::Error;
 // Constants
 const LOCK_HASH_LEN: usize = 32; // Number of bytes for a lock hash. (Blake2b 256-bit 32 bytes)
 const SUDT_DATA_LEN: usize = 16; // SUDT uses a u128, which is 16 bytes.
+const FLAGS_LEN: usize = 1; // Optional flags byte following the owner lock hash in the args.
+const FLAG_NON_TRANSFERABLE: u8 = 0b0000_0001; // Tokens can only be burned or returned to the same lock.
 
 /// Determine if owner mode is enabled.
 fn check_owner_mode(args: &Bytes) -> Result<bool, Error> {
-    // Verify the the arguments length matches the length of a Blake2b hash.
-    if args.len() != LOCK_HASH_LEN {
+    // Verify the the arguments length matches the length of a Blake2b hash, optionally followed by the flags byte.
+    if args.len() != LOCK_HASH_LEN && args.len() != LOCK_HASH_LEN + FLAGS_LEN {
         return Err(Error::ArgsLength);
     }
 
     // Compare the Lock Script Hash from the script args with the Lock Scripts
     // of each input cell to determine if a match exists.
     let is_owner_mode = QueryIter::new(load_cell_lock_hash, Source::Input)
-        .find(|lock_hash| args[..] == lock_hash[..])
+        .find(|lock_hash| args[0..LOCK_HASH_LEN] == lock_hash[..])
         .is_some();
 
     // Return the owner mode status.
     Ok(is_owner_mode)
 }
 
+/// Determine if the token was issued in non-transferable (soulbound) mode.
+fn check_non_transferable_mode(args: &Bytes) -> bool {
+    args.len() == LOCK_HASH_LEN + FLAGS_LEN && args[LOCK_HASH_LEN] & FLAG_NON_TRANSFERABLE != 0
+}
+
+/// Sum the number of tokens held by each lock hash in the specified source. Source should be either GroupInput or GroupOutput.
+fn determine_token_amount_by_lock(source: Source) -> Result<Vec<([u8; LOCK_HASH_LEN], u128)>, Error> {
+    let mut amounts: Vec<([u8; LOCK_HASH_LEN], u128)> = Vec::new();
+
+    let lock_hashes = QueryIter::new(load_cell_lock_hash, source);
+    let cell_data = QueryIter::new(load_cell_data, source);
+    for (lock_hash, data) in lock_hashes.zip(cell_data) {
+        // The data length was already verified when the total amounts were counted.
+        let mut buffer = [0u8; SUDT_DATA_LEN];
+        buffer.copy_from_slice(&data[0..SUDT_DATA_LEN]);
+        let amount = u128::from_le_bytes(buffer);
+
+        match amounts.iter_mut().find(|(hash, _)| *hash == lock_hash) {
+            Some((_, total)) => *total += amount,
+            None => amounts.push((lock_hash, amount)),
+        }
+    }
+
+    Ok(amounts)
+}
+
+/// Verify that no lock ends up with more tokens than it provided. Tokens may be burned
+/// (which is how they are cast as votes) or returned to the same lock, but never moved to another lock.
+fn validate_non_transferable() -> Result<(), Error> {
+    let input_amounts = determine_token_amount_by_lock(Source::GroupInput)?;
+    let output_amounts = determine_token_amount_by_lock(Source::GroupOutput)?;
+
+    for (lock_hash, output_amount) in output_amounts {
+        let input_amount = input_amounts
+            .iter()
+            .find(|(hash, _)| *hash == lock_hash)
+            .map(|(_, amount)| *amount)
+            .unwrap_or(0);
+
+        if output_amount > input_amount {
+            return Err(Error::NonTransferable);
+        }
+    }
+
+    Ok(())
+}
+
 /// Count the number of tokens in the specified source. Source should be either GroupInput or GroupOutput.
 fn determine_token_amount(source: Source) -> Result<u128, Error> {
     // Track the number of tokens that are counted.
@@ -79,6 +129,11 @@ pub fn main() -> Result<(), Error> {
         return Err(Error::Amount);
     }
 
+    // Non-transferable tokens must also stay with the lock that held them.
+    if check_non_transferable_mode(&args) {
+        validate_non_transferable()?;
+    }
+
     // No errors were found during validation. Return success.
     Ok(())
 }
@@ -0,0 +1,278 @@
+// Import from `core` instead of from `std` since we are in no-std mode.
+use core::result::Result;
+
+// Import CKB syscalls and structures.
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use blake2b_ref::Blake2bBuilder;
+use ckb_std::ckb_types::{bytes::Bytes, prelude::*};
+use ckb_std::high_level::{
+    load_cell, load_cell_capacity, load_cell_data, load_cell_data_hash, load_cell_lock_hash,
+    load_cell_type_hash, load_header, load_script, load_witness_args, QueryIter,
+};
+use ckb_std::{ckb_constants::Source, debug};
+use ckb_voting_layout::{
+    ACTION_HASH_OFFSET, BLAKE2B256_HASH_BYTESIZE, CORE_DATA_LEN, EXECUTION_DELAY_OFFSET,
+    IS_VOTING_FINISHED_OFFSET, U128_BYTESIZE, U64_BYTESIZE, VOTE_OPTION_YES,
+    VOTE_RESULT_OPTION_TYPE_OFFSET, VOTING_STATUS_FINISHED,
+};
+
+// Import our local error codes.
+use crate::error::Error;
+
+// Constants
+const CORE_TYPE_HASH_OFFSET: usize = 0;
+const PAYOUT_HASH_OFFSET: usize = CORE_TYPE_HASH_OFFSET + BLAKE2B256_HASH_BYTESIZE;
+const PAYOUT_ARGS_LEN: usize = PAYOUT_HASH_OFFSET + BLAKE2B256_HASH_BYTESIZE; // Core Cell type hash followed by the payout hash.
+const EXECUTOR_ARGS_LEN: usize = PAYOUT_HASH_OFFSET; // Only the Core Cell type hash, the actions are committed in the Core Cell.
+const U32_BYTESIZE: usize = 4;
+
+// Action layout, see `schemas/actions.mol`. Actions are serialized as a molecule fixvec: the number of items as a u32, followed by the items.
+const ACTION_KIND_OFFSET: usize = 0;
+const ACTION_LOCK_HASH_OFFSET: usize = ACTION_KIND_OFFSET + 1;
+const ACTION_TYPE_HASH_OFFSET: usize = ACTION_LOCK_HASH_OFFSET + BLAKE2B256_HASH_BYTESIZE;
+const ACTION_AMOUNT_OFFSET: usize = ACTION_TYPE_HASH_OFFSET + BLAKE2B256_HASH_BYTESIZE;
+const ACTION_DATA_HASH_OFFSET: usize = ACTION_AMOUNT_OFFSET + U128_BYTESIZE;
+const ACTION_BYTESIZE: usize = ACTION_DATA_HASH_OFFSET + BLAKE2B256_HASH_BYTESIZE; // 113 bytes
+
+// Action kinds.
+const ACTION_KIND_CKB_TRANSFER: u8 = 0;
+const ACTION_KIND_SUDT_TRANSFER: u8 = 1;
+const ACTION_KIND_CONTRACT_UPGRADE: u8 = 2;
+
+struct Action {
+    kind: u8,
+    lock_hash: [u8; BLAKE2B256_HASH_BYTESIZE],
+    type_hash: [u8; BLAKE2B256_HASH_BYTESIZE],
+    amount: u128,
+    data_hash: [u8; BLAKE2B256_HASH_BYTESIZE],
+}
+
+impl Action {
+    fn from_slice(data: &[u8]) -> Self {
+        let mut lock_hash = [0u8; BLAKE2B256_HASH_BYTESIZE];
+        lock_hash.copy_from_slice(&data[ACTION_LOCK_HASH_OFFSET..ACTION_TYPE_HASH_OFFSET]);
+        let mut type_hash = [0u8; BLAKE2B256_HASH_BYTESIZE];
+        type_hash.copy_from_slice(&data[ACTION_TYPE_HASH_OFFSET..ACTION_AMOUNT_OFFSET]);
+        let mut amount = [0u8; U128_BYTESIZE];
+        amount.copy_from_slice(&data[ACTION_AMOUNT_OFFSET..ACTION_DATA_HASH_OFFSET]);
+        let mut data_hash = [0u8; BLAKE2B256_HASH_BYTESIZE];
+        data_hash.copy_from_slice(&data[ACTION_DATA_HASH_OFFSET..ACTION_BYTESIZE]);
+
+        Action {
+            kind: data[ACTION_KIND_OFFSET],
+            lock_hash,
+            type_hash,
+            amount: u128::from_le_bytes(amount),
+            data_hash,
+        }
+    }
+
+    /// Check if the output at the specified index carries out this action.
+    fn is_executed_by(&self, index: usize) -> Result<bool, Error> {
+        if load_cell_lock_hash(index, Source::Output)? != self.lock_hash {
+            return Ok(false);
+        }
+
+        let type_hash = load_cell_type_hash(index, Source::Output)?;
+
+        match self.kind {
+            ACTION_KIND_CKB_TRANSFER => Ok(type_hash.is_none()
+                && load_cell_capacity(index, Source::Output)? as u128 == self.amount),
+            ACTION_KIND_SUDT_TRANSFER => {
+                let data = load_cell_data(index, Source::Output)?;
+
+                Ok(type_hash == Some(self.type_hash)
+                    && data.len() >= U128_BYTESIZE
+                    && data[0..U128_BYTESIZE] == self.amount.to_le_bytes())
+            }
+            ACTION_KIND_CONTRACT_UPGRADE => Ok(type_hash == Some(self.type_hash)
+                && load_cell_data_hash(index, Source::Output)? == self.data_hash),
+            _ => Err(Error::InvalidActions),
+        }
+    }
+}
+
+fn hash(data: &[u8]) -> [u8; BLAKE2B256_HASH_BYTESIZE] {
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_BYTESIZE)
+        .personal(b"ckb-default-hash")
+        .build();
+    blake2b.update(data);
+
+    let mut hash: [u8; BLAKE2B256_HASH_BYTESIZE] = [0; BLAKE2B256_HASH_BYTESIZE];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+/// Calculate the hash of all outputs of the transaction, except the executed Core Cell, whose data isn't
+/// known when the payout is committed to. Every output is hashed as the serialized CellOutput, followed by
+/// the data length as a u64 and the data.
+fn calculate_payout_hash(core_type_hash: &[u8]) -> Result<[u8; BLAKE2B256_HASH_BYTESIZE], Error> {
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_BYTESIZE)
+        .personal(b"ckb-default-hash")
+        .build();
+
+    for (i, output) in QueryIter::new(load_cell, Source::Output).enumerate() {
+        if let Some(type_hash) = load_cell_type_hash(i, Source::Output)? {
+            if type_hash[..] == core_type_hash[..] {
+                continue;
+            }
+        }
+
+        let data = load_cell_data(i, Source::Output)?;
+
+        blake2b.update(output.as_slice());
+        blake2b.update(&(data.len() as u64).to_le_bytes());
+        blake2b.update(&data);
+    }
+
+    let mut hash: [u8; BLAKE2B256_HASH_BYTESIZE] = [0; BLAKE2B256_HASH_BYTESIZE];
+    blake2b.finalize(&mut hash);
+
+    Ok(hash)
+}
+
+/// Find the Core Cell of the vote in the inputs and return its index and data. It is spent, and marked as
+/// executed by dao-core, so the funds of the vote are released only once. Vote Cells share the same type
+/// script, but their data is shorter than the Core Cell.
+fn load_core_cell_data(core_type_hash: &[u8]) -> Result<(usize, Bytes), Error> {
+    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, Source::Input).enumerate() {
+        match cell_type_hash {
+            Some(cell_type_hash) if cell_type_hash[..] == core_type_hash[..] => {
+                let data = load_cell_data(i, Source::Input)?;
+                if data.len() >= CORE_DATA_LEN {
+                    return Ok((i, data.into()));
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    Err(Error::CoreCellMissing)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut buffer = [0u8; U64_BYTESIZE];
+    buffer.copy_from_slice(&data[offset..offset + U64_BYTESIZE]);
+    u64::from_le_bytes(buffer)
+}
+
+/// Verify that the execution delay has passed since the vote was settled. The vote counts as settled in
+/// the block the finished Core Cell was created in, which is proven by including that block in the header
+/// deps. The SETTLEMENT_BLOCK_NUMBER recorded by dao-core can be earlier, since the finishing transaction
+/// can't know the block it is committed in. The transaction also has to include a header dep of a block at
+/// least EXECUTION_DELAY blocks after the settlement. Since the header is already part of the chain, the
+/// transaction can only be committed after it.
+fn validate_execution_delay(core_index: usize, core_data: &Bytes) -> Result<(), Error> {
+    let core_header =
+        load_header(core_index, Source::Input).map_err(|_| Error::CoreCellHeaderMissing)?;
+    let settlement_block_number: u64 = core_header.raw().number().unpack();
+    let executable_block_number = settlement_block_number
+        .checked_add(read_u64(core_data, EXECUTION_DELAY_OFFSET))
+        .ok_or(Error::ExecutionDelayNotPassed)?;
+    debug!("Executable from block: {:?}", executable_block_number);
+
+    let delay_passed = QueryIter::new(load_header, Source::HeaderDep).any(|header| {
+        let block_number: u64 = header.raw().number().unpack();
+        block_number >= executable_block_number
+    });
+
+    if !delay_passed {
+        return Err(Error::ExecutionDelayNotPassed);
+    }
+
+    Ok(())
+}
+
+/// Verify the transaction against the payout hash committed in the args.
+fn validate_payout(args: &Bytes) -> Result<(), Error> {
+    // Funds can only be released to the outputs the vote was about.
+    let payout_hash = calculate_payout_hash(&args[CORE_TYPE_HASH_OFFSET..PAYOUT_HASH_OFFSET])?;
+    debug!("Payout hash: {:?}", payout_hash);
+
+    if payout_hash[..] != args[PAYOUT_HASH_OFFSET..PAYOUT_ARGS_LEN] {
+        return Err(Error::PayoutMismatch);
+    }
+
+    Ok(())
+}
+
+/// Verify the transaction against the actions committed in the Core Cell. The actions are passed in the
+/// witness of the first treasury input. Output i has to carry out action i, and all outputs after the
+/// actions are change. The treasury can't be spent again once the vote is executed, so the change goes to
+/// the lock of the Core Cell, along with the executed Core Cell itself.
+fn validate_actions(core_index: usize, core_data: &Bytes) -> Result<(), Error> {
+    let witness_args = load_witness_args(0, Source::GroupInput)?;
+    let actions: Bytes = witness_args
+        .lock()
+        .to_opt()
+        .ok_or(Error::InvalidActions)?
+        .unpack();
+
+    if hash(&actions)[..] != core_data[ACTION_HASH_OFFSET..EXECUTION_DELAY_OFFSET] {
+        return Err(Error::ActionHashMismatch);
+    }
+
+    if actions.len() < U32_BYTESIZE {
+        return Err(Error::InvalidActions);
+    }
+
+    let mut action_count = [0u8; U32_BYTESIZE];
+    action_count.copy_from_slice(&actions[0..U32_BYTESIZE]);
+    let action_count = u32::from_le_bytes(action_count) as usize;
+
+    if actions.len() != U32_BYTESIZE + action_count * ACTION_BYTESIZE {
+        return Err(Error::InvalidActions);
+    }
+
+    let output_count = QueryIter::new(load_cell_lock_hash, Source::Output).count();
+    if output_count < action_count {
+        return Err(Error::ActionMismatch);
+    }
+
+    for (i, item) in actions[U32_BYTESIZE..].chunks(ACTION_BYTESIZE).enumerate() {
+        if !Action::from_slice(item).is_executed_by(i)? {
+            return Err(Error::ActionMismatch);
+        }
+    }
+
+    let core_lock_hash = load_cell_lock_hash(core_index, Source::Input)?;
+    for i in action_count..output_count {
+        if load_cell_lock_hash(i, Source::Output)? != core_lock_hash {
+            return Err(Error::ActionMismatch);
+        }
+    }
+
+    Ok(())
+}
+
+// Main entry point.
+pub fn main() -> Result<(), Error> {
+    // Load the currently executing script and get the args.
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+
+    if args.len() != PAYOUT_ARGS_LEN && args.len() != EXECUTOR_ARGS_LEN {
+        return Err(Error::InvalidArgsLength);
+    }
+
+    // The vote has to be finished with the proposal accepted.
+    let (core_index, core_data) =
+        load_core_cell_data(&args[CORE_TYPE_HASH_OFFSET..PAYOUT_HASH_OFFSET])?;
+
+    if core_data[IS_VOTING_FINISHED_OFFSET] != VOTING_STATUS_FINISHED
+        || core_data[VOTE_RESULT_OPTION_TYPE_OFFSET] != VOTE_OPTION_YES
+    {
+        return Err(Error::VoteNotPassed);
+    }
+
+    // Holders get time to react between the vote passing and the funds moving.
+    validate_execution_delay(core_index, &core_data)?;
+
+    match args.len() {
+        PAYOUT_ARGS_LEN => validate_payout(&args)?,
+        _ => validate_actions(core_index, &core_data)?,
+    }
+
+    Ok(())
+}
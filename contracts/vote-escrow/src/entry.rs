@@ -0,0 +1,140 @@
+// Import from `core` instead of from `std` since we are in no-std mode.
+use core::result::Result;
+
+// Import CKB syscalls and structures.
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use ckb_std::ckb_types::{bytes::Bytes, prelude::*};
+use ckb_std::high_level::{
+    load_cell_data, load_cell_lock_hash, load_cell_type_hash, load_script, load_script_hash,
+    QueryIter,
+};
+use ckb_std::{ckb_constants::Source, debug};
+use ckb_voting_layout::{
+    BLAKE2B256_HASH_BYTESIZE, CORE_DATA_LEN, IS_VOTING_FINISHED_OFFSET, RECEIPT_AMOUNT_OFFSET,
+    RECEIPT_DATA_LEN, U128_BYTESIZE, VOTING_STATUS_CANCELLED,
+};
+
+// Import our local error codes.
+use crate::error::Error;
+
+// Constants
+const ARGS_LEN: usize = BLAKE2B256_HASH_BYTESIZE; // The type hash of the Core Cell of the vote.
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    let mut buffer = [0u8; U128_BYTESIZE];
+    buffer.copy_from_slice(&data[offset..offset + U128_BYTESIZE]);
+    u128::from_le_bytes(buffer)
+}
+
+/// Find the Core Cell of the vote in the cell deps and return its data. Vote Cells and Receipt Cells
+/// share the same type script, but their data is shorter than the Core Cell.
+fn load_core_cell_data(core_type_hash: &[u8]) -> Result<Bytes, Error> {
+    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, Source::CellDep).enumerate() {
+        match cell_type_hash {
+            Some(cell_type_hash) if cell_type_hash[..] == core_type_hash[..] => {
+                let data = load_cell_data(i, Source::CellDep)?;
+                if data.len() >= CORE_DATA_LEN {
+                    return Ok(data.into());
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    Err(Error::CoreCellMissing)
+}
+
+/// Count the tokens leaving the escrow. All escrowed cells spent have to hold the same token, and any
+/// change has to be returned to the escrow lock.
+fn determine_refund_amount() -> Result<u128, Error> {
+    let token_type_hash =
+        load_cell_type_hash(0, Source::GroupInput)?.ok_or(Error::InvalidTokenCell)?;
+    let mut input_amount: u128 = 0;
+
+    for (i, data) in QueryIter::new(load_cell_data, Source::GroupInput).enumerate() {
+        if load_cell_type_hash(i, Source::GroupInput)? != Some(token_type_hash)
+            || data.len() < U128_BYTESIZE
+        {
+            return Err(Error::InvalidTokenCell);
+        }
+
+        input_amount = input_amount
+            .checked_add(read_u128(&data, 0))
+            .ok_or(Error::AmountOverflow)?;
+    }
+
+    let script_hash = load_script_hash()?;
+    let mut output_amount: u128 = 0;
+
+    for (i, lock_hash) in QueryIter::new(load_cell_lock_hash, Source::Output).enumerate() {
+        if lock_hash != script_hash
+            || load_cell_type_hash(i, Source::Output)? != Some(token_type_hash)
+        {
+            continue;
+        }
+
+        let data = load_cell_data(i, Source::Output)?;
+        if data.len() < U128_BYTESIZE {
+            return Err(Error::InvalidTokenCell);
+        }
+
+        output_amount = output_amount
+            .checked_add(read_u128(&data, 0))
+            .ok_or(Error::AmountOverflow)?;
+    }
+
+    Ok(input_amount.saturating_sub(output_amount))
+}
+
+/// Sum the amounts of all Receipt Cells of the vote being consumed. dao-core makes sure every receipt
+/// owner receives the amount it records.
+fn determine_receipt_amount(core_type_hash: &[u8]) -> Result<u128, Error> {
+    let mut receipt_amount: u128 = 0;
+
+    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, Source::Input).enumerate() {
+        match cell_type_hash {
+            Some(cell_type_hash) if cell_type_hash[..] == core_type_hash[..] => {
+                let data = load_cell_data(i, Source::Input)?;
+                if data.len() == RECEIPT_DATA_LEN {
+                    receipt_amount = receipt_amount
+                        .checked_add(read_u128(&data, RECEIPT_AMOUNT_OFFSET))
+                        .ok_or(Error::AmountOverflow)?;
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(receipt_amount)
+}
+
+pub fn main() -> Result<(), Error> {
+    // Load the currently executing script and get the args.
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+
+    if args.len() != ARGS_LEN {
+        return Err(Error::InvalidArgsLength);
+    }
+
+    // Escrowed tokens can only be released once the vote was cancelled.
+    let core_data = load_core_cell_data(&args)?;
+
+    if core_data[IS_VOTING_FINISHED_OFFSET] != VOTING_STATUS_CANCELLED {
+        return Err(Error::VoteNotCancelled);
+    }
+
+    let refund_amount = determine_refund_amount()?;
+    let receipt_amount = determine_receipt_amount(&args)?;
+
+    debug!(
+        "Refund amount: {:?}, receipt amount: {:?}",
+        refund_amount, receipt_amount
+    );
+
+    if refund_amount > receipt_amount {
+        return Err(Error::RefundExceedsReceipts);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,247 @@
+// Import from `core` instead of from `std` since we are in no-std mode.
+use core::result::Result;
+
+// Import CKB syscalls and structures.
+// https://nervosnetwork.github.io/ckb-std/riscv64imac-unknown-none-elf/doc/ckb_std/index.html
+use ckb_std::ckb_types::{bytes::Bytes, prelude::*};
+use ckb_std::high_level::{
+    load_cell_data, load_cell_type_hash, load_input, load_script, load_witness_args, QueryIter,
+};
+use ckb_std::{ckb_constants::Source, debug};
+
+use blake2b_ref::Blake2bBuilder;
+use ckb_voting_layout::{
+    BLAKE2B256_HASH_BYTESIZE, CORE_DATA_LEN, INTENT_AMOUNT_OFFSET, INTENT_BYTESIZE,
+    INTENT_OUT_POINT_OFFSET, INTENT_OWNER_LOCK_HASH_OFFSET, INTENT_SIGNATURE_OFFSET,
+    INTENT_VOTE_OPTION_TYPE_OFFSET, SHARD_INDEX_OFFSET, TOTAL_VOTES_COLLECTED_OFFSET,
+    U128_BYTESIZE, VOTE_DATA_LEN, VOTE_OPTIONS, VOTE_OPTION_TYPE_OFFSET,
+};
+
+// Import our local error codes.
+use crate::error::Error;
+
+// Constants
+const PUBKEY_HASH_BYTESIZE: usize = 20; // Number of bytes for a blake160 hash of a compressed public key.
+
+// Args layout.
+const CORE_TYPE_HASH_OFFSET: usize = 0; // The type hash of the Core Cell of the vote.
+const PUBKEY_HASH_OFFSET: usize = CORE_TYPE_HASH_OFFSET + BLAKE2B256_HASH_BYTESIZE; // The voter's pubkey hash.
+const ARGS_LEN: usize = PUBKEY_HASH_OFFSET + PUBKEY_HASH_BYTESIZE;
+
+// The signature of a vote intent signs all fields before it.
+const RECOVERY_ID_OFFSET: usize = INTENT_SIGNATURE_OFFSET + 64;
+
+const VOTE_OPTION_COUNT: usize = VOTE_OPTIONS.len();
+
+fn hash(data: &[u8]) -> [u8; BLAKE2B256_HASH_BYTESIZE] {
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_BYTESIZE)
+        .personal(b"ckb-default-hash")
+        .build();
+    blake2b.update(data);
+
+    let mut hash: [u8; BLAKE2B256_HASH_BYTESIZE] = [0; BLAKE2B256_HASH_BYTESIZE];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+/// Load the vote intents of the batched vote. They are stored in the witness of the first Vote Cell of the
+/// vote being spent.
+fn load_vote_intents(core_type_hash: &[u8]) -> Result<Bytes, Error> {
+    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, Source::Input).enumerate() {
+        match cell_type_hash {
+            Some(cell_type_hash) if cell_type_hash[..] == core_type_hash[..] => {
+                if load_cell_data(i, Source::Input)?.len() != VOTE_DATA_LEN {
+                    continue;
+                }
+
+                let witness_args = load_witness_args(i, Source::Input)?;
+                let intents: Bytes = witness_args
+                    .input_type()
+                    .to_opt()
+                    .ok_or(Error::IntentMissing)?
+                    .unpack();
+
+                if intents.is_empty() || intents.len() % INTENT_BYTESIZE != 0 {
+                    return Err(Error::InvalidIntent);
+                }
+
+                return Ok(intents);
+            }
+            _ => continue,
+        }
+    }
+
+    Err(Error::VoteCellMissing)
+}
+
+fn read_u128(data: &[u8]) -> u128 {
+    let mut buffer = [0u8; U128_BYTESIZE];
+    buffer.copy_from_slice(&data[..U128_BYTESIZE]);
+
+    u128::from_le_bytes(buffer)
+}
+
+/// Determine if the Core Cell of the vote is in the specified source.
+fn has_core_cell(core_type_hash: &[u8], source: Source) -> Result<bool, Error> {
+    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, source).enumerate() {
+        match cell_type_hash {
+            Some(cell_type_hash) if cell_type_hash[..] == core_type_hash[..] => {
+                if load_cell_data(i, source)?.len() == CORE_DATA_LEN {
+                    return Ok(true);
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(false)
+}
+
+/// Add up the votes and count the Vote Cells of every option of the vote in the specified source, in the order
+/// of VOTE_OPTIONS.
+fn load_tallies(
+    core_type_hash: &[u8],
+    source: Source,
+) -> Result<([u128; VOTE_OPTION_COUNT], [usize; VOTE_OPTION_COUNT]), Error> {
+    let mut tallies = [0u128; VOTE_OPTION_COUNT];
+    let mut vote_cell_counts = [0usize; VOTE_OPTION_COUNT];
+
+    for (i, cell_type_hash) in QueryIter::new(load_cell_type_hash, source).enumerate() {
+        match cell_type_hash {
+            Some(cell_type_hash) if cell_type_hash[..] == core_type_hash[..] => {
+                let data = load_cell_data(i, source)?;
+                if data.len() != VOTE_DATA_LEN {
+                    continue;
+                }
+
+                // Vote Cells of unknown options are rejected by dao-core.
+                let option_index = match VOTE_OPTIONS
+                    .iter()
+                    .position(|vote_option_type| *vote_option_type == data[VOTE_OPTION_TYPE_OFFSET])
+                {
+                    Some(option_index) => option_index,
+                    None => continue,
+                };
+
+                tallies[option_index] = tallies[option_index]
+                    .checked_add(read_u128(
+                        &data[TOTAL_VOTES_COLLECTED_OFFSET..SHARD_INDEX_OFFSET],
+                    ))
+                    .ok_or(Error::Encoding)?;
+                vote_cell_counts[option_index] += 1;
+            }
+            _ => continue,
+        }
+    }
+
+    Ok((tallies, vote_cell_counts))
+}
+
+/// Make sure the cells are spent casting votes, and not by another transaction spending the Vote Cells, like
+/// one finishing the vote. Votes are cast with the Core Cell as a cell dep, and recreate the Vote Cells of
+/// every option, whose tally has to grow by at least the AMOUNT of its intents.
+fn validate_vote(core_type_hash: &[u8], intents: &[u8]) -> Result<(), Error> {
+    if !has_core_cell(core_type_hash, Source::CellDep)? {
+        return Err(Error::CoreCellMissing);
+    }
+
+    if has_core_cell(core_type_hash, Source::Input)? {
+        return Err(Error::CoreCellSpent);
+    }
+
+    let (input_tallies, input_vote_cell_counts) = load_tallies(core_type_hash, Source::Input)?;
+    let (output_tallies, output_vote_cell_counts) = load_tallies(core_type_hash, Source::Output)?;
+
+    if input_vote_cell_counts != output_vote_cell_counts {
+        return Err(Error::VoteCellsNotRecreated);
+    }
+
+    for (option_index, vote_option_type) in VOTE_OPTIONS.iter().enumerate() {
+        let mut intent_votes: u128 = 0;
+        for intent in intents
+            .chunks(INTENT_BYTESIZE)
+            .filter(|intent| intent[INTENT_VOTE_OPTION_TYPE_OFFSET] == *vote_option_type)
+        {
+            intent_votes = intent_votes
+                .checked_add(read_u128(
+                    &intent[INTENT_AMOUNT_OFFSET..INTENT_OUT_POINT_OFFSET],
+                ))
+                .ok_or(Error::InvalidIntent)?;
+        }
+
+        let votes = output_tallies[option_index]
+            .checked_sub(input_tallies[option_index])
+            .ok_or(Error::IntentNotCounted)?;
+
+        debug!(
+            "Votes for option {:?}: {:?} of intents {:?}",
+            vote_option_type, votes, intent_votes
+        );
+
+        if votes < intent_votes {
+            return Err(Error::IntentNotCounted);
+        }
+    }
+
+    Ok(())
+}
+
+/// Recover the pubkey hash of the voter who signed the intent.
+fn recover_pubkey_hash(intent: &[u8]) -> Result<[u8; PUBKEY_HASH_BYTESIZE], Error> {
+    let message = hash(&intent[..INTENT_SIGNATURE_OFFSET]);
+    let recovery_id = libsecp256k1::RecoveryId::parse(intent[RECOVERY_ID_OFFSET])
+        .map_err(|_| Error::InvalidSignature)?;
+    let signature = libsecp256k1::Signature::parse_standard_slice(
+        &intent[INTENT_SIGNATURE_OFFSET..RECOVERY_ID_OFFSET],
+    )
+    .map_err(|_| Error::InvalidSignature)?;
+    let pubkey = libsecp256k1::recover(
+        &libsecp256k1::Message::parse(&message),
+        &signature,
+        &recovery_id,
+    )
+    .map_err(|_| Error::InvalidSignature)?;
+
+    let mut pubkey_hash = [0u8; PUBKEY_HASH_BYTESIZE];
+    pubkey_hash.copy_from_slice(&hash(&pubkey.serialize_compressed())[..PUBKEY_HASH_BYTESIZE]);
+
+    Ok(pubkey_hash)
+}
+
+pub fn main() -> Result<(), Error> {
+    // Load the currently executing script and get the args.
+    let script = load_script()?;
+    let args: Bytes = script.args().unpack();
+
+    if args.len() != ARGS_LEN {
+        return Err(Error::InvalidArgsLength);
+    }
+
+    // The cells can only be spent in a batched vote, which dao-core checks against the intents.
+    let core_type_hash = &args[CORE_TYPE_HASH_OFFSET..PUBKEY_HASH_OFFSET];
+    let intents = load_vote_intents(core_type_hash)?;
+    validate_vote(core_type_hash, &intents)?;
+
+    // Every cell of the voter has to be spent by an intent the voter signed.
+    for input in QueryIter::new(load_input, Source::GroupInput) {
+        let out_point = input.previous_output();
+        let intent = intents
+            .chunks(INTENT_BYTESIZE)
+            .find(|intent| {
+                &intent[INTENT_OUT_POINT_OFFSET..INTENT_OWNER_LOCK_HASH_OFFSET]
+                    == out_point.as_slice()
+            })
+            .ok_or(Error::IntentMissing)?;
+
+        let pubkey_hash = recover_pubkey_hash(intent)?;
+
+        debug!("Intent signed by: {:?}", pubkey_hash);
+
+        if pubkey_hash[..] != args[PUBKEY_HASH_OFFSET..] {
+            return Err(Error::InvalidSignature);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+//! Error codes of the dao-core, sUDT and anyone-can-pay contracts.
+//!
+//! The contracts exit with the code of the error they return, and host code decodes exit codes with the same
+//! enums, so both sides always agree on the meaning of a code. Codes are part of the interface of the contracts:
+//! new errors are added at the end and existing codes are never renumbered.
+
+#![no_std]
+
+/// Defines an error enum with explicit codes, along with the lookup of a variant by its code. The doc comment of
+/// every variant is its message.
+macro_rules! error_codes {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                #[doc = $message:literal]
+                $variant:ident = $code:literal,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[repr(i8)]
+        pub enum $name {
+            $(
+                #[doc = $message]
+                $variant = $code,
+            )*
+        }
+
+        impl $name {
+            /// Every error, in order of its code.
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn code(self) -> i8 {
+                self as i8
+            }
+
+            pub fn from_code(code: i8) -> Option<Self> {
+                match code {
+                    $($code => Some($name::$variant),)*
+                    _ => None,
+                }
+            }
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant),)*
+                }
+            }
+
+            pub fn message(self) -> &'static str {
+                match self {
+                    $($name::$variant => $message.trim(),)*
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    /// Errors of dao-core.
+    pub enum DaoCoreError {
+        /// A cell, field or witness was accessed at an index which doesn't exist.
+        IndexOutOfBound = 1,
+        /// A required item is missing.
+        ItemMissing = 2,
+        /// A loaded buffer is shorter than expected.
+        LengthNotEnough = 3,
+        /// Data failed to decode.
+        Encoding = 4,
+        /// A counter has an invalid value.
+        InvalidCounterValue = 5,
+        /// The cells of the vote don't match any mode of operation.
+        InvalidTransactionStructure = 6,
+        /// The data of an output cell is invalid for the mode of operation.
+        InvalidOutputCellData = 7,
+        /// A counter overflowed.
+        CounterValueOverflow = 8,
+        /// The transaction isn't authorized by the organizer of the vote.
+        Unauthorized = 9,
+        /// The data of a cell has the wrong size for its kind.
+        InvalidDataBytesize = 10,
+        /// A witness couldn't be loaded.
+        WitnessLoadFail = 11,
+        /// The data of an input cell is invalid for the mode of operation.
+        InvalidInputCellData = 12,
+        /// The args of the dao-core type script have the wrong length.
+        InvalidArgsLength = 13,
+        /// The lock args must be zero.
+        LockArgsOtherThanZero = 14,
+        /// The instance id in the type script args doesn't match the first input of the creating transaction.
+        InvalidInstanceId = 15,
+        /// The tokens sent to the voters don't add up to the total recorded in the Core Cell.
+        TokenDistributionMismatch = 16,
+        /// The voting weight mode of the Core Cell is unknown.
+        InvalidVotingWeightMode = 17,
+        /// The vote option of a cell is unknown or not offered by the vote.
+        InvalidVoteOption = 18,
+        /// The Vote Cells don't hold exactly one cell for every shard of every option.
+        InvalidVoteCellCount = 19,
+        /// The tally of a Vote Cell is invalid.
+        InvalidVoteTally = 20,
+        /// The data of a token cell isn't a valid sUDT amount.
+        InvalidTokenCellData = 21,
+        /// A sum of amounts overflowed.
+        AmountOverflow = 22,
+        /// The Core Cell of the vote isn't in the cell deps.
+        CoreCellMissing = 23,
+        /// The vote is already finished.
+        VotingFinished = 24,
+        /// The votes added to the Vote Cells don't match the tokens burned.
+        VoteTallyMismatch = 25,
+        /// The snapshot block of the vote hasn't been reached yet.
+        SnapshotNotReached = 26,
+        /// A token cell wasn't live at the snapshot block.
+        CellNotLiveAtSnapshot = 27,
+        /// The tokens counted at the snapshot weren't returned to the voter.
+        SnapshotTokensNotReturned = 28,
+        /// The token id mode of the Core Cell is unknown.
+        InvalidTokenIdMode = 29,
+        /// The token hash type of the Core Cell is unknown.
+        InvalidTokenHashType = 30,
+        /// No option has a majority of the votes.
+        NoMajority = 31,
+        /// The header of the settlement block isn't in the header deps.
+        SettlementHeaderMissing = 32,
+        /// The organizer mode of the Core Cell is unknown.
+        InvalidOrganizerMode = 33,
+        /// The organizer witness is malformed.
+        InvalidOrganizerWitness = 34,
+        /// Too few organizers signed the transaction.
+        OrganizerThresholdNotMet = 35,
+        /// An organizer signed the transaction more than once.
+        DuplicateOrganizerSigner = 36,
+        /// Refunds are only allowed once the vote is cancelled.
+        VoteNotCancelled = 37,
+        /// The Receipt Cells don't match the votes cast.
+        ReceiptMismatch = 38,
+        /// The refunded tokens don't match the burned Receipt Cells.
+        RefundMismatch = 39,
+        /// The voting end block isn't moved forward.
+        InvalidExtension = 40,
+        /// The extensions of the voting end add up to more blocks than the maximum extension.
+        ExtensionLimitExceeded = 41,
+        /// The voting end block hasn't been reached yet.
+        VotingNotEnded = 42,
+        /// The shard index of a Vote Cell is out of range.
+        InvalidVoteShard = 43,
+        /// The number of Vote Cell shards is invalid.
+        InvalidShardCount = 44,
+        /// A vote intent is malformed or doesn't match the token cell it spends.
+        InvalidVoteIntent = 45,
+        /// The votes cast don't match the vote intent.
+        VoteIntentMismatch = 46,
+        /// Only a finished vote can be marked as executed.
+        VoteNotFinished = 47,
+        /// Tokens held in escrow for the vote can still be refunded.
+        EscrowOutstanding = 48,
+        /// The escrow lock hash isn't the vote escrow lock of the vote.
+        InvalidEscrowLock = 49,
+        /// A vote is cast into a Vote Cell created after the voting end block, or its header is missing.
+        VotingEnded = 50,
+        /// CODE_HASH mode counts a token family whose instances anyone can issue, such as the standard sUDT.
+        MintableTokenFamily = 51,
+        /// A passed vote is burned or marked executed without releasing its treasury in the same transaction.
+        TreasuryNotReleased = 52,
+        /// A vote holding tokens in escrow is created for non-transferable sUDT, which can't be escrowed.
+        NonTransferableEscrow = 53,
+    }
+}
+
+error_codes! {
+    /// Errors of sUDT.
+    pub enum SudtError {
+        /// A cell or field was accessed at an index which doesn't exist.
+        IndexOutOfBound = 1,
+        /// A required item is missing.
+        ItemMissing = 2,
+        /// A loaded buffer is shorter than expected.
+        LengthNotEnough = 3,
+        /// The data of a token cell isn't a valid amount.
+        Encoding = 4,
+        /// The outputs hold more tokens than the inputs without the owner's approval.
+        Amount = 5,
+        /// The args of the sUDT type script have the wrong length.
+        ArgsLength = 6,
+        /// Non-transferable tokens were moved to another lock.
+        NonTransferable = 7,
+    }
+}
+
+error_codes! {
+    /// Errors of the anyone-can-pay lock.
+    pub enum AcpError {
+        /// A cell or field was accessed at an index which doesn't exist.
+        IndexOutOfBound = 1,
+        /// A required item is missing.
+        ItemMissing = 2,
+        /// A loaded buffer is shorter than expected.
+        LengthNotEnough = 3,
+        /// Data failed to decode.
+        Encoding = 4,
+        /// The args aren't an owner lock hash followed by at most two minimum amount exponents.
+        InvalidArgsLength = 5,
+        /// A minimum amount exponent overflows the amount.
+        InvalidMinimumAmount = 6,
+        /// The data of a spent cell with a type script isn't a valid UDT amount.
+        InvalidUdtCellData = 7,
+        /// No output with the same lock and type script pays back a spent cell.
+        OutputCellMissing = 8,
+        /// The outputs hold less capacity or UDT than the spent cells.
+        AmountDecreased = 9,
+        /// The payment is below the minimum amount of every asset.
+        MinimumAmountNotReached = 10,
+        /// The total capacity or UDT amount overflowed.
+        AmountOverflow = 11,
+    }
+}
+
+/// Converts errors of syscalls into the errors with the same name.
+#[cfg(feature = "ckb-std")]
+macro_rules! from_sys_error {
+    ($($name:ident),*) => {
+        $(
+            impl From<ckb_std::error::SysError> for $name {
+                fn from(err: ckb_std::error::SysError) -> Self {
+                    use ckb_std::error::SysError::*;
+                    match err {
+                        IndexOutOfBound => Self::IndexOutOfBound,
+                        ItemMissing => Self::ItemMissing,
+                        LengthNotEnough(_) => Self::LengthNotEnough,
+                        Encoding => Self::Encoding,
+                        Unknown(err_code) => panic!("unexpected sys error {}", err_code),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "ckb-std")]
+from_sys_error!(DaoCoreError, SudtError, AcpError);
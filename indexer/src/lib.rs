@@ -0,0 +1,333 @@
+//! Indexer of the vote lifecycle.
+//!
+//! Follows the main chain from a block source, recognises Core Cells, Vote Cells, Receipt Cells and sUDT cells by
+//! the code hashes of the deployed scripts, and stores votes, the ballots cast on them and the receipts of escrowed
+//! tokens in SQLite. Blocks which left the main chain are rolled back before indexing the new ones.
+
+mod store;
+
+pub use store::{Ballot, CellKind, IndexedCell, Receipt, Store, StoreTransaction, Vote};
+
+use std::fmt;
+
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{BlockView, TransactionView},
+    packed::{Byte32, CellOutput, OutPoint, WitnessArgs},
+    prelude::*,
+};
+use ckb_voting_layout::{
+    INTENT_AMOUNT_OFFSET, INTENT_BYTESIZE, INTENT_OUT_POINT_BYTESIZE, INTENT_OUT_POINT_OFFSET,
+};
+use ckb_voting_sdk::audit::BlockSource;
+use ckb_voting_sdk::{
+    parse_token_amount, DeployedScript, ReceiptCellData, VoteCellData, CORE_DATA_LEN,
+    RECEIPT_DATA_LEN, VOTE_DATA_LEN, VOTING_WEIGHT_MODE_SNAPSHOT,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Sdk(ckb_voting_sdk::Error),
+    Database(rusqlite::Error),
+    InvalidRow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Sdk(ckb_voting_sdk::Error::BlockSource(reason)) => {
+                write!(f, "could not load block: {}", reason)
+            }
+            Error::Sdk(err) => write!(f, "{:?}", err),
+            Error::Database(err) => write!(f, "database error: {}", err),
+            Error::InvalidRow => write!(f, "invalid row in the database"),
+        }
+    }
+}
+
+impl From<ckb_voting_sdk::Error> for Error {
+    fn from(err: ckb_voting_sdk::Error) -> Self {
+        Error::Sdk(err)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Database(err)
+    }
+}
+
+/// Parse the intents of a batched vote: option | amount | out point | signature.
+fn parse_vote_intents(witness: &Bytes) -> Option<Vec<(u8, u128, OutPoint)>> {
+    let intents = WitnessArgs::from_slice(witness)
+        .ok()?
+        .input_type()
+        .to_opt()?
+        .raw_data();
+    if intents.is_empty() || intents.len() % INTENT_BYTESIZE != 0 {
+        return None;
+    }
+
+    intents
+        .chunks(INTENT_BYTESIZE)
+        .map(|intent| {
+            let amount =
+                parse_token_amount(&intent[INTENT_AMOUNT_OFFSET..INTENT_OUT_POINT_OFFSET])?;
+            let out_point = OutPoint::from_slice(
+                &intent
+                    [INTENT_OUT_POINT_OFFSET..INTENT_OUT_POINT_OFFSET + INTENT_OUT_POINT_BYTESIZE],
+            )
+            .ok()?;
+
+            Some((intent[0], amount, out_point))
+        })
+        .collect()
+}
+
+pub struct Indexer {
+    dao_core: DeployedScript,
+    sudt: DeployedScript,
+    start_block_number: u64,
+}
+
+impl Indexer {
+    pub fn new(dao_core: &DeployedScript, sudt: &DeployedScript) -> Self {
+        Indexer {
+            dao_core: dao_core.clone(),
+            sudt: sudt.clone(),
+            start_block_number: 0,
+        }
+    }
+
+    /// First block to index, e.g. the block the scripts were deployed in. Defaults to genesis.
+    pub fn start_block_number(mut self, start_block_number: u64) -> Self {
+        self.start_block_number = start_block_number;
+        self
+    }
+
+    /// Index all blocks up to the tip of the source, and return the number of the last indexed block.
+    pub fn sync(&self, store: &mut Store, source: &impl BlockSource) -> Result<Option<u64>, Error> {
+        // The indexed tip may have left the main chain, e.g. for a shorter fork.
+        while let Some((number, hash)) = store.tip()? {
+            match source.block(number)? {
+                Some(block) if block.hash() == hash => break,
+                _ => store.rollback_block(number)?,
+            }
+        }
+
+        loop {
+            let tip = store.tip()?;
+            let next_number = match &tip {
+                Some((number, _)) => number + 1,
+                None => self.start_block_number,
+            };
+            let block = match source.block(next_number)? {
+                Some(block) => block,
+                None => break,
+            };
+
+            if let Some((number, hash)) = tip {
+                if block.parent_hash() != hash {
+                    store.rollback_block(number)?;
+                    continue;
+                }
+            }
+
+            self.index_block(store, &block)?;
+        }
+
+        Ok(store.tip()?.map(|(number, _)| number))
+    }
+
+    fn index_block(&self, store: &mut Store, block: &BlockView) -> Result<(), Error> {
+        let number = block.number();
+        let store_tx = store.begin()?;
+        store_tx.insert_block(block)?;
+
+        for tx in block.transactions() {
+            let mut spent_cells = vec![];
+            let mut snapshot_votes = vec![];
+            for (index, input) in tx.inputs().into_iter().enumerate() {
+                let out_point = input.previous_output();
+                let cell = match store_tx.spend_cell(&out_point, number)? {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+
+                if let (CellKind::Vote, Some(instance_id)) = (cell.kind, &cell.instance_id) {
+                    let snapshot = store_tx.core_data(instance_id)?.map(|core_data| {
+                        core_data.voting_weight_mode == VOTING_WEIGHT_MODE_SNAPSHOT
+                    });
+                    if snapshot == Some(true) && !snapshot_votes.contains(instance_id) {
+                        snapshot_votes.push(instance_id.clone());
+                    }
+                }
+                spent_cells.push((index, out_point, cell));
+            }
+
+            let mut created_cells = vec![];
+            for (index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+                let cell = match self.classify(&output, &data) {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+
+                let out_point = OutPoint::new(tx.hash(), index as u32);
+                store_tx.insert_cell(&out_point, number, &cell)?;
+                match (cell.kind, &cell.instance_id) {
+                    (CellKind::Core, Some(instance_id)) => {
+                        store_tx.insert_vote(instance_id, &tx.hash(), number)?;
+                    }
+                    (CellKind::Receipt, Some(instance_id)) => {
+                        if let Some(receipt_data) = ReceiptCellData::from_slice(&cell.data) {
+                            let receipt =
+                                Receipt::new(out_point, number, instance_id.clone(), &receipt_data);
+                            store_tx.insert_receipt(&receipt)?;
+                        }
+                    }
+                    _ => {}
+                }
+                created_cells.push(cell);
+            }
+
+            let ballots = find_ballots(&tx, number, &spent_cells, &created_cells, &snapshot_votes);
+            for ballot in ballots {
+                store_tx.insert_ballot(&ballot)?;
+            }
+        }
+
+        store_tx.commit()
+    }
+
+    fn classify(&self, output: &CellOutput, data: &Bytes) -> Option<IndexedCell> {
+        let type_script = output.type_().to_opt()?;
+        let lock_hash = output.lock().calc_script_hash();
+
+        let (kind, instance_id) = if self.dao_core.matches(&type_script) {
+            let instance_id = Byte32::from_slice(&type_script.args().raw_data()).ok()?;
+            match data.len() {
+                CORE_DATA_LEN => (CellKind::Core, Some(instance_id)),
+                VOTE_DATA_LEN => (CellKind::Vote, Some(instance_id)),
+                RECEIPT_DATA_LEN => (CellKind::Receipt, Some(instance_id)),
+                _ => return None,
+            }
+        } else if self.sudt.matches(&type_script) && parse_token_amount(data).is_some() {
+            (CellKind::Token, None)
+        } else {
+            return None;
+        };
+
+        Some(IndexedCell {
+            kind,
+            instance_id,
+            lock_hash,
+            data: data.to_vec(),
+        })
+    }
+}
+
+/// Votes are cast by recreating a Vote Cell with a higher tally and burning tokens. Batched votes carry an
+/// intent for every token cell, otherwise every voter votes for the option whose tally went up with the tokens
+/// they didn't get back. Votes in snapshot mode return all tokens, so the voters vote with all they spent.
+fn find_ballots(
+    tx: &TransactionView,
+    block_number: u64,
+    spent_cells: &[(usize, OutPoint, IndexedCell)],
+    created_cells: &[IndexedCell],
+    snapshot_votes: &[Byte32],
+) -> Vec<Ballot> {
+    let mut tallied: Option<(Byte32, u8)> = None;
+    let mut first_vote_input = None;
+    for (index, _, spent_cell) in spent_cells {
+        if spent_cell.kind != CellKind::Vote {
+            continue;
+        }
+        let spent_data = match VoteCellData::from_slice(&spent_cell.data) {
+            Some(spent_data) => spent_data,
+            None => continue,
+        };
+        first_vote_input = first_vote_input.or(Some(*index));
+
+        let increased = created_cells.iter().any(|created_cell| {
+            created_cell.kind == CellKind::Vote
+                && created_cell.instance_id == spent_cell.instance_id
+                && VoteCellData::from_slice(&created_cell.data)
+                    .map(|created_data| {
+                        created_data.vote_option_type == spent_data.vote_option_type
+                            && created_data.shard_index == spent_data.shard_index
+                            && created_data.total_votes_collected > spent_data.total_votes_collected
+                    })
+                    .unwrap_or(false)
+        });
+        if increased && tallied.is_none() {
+            tallied = spent_cell
+                .instance_id
+                .clone()
+                .map(|instance_id| (instance_id, spent_data.vote_option_type));
+        }
+    }
+
+    let (instance_id, tallied_option) = match tallied {
+        Some(tallied) => tallied,
+        None => return vec![],
+    };
+    let snapshot = snapshot_votes.contains(&instance_id);
+    let ballot = |voter_lock_hash: &Byte32, vote_option_type: u8, votes: u128| Ballot {
+        tx_hash: tx.hash(),
+        block_number,
+        instance_id: instance_id.clone(),
+        voter_lock_hash: voter_lock_hash.clone(),
+        vote_option_type,
+        votes,
+    };
+
+    let token_cells: Vec<(&OutPoint, &IndexedCell, u128)> = spent_cells
+        .iter()
+        .filter(|(_, _, cell)| cell.kind == CellKind::Token)
+        .filter_map(|(_, out_point, cell)| {
+            parse_token_amount(&cell.data).map(|amount| (out_point, cell, amount))
+        })
+        .collect();
+
+    let intents = first_vote_input
+        .and_then(|index| tx.witnesses().get(index))
+        .and_then(|witness| parse_vote_intents(&witness.raw_data()));
+    let mut ballots: Vec<Ballot> = vec![];
+    if let Some(intents) = intents {
+        for (vote_option_type, amount, out_point) in intents {
+            if let Some((_, cell, _)) = token_cells.iter().find(|(op, _, _)| **op == out_point) {
+                ballots.push(ballot(&cell.lock_hash, vote_option_type, amount));
+            }
+        }
+    } else {
+        for (_, cell, _) in &token_cells {
+            if ballots
+                .iter()
+                .any(|ballot| ballot.voter_lock_hash == cell.lock_hash)
+            {
+                continue;
+            }
+
+            let spent: u128 = token_cells
+                .iter()
+                .filter(|(_, other, _)| other.lock_hash == cell.lock_hash)
+                .fold(0, |total, (_, _, amount)| total.saturating_add(*amount));
+            let returned: u128 = created_cells
+                .iter()
+                .filter(|created| {
+                    created.kind == CellKind::Token && created.lock_hash == cell.lock_hash
+                })
+                .filter_map(|created| parse_token_amount(&created.data))
+                .fold(0, |total, amount| total.saturating_add(amount));
+            let votes = match snapshot {
+                true => spent,
+                false => spent.saturating_sub(returned),
+            };
+            if votes > 0 {
+                ballots.push(ballot(&cell.lock_hash, tallied_option, votes));
+            }
+        }
+    }
+
+    ballots
+}
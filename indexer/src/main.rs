@@ -0,0 +1,115 @@
+//! Follows a CKB node and indexes votes into an SQLite database.
+
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use ckb_voting::chain::Chain;
+use ckb_voting::deployment::Deployment;
+use ckb_voting_indexer::{Indexer, Store};
+use clap::{App, Arg, ArgMatches};
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("ckb-voting-indexer")
+        .about("Index the votes on a CKB chain into an SQLite database")
+        .arg(
+            Arg::with_name("rpc")
+                .long("rpc")
+                .takes_value(true)
+                .default_value("http://127.0.0.1:8114")
+                .help("URL of the CKB node"),
+        )
+        .arg(
+            Arg::with_name("deployment")
+                .long("deployment")
+                .takes_value(true)
+                .default_value("deployment.toml")
+                .help("Deployment config of the scripts"),
+        )
+        .arg(
+            Arg::with_name("migration")
+                .long("migration")
+                .takes_value(true)
+                .help("Migration file written by capsule deploy"),
+        )
+        .arg(
+            Arg::with_name("db")
+                .long("db")
+                .takes_value(true)
+                .default_value("votes.db")
+                .help("SQLite database file"),
+        )
+        .arg(
+            Arg::with_name("start-block")
+                .long("start-block")
+                .takes_value(true)
+                .default_value("0")
+                .help("First block to index"),
+        )
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .takes_value(true)
+                .default_value("5")
+                .help("Seconds to wait for new blocks"),
+        )
+        .arg(
+            Arg::with_name("once")
+                .long("once")
+                .help("Exit after indexing up to the tip"),
+        )
+}
+
+fn parse_number(matches: &ArgMatches, name: &str) -> Result<u64, String> {
+    let value = matches.value_of(name).unwrap_or_default();
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} {}", name, value))
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let chain = Chain::new(matches.value_of("rpc").unwrap_or_default());
+    let mut deployment = Deployment::load(
+        Path::new(matches.value_of("deployment").unwrap_or_default()),
+        matches.value_of("migration").map(Path::new),
+    )
+    .map_err(|err| err.to_string())?;
+    deployment
+        .resolve_on_chain_cells(|out_point| chain.live_cell(out_point))
+        .map_err(|err| err.to_string())?;
+
+    let dao_core = deployment
+        .script("dao-core")
+        .map_err(|err| err.to_string())?;
+    let sudt = deployment.script("sudt").map_err(|err| err.to_string())?;
+    let indexer =
+        Indexer::new(&dao_core, &sudt).start_block_number(parse_number(matches, "start-block")?);
+    let interval = Duration::from_secs(parse_number(matches, "interval")?);
+
+    let mut store = Store::open(Path::new(matches.value_of("db").unwrap_or_default()))
+        .map_err(|err| err.to_string())?;
+
+    loop {
+        match indexer.sync(&mut store, &chain) {
+            Ok(Some(tip)) => println!("indexed up to block {}", tip),
+            Ok(None) => {}
+            // The node may be restarting, try again later.
+            Err(err) => eprintln!("{}", err),
+        }
+
+        if matches.is_present("once") {
+            return Ok(());
+        }
+        thread::sleep(interval);
+    }
+}
+
+fn main() {
+    let matches = app().get_matches();
+
+    if let Err(err) = run(&matches) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
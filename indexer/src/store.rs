@@ -0,0 +1,545 @@
+//! SQLite storage of the indexed cells, votes, ballots and receipts.
+//!
+//! Every row records the block it was added in, so a block can be rolled back when it leaves the main chain.
+//! Token amounts don't fit into SQLite integers and are stored as decimal text.
+
+use std::path::Path;
+
+use ckb_tool::ckb_types::{
+    core::BlockView,
+    packed::{Byte32, OutPoint},
+    prelude::*,
+};
+use ckb_voting_sdk::{CoreCellData, ReceiptCellData, VoteCellData};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::Error;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+    number INTEGER PRIMARY KEY,
+    hash BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS cells (
+    tx_hash BLOB NOT NULL,
+    output_index INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    kind INTEGER NOT NULL,
+    instance_id BLOB,
+    lock_hash BLOB NOT NULL,
+    data BLOB NOT NULL,
+    spent_block_number INTEGER,
+    PRIMARY KEY (tx_hash, output_index)
+);
+CREATE INDEX IF NOT EXISTS cells_by_instance ON cells (instance_id, kind);
+CREATE INDEX IF NOT EXISTS cells_by_spent_block ON cells (spent_block_number);
+CREATE TABLE IF NOT EXISTS votes (
+    instance_id BLOB PRIMARY KEY,
+    tx_hash BLOB NOT NULL,
+    block_number INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS ballots (
+    tx_hash BLOB NOT NULL,
+    block_number INTEGER NOT NULL,
+    instance_id BLOB NOT NULL,
+    voter_lock_hash BLOB NOT NULL,
+    vote_option_type INTEGER NOT NULL,
+    votes TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS ballots_by_voter ON ballots (voter_lock_hash);
+CREATE INDEX IF NOT EXISTS ballots_by_block ON ballots (block_number);
+CREATE TABLE IF NOT EXISTS receipts (
+    tx_hash BLOB NOT NULL,
+    output_index INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    instance_id BLOB NOT NULL,
+    owner_lock_hash BLOB NOT NULL,
+    vote_option_type INTEGER NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (tx_hash, output_index)
+);
+CREATE INDEX IF NOT EXISTS receipts_by_owner ON receipts (owner_lock_hash);
+CREATE INDEX IF NOT EXISTS receipts_by_block ON receipts (block_number);
+";
+
+/// Kinds of indexed cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellKind {
+    Core = 0,
+    Vote = 1,
+    Token = 2,
+    Receipt = 3,
+}
+
+impl CellKind {
+    fn from_i64(kind: i64) -> Option<Self> {
+        match kind {
+            0 => Some(CellKind::Core),
+            1 => Some(CellKind::Vote),
+            2 => Some(CellKind::Token),
+            3 => Some(CellKind::Receipt),
+            _ => None,
+        }
+    }
+}
+
+/// A cell created by an indexed transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedCell {
+    pub kind: CellKind,
+    /// Instance id of the vote, for Core Cells, Vote Cells and Receipt Cells.
+    pub instance_id: Option<Byte32>,
+    pub lock_hash: Byte32,
+    pub data: Vec<u8>,
+}
+
+/// A vote, with its Core Cell as of the indexed tip.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vote {
+    pub instance_id: Byte32,
+    pub tx_hash: Byte32,
+    pub block_number: u64,
+    pub core_data: CoreCellData,
+}
+
+/// Votes cast by a voter in a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ballot {
+    pub tx_hash: Byte32,
+    pub block_number: u64,
+    pub instance_id: Byte32,
+    pub voter_lock_hash: Byte32,
+    pub vote_option_type: u8,
+    pub votes: u128,
+}
+
+/// Tokens a voter escrowed in a vote, from the Receipt Cell issued for them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Receipt {
+    pub out_point: OutPoint,
+    pub block_number: u64,
+    pub instance_id: Byte32,
+    pub owner_lock_hash: Byte32,
+    pub vote_option_type: u8,
+    pub amount: u128,
+    /// Block the Receipt Cell was spent in, e.g. to refund the tokens of a cancelled vote.
+    pub spent_block_number: Option<u64>,
+}
+
+impl Receipt {
+    pub fn new(
+        out_point: OutPoint,
+        block_number: u64,
+        instance_id: Byte32,
+        receipt_data: &ReceiptCellData,
+    ) -> Self {
+        Receipt {
+            out_point,
+            block_number,
+            instance_id,
+            owner_lock_hash: receipt_data.owner_lock_hash.pack(),
+            vote_option_type: receipt_data.vote_option_type,
+            amount: receipt_data.amount,
+            spent_block_number: None,
+        }
+    }
+}
+
+fn to_byte32(bytes: Vec<u8>) -> Result<Byte32, Error> {
+    Byte32::from_slice(&bytes).map_err(|_| Error::InvalidRow)
+}
+
+fn to_u128(votes: String) -> Result<u128, Error> {
+    votes.parse().map_err(|_| Error::InvalidRow)
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        Store::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Store::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Store { conn })
+    }
+
+    /// Number and hash of the last indexed block.
+    pub fn tip(&self) -> Result<Option<(u64, Byte32)>, Error> {
+        let tip = self
+            .conn
+            .query_row(
+                "SELECT number, hash FROM blocks ORDER BY number DESC LIMIT 1",
+                params![],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()?;
+
+        match tip {
+            Some((number, hash)) => Ok(Some((number as u64, to_byte32(hash)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Start a database transaction, which adds the rows of a block at once.
+    pub fn begin(&mut self) -> Result<StoreTransaction<'_>, Error> {
+        Ok(StoreTransaction {
+            tx: self.conn.transaction()?,
+        })
+    }
+
+    /// Remove everything added in the block, and unspend the cells it spent.
+    pub fn rollback_block(&mut self, number: u64) -> Result<(), Error> {
+        let number = number as i64;
+        let tx = self.conn.transaction()?;
+
+        tx.execute("DELETE FROM cells WHERE block_number = ?", params![number])?;
+        tx.execute(
+            "UPDATE cells SET spent_block_number = NULL WHERE spent_block_number = ?",
+            params![number],
+        )?;
+        tx.execute("DELETE FROM votes WHERE block_number = ?", params![number])?;
+        tx.execute(
+            "DELETE FROM ballots WHERE block_number = ?",
+            params![number],
+        )?;
+        tx.execute(
+            "DELETE FROM receipts WHERE block_number = ?",
+            params![number],
+        )?;
+        tx.execute("DELETE FROM blocks WHERE number = ?", params![number])?;
+
+        Ok(tx.commit()?)
+    }
+
+    pub fn vote(&self, instance_id: &Byte32) -> Result<Option<Vote>, Error> {
+        let vote = self
+            .conn
+            .query_row(
+                "SELECT tx_hash, block_number FROM votes WHERE instance_id = ?",
+                params![instance_id.as_slice()],
+                |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .optional()?;
+        let (tx_hash, block_number) = match vote {
+            Some(vote) => vote,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Vote {
+            instance_id: instance_id.clone(),
+            tx_hash: to_byte32(tx_hash)?,
+            block_number: block_number as u64,
+            core_data: core_data(&self.conn, instance_id)?.ok_or(Error::InvalidRow)?,
+        }))
+    }
+
+    /// All votes, in the order they were created.
+    pub fn votes(&self) -> Result<Vec<Vote>, Error> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT instance_id FROM votes ORDER BY block_number, rowid")?;
+        let instance_ids = statement
+            .query_map(params![], |row| row.get::<_, Vec<u8>>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut votes = vec![];
+        for instance_id in instance_ids {
+            if let Some(vote) = self.vote(&to_byte32(instance_id)?)? {
+                votes.push(vote);
+            }
+        }
+
+        Ok(votes)
+    }
+
+    /// Tallies of every Vote Cell of the vote, from the last Vote Cell of every option and shard. These are
+    /// kept after the vote is finished.
+    pub fn vote_cells(&self, instance_id: &Byte32) -> Result<Vec<VoteCellData>, Error> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT data FROM cells WHERE instance_id = ? AND kind = ? ORDER BY rowid")?;
+        let rows = statement.query_map(
+            params![instance_id.as_slice(), CellKind::Vote as i64],
+            |row| row.get::<_, Vec<u8>>(0),
+        )?;
+
+        let mut vote_cells: Vec<VoteCellData> = vec![];
+        for data in rows {
+            let vote_data = VoteCellData::from_slice(&data?).ok_or(Error::InvalidRow)?;
+            vote_cells.retain(|vote_cell| {
+                (vote_cell.vote_option_type, vote_cell.shard_index)
+                    != (vote_data.vote_option_type, vote_data.shard_index)
+            });
+            vote_cells.push(vote_data);
+        }
+        vote_cells.sort_by_key(|vote_cell| (vote_cell.vote_option_type, vote_cell.shard_index));
+
+        Ok(vote_cells)
+    }
+
+    pub fn ballots(&self, instance_id: &Byte32) -> Result<Vec<Ballot>, Error> {
+        self.query_ballots(
+            "SELECT tx_hash, block_number, instance_id, voter_lock_hash, vote_option_type, votes
+            FROM ballots WHERE instance_id = ? ORDER BY rowid",
+            instance_id,
+        )
+    }
+
+    /// Ballots cast by the lock, in the order they were cast.
+    pub fn voter_ballots(&self, voter_lock_hash: &Byte32) -> Result<Vec<Ballot>, Error> {
+        self.query_ballots(
+            "SELECT tx_hash, block_number, instance_id, voter_lock_hash, vote_option_type, votes
+            FROM ballots WHERE voter_lock_hash = ? ORDER BY rowid",
+            voter_lock_hash,
+        )
+    }
+
+    /// Receipts issued to the lock, in the order they were issued.
+    pub fn voter_receipts(&self, owner_lock_hash: &Byte32) -> Result<Vec<Receipt>, Error> {
+        let mut statement = self.conn.prepare(
+            "SELECT receipts.tx_hash, receipts.output_index, receipts.block_number, receipts.instance_id,
+                receipts.vote_option_type, receipts.amount, cells.spent_block_number
+            FROM receipts JOIN cells
+                ON cells.tx_hash = receipts.tx_hash AND cells.output_index = receipts.output_index
+            WHERE receipts.owner_lock_hash = ? ORDER BY receipts.rowid",
+        )?;
+        let rows = statement.query_map(params![owner_lock_hash.as_slice()], |row| {
+            Ok((
+                row.get::<_, Vec<u8>>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, Option<i64>>(6)?,
+            ))
+        })?;
+
+        let mut receipts = vec![];
+        for row in rows {
+            let (
+                tx_hash,
+                output_index,
+                block_number,
+                instance_id,
+                vote_option_type,
+                amount,
+                spent_block_number,
+            ) = row?;
+            receipts.push(Receipt {
+                out_point: OutPoint::new(to_byte32(tx_hash)?, output_index as u32),
+                block_number: block_number as u64,
+                instance_id: to_byte32(instance_id)?,
+                owner_lock_hash: owner_lock_hash.clone(),
+                vote_option_type: vote_option_type as u8,
+                amount: to_u128(amount)?,
+                spent_block_number: spent_block_number.map(|number| number as u64),
+            });
+        }
+
+        Ok(receipts)
+    }
+
+    fn query_ballots(&self, sql: &str, key: &Byte32) -> Result<Vec<Ballot>, Error> {
+        let mut statement = self.conn.prepare(sql)?;
+        let rows = statement.query_map(params![key.as_slice()], |row| {
+            Ok((
+                row.get::<_, Vec<u8>>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?;
+
+        let mut ballots = vec![];
+        for row in rows {
+            let (tx_hash, block_number, instance_id, voter_lock_hash, vote_option_type, votes) =
+                row?;
+            ballots.push(Ballot {
+                tx_hash: to_byte32(tx_hash)?,
+                block_number: block_number as u64,
+                instance_id: to_byte32(instance_id)?,
+                voter_lock_hash: to_byte32(voter_lock_hash)?,
+                vote_option_type: vote_option_type as u8,
+                votes: to_u128(votes)?,
+            });
+        }
+
+        Ok(ballots)
+    }
+}
+
+// The Core Cell created last is the current one, spent or not.
+fn core_data(conn: &Connection, instance_id: &Byte32) -> Result<Option<CoreCellData>, Error> {
+    let data: Option<Vec<u8>> = conn
+        .query_row(
+            "SELECT data FROM cells WHERE instance_id = ? AND kind = ? ORDER BY rowid DESC LIMIT 1",
+            params![instance_id.as_slice(), CellKind::Core as i64],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match data {
+        Some(data) => Ok(Some(
+            CoreCellData::from_slice(&data).ok_or(Error::InvalidRow)?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Writes the rows of a single block.
+pub struct StoreTransaction<'a> {
+    tx: Transaction<'a>,
+}
+
+impl<'a> StoreTransaction<'a> {
+    pub fn insert_block(&self, block: &BlockView) -> Result<(), Error> {
+        self.tx.execute(
+            "INSERT INTO blocks (number, hash) VALUES (?, ?)",
+            params![block.number() as i64, block.hash().as_slice()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Current Core Cell data of the vote, including the rows written so far.
+    pub fn core_data(&self, instance_id: &Byte32) -> Result<Option<CoreCellData>, Error> {
+        core_data(&self.tx, instance_id)
+    }
+
+    /// Mark the indexed cell at the out point as spent, and return it.
+    pub fn spend_cell(
+        &self,
+        out_point: &OutPoint,
+        block_number: u64,
+    ) -> Result<Option<IndexedCell>, Error> {
+        let tx_hash = out_point.tx_hash();
+        let index: u32 = out_point.index().unpack();
+        let cell = self
+            .tx
+            .query_row(
+                "SELECT kind, instance_id, lock_hash, data FROM cells
+                WHERE tx_hash = ? AND output_index = ? AND spent_block_number IS NULL",
+                params![tx_hash.as_slice(), index as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<Vec<u8>>>(1)?,
+                        row.get::<_, Vec<u8>>(2)?,
+                        row.get::<_, Vec<u8>>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let (kind, instance_id, lock_hash, data) = match cell {
+            Some(cell) => cell,
+            None => return Ok(None),
+        };
+
+        self.tx.execute(
+            "UPDATE cells SET spent_block_number = ? WHERE tx_hash = ? AND output_index = ?",
+            params![block_number as i64, tx_hash.as_slice(), index as i64],
+        )?;
+
+        Ok(Some(IndexedCell {
+            kind: CellKind::from_i64(kind).ok_or(Error::InvalidRow)?,
+            instance_id: instance_id.map(to_byte32).transpose()?,
+            lock_hash: to_byte32(lock_hash)?,
+            data,
+        }))
+    }
+
+    pub fn insert_cell(
+        &self,
+        out_point: &OutPoint,
+        block_number: u64,
+        cell: &IndexedCell,
+    ) -> Result<(), Error> {
+        let index: u32 = out_point.index().unpack();
+        self.tx.execute(
+            "INSERT INTO cells (tx_hash, output_index, block_number, kind, instance_id, lock_hash, data)
+            VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                out_point.tx_hash().as_slice(),
+                index as i64,
+                block_number as i64,
+                cell.kind as i64,
+                cell.instance_id.as_ref().map(|instance_id| instance_id.as_slice().to_vec()),
+                cell.lock_hash.as_slice(),
+                cell.data,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Record the creation of a vote, unless it's known already.
+    pub fn insert_vote(
+        &self,
+        instance_id: &Byte32,
+        tx_hash: &Byte32,
+        block_number: u64,
+    ) -> Result<(), Error> {
+        self.tx.execute(
+            "INSERT OR IGNORE INTO votes (instance_id, tx_hash, block_number) VALUES (?, ?, ?)",
+            params![
+                instance_id.as_slice(),
+                tx_hash.as_slice(),
+                block_number as i64
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn insert_ballot(&self, ballot: &Ballot) -> Result<(), Error> {
+        self.tx.execute(
+            "INSERT INTO ballots (tx_hash, block_number, instance_id, voter_lock_hash, vote_option_type, votes)
+            VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                ballot.tx_hash.as_slice(),
+                ballot.block_number as i64,
+                ballot.instance_id.as_slice(),
+                ballot.voter_lock_hash.as_slice(),
+                ballot.vote_option_type as i64,
+                ballot.votes.to_string(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn insert_receipt(&self, receipt: &Receipt) -> Result<(), Error> {
+        let index: u32 = receipt.out_point.index().unpack();
+        self.tx.execute(
+            "INSERT INTO receipts (tx_hash, output_index, block_number, instance_id, owner_lock_hash, vote_option_type, amount)
+            VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                receipt.out_point.tx_hash().as_slice(),
+                index as i64,
+                receipt.block_number as i64,
+                receipt.instance_id.as_slice(),
+                receipt.owner_lock_hash.as_slice(),
+                receipt.vote_option_type as i64,
+                receipt.amount.to_string(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn commit(self) -> Result<(), Error> {
+        Ok(self.tx.commit()?)
+    }
+}
@@ -0,0 +1,108 @@
+//! Data layout of the cells and witnesses of a vote.
+//!
+//! dao-core writes the Core Cell, Vote Cells and Receipt Cells, and the treasury, vote-escrow and vote-intent locks
+//! and host code read them. All of them take the offsets from here, so a new field only has to be added once.
+//! Numbers are stored little endian. See the README for the meaning of every field.
+
+#![no_std]
+
+// Sizes
+pub const BLAKE2B256_HASH_BYTESIZE: usize = 32; // Number of bytes for a Blake2b-256 hash.
+pub const CODE_HASH_BYTESIZE: usize = 32;
+pub const U64_BYTESIZE: usize = 8;
+pub const U128_BYTESIZE: usize = 16;
+
+// Core Cell data layout.
+pub const TOKEN_CODE_HASH_OFFSET: usize = 0;
+pub const VOTE_TITLE_OFFSET: usize = TOKEN_CODE_HASH_OFFSET + CODE_HASH_BYTESIZE;
+pub const VOTE_TITLE_BYTESIZE: usize = 32;
+pub const TOTAL_DISTRIBUTED_TOKENS_OFFSET: usize = VOTE_TITLE_OFFSET + VOTE_TITLE_BYTESIZE;
+pub const TOTAL_DISTRIBUTED_TOKENS_BYTESIZE: usize = U128_BYTESIZE;
+pub const IS_VOTING_FINISHED_OFFSET: usize =
+    TOTAL_DISTRIBUTED_TOKENS_OFFSET + TOTAL_DISTRIBUTED_TOKENS_BYTESIZE;
+pub const IS_VOTING_FINISHED_BYTESIZE: usize = 1;
+pub const VOTE_RESULT_OPTION_TYPE_OFFSET: usize =
+    IS_VOTING_FINISHED_OFFSET + IS_VOTING_FINISHED_BYTESIZE;
+pub const VOTE_RESULT_OPTION_TYPE_BYTESIZE: usize = 1;
+pub const VOTING_WEIGHT_MODE_OFFSET: usize =
+    VOTE_RESULT_OPTION_TYPE_OFFSET + VOTE_RESULT_OPTION_TYPE_BYTESIZE;
+pub const VOTING_WEIGHT_MODE_BYTESIZE: usize = 1;
+pub const SNAPSHOT_BLOCK_NUMBER_OFFSET: usize =
+    VOTING_WEIGHT_MODE_OFFSET + VOTING_WEIGHT_MODE_BYTESIZE;
+pub const SNAPSHOT_BLOCK_NUMBER_BYTESIZE: usize = U64_BYTESIZE;
+pub const TOKEN_ID_MODE_OFFSET: usize =
+    SNAPSHOT_BLOCK_NUMBER_OFFSET + SNAPSHOT_BLOCK_NUMBER_BYTESIZE;
+pub const TOKEN_ID_MODE_BYTESIZE: usize = 1;
+pub const TOKEN_HASH_TYPE_OFFSET: usize = TOKEN_ID_MODE_OFFSET + TOKEN_ID_MODE_BYTESIZE;
+pub const TOKEN_HASH_TYPE_BYTESIZE: usize = 1;
+pub const ACTION_HASH_OFFSET: usize = TOKEN_HASH_TYPE_OFFSET + TOKEN_HASH_TYPE_BYTESIZE;
+pub const ACTION_HASH_BYTESIZE: usize = BLAKE2B256_HASH_BYTESIZE;
+pub const EXECUTION_DELAY_OFFSET: usize = ACTION_HASH_OFFSET + ACTION_HASH_BYTESIZE;
+pub const EXECUTION_DELAY_BYTESIZE: usize = U64_BYTESIZE;
+pub const SETTLEMENT_BLOCK_NUMBER_OFFSET: usize = EXECUTION_DELAY_OFFSET + EXECUTION_DELAY_BYTESIZE;
+pub const SETTLEMENT_BLOCK_NUMBER_BYTESIZE: usize = U64_BYTESIZE;
+pub const ORGANIZER_LOCK_HASH_OFFSET: usize =
+    SETTLEMENT_BLOCK_NUMBER_OFFSET + SETTLEMENT_BLOCK_NUMBER_BYTESIZE;
+pub const ORGANIZER_LOCK_HASH_BYTESIZE: usize = BLAKE2B256_HASH_BYTESIZE;
+pub const ORGANIZER_MODE_OFFSET: usize = ORGANIZER_LOCK_HASH_OFFSET + ORGANIZER_LOCK_HASH_BYTESIZE;
+pub const ORGANIZER_MODE_BYTESIZE: usize = 1;
+pub const ESCROW_LOCK_HASH_OFFSET: usize = ORGANIZER_MODE_OFFSET + ORGANIZER_MODE_BYTESIZE;
+pub const ESCROW_LOCK_HASH_BYTESIZE: usize = BLAKE2B256_HASH_BYTESIZE;
+pub const VOTING_END_BLOCK_NUMBER_OFFSET: usize =
+    ESCROW_LOCK_HASH_OFFSET + ESCROW_LOCK_HASH_BYTESIZE;
+pub const VOTING_END_BLOCK_NUMBER_BYTESIZE: usize = U64_BYTESIZE;
+pub const MAX_EXTENSION_OFFSET: usize =
+    VOTING_END_BLOCK_NUMBER_OFFSET + VOTING_END_BLOCK_NUMBER_BYTESIZE;
+pub const MAX_EXTENSION_BYTESIZE: usize = U64_BYTESIZE;
+pub const TOTAL_EXTENSION_OFFSET: usize = MAX_EXTENSION_OFFSET + MAX_EXTENSION_BYTESIZE;
+pub const TOTAL_EXTENSION_BYTESIZE: usize = U64_BYTESIZE;
+pub const VOTE_SHARD_COUNT_OFFSET: usize = TOTAL_EXTENSION_OFFSET + TOTAL_EXTENSION_BYTESIZE;
+pub const VOTE_SHARD_COUNT_BYTESIZE: usize = 1;
+pub const CORE_DATA_LEN: usize = VOTE_SHARD_COUNT_OFFSET + VOTE_SHARD_COUNT_BYTESIZE; // Number of bytes required for Core Cell data. (231 bytes)
+
+// Vote Cell data layout.
+pub const VOTE_OPTION_TYPE_OFFSET: usize = 0;
+pub const VOTE_OPTION_TYPE_BYTESIZE: usize = 1;
+pub const TOTAL_VOTES_COLLECTED_OFFSET: usize = VOTE_OPTION_TYPE_OFFSET + VOTE_OPTION_TYPE_BYTESIZE;
+pub const TOTAL_VOTES_COLLECTED_BYTESIZE: usize = U128_BYTESIZE;
+pub const SHARD_INDEX_OFFSET: usize = TOTAL_VOTES_COLLECTED_OFFSET + TOTAL_VOTES_COLLECTED_BYTESIZE;
+pub const SHARD_INDEX_BYTESIZE: usize = 1;
+pub const VOTE_DATA_LEN: usize = SHARD_INDEX_OFFSET + SHARD_INDEX_BYTESIZE; // Number of bytes required for Vote Cell data. (18 bytes)
+
+// Receipt Cell data layout.
+pub const RECEIPT_VOTE_OPTION_TYPE_OFFSET: usize = 0;
+pub const RECEIPT_VOTE_OPTION_TYPE_BYTESIZE: usize = 1;
+pub const RECEIPT_AMOUNT_OFFSET: usize =
+    RECEIPT_VOTE_OPTION_TYPE_OFFSET + RECEIPT_VOTE_OPTION_TYPE_BYTESIZE;
+pub const RECEIPT_AMOUNT_BYTESIZE: usize = U128_BYTESIZE;
+pub const RECEIPT_OWNER_LOCK_HASH_OFFSET: usize = RECEIPT_AMOUNT_OFFSET + RECEIPT_AMOUNT_BYTESIZE;
+pub const RECEIPT_OWNER_LOCK_HASH_BYTESIZE: usize = BLAKE2B256_HASH_BYTESIZE;
+pub const RECEIPT_DATA_LEN: usize =
+    RECEIPT_OWNER_LOCK_HASH_OFFSET + RECEIPT_OWNER_LOCK_HASH_BYTESIZE; // Number of bytes required for Receipt Cell data. (49 bytes)
+
+// Vote intent layout. Intents are passed in the witness of the first Vote Cell of a batched vote.
+pub const INTENT_VOTE_OPTION_TYPE_OFFSET: usize = 0;
+pub const INTENT_VOTE_OPTION_TYPE_BYTESIZE: usize = 1;
+pub const INTENT_AMOUNT_OFFSET: usize =
+    INTENT_VOTE_OPTION_TYPE_OFFSET + INTENT_VOTE_OPTION_TYPE_BYTESIZE;
+pub const INTENT_AMOUNT_BYTESIZE: usize = U128_BYTESIZE;
+pub const INTENT_OUT_POINT_OFFSET: usize = INTENT_AMOUNT_OFFSET + INTENT_AMOUNT_BYTESIZE; // Out point of the token cell spent by the intent.
+pub const INTENT_OUT_POINT_BYTESIZE: usize = 36; // Transaction hash followed by the output index as a u32.
+pub const INTENT_OWNER_LOCK_HASH_OFFSET: usize =
+    INTENT_OUT_POINT_OFFSET + INTENT_OUT_POINT_BYTESIZE; // Lock receipts for the tokens are issued to.
+pub const INTENT_OWNER_LOCK_HASH_BYTESIZE: usize = BLAKE2B256_HASH_BYTESIZE;
+pub const INTENT_SIGNATURE_OFFSET: usize =
+    INTENT_OWNER_LOCK_HASH_OFFSET + INTENT_OWNER_LOCK_HASH_BYTESIZE;
+pub const INTENT_SIGNATURE_BYTESIZE: usize = 65; // Recoverable secp256k1 signature of the hash of all other fields.
+pub const INTENT_BYTESIZE: usize = INTENT_SIGNATURE_OFFSET + INTENT_SIGNATURE_BYTESIZE; // Number of bytes of a vote intent. (150 bytes)
+
+// Voting statuses, stored in IS_VOTING_FINISHED.
+pub const VOTING_STATUS_OPEN: u8 = 0;
+pub const VOTING_STATUS_FINISHED: u8 = 1;
+pub const VOTING_STATUS_CANCELLED: u8 = 2;
+pub const VOTING_STATUS_EXECUTED: u8 = 3; // Finished, and the treasury released the funds of the vote.
+
+// Vote options.
+pub const VOTE_OPTION_NO: u8 = 0;
+pub const VOTE_OPTION_YES: u8 = 1;
+pub const VOTE_OPTIONS: [u8; 2] = [VOTE_OPTION_NO, VOTE_OPTION_YES];
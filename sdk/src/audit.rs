@@ -0,0 +1,193 @@
+//! Auditing the token supply of a vote.
+//!
+//! The sUDT can be minted by its owner at any time, including while a vote is open. The auditor follows the
+//! chain from genesis, keeps track of every cell of the token counted by the vote, and reports every mint after
+//! the vote was created. See the Known issues section of the README.
+
+use std::collections::HashMap;
+
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    core::BlockView,
+    packed::{Byte32, OutPoint, Script},
+    prelude::*,
+};
+
+use crate::data::*;
+use crate::{Error, LiveCell};
+
+/// Blocks of the main chain, from a node or a snapshot.
+pub trait BlockSource {
+    fn tip_block_number(&self) -> Result<u64, Error>;
+
+    fn block(&self, number: u64) -> Result<Option<BlockView>, Error>;
+}
+
+/// Blocks kept in memory, e.g. saved from a local chain.
+pub struct ChainSnapshot {
+    blocks: Vec<BlockView>,
+}
+
+impl ChainSnapshot {
+    pub fn new(blocks: Vec<BlockView>) -> Self {
+        ChainSnapshot { blocks }
+    }
+
+    /// Load a JSON list of blocks, in the format returned by the get_block_by_number RPC.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let blocks: Vec<json_types::BlockView> =
+            serde_json::from_str(json).map_err(|err| Error::BlockSource(err.to_string()))?;
+
+        Ok(ChainSnapshot::new(
+            blocks.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    pub fn blocks(&self) -> &[BlockView] {
+        &self.blocks
+    }
+
+    /// The cell created at the out point, whether it was spent or not.
+    pub fn cell(&self, out_point: &OutPoint) -> Option<LiveCell> {
+        let index: u32 = out_point.index().unpack();
+
+        self.blocks
+            .iter()
+            .flat_map(|block| block.transactions())
+            .find(|tx| tx.hash() == out_point.tx_hash())
+            .and_then(|tx| tx.output_with_data(index as usize))
+            .map(|(output, data)| LiveCell::new(out_point.clone(), output, data))
+    }
+}
+
+impl BlockSource for ChainSnapshot {
+    fn tip_block_number(&self) -> Result<u64, Error> {
+        Ok(self
+            .blocks
+            .iter()
+            .map(|block| block.number())
+            .max()
+            .unwrap_or(0))
+    }
+
+    fn block(&self, number: u64) -> Result<Option<BlockView>, Error> {
+        Ok(self
+            .blocks
+            .iter()
+            .find(|block| block.number() == number)
+            .cloned())
+    }
+}
+
+/// Tokens created by a transaction which didn't consume as many.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mint {
+    pub tx_hash: Byte32,
+    pub block_number: u64,
+    pub amount: u128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditReport {
+    pub creation_tx_hash: Byte32,
+    pub creation_block_number: u64,
+    pub total_distributed_tokens: u128,
+    /// Tokens in live cells at the tip.
+    pub live_tokens: u128,
+    /// Tokens consumed without being recreated, e.g. to cast votes.
+    pub burned_tokens: u128,
+    /// Mints after the transaction creating the vote.
+    pub mints: Vec<Mint>,
+}
+
+impl AuditReport {
+    /// All tokens ever issued, live or spent.
+    pub fn issued_tokens(&self) -> u128 {
+        self.live_tokens.saturating_add(self.burned_tokens)
+    }
+
+    /// Whether the supply of the token matches TOTAL_DISTRIBUTED_TOKENS and nothing was minted since the vote
+    /// was created.
+    pub fn is_supply_consistent(&self) -> bool {
+        self.issued_tokens() == self.total_distributed_tokens && self.mints.is_empty()
+    }
+}
+
+/// Audit the token supply of the vote of the Core Cell, from genesis to the tip of the source.
+pub fn audit_vote(source: &impl BlockSource, core_cell: &LiveCell) -> Result<AuditReport, Error> {
+    let core_data = CoreCellData::from_slice(&core_cell.data).ok_or(Error::InvalidCellData)?;
+    let core_type_script: Script = core_cell
+        .output
+        .type_()
+        .to_opt()
+        .ok_or(Error::InvalidCellData)?;
+
+    let mut token_cells: HashMap<OutPoint, u128> = HashMap::new();
+    let mut creation: Option<(Byte32, u64)> = None;
+    let mut burned_tokens: u128 = 0;
+    let mut mints = vec![];
+
+    for number in 0..=source.tip_block_number()? {
+        let block = match source.block(number)? {
+            Some(block) => block,
+            None => continue,
+        };
+
+        for tx in block.transactions() {
+            let mut consumed: u128 = 0;
+            for input in tx.inputs() {
+                if let Some(amount) = token_cells.remove(&input.previous_output()) {
+                    consumed = consumed.checked_add(amount).ok_or(Error::AmountOverflow)?;
+                }
+            }
+
+            let mut created: u128 = 0;
+            let mut creates_vote = false;
+            for (index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+                let type_script = match output.type_().to_opt() {
+                    Some(type_script) => type_script,
+                    None => continue,
+                };
+
+                if type_script == core_type_script && data.len() == CORE_DATA_LEN {
+                    creates_vote = true;
+                } else if core_data.is_token(&type_script) {
+                    let amount = parse_token_amount(&data).ok_or(Error::InvalidCellData)?;
+                    created = created.checked_add(amount).ok_or(Error::AmountOverflow)?;
+                    token_cells.insert(OutPoint::new(tx.hash(), index as u32), amount);
+                }
+            }
+
+            if creates_vote && creation.is_none() {
+                creation = Some((tx.hash(), block.number()));
+            } else if creation.is_some() && created > consumed {
+                mints.push(Mint {
+                    tx_hash: tx.hash(),
+                    block_number: block.number(),
+                    amount: created - consumed,
+                });
+            }
+
+            if consumed > created {
+                burned_tokens = burned_tokens
+                    .checked_add(consumed - created)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+        }
+    }
+
+    let (creation_tx_hash, creation_block_number) = creation.ok_or(Error::VoteNotFound)?;
+    let live_tokens = token_cells
+        .values()
+        .try_fold(0u128, |total, amount| total.checked_add(*amount))
+        .ok_or(Error::AmountOverflow)?;
+
+    Ok(AuditReport {
+        creation_tx_hash,
+        creation_block_number,
+        total_distributed_tokens: core_data.total_distributed_tokens,
+        live_tokens,
+        burned_tokens,
+        mints,
+    })
+}
@@ -0,0 +1,798 @@
+use std::iter;
+
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{HeaderView, TransactionBuilder, TransactionView},
+    packed::{self, Byte32, CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs},
+    prelude::*,
+};
+
+use crate::capacity::*;
+use crate::data::*;
+use crate::{DeployedScript, Error, LiveCell};
+
+fn build_input(out_point: &OutPoint) -> CellInput {
+    CellInput::new_builder()
+        .previous_output(out_point.clone())
+        .build()
+}
+
+// Without a capacity, the output gets the capacity occupied by it and its data.
+fn build_output(
+    capacity: Option<u64>,
+    lock: &Script,
+    type_script: &Script,
+    data: &Bytes,
+) -> Result<CellOutput, Error> {
+    let output = CellOutput::new_builder()
+        .lock(lock.clone())
+        .type_(Some(type_script.clone()).pack())
+        .build();
+    let capacity = match capacity {
+        Some(capacity) => capacity,
+        None => occupied_capacity(&output, data.len())?,
+    };
+
+    Ok(output.as_builder().capacity(capacity.pack()).build())
+}
+
+// Balance the transaction when capacity cells were given, otherwise it is left to the wallet.
+fn complete_tx(
+    tx: TransactionView,
+    input_cell: &LiveCell,
+    capacity_cells: &[LiveCell],
+    change_lock: &Option<Script>,
+    fee_rate: u64,
+) -> Result<TransactionView, Error> {
+    if capacity_cells.is_empty() {
+        return Ok(tx);
+    }
+
+    let change_lock = change_lock
+        .clone()
+        .unwrap_or_else(|| input_cell.output.lock());
+    balance_tx(
+        tx,
+        std::slice::from_ref(input_cell),
+        capacity_cells,
+        &change_lock,
+        fee_rate,
+    )
+}
+
+// Return the capacity freed by the transaction to the change lock, less the fee. Capacity cells are spent when
+// the freed capacity doesn't cover the fee and a change cell. Without capacity cells such a transaction is left
+// to the wallet to balance.
+fn return_change(
+    tx: TransactionView,
+    input_cells: &[LiveCell],
+    capacity_cells: &[LiveCell],
+    change_lock: &Script,
+    fee_rate: u64,
+) -> Result<TransactionView, Error> {
+    match balance_tx(
+        tx.clone(),
+        input_cells,
+        capacity_cells,
+        change_lock,
+        fee_rate,
+    ) {
+        Err(Error::InsufficientCapacity) if capacity_cells.is_empty() => Ok(tx),
+        result => result,
+    }
+}
+
+fn token_data(amount: u128) -> Bytes {
+    Bytes::from(amount.to_le_bytes().to_vec())
+}
+
+/// Mints sUDT tokens to the recipients. The token is owned by the lock of the owner cell, which is spent to
+/// prove ownership.
+pub struct MintTokens {
+    sudt: DeployedScript,
+    owner_cell: LiveCell,
+    recipients: Vec<(Script, u128)>,
+    capacity: Option<u64>,
+    capacity_cells: Vec<LiveCell>,
+    change_lock: Option<Script>,
+    fee_rate: u64,
+    cell_deps: Vec<CellDep>,
+}
+
+impl MintTokens {
+    pub fn new(sudt: &DeployedScript, owner_cell: LiveCell) -> Self {
+        MintTokens {
+            sudt: sudt.clone(),
+            owner_cell,
+            recipients: vec![],
+            capacity: None,
+            capacity_cells: vec![],
+            change_lock: None,
+            fee_rate: DEFAULT_FEE_RATE,
+            cell_deps: vec![],
+        }
+    }
+
+    pub fn token_type_script(&self) -> Script {
+        let owner_lock_hash = self.owner_cell.output.lock().calc_script_hash();
+        self.sudt.script(owner_lock_hash.raw_data())
+    }
+
+    pub fn recipient(mut self, lock: Script, amount: u128) -> Self {
+        self.recipients.push((lock, amount));
+        self
+    }
+
+    /// Capacity of every token cell created. Defaults to the capacity occupied by the cell.
+    pub fn capacity(mut self, capacity: u64) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Adds a cell paying for the token cells and the fee. Capacity cells are spent in order, as far as needed.
+    pub fn capacity_cell(mut self, capacity_cell: LiveCell) -> Self {
+        self.capacity_cells.push(capacity_cell);
+        self
+    }
+
+    /// Lock of the change cell. Defaults to the lock of the owner cell.
+    pub fn change_lock(mut self, lock: Script) -> Self {
+        self.change_lock = Some(lock);
+        self
+    }
+
+    /// Fee rate in shannons per 1,000 bytes, paid from the capacity cells.
+    pub fn fee_rate(mut self, fee_rate: u64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// Adds a cell dep, for example for the lock of the owner cell.
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    pub fn build(&self) -> Result<TransactionView, Error> {
+        let token_type_script = self.token_type_script();
+        let mut outputs = vec![];
+        let mut outputs_data = vec![];
+        for (lock, amount) in &self.recipients {
+            let data = token_data(*amount);
+            outputs.push(build_output(
+                self.capacity,
+                lock,
+                &token_type_script,
+                &data,
+            )?);
+            outputs_data.push(data);
+        }
+
+        let tx = TransactionBuilder::default()
+            .input(build_input(&self.owner_cell.out_point))
+            .outputs(outputs)
+            .outputs_data(outputs_data.pack())
+            .cell_dep(self.sudt.cell_dep())
+            .cell_deps(self.cell_deps.clone())
+            .build();
+
+        complete_tx(
+            tx,
+            &self.owner_cell,
+            &self.capacity_cells,
+            &self.change_lock,
+            self.fee_rate,
+        )
+    }
+}
+
+/// Creates a vote counting a token owned by the seed cell's lock. The Core Cell comes first, followed by the
+/// Vote Cells of every shard and the tokens distributed to the voters.
+pub struct CreateVote {
+    dao_core: DeployedScript,
+    sudt: DeployedScript,
+    seed_cell: LiveCell,
+    lock: Script,
+    core_data: CoreCellData,
+    voters: Vec<(Script, u128)>,
+    capacity: Option<u64>,
+    capacity_cells: Vec<LiveCell>,
+    change_lock: Option<Script>,
+    fee_rate: u64,
+    cell_deps: Vec<CellDep>,
+}
+
+impl CreateVote {
+    pub fn new(dao_core: &DeployedScript, sudt: &DeployedScript, seed_cell: LiveCell) -> Self {
+        CreateVote {
+            dao_core: dao_core.clone(),
+            sudt: sudt.clone(),
+            lock: seed_cell.output.lock(),
+            seed_cell,
+            core_data: CoreCellData::new([0u8; 32], 0),
+            voters: vec![],
+            capacity: None,
+            capacity_cells: vec![],
+            change_lock: None,
+            fee_rate: DEFAULT_FEE_RATE,
+            cell_deps: vec![],
+        }
+    }
+
+    /// The type script shared by the Core Cell and Vote Cells of the vote.
+    pub fn type_script(&self) -> Script {
+        let instance_id = calculate_instance_id(&self.seed_cell.out_point, 0);
+        self.dao_core.script(Bytes::from(instance_id.to_vec()))
+    }
+
+    pub fn token_type_script(&self) -> Script {
+        let owner_lock_hash = self.seed_cell.output.lock().calc_script_hash();
+        self.sudt.script(owner_lock_hash.raw_data())
+    }
+
+    pub fn title(mut self, vote_title: &str) -> Self {
+        self.core_data.vote_title = vote_title.to_string();
+        self
+    }
+
+    /// Settings of the vote. The token and the number of distributed tokens are filled in when building.
+    pub fn core_data(mut self, core_data: CoreCellData) -> Self {
+        self.core_data = core_data;
+        self
+    }
+
+    /// Lock of the Core Cell and Vote Cells. Defaults to the lock of the seed cell.
+    pub fn lock(mut self, lock: Script) -> Self {
+        self.lock = lock;
+        self
+    }
+
+    pub fn voter(mut self, lock: Script, amount: u128) -> Self {
+        self.voters.push((lock, amount));
+        self
+    }
+
+    /// Capacity of every cell created. Defaults to the capacity occupied by the cell.
+    pub fn capacity(mut self, capacity: u64) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Adds a cell paying for the new cells and the fee. Capacity cells are spent in order, as far as needed.
+    pub fn capacity_cell(mut self, capacity_cell: LiveCell) -> Self {
+        self.capacity_cells.push(capacity_cell);
+        self
+    }
+
+    /// Lock of the change cell. Defaults to the lock of the seed cell.
+    pub fn change_lock(mut self, lock: Script) -> Self {
+        self.change_lock = Some(lock);
+        self
+    }
+
+    /// Fee rate in shannons per 1,000 bytes, paid from the capacity cells.
+    pub fn fee_rate(mut self, fee_rate: u64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    /// Minimum capacity of the Core Cell, every Vote Cell and every token cell of the voters, and the fee of the
+    /// transaction at the fee rate. The fee includes the capacity cells spent and the change cell when capacity
+    /// cells were given, and a signature for every lock of the inputs.
+    pub fn required_capacity(&self) -> Result<VoteCapacity, Error> {
+        let (outputs, outputs_data) = self.outputs(None)?;
+        let mut capacities = outputs
+            .iter()
+            .zip(outputs_data.iter())
+            .map(|(output, data)| occupied_capacity(output, data.len()))
+            .collect::<Result<Vec<u64>, Error>>()?;
+        let token_cells = capacities.split_off(capacities.len() - self.voters.len());
+        let vote_cells = capacities.split_off(1);
+
+        let tx = self.build()?;
+        let input_locks: Vec<Script> = iter::once(&self.seed_cell)
+            .chain(self.capacity_cells.iter())
+            .take(tx.inputs().len())
+            .map(|cell| cell.output.lock())
+            .collect();
+        let fee = transaction_fee(
+            &with_signature_placeholders(&tx, &input_locks),
+            self.fee_rate,
+        );
+
+        Ok(VoteCapacity {
+            core_cell: capacities[0],
+            vote_cells,
+            token_cells,
+            fee,
+        })
+    }
+
+    // The Core Cell, Vote Cells and token cells with their data.
+    fn outputs(&self, capacity: Option<u64>) -> Result<(Vec<CellOutput>, Vec<Bytes>), Error> {
+        let type_script = self.type_script();
+        let token_type_script = self.token_type_script();
+
+        let mut core_data = self.core_data.clone();
+        core_data.token_code_hash = match core_data.token_id_mode {
+            TOKEN_ID_MODE_CODE_HASH => {
+                core_data.token_hash_type = token_type_script.hash_type().as_slice()[0];
+                token_type_script.code_hash().unpack()
+            }
+            _ => token_type_script.calc_script_hash().unpack(),
+        };
+
+        // With a snapshot the tokens were distributed before, so the supply is taken from the settings.
+        if core_data.voting_weight_mode == VOTING_WEIGHT_MODE_DISTRIBUTED {
+            core_data.total_distributed_tokens = self
+                .voters
+                .iter()
+                .try_fold(0u128, |total, (_, amount)| total.checked_add(*amount))
+                .ok_or(Error::AmountOverflow)?;
+        }
+
+        let data = core_data.to_bytes();
+        let mut outputs = vec![build_output(capacity, &self.lock, &type_script, &data)?];
+        let mut outputs_data = vec![data];
+
+        for shard_index in 0..core_data.vote_shard_count {
+            for vote_option_type in VOTE_OPTIONS.iter() {
+                let data = VoteCellData::new(*vote_option_type, shard_index, 0).to_bytes();
+                outputs.push(build_output(capacity, &self.lock, &type_script, &data)?);
+                outputs_data.push(data);
+            }
+        }
+
+        for (lock, amount) in &self.voters {
+            let data = token_data(*amount);
+            outputs.push(build_output(capacity, lock, &token_type_script, &data)?);
+            outputs_data.push(data);
+        }
+
+        Ok((outputs, outputs_data))
+    }
+
+    pub fn build(&self) -> Result<TransactionView, Error> {
+        let (outputs, outputs_data) = self.outputs(self.capacity)?;
+        let tx = TransactionBuilder::default()
+            .input(build_input(&self.seed_cell.out_point))
+            .outputs(outputs)
+            .outputs_data(outputs_data.pack())
+            .cell_dep(self.dao_core.cell_dep())
+            .cell_dep(self.sudt.cell_dep())
+            .cell_deps(self.cell_deps.clone())
+            .build();
+
+        complete_tx(
+            tx,
+            &self.seed_cell,
+            &self.capacity_cells,
+            &self.change_lock,
+            self.fee_rate,
+        )
+    }
+}
+
+/// Casts votes with the tokens of the token cells. The tally of the Vote Cell grows by the votes cast. Depending
+/// on the vote the tokens are burned, moved to the vote escrow with a Receipt Cell for every lock that spent
+/// them, or all returned after counting the snapshot balance. Remaining tokens are returned to the lock of the
+/// first token cell, and the capacity freed by the token cells, less the fee, to the change lock.
+pub struct CastVote {
+    dao_core: DeployedScript,
+    sudt: DeployedScript,
+    core_cell: LiveCell,
+    vote_cell: LiveCell,
+    token_cells: Vec<LiveCell>,
+    votes: Option<u128>,
+    vote_escrow: Option<DeployedScript>,
+    capacity_cells: Vec<LiveCell>,
+    change_lock: Option<Script>,
+    fee_rate: u64,
+    header_deps: Vec<Byte32>,
+    cell_deps: Vec<CellDep>,
+}
+
+impl CastVote {
+    pub fn new(
+        dao_core: &DeployedScript,
+        sudt: &DeployedScript,
+        core_cell: LiveCell,
+        vote_cell: LiveCell,
+    ) -> Self {
+        CastVote {
+            dao_core: dao_core.clone(),
+            sudt: sudt.clone(),
+            core_cell,
+            vote_cell,
+            token_cells: vec![],
+            votes: None,
+            vote_escrow: None,
+            capacity_cells: vec![],
+            change_lock: None,
+            fee_rate: DEFAULT_FEE_RATE,
+            header_deps: vec![],
+            cell_deps: vec![],
+        }
+    }
+
+    pub fn token_cell(mut self, token_cell: LiveCell) -> Self {
+        self.token_cells.push(token_cell);
+        self
+    }
+
+    /// Number of votes cast. Defaults to all tokens of the token cells, which snapshot votes have to cast.
+    pub fn votes(mut self, votes: u128) -> Self {
+        self.votes = Some(votes);
+        self
+    }
+
+    /// The vote-escrow script, needed when the vote holds the tokens in escrow.
+    pub fn vote_escrow(mut self, vote_escrow: &DeployedScript) -> Self {
+        self.vote_escrow = Some(vote_escrow.clone());
+        self
+    }
+
+    /// Adds a cell paying for the new cells and the fee, when the capacity of the token cells doesn't cover them.
+    pub fn capacity_cell(mut self, capacity_cell: LiveCell) -> Self {
+        self.capacity_cells.push(capacity_cell);
+        self
+    }
+
+    /// Lock of the change cell. Defaults to the lock of the first token cell.
+    pub fn change_lock(mut self, lock: Script) -> Self {
+        self.change_lock = Some(lock);
+        self
+    }
+
+    /// Fee rate in shannons per 1,000 bytes, paid from the freed capacity or the capacity cells.
+    pub fn fee_rate(mut self, fee_rate: u64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// Adds a header dep. Once the vote has a voting end, the header of the block the Vote Cell was created
+    /// in is needed. Snapshot votes need the headers of the token cells and one at or after the snapshot.
+    pub fn header_dep(mut self, block_hash: Byte32) -> Self {
+        self.header_deps.push(block_hash);
+        self
+    }
+
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    pub fn build(&self) -> Result<TransactionView, Error> {
+        let core_data =
+            CoreCellData::from_slice(&self.core_cell.data).ok_or(Error::InvalidCellData)?;
+        let mut vote_data =
+            VoteCellData::from_slice(&self.vote_cell.data).ok_or(Error::InvalidCellData)?;
+        let token_cell = self.token_cells.first().ok_or(Error::InsufficientTokens)?;
+        let token_lock = token_cell.output.lock();
+        let token_type_script = token_cell
+            .output
+            .type_()
+            .to_opt()
+            .ok_or(Error::InvalidCellData)?;
+
+        // Tokens spent by every lock, in the order of the token cells.
+        let mut inputs = vec![build_input(&self.vote_cell.out_point)];
+        let mut token_amount: u128 = 0;
+        let mut owner_amounts: Vec<(Script, u128)> = vec![];
+        for token_cell in &self.token_cells {
+            let amount = parse_token_amount(&token_cell.data).ok_or(Error::InvalidCellData)?;
+            token_amount = token_amount
+                .checked_add(amount)
+                .ok_or(Error::AmountOverflow)?;
+            match owner_amounts
+                .iter_mut()
+                .find(|(lock, _)| *lock == token_cell.output.lock())
+            {
+                Some((_, owner_amount)) => *owner_amount += amount,
+                None => owner_amounts.push((token_cell.output.lock(), amount)),
+            }
+            inputs.push(build_input(&token_cell.out_point));
+        }
+
+        let votes = self.votes.unwrap_or(token_amount);
+        if votes > token_amount {
+            return Err(Error::InsufficientTokens);
+        }
+
+        // Snapshot votes count the balance of the token cells, which is returned in full.
+        let is_snapshot = core_data.voting_weight_mode == VOTING_WEIGHT_MODE_SNAPSHOT;
+        if is_snapshot && votes != token_amount {
+            return Err(Error::PartialSnapshotVote);
+        }
+        let uses_escrow = core_data.escrow_lock_hash != [0u8; 32] && !is_snapshot;
+        let returned_amount = match is_snapshot {
+            true => token_amount,
+            false => token_amount - votes,
+        };
+
+        vote_data.total_votes_collected = vote_data
+            .total_votes_collected
+            .checked_add(votes)
+            .ok_or(Error::AmountOverflow)?;
+
+        let mut outputs = vec![self.vote_cell.output.clone()];
+        let mut outputs_data = vec![vote_data.to_bytes()];
+
+        if returned_amount > 0 {
+            let data = token_data(returned_amount);
+            outputs.push(build_output(None, &token_lock, &token_type_script, &data)?);
+            outputs_data.push(data);
+        }
+
+        if uses_escrow && votes > 0 {
+            let type_script = self
+                .vote_cell
+                .output
+                .type_()
+                .to_opt()
+                .ok_or(Error::InvalidCellData)?;
+            let escrow_lock = self
+                .vote_escrow
+                .as_ref()
+                .ok_or(Error::InvalidEscrowLock)?
+                .script(type_script.calc_script_hash().raw_data());
+            let escrow_lock_hash: [u8; 32] = escrow_lock.calc_script_hash().unpack();
+            if escrow_lock_hash != core_data.escrow_lock_hash {
+                return Err(Error::InvalidEscrowLock);
+            }
+
+            let data = token_data(votes);
+            outputs.push(build_output(None, &escrow_lock, &token_type_script, &data)?);
+            outputs_data.push(data);
+
+            // Every lock gets a receipt for the tokens it spent, less the tokens returned to it.
+            for (lock, amount) in &owner_amounts {
+                let amount = match *lock == token_lock {
+                    true => amount
+                        .checked_sub(returned_amount)
+                        .ok_or(Error::InsufficientTokens)?,
+                    false => *amount,
+                };
+                if amount == 0 {
+                    continue;
+                }
+
+                let data = ReceiptCellData::new(
+                    vote_data.vote_option_type,
+                    amount,
+                    lock.calc_script_hash().unpack(),
+                )
+                .to_bytes();
+                outputs.push(build_output(None, lock, &type_script, &data)?);
+                outputs_data.push(data);
+            }
+        }
+
+        // The Vote Cell and token cells can share a block.
+        let mut header_deps: Vec<Byte32> = vec![];
+        for block_hash in &self.header_deps {
+            if !header_deps.contains(block_hash) {
+                header_deps.push(block_hash.clone());
+            }
+        }
+
+        let tx = TransactionBuilder::default()
+            .inputs(inputs)
+            .outputs(outputs)
+            .outputs_data(outputs_data.pack())
+            .header_deps(header_deps)
+            .cell_dep(self.dao_core.cell_dep())
+            .cell_dep(self.sudt.cell_dep())
+            .cell_dep(
+                CellDep::new_builder()
+                    .out_point(self.core_cell.out_point.clone())
+                    .build(),
+            )
+            .cell_deps(self.cell_deps.clone())
+            .build();
+
+        let input_cells: Vec<LiveCell> = iter::once(&self.vote_cell)
+            .chain(self.token_cells.iter())
+            .cloned()
+            .collect();
+        let change_lock = self.change_lock.clone().unwrap_or(token_lock);
+        return_change(
+            tx,
+            &input_cells,
+            &self.capacity_cells,
+            &change_lock,
+            self.fee_rate,
+        )
+    }
+}
+
+/// Finishes a vote by collecting all Vote Cells. The headers of the blocks every cell was created in are
+/// needed to determine the settlement block.
+pub struct FinishVote {
+    dao_core: DeployedScript,
+    core_cell: (LiveCell, HeaderView),
+    vote_cells: Vec<(LiveCell, HeaderView)>,
+    casting_vote: Option<u8>,
+    organizer_cell: Option<LiveCell>,
+    organizer_multisig: Option<Bytes>,
+    header_deps: Vec<Byte32>,
+    cell_deps: Vec<CellDep>,
+}
+
+impl FinishVote {
+    pub fn new(dao_core: &DeployedScript, core_cell: LiveCell, header: &HeaderView) -> Self {
+        FinishVote {
+            dao_core: dao_core.clone(),
+            core_cell: (core_cell, header.clone()),
+            vote_cells: vec![],
+            casting_vote: None,
+            organizer_cell: None,
+            organizer_multisig: None,
+            header_deps: vec![],
+            cell_deps: vec![],
+        }
+    }
+
+    pub fn vote_cell(mut self, vote_cell: LiveCell, header: &HeaderView) -> Self {
+        self.vote_cells.push((vote_cell, header.clone()));
+        self
+    }
+
+    /// Result used when no option reached a majority. The organizer has to authorize the transaction.
+    pub fn casting_vote(mut self, vote_option_type: u8) -> Self {
+        self.casting_vote = Some(vote_option_type);
+        self
+    }
+
+    /// Authorizes the organizer of a vote in lock mode by spending a cell with the organizer lock. The cell is
+    /// recreated unchanged.
+    pub fn organizer_input(mut self, organizer_cell: LiveCell) -> Self {
+        self.organizer_cell = Some(organizer_cell);
+        self
+    }
+
+    /// Authorizes the organizer of a vote in multisig mode. The config is the threshold, the number of keys and
+    /// the blake160 hashes of the keys, and is signed with `sign_as_organizer`.
+    pub fn organizer_multisig(mut self, multisig_config: Bytes) -> Self {
+        self.organizer_multisig = Some(multisig_config);
+        self
+    }
+
+    /// Adds a header dep, for example a header at or after the voting end.
+    pub fn header_dep(mut self, block_hash: Byte32) -> Self {
+        self.header_deps.push(block_hash);
+        self
+    }
+
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    /// Determine the result of the vote from the tallies of the Vote Cells.
+    pub fn vote_result(&self) -> Result<u8, Error> {
+        let core_data =
+            CoreCellData::from_slice(&self.core_cell.0.data).ok_or(Error::InvalidCellData)?;
+        let majority_threshold = core_data
+            .total_distributed_tokens
+            .checked_mul(MAJORITY_PERCENTAGE)
+            .ok_or(Error::AmountOverflow)?;
+
+        for vote_option_type in VOTE_OPTIONS.iter() {
+            let mut option_tally: u128 = 0;
+            for (vote_cell, _) in &self.vote_cells {
+                let vote_data =
+                    VoteCellData::from_slice(&vote_cell.data).ok_or(Error::InvalidCellData)?;
+                if vote_data.vote_option_type == *vote_option_type {
+                    option_tally = option_tally
+                        .checked_add(vote_data.total_votes_collected)
+                        .ok_or(Error::AmountOverflow)?;
+                }
+            }
+
+            if option_tally.saturating_mul(100) >= majority_threshold {
+                return Ok(*vote_option_type);
+            }
+        }
+
+        self.casting_vote.ok_or(Error::NoMajority)
+    }
+
+    pub fn build(&self) -> Result<TransactionView, Error> {
+        let vote_result_option_type = self.vote_result()?;
+        let (core_cell, _) = &self.core_cell;
+
+        // The Core Cell is spent first, followed by all Vote Cells.
+        let cells: Vec<&(LiveCell, HeaderView)> = iter::once(&self.core_cell)
+            .chain(self.vote_cells.iter())
+            .collect();
+        let settlement_block_number = cells
+            .iter()
+            .map(|(_, header)| header.number())
+            .max()
+            .unwrap_or(0);
+
+        let mut header_deps: Vec<Byte32> = vec![];
+        for block_hash in cells
+            .iter()
+            .map(|(_, header)| header.hash())
+            .chain(self.header_deps.iter().cloned())
+        {
+            if !header_deps.contains(&block_hash) {
+                header_deps.push(block_hash);
+            }
+        }
+
+        let mut finished_data = core_cell.data.to_vec();
+        finished_data[IS_VOTING_FINISHED_OFFSET] = VOTING_STATUS_FINISHED;
+        finished_data[VOTE_RESULT_OPTION_TYPE_OFFSET] = vote_result_option_type;
+        finished_data[SETTLEMENT_BLOCK_NUMBER_OFFSET..SETTLEMENT_BLOCK_NUMBER_OFFSET + 8]
+            .copy_from_slice(&settlement_block_number.to_le_bytes());
+
+        let mut builder = TransactionBuilder::default()
+            .inputs(
+                cells
+                    .iter()
+                    .map(|(cell, _)| build_input(&cell.out_point))
+                    .collect::<Vec<_>>(),
+            )
+            .output(core_cell.output.clone())
+            .output_data(Bytes::from(finished_data).pack())
+            .cell_dep(self.dao_core.cell_dep())
+            .cell_deps(self.cell_deps.clone())
+            .header_deps(header_deps);
+
+        // The organizer cell is spent after the Vote Cells.
+        if let Some(organizer_cell) = &self.organizer_cell {
+            builder = builder
+                .input(build_input(&organizer_cell.out_point))
+                .output(organizer_cell.output.clone())
+                .output_data(organizer_cell.data.pack());
+        }
+
+        // The signatures follow the config in the witness of the Core Cell.
+        if let Some(multisig_config) = &self.organizer_multisig {
+            let witness_args = WitnessArgs::new_builder()
+                .input_type(Some(multisig_config.clone()).pack())
+                .build();
+            builder = builder.witness(witness_args.as_bytes().pack());
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// Add the signature of the key to the organizer witness of a transaction built by `FinishVote` with a multisig
+/// config. The transaction hash is signed, so sign after the transaction is balanced, but before the inputs are
+/// signed by their locks.
+pub fn sign_as_organizer(tx: TransactionView, key: &Privkey) -> Result<TransactionView, Error> {
+    let mut witnesses: Vec<packed::Bytes> = tx.witnesses().into_iter().collect();
+    let witness_args = witnesses
+        .first()
+        .and_then(|witness| WitnessArgs::from_slice(&witness.raw_data()).ok())
+        .ok_or(Error::MissingOrganizerWitness)?;
+    let mut organizer_witness: Vec<u8> = witness_args
+        .input_type()
+        .to_opt()
+        .map(|input_type| input_type.raw_data().to_vec())
+        .ok_or(Error::MissingOrganizerWitness)?;
+
+    let signature = key
+        .sign_recoverable(&tx.hash().unpack())
+        .map_err(|_| Error::InvalidSignature)?;
+    organizer_witness.extend_from_slice(&signature.serialize());
+    witnesses[0] = witness_args
+        .as_builder()
+        .input_type(Some(Bytes::from(organizer_witness)).pack())
+        .build()
+        .as_bytes()
+        .pack();
+
+    Ok(tx.as_advanced_builder().set_witnesses(witnesses).build())
+}
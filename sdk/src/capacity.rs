@@ -0,0 +1,153 @@
+//! Capacity and fees of transactions.
+//!
+//! Every cell has to hold at least the capacity occupied by its fields and data, one CKByte per byte. The fee of
+//! a transaction is charged by its size in the block, including the signatures the wallet adds later.
+
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionView},
+    packed::{self, CellInput, CellOutput, Script, WitnessArgs},
+    prelude::*,
+};
+
+use crate::{Error, LiveCell};
+
+/// Fee rate in shannons per 1,000 bytes, the minimum accepted by the transaction pool by default.
+pub const DEFAULT_FEE_RATE: u64 = 1_000;
+
+// Size of a secp256k1 recoverable signature, in the lock of the first witness of every lock group.
+const SIGNATURE_BYTESIZE: usize = 65;
+
+/// Capacity occupied by the output with data of the length, in shannons.
+pub fn occupied_capacity(output: &CellOutput, data_len: usize) -> Result<u64, Error> {
+    let data_capacity = Capacity::bytes(data_len).map_err(|_| Error::InsufficientCapacity)?;
+    let capacity = output
+        .occupied_capacity(data_capacity)
+        .map_err(|_| Error::InsufficientCapacity)?;
+
+    Ok(capacity.as_u64())
+}
+
+/// Fee of the transaction at the fee rate, rounded up to the next shannon.
+pub fn transaction_fee(tx: &TransactionView, fee_rate: u64) -> u64 {
+    let size = tx.data().serialized_size_in_block() as u64;
+
+    size.saturating_mul(fee_rate).div_ceil(1_000)
+}
+
+/// Reserve a signature in the first witness of every lock group, so the fee covers the signed transaction.
+/// The input locks have to be in the order of the inputs of the transaction.
+pub fn with_signature_placeholders(
+    tx: &TransactionView,
+    input_locks: &[Script],
+) -> TransactionView {
+    let mut witnesses: Vec<Bytes> = tx.witnesses().into_iter().map(|w| w.unpack()).collect();
+    if witnesses.len() < input_locks.len() {
+        witnesses.resize(input_locks.len(), Bytes::new());
+    }
+
+    for (index, lock) in input_locks.iter().enumerate() {
+        let is_first_of_group = !input_locks[..index].contains(lock);
+        if is_first_of_group && witnesses[index].is_empty() {
+            witnesses[index] = WitnessArgs::new_builder()
+                .lock(Some(Bytes::from(vec![0u8; SIGNATURE_BYTESIZE])).pack())
+                .build()
+                .as_bytes();
+        }
+    }
+
+    tx.as_advanced_builder()
+        .set_witnesses(
+            witnesses
+                .into_iter()
+                .map(|witness| witness.pack())
+                .collect::<Vec<packed::Bytes>>(),
+        )
+        .build()
+}
+
+pub(crate) fn total_capacity(outputs: impl IntoIterator<Item = CellOutput>) -> Result<u64, Error> {
+    outputs.into_iter().try_fold(0u64, |total, output| {
+        let capacity: Capacity = output.capacity().unpack();
+        total
+            .checked_add(capacity.as_u64())
+            .ok_or(Error::InsufficientCapacity)
+    })
+}
+
+/// Pay for the outputs and the fee at the fee rate. Capacity cells are added as inputs, in order, until they
+/// cover the outputs, the fee and a change cell with the change lock. The input cells are the cells already
+/// spent by the transaction.
+pub fn balance_tx(
+    tx: TransactionView,
+    input_cells: &[LiveCell],
+    capacity_cells: &[LiveCell],
+    change_lock: &Script,
+    fee_rate: u64,
+) -> Result<TransactionView, Error> {
+    let output_capacity = total_capacity(tx.outputs())?;
+    let change_output = CellOutput::new_builder().lock(change_lock.clone()).build();
+    let change_occupied_capacity = occupied_capacity(&change_output, 0)?;
+
+    for spent in 0..=capacity_cells.len() {
+        let cells: Vec<&LiveCell> = input_cells
+            .iter()
+            .chain(capacity_cells[..spent].iter())
+            .collect();
+        let input_capacity = total_capacity(cells.iter().map(|cell| cell.output.clone()))?;
+        let input_locks: Vec<Script> = cells.iter().map(|cell| cell.output.lock()).collect();
+
+        // The capacity of the change cell doesn't change the size of the transaction.
+        let balanced_tx = |change: u64| {
+            tx.as_advanced_builder()
+                .inputs(capacity_cells[..spent].iter().map(|cell| {
+                    CellInput::new_builder()
+                        .previous_output(cell.out_point.clone())
+                        .build()
+                }))
+                .output(
+                    change_output
+                        .clone()
+                        .as_builder()
+                        .capacity(change.pack())
+                        .build(),
+                )
+                .output_data(Bytes::new().pack())
+                .build()
+        };
+        let fee = transaction_fee(
+            &with_signature_placeholders(&balanced_tx(0), &input_locks),
+            fee_rate,
+        );
+
+        let change = output_capacity
+            .checked_add(fee)
+            .and_then(|required_capacity| input_capacity.checked_sub(required_capacity));
+        if let Some(change) = change.filter(|change| *change >= change_occupied_capacity) {
+            return Ok(balanced_tx(change));
+        }
+    }
+
+    Err(Error::InsufficientCapacity)
+}
+
+/// Minimum capacity of every cell created by a vote, and the fee of the transaction creating it, in shannons.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoteCapacity {
+    pub core_cell: u64,
+    /// Vote Cells, in the order of the outputs.
+    pub vote_cells: Vec<u64>,
+    /// Token cells of the voters, in the order of the voters.
+    pub token_cells: Vec<u64>,
+    pub fee: u64,
+}
+
+impl VoteCapacity {
+    /// Capacity the organizer has to provide for the vote.
+    pub fn total(&self) -> u64 {
+        self.vote_cells.iter().chain(self.token_cells.iter()).fold(
+            self.core_cell.saturating_add(self.fee),
+            |total, capacity| total.saturating_add(*capacity),
+        )
+    }
+}
@@ -0,0 +1,277 @@
+use ckb_tool::ckb_hash::{new_blake2b, BLAKE2B_LEN};
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    packed::{OutPoint, Script},
+    prelude::*,
+};
+
+// Cell data layout, shared with the contracts. See the Core Cell section of the README.
+use ckb_voting_layout::*;
+pub use ckb_voting_layout::{
+    CORE_DATA_LEN, RECEIPT_DATA_LEN, VOTE_DATA_LEN, VOTE_OPTIONS, VOTE_OPTION_NO, VOTE_OPTION_YES,
+    VOTING_STATUS_CANCELLED, VOTING_STATUS_EXECUTED, VOTING_STATUS_FINISHED, VOTING_STATUS_OPEN,
+};
+pub(crate) use ckb_voting_layout::{
+    IS_VOTING_FINISHED_OFFSET, SETTLEMENT_BLOCK_NUMBER_OFFSET, VOTE_RESULT_OPTION_TYPE_OFFSET,
+};
+
+// Voting Weight Modes
+pub const VOTING_WEIGHT_MODE_DISTRIBUTED: u8 = 0;
+pub const VOTING_WEIGHT_MODE_SNAPSHOT: u8 = 1;
+
+// Token Id Modes
+pub const TOKEN_ID_MODE_TYPE_HASH: u8 = 0;
+pub const TOKEN_ID_MODE_CODE_HASH: u8 = 1;
+
+// Organizer Modes
+pub const ORGANIZER_MODE_LOCK: u8 = 0;
+pub const ORGANIZER_MODE_MULTISIG: u8 = 1;
+
+// Percentage of all distributed tokens an option needs to win the vote.
+pub const MAJORITY_PERCENTAGE: u128 = 51;
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(buffer)
+}
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    let mut buffer = [0u8; 16];
+    buffer.copy_from_slice(&data[offset..offset + 16]);
+    u128::from_le_bytes(buffer)
+}
+
+fn read_hash(data: &[u8], offset: usize) -> [u8; 32] {
+    let mut buffer = [0u8; 32];
+    buffer.copy_from_slice(&data[offset..offset + 32]);
+    buffer
+}
+
+/// Calculate the instance id of a vote, which is used as the args of its type script. It is derived from
+/// the first input of the creating transaction and the index of the Core Cell among its outputs.
+pub fn calculate_instance_id(seed_cell_outpoint: &OutPoint, output_index: usize) -> [u8; 32] {
+    let mut blake2b = new_blake2b();
+
+    blake2b.update(&seed_cell_outpoint.tx_hash().raw_data());
+    blake2b.update(&seed_cell_outpoint.index().raw_data());
+    blake2b.update(&(output_index as u32).to_le_bytes());
+
+    let mut hash: [u8; BLAKE2B_LEN] = [0; BLAKE2B_LEN];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+/// Data stored in the Core Cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoreCellData {
+    pub token_code_hash: [u8; 32],
+    pub vote_title: String,
+    pub total_distributed_tokens: u128,
+    pub is_voting_finished: u8,
+    pub vote_result_option_type: u8,
+    pub voting_weight_mode: u8,
+    pub snapshot_block_number: u64,
+    pub token_id_mode: u8,
+    pub token_hash_type: u8,
+    pub action_hash: [u8; 32],
+    pub execution_delay: u64,
+    pub settlement_block_number: u64,
+    pub organizer_lock_hash: [u8; 32],
+    pub organizer_mode: u8,
+    pub escrow_lock_hash: [u8; 32],
+    pub voting_end_block_number: u64,
+    pub max_extension: u64,
+    pub total_extension: u64,
+    pub vote_shard_count: u8,
+}
+
+impl CoreCellData {
+    pub fn new(token_code_hash: [u8; 32], total_distributed_tokens: u128) -> Self {
+        CoreCellData {
+            token_code_hash,
+            vote_title: String::from("Should Christmas last all year?"),
+            total_distributed_tokens,
+            is_voting_finished: VOTING_STATUS_OPEN,
+            vote_result_option_type: 0,
+            voting_weight_mode: VOTING_WEIGHT_MODE_DISTRIBUTED,
+            snapshot_block_number: 0,
+            token_id_mode: TOKEN_ID_MODE_TYPE_HASH,
+            token_hash_type: 0,
+            action_hash: [0u8; 32],
+            execution_delay: 0,
+            settlement_block_number: 0,
+            organizer_lock_hash: [0u8; 32],
+            organizer_mode: ORGANIZER_MODE_LOCK,
+            escrow_lock_hash: [0u8; 32],
+            voting_end_block_number: 0,
+            max_extension: 0,
+            total_extension: 0,
+            vote_shard_count: 1,
+        }
+    }
+
+    /// Decode the data of a Core Cell. The title is padded with spaces, which are removed.
+    pub fn from_slice(data: &[u8]) -> Option<Self> {
+        if data.len() != CORE_DATA_LEN {
+            return None;
+        }
+
+        let vote_title = String::from_utf8_lossy(
+            &data[VOTE_TITLE_OFFSET..VOTE_TITLE_OFFSET + VOTE_TITLE_BYTESIZE],
+        )
+        .trim_end()
+        .to_string();
+
+        Some(CoreCellData {
+            token_code_hash: read_hash(data, TOKEN_CODE_HASH_OFFSET),
+            vote_title,
+            total_distributed_tokens: read_u128(data, TOTAL_DISTRIBUTED_TOKENS_OFFSET),
+            is_voting_finished: data[IS_VOTING_FINISHED_OFFSET],
+            vote_result_option_type: data[VOTE_RESULT_OPTION_TYPE_OFFSET],
+            voting_weight_mode: data[VOTING_WEIGHT_MODE_OFFSET],
+            snapshot_block_number: read_u64(data, SNAPSHOT_BLOCK_NUMBER_OFFSET),
+            token_id_mode: data[TOKEN_ID_MODE_OFFSET],
+            token_hash_type: data[TOKEN_HASH_TYPE_OFFSET],
+            action_hash: read_hash(data, ACTION_HASH_OFFSET),
+            execution_delay: read_u64(data, EXECUTION_DELAY_OFFSET),
+            settlement_block_number: read_u64(data, SETTLEMENT_BLOCK_NUMBER_OFFSET),
+            organizer_lock_hash: read_hash(data, ORGANIZER_LOCK_HASH_OFFSET),
+            organizer_mode: data[ORGANIZER_MODE_OFFSET],
+            escrow_lock_hash: read_hash(data, ESCROW_LOCK_HASH_OFFSET),
+            voting_end_block_number: read_u64(data, VOTING_END_BLOCK_NUMBER_OFFSET),
+            max_extension: read_u64(data, MAX_EXTENSION_OFFSET),
+            total_extension: read_u64(data, TOTAL_EXTENSION_OFFSET),
+            vote_shard_count: data[VOTE_SHARD_COUNT_OFFSET],
+        })
+    }
+
+    /// Whether the type script is the token counted by the vote, as identified by TOKEN_ID_MODE.
+    pub fn is_token(&self, type_script: &Script) -> bool {
+        match self.token_id_mode {
+            TOKEN_ID_MODE_CODE_HASH => {
+                type_script.code_hash().as_slice() == self.token_code_hash
+                    && type_script.hash_type().as_slice()[0] == self.token_hash_type
+            }
+            _ => type_script.calc_script_hash().as_slice() == self.token_code_hash,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut data: Vec<u8> = vec![];
+
+        data.extend_from_slice(&self.token_code_hash);
+
+        let mut vote_title = self.vote_title.clone();
+        while vote_title.len() < VOTE_TITLE_BYTESIZE {
+            vote_title += " ";
+        }
+        data.extend_from_slice(vote_title.as_bytes());
+
+        data.extend_from_slice(&self.total_distributed_tokens.to_le_bytes());
+        data.push(self.is_voting_finished);
+        data.push(self.vote_result_option_type);
+        data.push(self.voting_weight_mode);
+        data.extend_from_slice(&self.snapshot_block_number.to_le_bytes());
+        data.push(self.token_id_mode);
+        data.push(self.token_hash_type);
+        data.extend_from_slice(&self.action_hash);
+        data.extend_from_slice(&self.execution_delay.to_le_bytes());
+        data.extend_from_slice(&self.settlement_block_number.to_le_bytes());
+        data.extend_from_slice(&self.organizer_lock_hash);
+        data.push(self.organizer_mode);
+        data.extend_from_slice(&self.escrow_lock_hash);
+        data.extend_from_slice(&self.voting_end_block_number.to_le_bytes());
+        data.extend_from_slice(&self.max_extension.to_le_bytes());
+        data.extend_from_slice(&self.total_extension.to_le_bytes());
+        data.push(self.vote_shard_count);
+
+        Bytes::from(data)
+    }
+}
+
+/// Data stored in a Vote Cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoteCellData {
+    pub vote_option_type: u8,
+    pub total_votes_collected: u128,
+    pub shard_index: u8,
+}
+
+impl VoteCellData {
+    pub fn new(vote_option_type: u8, shard_index: u8, total_votes_collected: u128) -> Self {
+        VoteCellData {
+            vote_option_type,
+            total_votes_collected,
+            shard_index,
+        }
+    }
+
+    pub fn from_slice(data: &[u8]) -> Option<Self> {
+        if data.len() != VOTE_DATA_LEN {
+            return None;
+        }
+
+        Some(VoteCellData {
+            vote_option_type: data[VOTE_OPTION_TYPE_OFFSET],
+            total_votes_collected: read_u128(data, TOTAL_VOTES_COLLECTED_OFFSET),
+            shard_index: data[SHARD_INDEX_OFFSET],
+        })
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut data = vec![self.vote_option_type];
+        data.extend_from_slice(&self.total_votes_collected.to_le_bytes());
+        data.push(self.shard_index);
+
+        Bytes::from(data)
+    }
+}
+
+/// Data stored in a Receipt Cell, issued for tokens held in escrow while voting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceiptCellData {
+    pub vote_option_type: u8,
+    pub amount: u128,
+    pub owner_lock_hash: [u8; 32],
+}
+
+impl ReceiptCellData {
+    pub fn new(vote_option_type: u8, amount: u128, owner_lock_hash: [u8; 32]) -> Self {
+        ReceiptCellData {
+            vote_option_type,
+            amount,
+            owner_lock_hash,
+        }
+    }
+
+    pub fn from_slice(data: &[u8]) -> Option<Self> {
+        if data.len() != RECEIPT_DATA_LEN {
+            return None;
+        }
+
+        Some(ReceiptCellData {
+            vote_option_type: data[RECEIPT_VOTE_OPTION_TYPE_OFFSET],
+            amount: read_u128(data, RECEIPT_AMOUNT_OFFSET),
+            owner_lock_hash: read_hash(data, RECEIPT_OWNER_LOCK_HASH_OFFSET),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut data = vec![self.vote_option_type];
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        data.extend_from_slice(&self.owner_lock_hash);
+
+        Bytes::from(data)
+    }
+}
+
+/// Decode the amount of tokens stored in a sUDT cell.
+pub fn parse_token_amount(data: &[u8]) -> Option<u128> {
+    if data.len() < 16 {
+        return None;
+    }
+
+    Some(read_u128(data, 0))
+}
@@ -0,0 +1,1113 @@
+//! Explaining voting transactions.
+//!
+//! Every cell of a transaction is labelled with its role and its data is decoded. For every vote the transaction
+//! touches, the mode dao-core validates it in is determined. The checks of dao-core and sUDT which only depend on
+//! the transaction and the cells it spends are then run on the host, to predict the error code the scripts would
+//! return. Header deps, signatures, vote intents, treasury releases and the balances of snapshot votes are not
+//! checked, so a transaction without a predicted error can still fail.
+
+use std::fmt;
+
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionView},
+    packed::{Byte32, OutPoint, Script, WitnessArgs},
+    prelude::*,
+};
+
+use crate::data::*;
+use crate::script_error::{ContractError, DaoCoreError, SudtError};
+use crate::{DeployedScript, LiveCell};
+
+// sUDT args: owner lock hash, optionally followed by flags.
+const SUDT_OWNER_LOCK_HASH_BYTESIZE: usize = 32;
+const SUDT_FLAG_NON_TRANSFERABLE: u8 = 0b0000_0001;
+
+const HASH_TYPE_DATA: u8 = 0;
+const HASH_TYPE_TYPE: u8 = 1;
+
+/// Role of a cell in a voting transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellRole {
+    Core,
+    Vote,
+    Receipt,
+    /// A cell of the sUDT, held by a voter or the owner.
+    Token,
+    /// The first input of a transaction creating a vote, from which the instance id is derived.
+    Seed,
+    Other,
+    /// An input or cell dep which wasn't found.
+    Unresolved,
+}
+
+impl fmt::Display for CellRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CellRole::Core => "Core Cell",
+            CellRole::Vote => "Vote Cell",
+            CellRole::Receipt => "Receipt Cell",
+            CellRole::Token => "sUDT cell",
+            CellRole::Seed => "seed cell",
+            CellRole::Other => "other cell",
+            CellRole::Unresolved => "unresolved cell",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Data of a cell, decoded according to its role.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodedData {
+    Core(CoreCellData),
+    Vote(VoteCellData),
+    Receipt(ReceiptCellData),
+    Token(u128),
+    Raw(Bytes),
+}
+
+/// The mode of operation dao-core determines from the cells of a vote in the transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Burn,
+    Create,
+    Vote,
+    Finish,
+    Cancel,
+    Extend,
+    Execute,
+    Refund,
+}
+
+/// A cell of the transaction and what it holds.
+#[derive(Clone)]
+pub struct ExplainedCell {
+    pub out_point: OutPoint,
+    pub role: CellRole,
+    /// Not set for unresolved cells.
+    pub cell: Option<LiveCell>,
+    pub data: Option<DecodedData>,
+}
+
+/// A vote touched by the transaction, identified by the type script of its cells.
+#[derive(Clone)]
+pub struct ExplainedVote {
+    pub type_script: Script,
+    /// Not set if the mode couldn't be determined.
+    pub mode: Option<Mode>,
+    pub error: Option<ContractError>,
+}
+
+impl ExplainedVote {
+    pub fn instance_id(&self) -> Bytes {
+        self.type_script.args().raw_data()
+    }
+}
+
+/// A token touched by the transaction, identified by its type script.
+#[derive(Clone)]
+pub struct ExplainedToken {
+    pub type_script: Script,
+    pub input_amount: u128,
+    pub output_amount: u128,
+    pub error: Option<ContractError>,
+}
+
+pub struct Explanation {
+    pub tx_hash: Byte32,
+    pub inputs: Vec<ExplainedCell>,
+    pub outputs: Vec<ExplainedCell>,
+    pub cell_deps: Vec<ExplainedCell>,
+    pub votes: Vec<ExplainedVote>,
+    pub tokens: Vec<ExplainedToken>,
+}
+
+impl Explanation {
+    /// Whether every input was resolved. Modes and errors are only predicted if so.
+    pub fn is_complete(&self) -> bool {
+        self.inputs
+            .iter()
+            .all(|input| input.role != CellRole::Unresolved)
+    }
+
+    /// The first error predicted for a dao-core or sUDT script of the transaction.
+    pub fn predicted_error(&self) -> Option<ContractError> {
+        self.votes
+            .iter()
+            .filter_map(|vote| vote.error)
+            .chain(self.tokens.iter().filter_map(|token| token.error))
+            .next()
+    }
+}
+
+fn option_name(vote_option_type: u8) -> String {
+    match vote_option_type {
+        VOTE_OPTION_NO => String::from("no"),
+        VOTE_OPTION_YES => String::from("yes"),
+        vote_option_type => format!("unknown option {}", vote_option_type),
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    let digits: String = data.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("0x{}", digits)
+}
+
+fn write_cell(f: &mut fmt::Formatter, index: usize, cell: &ExplainedCell) -> fmt::Result {
+    write!(
+        f,
+        "  #{} {} {:#x}:{}",
+        index,
+        cell.role,
+        cell.out_point.tx_hash(),
+        Unpack::<u32>::unpack(&cell.out_point.index())
+    )?;
+    if let Some(cell) = &cell.cell {
+        let capacity: Capacity = cell.output.capacity().unpack();
+        write!(
+            f,
+            ", {} shannons, lock {:#x}",
+            capacity.as_u64(),
+            cell.output.lock().calc_script_hash()
+        )?;
+    }
+    writeln!(f)?;
+
+    match &cell.data {
+        Some(DecodedData::Core(core_data)) => {
+            let status = match core_data.is_voting_finished {
+                VOTING_STATUS_OPEN => String::from("open"),
+                VOTING_STATUS_FINISHED => format!(
+                    "finished with {} at block {}",
+                    option_name(core_data.vote_result_option_type),
+                    core_data.settlement_block_number
+                ),
+                VOTING_STATUS_CANCELLED => String::from("cancelled"),
+                VOTING_STATUS_EXECUTED => format!(
+                    "executed, finished with {} at block {}",
+                    option_name(core_data.vote_result_option_type),
+                    core_data.settlement_block_number
+                ),
+                status => format!("unknown status {}", status),
+            };
+            writeln!(f, "      title: {}", core_data.vote_title)?;
+            writeln!(f, "      status: {}", status)?;
+            writeln!(
+                f,
+                "      distributed tokens: {}",
+                core_data.total_distributed_tokens
+            )?;
+            writeln!(f, "      vote shards: {}", core_data.vote_shard_count)?;
+            if core_data.voting_end_block_number > 0 {
+                writeln!(
+                    f,
+                    "      voting end: {} (extended by {} of {})",
+                    core_data.voting_end_block_number,
+                    core_data.total_extension,
+                    core_data.max_extension
+                )?;
+            }
+        }
+        Some(DecodedData::Vote(vote_data)) => writeln!(
+            f,
+            "      {} (shard {}): {} votes",
+            option_name(vote_data.vote_option_type),
+            vote_data.shard_index,
+            vote_data.total_votes_collected
+        )?,
+        Some(DecodedData::Receipt(receipt_data)) => writeln!(
+            f,
+            "      {} tokens voted {} by {}",
+            receipt_data.amount,
+            option_name(receipt_data.vote_option_type),
+            hex(&receipt_data.owner_lock_hash)
+        )?,
+        Some(DecodedData::Token(amount)) => writeln!(f, "      {} tokens", amount)?,
+        Some(DecodedData::Raw(data)) if !data.is_empty() => {
+            writeln!(f, "      {} bytes of data", data.len())?
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "transaction {:#x}", self.tx_hash)?;
+        for (name, cells) in &[
+            ("cell deps", &self.cell_deps),
+            ("inputs", &self.inputs),
+            ("outputs", &self.outputs),
+        ] {
+            writeln!(f, "{}:", name)?;
+            for (index, cell) in cells.iter().enumerate() {
+                write_cell(f, index, cell)?;
+            }
+        }
+
+        for vote in &self.votes {
+            write!(f, "vote {}: ", hex(&vote.instance_id()))?;
+            match vote.mode {
+                Some(mode) => writeln!(f, "{:?} mode", mode)?,
+                None => writeln!(f, "mode unknown")?,
+            }
+        }
+        for token in &self.tokens {
+            writeln!(
+                f,
+                "token {:#x}: {} in, {} out",
+                token.type_script.calc_script_hash(),
+                token.input_amount,
+                token.output_amount
+            )?;
+        }
+
+        if !self.is_complete() {
+            writeln!(f, "not all inputs were found, no errors predicted")
+        } else {
+            match self.predicted_error() {
+                Some(error) => writeln!(f, "predicted failure: {}", error),
+                None => writeln!(f, "no errors predicted"),
+            }
+        }
+    }
+}
+
+fn type_script(cell: &LiveCell) -> Option<Script> {
+    cell.output.type_().to_opt()
+}
+
+fn has_type(cell: &LiveCell, script: &Script) -> bool {
+    type_script(cell).as_ref() == Some(script)
+}
+
+fn add(total: u128, amount: u128) -> Result<u128, ContractError> {
+    total
+        .checked_add(amount)
+        .ok_or(DaoCoreError::AmountOverflow.into())
+}
+
+fn decode(
+    cell: &LiveCell,
+    dao_core: &DeployedScript,
+    sudt: &DeployedScript,
+) -> (CellRole, DecodedData) {
+    let raw = || DecodedData::Raw(cell.data.clone());
+
+    match type_script(cell) {
+        Some(script) if dao_core.matches(&script) => match cell.data.len() {
+            CORE_DATA_LEN => (
+                CellRole::Core,
+                CoreCellData::from_slice(&cell.data).map_or_else(raw, DecodedData::Core),
+            ),
+            VOTE_DATA_LEN => (
+                CellRole::Vote,
+                VoteCellData::from_slice(&cell.data).map_or_else(raw, DecodedData::Vote),
+            ),
+            RECEIPT_DATA_LEN => (
+                CellRole::Receipt,
+                ReceiptCellData::from_slice(&cell.data).map_or_else(raw, DecodedData::Receipt),
+            ),
+            _ => (CellRole::Other, raw()),
+        },
+        Some(script) if sudt.matches(&script) => (
+            CellRole::Token,
+            parse_token_amount(&cell.data).map_or_else(raw, DecodedData::Token),
+        ),
+        _ => (CellRole::Other, raw()),
+    }
+}
+
+fn explain_cell(
+    out_point: OutPoint,
+    cell: Option<LiveCell>,
+    dao_core: &DeployedScript,
+    sudt: &DeployedScript,
+) -> ExplainedCell {
+    match cell {
+        Some(cell) => {
+            let (role, data) = decode(&cell, dao_core, sudt);
+            ExplainedCell {
+                out_point,
+                role,
+                cell: Some(cell),
+                data: Some(data),
+            }
+        }
+        None => ExplainedCell {
+            out_point,
+            role: CellRole::Unresolved,
+            cell: None,
+            data: None,
+        },
+    }
+}
+
+/// Cells of a vote as dao-core loads them from a group source.
+#[derive(Default)]
+struct GroupCells {
+    count: usize,
+    core_cells: Vec<(usize, CoreCellData)>,
+    vote_cells: Vec<VoteCellData>,
+    receipt_cells: Vec<ReceiptCellData>,
+}
+
+// Mirrors the checks of Cell::from_data in dao-core.
+fn load_group_cells<'a>(
+    cells: impl Iterator<Item = &'a LiveCell>,
+) -> Result<GroupCells, ContractError> {
+    let mut group = GroupCells::default();
+
+    for (index, cell) in cells.enumerate() {
+        group.count += 1;
+        match cell.data.len() {
+            CORE_DATA_LEN => {
+                let core_data = CoreCellData::from_slice(&cell.data)
+                    .ok_or(DaoCoreError::InvalidDataBytesize)?;
+                if core_data.voting_weight_mode != VOTING_WEIGHT_MODE_DISTRIBUTED
+                    && core_data.voting_weight_mode != VOTING_WEIGHT_MODE_SNAPSHOT
+                {
+                    return Err(DaoCoreError::InvalidVotingWeightMode.into());
+                }
+                if core_data.token_id_mode != TOKEN_ID_MODE_TYPE_HASH
+                    && core_data.token_id_mode != TOKEN_ID_MODE_CODE_HASH
+                {
+                    return Err(DaoCoreError::InvalidTokenIdMode.into());
+                }
+                if core_data.token_hash_type != HASH_TYPE_DATA
+                    && core_data.token_hash_type != HASH_TYPE_TYPE
+                {
+                    return Err(DaoCoreError::InvalidTokenHashType.into());
+                }
+                if core_data.organizer_mode != ORGANIZER_MODE_LOCK
+                    && core_data.organizer_mode != ORGANIZER_MODE_MULTISIG
+                {
+                    return Err(DaoCoreError::InvalidOrganizerMode.into());
+                }
+                group.core_cells.push((index, core_data));
+            }
+            VOTE_DATA_LEN => {
+                let vote_data = VoteCellData::from_slice(&cell.data)
+                    .ok_or(DaoCoreError::InvalidDataBytesize)?;
+                if !VOTE_OPTIONS.contains(&vote_data.vote_option_type) {
+                    return Err(DaoCoreError::InvalidVoteOption.into());
+                }
+                group.vote_cells.push(vote_data);
+            }
+            RECEIPT_DATA_LEN => {
+                let receipt_data = ReceiptCellData::from_slice(&cell.data)
+                    .ok_or(DaoCoreError::InvalidDataBytesize)?;
+                if !VOTE_OPTIONS.contains(&receipt_data.vote_option_type) {
+                    return Err(DaoCoreError::InvalidVoteOption.into());
+                }
+                group.receipt_cells.push(receipt_data);
+            }
+            _ => return Err(DaoCoreError::InvalidDataBytesize.into()),
+        }
+    }
+
+    Ok(group)
+}
+
+// Mirrors determine_mode in dao-core.
+fn determine_mode(inputs: &GroupCells, outputs: &GroupCells) -> Result<Mode, ContractError> {
+    if !inputs.core_cells.is_empty() && outputs.count == 0 {
+        return Ok(Mode::Burn);
+    }
+    if inputs.count == 0 && outputs.core_cells.len() == 1 {
+        return Ok(Mode::Create);
+    }
+    if inputs.core_cells.len() == 1
+        && !inputs.vote_cells.is_empty()
+        && outputs.core_cells.len() == 1
+        && outputs.count == 1
+    {
+        return Ok(Mode::Finish);
+    }
+    if inputs.core_cells.len() == 1
+        && inputs.count == 1
+        && outputs.core_cells.len() == 1
+        && outputs.count == 1
+    {
+        match outputs.core_cells[0].1.is_voting_finished {
+            VOTING_STATUS_CANCELLED => return Ok(Mode::Cancel),
+            VOTING_STATUS_EXECUTED => return Ok(Mode::Execute),
+            _ => {}
+        }
+
+        return Ok(Mode::Extend);
+    }
+    if inputs.core_cells.is_empty()
+        && outputs.core_cells.is_empty()
+        && !inputs.vote_cells.is_empty()
+        && inputs.count == inputs.vote_cells.len()
+        && inputs.count == outputs.vote_cells.len()
+    {
+        return Ok(Mode::Vote);
+    }
+    if !inputs.receipt_cells.is_empty()
+        && inputs.count == inputs.receipt_cells.len()
+        && outputs.count == 0
+    {
+        return Ok(Mode::Refund);
+    }
+
+    Err(DaoCoreError::InvalidTransactionStructure.into())
+}
+
+/// The transaction with every input resolved.
+struct ResolvedTx<'a> {
+    tx: &'a TransactionView,
+    inputs: Vec<LiveCell>,
+    outputs: Vec<LiveCell>,
+    cell_deps: Vec<LiveCell>,
+}
+
+impl ResolvedTx<'_> {
+    fn token_amount(
+        &self,
+        cells: &[LiveCell],
+        core_data: &CoreCellData,
+        lock_hash: Option<&[u8; 32]>,
+    ) -> Result<u128, ContractError> {
+        let mut amount = 0u128;
+
+        for cell in cells {
+            let is_token = type_script(cell).is_some_and(|script| core_data.is_token(&script));
+            let is_locked = lock_hash.is_none_or(|lock_hash| {
+                cell.output.lock().calc_script_hash().as_slice() == lock_hash
+            });
+
+            if is_token && is_locked {
+                let cell_amount =
+                    parse_token_amount(&cell.data).ok_or(DaoCoreError::InvalidTokenCellData)?;
+                amount = add(amount, cell_amount)?;
+            }
+        }
+
+        Ok(amount)
+    }
+
+    fn core_cell_dep(&self, type_script: &Script) -> Result<CoreCellData, ContractError> {
+        for cell in self
+            .cell_deps
+            .iter()
+            .filter(|cell| has_type(cell, type_script))
+        {
+            let group = load_group_cells(std::iter::once(cell))?;
+            if let Some((_, core_data)) = group.core_cells.into_iter().next() {
+                return Ok(core_data);
+            }
+        }
+
+        Err(DaoCoreError::CoreCellMissing.into())
+    }
+
+    // Only the organizer lock is checked. Multisig signatures are assumed to be valid if present.
+    fn is_organizer_authorized(
+        &self,
+        core_data: &CoreCellData,
+        type_script: &Script,
+        group_index: usize,
+    ) -> bool {
+        match core_data.organizer_mode {
+            ORGANIZER_MODE_MULTISIG => self
+                .inputs
+                .iter()
+                .enumerate()
+                .filter(|(_, cell)| has_type(cell, type_script))
+                .nth(group_index)
+                .and_then(|(index, _)| self.tx.witnesses().get(index))
+                .and_then(|witness| WitnessArgs::from_slice(&witness.raw_data()).ok())
+                .is_some_and(|witness_args| witness_args.input_type().is_some()),
+            _ => self.inputs.iter().any(|cell| {
+                cell.output.lock().calc_script_hash().as_slice() == core_data.organizer_lock_hash
+            }),
+        }
+    }
+
+    // Whether the vote intents of a batched vote are passed in the witness of the first group input.
+    fn has_vote_intents(&self, type_script: &Script) -> bool {
+        self.inputs
+            .iter()
+            .position(|cell| has_type(cell, type_script))
+            .and_then(|index| self.tx.witnesses().get(index))
+            .and_then(|witness| WitnessArgs::from_slice(&witness.raw_data()).ok())
+            .is_some_and(|witness_args| witness_args.input_type().is_some())
+    }
+
+    fn explain_vote(&self, type_script: &Script) -> (Option<Mode>, Option<ContractError>) {
+        if type_script.args().raw_data().len() < 32 {
+            return (None, Some(DaoCoreError::InvalidArgsLength.into()));
+        }
+
+        let group_inputs = load_group_cells(
+            self.inputs
+                .iter()
+                .filter(|cell| has_type(cell, type_script)),
+        );
+        let group_outputs = load_group_cells(
+            self.outputs
+                .iter()
+                .filter(|cell| has_type(cell, type_script)),
+        );
+        let (group_inputs, group_outputs) = match (group_inputs, group_outputs) {
+            (Ok(group_inputs), Ok(group_outputs)) => (group_inputs, group_outputs),
+            (Err(err), _) | (_, Err(err)) => return (None, Some(err)),
+        };
+
+        let mode = match determine_mode(&group_inputs, &group_outputs) {
+            Ok(mode) => mode,
+            Err(err) => return (None, Some(err)),
+        };
+        let result = match mode {
+            Mode::Burn => self.validate_burn(type_script, &group_inputs),
+            Mode::Create => self.validate_create(type_script, &group_outputs),
+            Mode::Vote => self.validate_vote(type_script, &group_inputs, &group_outputs),
+            Mode::Finish => self.validate_finish(type_script, &group_inputs, &group_outputs),
+            Mode::Cancel => self.validate_cancel(type_script, &group_inputs, &group_outputs),
+            Mode::Extend => self.validate_extend(type_script, &group_inputs, &group_outputs),
+            Mode::Execute => self.validate_execute(&group_inputs, &group_outputs),
+            Mode::Refund => self.validate_refund(type_script, &group_inputs),
+        };
+
+        (Some(mode), result.err())
+    }
+
+    fn validate_create(
+        &self,
+        type_script: &Script,
+        outputs: &GroupCells,
+    ) -> Result<(), ContractError> {
+        let (_, core_data) = &outputs.core_cells[0];
+
+        let seed_cell_outpoint = self
+            .tx
+            .inputs()
+            .get(0)
+            .ok_or(DaoCoreError::IndexOutOfBound)?
+            .previous_output();
+        let output_index = self
+            .outputs
+            .iter()
+            .position(|cell| has_type(cell, type_script) && cell.data.len() == CORE_DATA_LEN)
+            .ok_or(DaoCoreError::CoreCellMissing)?;
+        if type_script.args().raw_data()[..]
+            != calculate_instance_id(&seed_cell_outpoint, output_index)
+        {
+            return Err(DaoCoreError::InvalidInstanceId.into());
+        }
+
+        if core_data.is_voting_finished != VOTING_STATUS_OPEN
+            || core_data.vote_result_option_type != 0
+            || core_data.settlement_block_number != 0
+            || core_data.total_extension != 0
+        {
+            return Err(DaoCoreError::InvalidOutputCellData.into());
+        }
+        if !outputs.receipt_cells.is_empty() {
+            return Err(DaoCoreError::ReceiptMismatch.into());
+        }
+        // The escrow lock isn't checked, since the code of the vote escrow lock isn't known here.
+        if core_data.vote_shard_count == 0 {
+            return Err(DaoCoreError::InvalidShardCount.into());
+        }
+        validate_vote_shards(&outputs.vote_cells, core_data.vote_shard_count)?;
+        if outputs
+            .vote_cells
+            .iter()
+            .any(|vote_data| vote_data.total_votes_collected != 0)
+        {
+            return Err(DaoCoreError::InvalidVoteTally.into());
+        }
+
+        if core_data.voting_weight_mode == VOTING_WEIGHT_MODE_SNAPSHOT {
+            return Ok(());
+        }
+        if self.token_amount(&self.outputs, core_data, None)? != core_data.total_distributed_tokens
+        {
+            return Err(DaoCoreError::TokenDistributionMismatch.into());
+        }
+
+        Ok(())
+    }
+
+    fn validate_vote(
+        &self,
+        type_script: &Script,
+        inputs: &GroupCells,
+        outputs: &GroupCells,
+    ) -> Result<(), ContractError> {
+        let core_data = self.core_cell_dep(type_script)?;
+        if core_data.is_voting_finished != VOTING_STATUS_OPEN {
+            return Err(DaoCoreError::VotingFinished.into());
+        }
+
+        let uses_escrow = core_data.escrow_lock_hash != [0u8; 32]
+            && core_data.voting_weight_mode == VOTING_WEIGHT_MODE_DISTRIBUTED;
+        if !uses_escrow && !outputs.receipt_cells.is_empty() {
+            return Err(DaoCoreError::ReceiptMismatch.into());
+        }
+
+        let mut votes_cast = 0u128;
+        let mut option_votes_cast = [0u128; VOTE_OPTIONS.len()];
+        for (input, output) in inputs.vote_cells.iter().zip(outputs.vote_cells.iter()) {
+            if input.vote_option_type != output.vote_option_type {
+                return Err(DaoCoreError::InvalidVoteOption.into());
+            }
+            if input.shard_index != output.shard_index {
+                return Err(DaoCoreError::InvalidVoteShard.into());
+            }
+            if output.total_votes_collected < input.total_votes_collected {
+                return Err(DaoCoreError::InvalidVoteTally.into());
+            }
+
+            let shard_votes_cast = output.total_votes_collected - input.total_votes_collected;
+            let option_index = output.vote_option_type as usize;
+            votes_cast = add(votes_cast, shard_votes_cast)?;
+            option_votes_cast[option_index] =
+                add(option_votes_cast[option_index], shard_votes_cast)?;
+        }
+
+        if uses_escrow {
+            for (option, option_votes_cast) in VOTE_OPTIONS.iter().zip(option_votes_cast.iter()) {
+                let receipt_amount = outputs
+                    .receipt_cells
+                    .iter()
+                    .filter(|receipt_data| receipt_data.vote_option_type == *option)
+                    .try_fold(0u128, |total, receipt_data| add(total, receipt_data.amount))?;
+                if receipt_amount != *option_votes_cast {
+                    return Err(DaoCoreError::ReceiptMismatch.into());
+                }
+            }
+        }
+
+        // The receipts of every owner have to add up to the tokens its lock spent. Batched votes are assumed to be
+        // valid, since the vote intents aren't decoded.
+        if uses_escrow && !self.has_vote_intents(type_script) {
+            for receipt_data in &outputs.receipt_cells {
+                let owner_lock_hash = &receipt_data.owner_lock_hash;
+                let receipt_amount = outputs
+                    .receipt_cells
+                    .iter()
+                    .filter(|receipt_data| receipt_data.owner_lock_hash == *owner_lock_hash)
+                    .try_fold(0u128, |total, receipt_data| add(total, receipt_data.amount))?;
+                let input_amount =
+                    self.token_amount(&self.inputs, &core_data, Some(owner_lock_hash))?;
+                let output_amount =
+                    self.token_amount(&self.outputs, &core_data, Some(owner_lock_hash))?;
+
+                if input_amount.checked_sub(output_amount) != Some(receipt_amount) {
+                    return Err(DaoCoreError::ReceiptMismatch.into());
+                }
+            }
+        }
+
+        let weight = match core_data.voting_weight_mode {
+            VOTING_WEIGHT_MODE_SNAPSHOT => return Ok(()),
+            _ if uses_escrow => {
+                let escrow_lock_hash = Some(&core_data.escrow_lock_hash);
+                let input_amount = self.token_amount(&self.inputs, &core_data, escrow_lock_hash)?;
+                let output_amount =
+                    self.token_amount(&self.outputs, &core_data, escrow_lock_hash)?;
+                output_amount
+                    .checked_sub(input_amount)
+                    .ok_or(DaoCoreError::VoteTallyMismatch)?
+            }
+            _ => {
+                let input_amount = self.token_amount(&self.inputs, &core_data, None)?;
+                let output_amount = self.token_amount(&self.outputs, &core_data, None)?;
+                input_amount
+                    .checked_sub(output_amount)
+                    .ok_or(DaoCoreError::VoteTallyMismatch)?
+            }
+        };
+        if votes_cast != weight {
+            return Err(DaoCoreError::VoteTallyMismatch.into());
+        }
+
+        Ok(())
+    }
+
+    fn validate_finish(
+        &self,
+        type_script: &Script,
+        inputs: &GroupCells,
+        outputs: &GroupCells,
+    ) -> Result<(), ContractError> {
+        let (core_index, core_data) = &inputs.core_cells[0];
+        let (_, output_core_data) = &outputs.core_cells[0];
+        if core_data.is_voting_finished != VOTING_STATUS_OPEN {
+            return Err(DaoCoreError::VotingFinished.into());
+        }
+        validate_vote_shards(&inputs.vote_cells, core_data.vote_shard_count)?;
+
+        let mut option_tallies = [0u128; VOTE_OPTIONS.len()];
+        for vote_data in &inputs.vote_cells {
+            let option_index = vote_data.vote_option_type as usize;
+            option_tallies[option_index] = add(
+                option_tallies[option_index],
+                vote_data.total_votes_collected,
+            )?;
+        }
+
+        let majority_threshold = core_data
+            .total_distributed_tokens
+            .checked_mul(MAJORITY_PERCENTAGE)
+            .ok_or(DaoCoreError::AmountOverflow)?;
+        let winning_vote_option_type = match VOTE_OPTIONS
+            .iter()
+            .zip(option_tallies.iter())
+            .find(|(_, option_tally)| option_tally.saturating_mul(100) >= majority_threshold)
+        {
+            Some((winning_vote_option_type, _)) => *winning_vote_option_type,
+            None if self.is_organizer_authorized(core_data, type_script, *core_index) => {
+                if !VOTE_OPTIONS.contains(&output_core_data.vote_result_option_type) {
+                    return Err(DaoCoreError::InvalidOutputCellData.into());
+                }
+
+                output_core_data.vote_result_option_type
+            }
+            None => return Err(DaoCoreError::NoMajority.into()),
+        };
+
+        // The settlement block depends on the header deps, so it is taken from the output.
+        let mut finished_data = core_data.clone();
+        finished_data.is_voting_finished = VOTING_STATUS_FINISHED;
+        finished_data.vote_result_option_type = winning_vote_option_type;
+        finished_data.settlement_block_number = output_core_data.settlement_block_number;
+        if *output_core_data != finished_data {
+            return Err(DaoCoreError::InvalidOutputCellData.into());
+        }
+
+        Ok(())
+    }
+
+    fn validate_cancel(
+        &self,
+        type_script: &Script,
+        inputs: &GroupCells,
+        outputs: &GroupCells,
+    ) -> Result<(), ContractError> {
+        let (core_index, core_data) = &inputs.core_cells[0];
+        let (_, output_core_data) = &outputs.core_cells[0];
+        if core_data.is_voting_finished != VOTING_STATUS_OPEN {
+            return Err(DaoCoreError::VotingFinished.into());
+        }
+        if !self.is_organizer_authorized(core_data, type_script, *core_index) {
+            return Err(DaoCoreError::Unauthorized.into());
+        }
+
+        let mut cancelled_data = core_data.clone();
+        cancelled_data.is_voting_finished = VOTING_STATUS_CANCELLED;
+        if *output_core_data != cancelled_data {
+            return Err(DaoCoreError::InvalidOutputCellData.into());
+        }
+
+        Ok(())
+    }
+
+    fn validate_extend(
+        &self,
+        type_script: &Script,
+        inputs: &GroupCells,
+        outputs: &GroupCells,
+    ) -> Result<(), ContractError> {
+        let (core_index, core_data) = &inputs.core_cells[0];
+        let (_, output_core_data) = &outputs.core_cells[0];
+        if core_data.is_voting_finished != VOTING_STATUS_OPEN {
+            return Err(DaoCoreError::VotingFinished.into());
+        }
+        if !self.is_organizer_authorized(core_data, type_script, *core_index) {
+            return Err(DaoCoreError::Unauthorized.into());
+        }
+        if output_core_data.voting_end_block_number <= core_data.voting_end_block_number {
+            return Err(DaoCoreError::InvalidExtension.into());
+        }
+
+        let total_extension = core_data
+            .total_extension
+            .checked_add(
+                output_core_data.voting_end_block_number - core_data.voting_end_block_number,
+            )
+            .filter(|total_extension| *total_extension <= core_data.max_extension)
+            .ok_or(DaoCoreError::ExtensionLimitExceeded)?;
+
+        let mut extended_data = core_data.clone();
+        extended_data.voting_end_block_number = output_core_data.voting_end_block_number;
+        extended_data.total_extension = total_extension;
+        if *output_core_data != extended_data {
+            return Err(DaoCoreError::InvalidOutputCellData.into());
+        }
+
+        Ok(())
+    }
+
+    fn validate_execute(
+        &self,
+        inputs: &GroupCells,
+        outputs: &GroupCells,
+    ) -> Result<(), ContractError> {
+        let (_, core_data) = &inputs.core_cells[0];
+        let (_, output_core_data) = &outputs.core_cells[0];
+        if core_data.is_voting_finished != VOTING_STATUS_FINISHED {
+            return Err(DaoCoreError::VoteNotFinished.into());
+        }
+
+        let mut executed_data = core_data.clone();
+        executed_data.is_voting_finished = VOTING_STATUS_EXECUTED;
+        if *output_core_data != executed_data {
+            return Err(DaoCoreError::InvalidOutputCellData.into());
+        }
+
+        Ok(())
+    }
+
+    fn validate_burn(
+        &self,
+        type_script: &Script,
+        inputs: &GroupCells,
+    ) -> Result<(), ContractError> {
+        for (core_index, core_data) in &inputs.core_cells {
+            if !self.is_organizer_authorized(core_data, type_script, *core_index) {
+                return Err(DaoCoreError::Unauthorized.into());
+            }
+
+            let uses_escrow = core_data.escrow_lock_hash != [0u8; 32]
+                && core_data.voting_weight_mode == VOTING_WEIGHT_MODE_DISTRIBUTED;
+            if uses_escrow
+                && (core_data.is_voting_finished == VOTING_STATUS_OPEN
+                    || core_data.is_voting_finished == VOTING_STATUS_CANCELLED)
+            {
+                return Err(DaoCoreError::EscrowOutstanding.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_refund(
+        &self,
+        type_script: &Script,
+        inputs: &GroupCells,
+    ) -> Result<(), ContractError> {
+        let core_data = self.core_cell_dep(type_script)?;
+        if core_data.is_voting_finished != VOTING_STATUS_CANCELLED {
+            return Err(DaoCoreError::VoteNotCancelled.into());
+        }
+
+        for receipt_data in &inputs.receipt_cells {
+            let owner_lock_hash = &receipt_data.owner_lock_hash;
+            let refund_amount = inputs
+                .receipt_cells
+                .iter()
+                .filter(|receipt_data| receipt_data.owner_lock_hash == *owner_lock_hash)
+                .try_fold(0u128, |total, receipt_data| add(total, receipt_data.amount))?;
+            let input_amount =
+                self.token_amount(&self.inputs, &core_data, Some(owner_lock_hash))?;
+            let output_amount =
+                self.token_amount(&self.outputs, &core_data, Some(owner_lock_hash))?;
+
+            if output_amount.saturating_sub(input_amount) < refund_amount {
+                return Err(DaoCoreError::RefundMismatch.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    // Mirrors the checks of sUDT, which are run for every type script of the sUDT code.
+    fn explain_token(&self, type_script: &Script) -> ExplainedToken {
+        let mut token = ExplainedToken {
+            type_script: type_script.clone(),
+            input_amount: 0,
+            output_amount: 0,
+            error: None,
+        };
+
+        let mut amounts_by_lock: Vec<(Byte32, u128, u128)> = vec![];
+        for (cells, is_input) in &[(&self.inputs, true), (&self.outputs, false)] {
+            for cell in cells.iter().filter(|cell| has_type(cell, type_script)) {
+                let amount = match parse_token_amount(&cell.data) {
+                    Some(amount) => amount,
+                    None => {
+                        token.error = Some(SudtError::Encoding.into());
+                        continue;
+                    }
+                };
+
+                let lock_hash = cell.output.lock().calc_script_hash();
+                let index = match amounts_by_lock
+                    .iter()
+                    .position(|(hash, _, _)| *hash == lock_hash)
+                {
+                    Some(index) => index,
+                    None => {
+                        amounts_by_lock.push((lock_hash, 0, 0));
+                        amounts_by_lock.len() - 1
+                    }
+                };
+                if *is_input {
+                    token.input_amount = token.input_amount.saturating_add(amount);
+                    amounts_by_lock[index].1 = amounts_by_lock[index].1.saturating_add(amount);
+                } else {
+                    token.output_amount = token.output_amount.saturating_add(amount);
+                    amounts_by_lock[index].2 = amounts_by_lock[index].2.saturating_add(amount);
+                }
+            }
+        }
+
+        let args = type_script.args().raw_data();
+        if args.len() != SUDT_OWNER_LOCK_HASH_BYTESIZE
+            && args.len() != SUDT_OWNER_LOCK_HASH_BYTESIZE + 1
+        {
+            token.error = Some(SudtError::ArgsLength.into());
+            return token;
+        }
+        let is_owner_mode = self.inputs.iter().any(|cell| {
+            cell.output.lock().calc_script_hash().as_slice()
+                == &args[..SUDT_OWNER_LOCK_HASH_BYTESIZE]
+        });
+        if is_owner_mode {
+            token.error = None;
+            return token;
+        }
+        if token.error.is_some() {
+            return token;
+        }
+
+        if token.output_amount > token.input_amount {
+            token.error = Some(SudtError::Amount.into());
+        } else if args.len() > SUDT_OWNER_LOCK_HASH_BYTESIZE
+            && args[SUDT_OWNER_LOCK_HASH_BYTESIZE] & SUDT_FLAG_NON_TRANSFERABLE != 0
+            && amounts_by_lock
+                .iter()
+                .any(|(_, input_amount, output_amount)| output_amount > input_amount)
+        {
+            token.error = Some(SudtError::NonTransferable.into());
+        }
+
+        token
+    }
+}
+
+// Mirrors validate_vote_shards in dao-core.
+fn validate_vote_shards(
+    vote_cells: &[VoteCellData],
+    vote_shard_count: u8,
+) -> Result<(), ContractError> {
+    let is_complete = vote_cells.len() == VOTE_OPTIONS.len() * vote_shard_count as usize
+        && VOTE_OPTIONS.iter().all(|option| {
+            (0..vote_shard_count).all(|shard_index| {
+                vote_cells.iter().any(|vote_data| {
+                    vote_data.vote_option_type == *option && vote_data.shard_index == shard_index
+                })
+            })
+        });
+
+    match is_complete {
+        true => Ok(()),
+        false => Err(DaoCoreError::InvalidVoteCellCount.into()),
+    }
+}
+
+/// Explain a transaction. Inputs and cell deps are looked up with `resolve`. Cells it doesn't find are
+/// reported as unresolved, and no modes or errors are predicted unless every input is found.
+pub fn explain_tx(
+    tx: &TransactionView,
+    dao_core: &DeployedScript,
+    sudt: &DeployedScript,
+    resolve: impl Fn(&OutPoint) -> Option<LiveCell>,
+) -> Explanation {
+    let mut inputs: Vec<ExplainedCell> = tx
+        .input_pts_iter()
+        .map(|out_point| explain_cell(out_point.clone(), resolve(&out_point), dao_core, sudt))
+        .collect();
+    let outputs: Vec<ExplainedCell> = tx
+        .outputs_with_data_iter()
+        .enumerate()
+        .map(|(index, (output, data))| {
+            let out_point = OutPoint::new(tx.hash(), index as u32);
+            let cell = LiveCell::new(out_point.clone(), output, data);
+            explain_cell(out_point, Some(cell), dao_core, sudt)
+        })
+        .collect();
+    let cell_deps: Vec<ExplainedCell> = tx
+        .cell_deps_iter()
+        .map(|cell_dep| {
+            let out_point = cell_dep.out_point();
+            explain_cell(out_point.clone(), resolve(&out_point), dao_core, sudt)
+        })
+        .collect();
+
+    let mut explanation = Explanation {
+        tx_hash: tx.hash(),
+        inputs: vec![],
+        outputs,
+        cell_deps,
+        votes: vec![],
+        tokens: vec![],
+    };
+
+    let resolved_inputs: Option<Vec<LiveCell>> =
+        inputs.iter().map(|input| input.cell.clone()).collect();
+    if let Some(resolved_inputs) = resolved_inputs {
+        let resolved = ResolvedTx {
+            tx,
+            inputs: resolved_inputs,
+            outputs: explanation
+                .outputs
+                .iter()
+                .filter_map(|output| output.cell.clone())
+                .collect(),
+            cell_deps: explanation
+                .cell_deps
+                .iter()
+                .filter_map(|cell_dep| cell_dep.cell.clone())
+                .collect(),
+        };
+
+        // Every distinct type script runs as its own script group.
+        let mut type_scripts: Vec<Script> = vec![];
+        for cell in resolved.inputs.iter().chain(resolved.outputs.iter()) {
+            if let Some(script) = type_script(cell) {
+                if !type_scripts.contains(&script) {
+                    type_scripts.push(script);
+                }
+            }
+        }
+
+        for type_script in type_scripts {
+            if dao_core.matches(&type_script) {
+                let (mode, error) = resolved.explain_vote(&type_script);
+                explanation.votes.push(ExplainedVote {
+                    type_script,
+                    mode,
+                    error,
+                });
+            } else if sudt.matches(&type_script) {
+                explanation
+                    .tokens
+                    .push(resolved.explain_token(&type_script));
+            }
+        }
+
+        // The instance id of a new vote is derived from the first input.
+        if explanation
+            .votes
+            .iter()
+            .any(|vote| vote.mode == Some(Mode::Create))
+        {
+            if let Some(input) = inputs.first_mut() {
+                if input.role == CellRole::Other {
+                    input.role = CellRole::Seed;
+                }
+            }
+        }
+    }
+    explanation.inputs = inputs;
+
+    explanation
+}
@@ -0,0 +1,119 @@
+//! Client SDK for ckb-voting.
+//!
+//! The builders return unsigned transactions for every step of the vote lifecycle. New cells get the capacity
+//! they occupy, and builders given capacity cells pay for them and the fee, see `capacity`. Otherwise balancing
+//! the capacity is left to the wallet, though capacity freed by spent token cells is still returned in a change
+//! cell. Signing is left to the wallet, or to a `PartialTx` when cells of several
+//! parties are spent. See the Transactions section of the README for the structure of every transaction.
+
+pub mod audit;
+mod builders;
+pub mod capacity;
+mod data;
+pub mod explain;
+pub mod partial;
+pub mod report;
+pub mod script_error;
+
+pub use builders::{sign_as_organizer, CastVote, CreateVote, FinishVote, MintTokens};
+pub use data::*;
+
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    packed::{Byte, Byte32, CellDep, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    InvalidCellData,
+    InsufficientTokens,
+    /// Votes in snapshot mode have to be cast with the whole balance of the token cells.
+    PartialSnapshotVote,
+    /// The vote holds tokens in escrow, but the vote-escrow script wasn't given or isn't its escrow lock.
+    InvalidEscrowLock,
+    NoMajority,
+    AmountOverflow,
+    VoteNotFound,
+    BlockSource(String),
+    InvalidPartialTx(String),
+    /// Partial transactions of different transactions were combined.
+    PartialTxMismatch,
+    /// A signature isn't made by a key of its lock group.
+    InvalidSignature,
+    MissingSignatures,
+    /// The transaction has no multisig config of the organizer to sign.
+    MissingOrganizerWitness,
+    /// The capacity cells don't cover the new cells and the fee.
+    InsufficientCapacity,
+    /// A vote report doesn't match the chain, or can't be generated from it.
+    InvalidReport(String),
+}
+
+/// A deployed contract, referenced by the out point of the cell holding its code.
+#[derive(Clone)]
+pub struct DeployedScript {
+    pub out_point: OutPoint,
+    pub code_hash: Byte32,
+    pub hash_type: ScriptHashType,
+}
+
+impl DeployedScript {
+    pub fn new(out_point: OutPoint, code_hash: Byte32, hash_type: ScriptHashType) -> Self {
+        DeployedScript {
+            out_point,
+            code_hash,
+            hash_type,
+        }
+    }
+
+    /// Reference the contract by the hash of its code.
+    pub fn from_code(out_point: OutPoint, code: &Bytes) -> Self {
+        DeployedScript::new(
+            out_point,
+            CellOutput::calc_data_hash(code),
+            ScriptHashType::Data,
+        )
+    }
+
+    /// Whether the script runs this contract.
+    pub fn matches(&self, script: &Script) -> bool {
+        let hash_type: Byte = self.hash_type.into();
+
+        script.code_hash() == self.code_hash
+            && script.hash_type().as_slice() == hash_type.as_slice()
+    }
+
+    pub fn script(&self, args: Bytes) -> Script {
+        Script::new_builder()
+            .code_hash(self.code_hash.clone())
+            .hash_type(self.hash_type.into())
+            .args(args.pack())
+            .build()
+    }
+
+    pub fn cell_dep(&self) -> CellDep {
+        CellDep::new_builder()
+            .out_point(self.out_point.clone())
+            .build()
+    }
+}
+
+/// A live cell spent by a transaction.
+#[derive(Clone)]
+pub struct LiveCell {
+    pub out_point: OutPoint,
+    pub output: CellOutput,
+    pub data: Bytes,
+}
+
+impl LiveCell {
+    pub fn new(out_point: OutPoint, output: CellOutput, data: Bytes) -> Self {
+        LiveCell {
+            out_point,
+            output,
+            data,
+        }
+    }
+}
@@ -0,0 +1,710 @@
+//! Partially signed transactions.
+//!
+//! A transaction spending cells of several parties, like the organizer, a funder and the members of a multisig,
+//! is created once and then passed between them as JSON. Every party adds the signatures of the lock groups it
+//! holds keys of, offline, and the copies are combined. Once every group is signed, the transaction is finalised
+//! by putting the signatures into the lock field of the witness of the first input of every group.
+//!
+//! Lock groups of the secp256k1 sighash all and multisig all scripts are signed. The witnesses are fixed when
+//! the partial transaction is created, with placeholders for the signatures, so signing one group never
+//! invalidates the signatures of another. Cells with other locks are spent without signatures.
+
+use std::fmt;
+
+use ckb_tool::ckb_crypto::secp::{Privkey, Signature};
+use ckb_tool::ckb_hash::{blake2b_256, new_blake2b};
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionView},
+    packed::{self, Script, WitnessArgs},
+    prelude::*,
+    H256,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{DeployedScript, Error, LiveCell};
+
+const SIGNATURE_BYTESIZE: usize = 65;
+const PUBKEY_HASH_BYTESIZE: usize = 20;
+// Multisig lock args: the hash of the multisig config, optionally followed by a since value.
+const MULTISIG_SINCE_BYTESIZE: usize = 8;
+const MULTISIG_CONFIG_HEADER_BYTESIZE: usize = 4;
+const MULTISIG_CONFIG_RESERVED: u8 = 0;
+
+/// Blake160 hash, the first 20 bytes of the blake2b hash.
+pub type PubkeyHash = [u8; PUBKEY_HASH_BYTESIZE];
+
+fn blake160(data: &[u8]) -> PubkeyHash {
+    let mut hash = [0u8; PUBKEY_HASH_BYTESIZE];
+    hash.copy_from_slice(&blake2b_256(data)[..PUBKEY_HASH_BYTESIZE]);
+
+    hash
+}
+
+/// Keys of a secp256k1 multisig lock. Signatures of `threshold` keys are required, which include the first
+/// `require_first_n` keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultisigConfig {
+    pub require_first_n: u8,
+    pub threshold: u8,
+    pub pubkey_hashes: Vec<PubkeyHash>,
+}
+
+impl MultisigConfig {
+    pub fn new(require_first_n: u8, threshold: u8, pubkey_hashes: Vec<PubkeyHash>) -> Self {
+        MultisigConfig {
+            require_first_n,
+            threshold,
+            pubkey_hashes,
+        }
+    }
+
+    pub fn from_slice(data: &[u8]) -> Option<Self> {
+        if data.len() < MULTISIG_CONFIG_HEADER_BYTESIZE || data[0] != MULTISIG_CONFIG_RESERVED {
+            return None;
+        }
+
+        let pubkey_count = data[3] as usize;
+        if data.len() != MULTISIG_CONFIG_HEADER_BYTESIZE + pubkey_count * PUBKEY_HASH_BYTESIZE {
+            return None;
+        }
+        let pubkey_hashes = data[MULTISIG_CONFIG_HEADER_BYTESIZE..]
+            .chunks(PUBKEY_HASH_BYTESIZE)
+            .map(|chunk| {
+                let mut pubkey_hash = [0u8; PUBKEY_HASH_BYTESIZE];
+                pubkey_hash.copy_from_slice(chunk);
+                pubkey_hash
+            })
+            .collect();
+        let config = MultisigConfig::new(data[1], data[2], pubkey_hashes);
+
+        match config.is_valid() {
+            true => Some(config),
+            false => None,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.threshold > 0
+            && self.require_first_n <= self.threshold
+            && self.threshold as usize <= self.pubkey_hashes.len()
+            && self.pubkey_hashes.len() <= u8::MAX as usize
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut data = vec![
+            MULTISIG_CONFIG_RESERVED,
+            self.require_first_n,
+            self.threshold,
+            self.pubkey_hashes.len() as u8,
+        ];
+        for pubkey_hash in &self.pubkey_hashes {
+            data.extend_from_slice(pubkey_hash);
+        }
+
+        Bytes::from(data)
+    }
+
+    /// The hash in the args of the multisig lock.
+    pub fn hash(&self) -> PubkeyHash {
+        blake160(&self.to_bytes())
+    }
+}
+
+/// How the inputs of a lock group are signed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LockKind {
+    /// A single key, whose hash is in the args.
+    Sighash(PubkeyHash),
+    Multisig(MultisigConfig),
+}
+
+/// Inputs with the same lock, which are signed together.
+#[derive(Clone)]
+pub struct LockGroup {
+    pub lock: Script,
+    pub kind: LockKind,
+    /// Indices of the inputs with the lock. The signatures go into the witness of the first.
+    pub input_indices: Vec<usize>,
+    /// Signatures collected so far, along with the hash of the key that made them.
+    pub signatures: Vec<(PubkeyHash, Bytes)>,
+}
+
+impl LockGroup {
+    /// Number of signatures still required.
+    pub fn missing_signatures(&self) -> usize {
+        match &self.kind {
+            LockKind::Sighash(_) => 1 - self.signatures.len().min(1),
+            LockKind::Multisig(config) => {
+                let required_missing = config.pubkey_hashes[..config.require_first_n as usize]
+                    .iter()
+                    .filter(|pubkey_hash| !self.has_signed(pubkey_hash))
+                    .count();
+                let missing = (config.threshold as usize).saturating_sub(self.signatures.len());
+
+                missing.max(required_missing)
+            }
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.missing_signatures() == 0
+    }
+
+    fn has_signed(&self, pubkey_hash: &PubkeyHash) -> bool {
+        self.signatures
+            .iter()
+            .any(|(signer, _)| signer == pubkey_hash)
+    }
+
+    fn is_signer(&self, pubkey_hash: &PubkeyHash) -> bool {
+        match &self.kind {
+            LockKind::Sighash(lock_pubkey_hash) => lock_pubkey_hash == pubkey_hash,
+            LockKind::Multisig(config) => config.pubkey_hashes.contains(pubkey_hash),
+        }
+    }
+
+    // The lock field of the witness while signing, which is covered by the signing message. Multisig locks keep
+    // the config in front of the signatures.
+    fn placeholder(&self) -> Bytes {
+        match &self.kind {
+            LockKind::Sighash(_) => Bytes::from(vec![0u8; SIGNATURE_BYTESIZE]),
+            LockKind::Multisig(config) => {
+                let mut lock = config.to_bytes().to_vec();
+                lock.resize(
+                    lock.len() + config.threshold as usize * SIGNATURE_BYTESIZE,
+                    0,
+                );
+                Bytes::from(lock)
+            }
+        }
+    }
+
+    // The lock field of the witness once signed. Multisig signatures are taken in the order of the keys in the
+    // config until the threshold is reached, so the required first keys are always included.
+    fn lock_field(&self) -> Result<Bytes, Error> {
+        if !self.is_signed() {
+            return Err(Error::MissingSignatures);
+        }
+
+        match &self.kind {
+            LockKind::Sighash(_) => Ok(self.signatures[0].1.clone()),
+            LockKind::Multisig(config) => {
+                let mut lock = config.to_bytes().to_vec();
+                let signatures = config.pubkey_hashes.iter().filter_map(|pubkey_hash| {
+                    self.signatures
+                        .iter()
+                        .find(|(signer, _)| signer == pubkey_hash)
+                });
+                for (_, signature) in signatures.take(config.threshold as usize) {
+                    lock.extend_from_slice(signature);
+                }
+
+                Ok(Bytes::from(lock))
+            }
+        }
+    }
+}
+
+/// A transaction along with the cells it spends and the signatures collected for its lock groups.
+#[derive(Clone)]
+pub struct PartialTx {
+    tx: TransactionView,
+    inputs: Vec<LiveCell>,
+    lock_groups: Vec<LockGroup>,
+}
+
+impl PartialTx {
+    /// Prepare the transaction for signing. The inputs are the cells spent by the transaction, in the order of
+    /// its inputs. Inputs are grouped by lock, and the configs of all multisig locks have to be passed.
+    pub fn new(
+        tx: TransactionView,
+        inputs: Vec<LiveCell>,
+        sighash: &DeployedScript,
+        multisig: &DeployedScript,
+        multisig_configs: &[MultisigConfig],
+    ) -> Result<Self, Error> {
+        check_inputs(&tx, &inputs)?;
+
+        let mut lock_groups: Vec<LockGroup> = vec![];
+        for (index, input) in inputs.iter().enumerate() {
+            let lock = input.output.lock();
+            if let Some(lock_group) = lock_groups
+                .iter_mut()
+                .find(|lock_group| lock_group.lock == lock)
+            {
+                lock_group.input_indices.push(index);
+                continue;
+            }
+
+            let args = lock.args().raw_data();
+            let kind = if sighash.matches(&lock) {
+                if args.len() != PUBKEY_HASH_BYTESIZE {
+                    return Err(invalid("sighash lock args are not a pubkey hash"));
+                }
+                let mut pubkey_hash = [0u8; PUBKEY_HASH_BYTESIZE];
+                pubkey_hash.copy_from_slice(&args);
+                LockKind::Sighash(pubkey_hash)
+            } else if multisig.matches(&lock) {
+                if args.len() != PUBKEY_HASH_BYTESIZE
+                    && args.len() != PUBKEY_HASH_BYTESIZE + MULTISIG_SINCE_BYTESIZE
+                {
+                    return Err(invalid("multisig lock args are not a config hash"));
+                }
+                let config = multisig_configs
+                    .iter()
+                    .find(|config| config.hash()[..] == args[..PUBKEY_HASH_BYTESIZE])
+                    .ok_or_else(|| invalid("multisig config of a lock is missing"))?;
+                LockKind::Multisig(config.clone())
+            } else {
+                continue;
+            };
+
+            lock_groups.push(LockGroup {
+                lock,
+                kind,
+                input_indices: vec![index],
+                signatures: vec![],
+            });
+        }
+
+        // Reserve the lock field of every group for the signatures.
+        let mut witnesses: Vec<Bytes> = tx.witnesses().into_iter().map(|w| w.unpack()).collect();
+        if witnesses.len() < inputs.len() {
+            witnesses.resize(inputs.len(), Bytes::new());
+        }
+        for lock_group in &lock_groups {
+            let index = lock_group.input_indices[0];
+            let witness_args = match witnesses[index].is_empty() {
+                true => WitnessArgs::default(),
+                false => WitnessArgs::from_slice(&witnesses[index])
+                    .map_err(|_| invalid("witness of an input is not WitnessArgs"))?,
+            };
+            witnesses[index] = witness_args
+                .as_builder()
+                .lock(Some(lock_group.placeholder()).pack())
+                .build()
+                .as_bytes();
+        }
+        let tx = tx
+            .as_advanced_builder()
+            .set_witnesses(
+                witnesses
+                    .into_iter()
+                    .map(|witness| witness.pack())
+                    .collect::<Vec<packed::Bytes>>(),
+            )
+            .build();
+
+        Ok(PartialTx {
+            tx,
+            inputs,
+            lock_groups,
+        })
+    }
+
+    /// The transaction, with placeholders for the signatures.
+    pub fn tx(&self) -> &TransactionView {
+        &self.tx
+    }
+
+    pub fn inputs(&self) -> &[LiveCell] {
+        &self.inputs
+    }
+
+    pub fn lock_groups(&self) -> &[LockGroup] {
+        &self.lock_groups
+    }
+
+    /// The message the keys of a lock group sign, following the sighash all scheme: the transaction hash, the
+    /// witnesses of the group, and the witnesses without an input.
+    pub fn signing_message(&self, lock_group: &LockGroup) -> H256 {
+        let witnesses: Vec<Bytes> = self
+            .tx
+            .witnesses()
+            .into_iter()
+            .map(|w| w.unpack())
+            .collect();
+
+        let mut blake2b = new_blake2b();
+        blake2b.update(&self.tx.hash().raw_data());
+        for witness in lock_group
+            .input_indices
+            .iter()
+            .map(|index| &witnesses[*index])
+            .chain(witnesses[self.inputs.len()..].iter())
+        {
+            blake2b.update(&(witness.len() as u64).to_le_bytes());
+            blake2b.update(witness);
+        }
+
+        let mut message = [0u8; 32];
+        blake2b.finalize(&mut message);
+
+        H256::from(message)
+    }
+
+    /// Sign every lock group the key belongs to. Returns the number of groups signed.
+    pub fn sign(&mut self, key: &Privkey) -> Result<usize, Error> {
+        let pubkey = key.pubkey().map_err(|_| Error::InvalidSignature)?;
+        let pubkey_hash = blake160(&pubkey.serialize());
+
+        let mut signed = 0;
+        for index in 0..self.lock_groups.len() {
+            if !self.lock_groups[index].is_signer(&pubkey_hash) {
+                continue;
+            }
+
+            let message = self.signing_message(&self.lock_groups[index]);
+            let signature = key
+                .sign_recoverable(&message)
+                .map_err(|_| Error::InvalidSignature)?;
+            self.add_signature(index, Bytes::from(signature.serialize()))?;
+            signed += 1;
+        }
+
+        Ok(signed)
+    }
+
+    /// Add a signature made elsewhere, like on a hardware wallet, to a lock group. The signature has to be made by a
+    /// key of the group, over the signing message of the group.
+    pub fn add_signature(&mut self, group_index: usize, signature: Bytes) -> Result<(), Error> {
+        let lock_group = self
+            .lock_groups
+            .get(group_index)
+            .ok_or(Error::InvalidSignature)?;
+        let signer = recover_signer(&self.signing_message(lock_group), &signature)?;
+        if !lock_group.is_signer(&signer) {
+            return Err(Error::InvalidSignature);
+        }
+
+        if !lock_group.has_signed(&signer) {
+            self.lock_groups[group_index]
+                .signatures
+                .push((signer, signature));
+        }
+
+        Ok(())
+    }
+
+    /// Add the signatures of another copy of the same partial transaction.
+    pub fn combine(&mut self, other: &PartialTx) -> Result<(), Error> {
+        if self.tx.data().as_slice() != other.tx.data().as_slice()
+            || self.lock_groups.len() != other.lock_groups.len()
+        {
+            return Err(Error::PartialTxMismatch);
+        }
+
+        for (index, lock_group) in other.lock_groups.iter().enumerate() {
+            for (_, signature) in &lock_group.signatures {
+                self.add_signature(index, signature.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.lock_groups.iter().all(LockGroup::is_signed)
+    }
+
+    /// The transaction with the signatures in the witnesses, once every lock group is signed.
+    pub fn finalize(&self) -> Result<TransactionView, Error> {
+        let mut witnesses: Vec<Bytes> = self
+            .tx
+            .witnesses()
+            .into_iter()
+            .map(|w| w.unpack())
+            .collect();
+        for lock_group in &self.lock_groups {
+            let index = lock_group.input_indices[0];
+            let witness_args = WitnessArgs::from_slice(&witnesses[index])
+                .map_err(|_| invalid("witness of an input is not WitnessArgs"))?;
+            witnesses[index] = witness_args
+                .as_builder()
+                .lock(Some(lock_group.lock_field()?).pack())
+                .build()
+                .as_bytes();
+        }
+
+        Ok(self
+            .tx
+            .as_advanced_builder()
+            .set_witnesses(
+                witnesses
+                    .into_iter()
+                    .map(|witness| witness.pack())
+                    .collect::<Vec<packed::Bytes>>(),
+            )
+            .build())
+    }
+
+    pub fn to_json(&self) -> String {
+        let partial_tx = PartialTxJson {
+            transaction: self.tx.data().into(),
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| InputJson {
+                    output: input.output.clone().into(),
+                    data: json_types::JsonBytes::from_bytes(input.data.clone()),
+                })
+                .collect(),
+            lock_groups: self
+                .lock_groups
+                .iter()
+                .map(|lock_group| LockGroupJson {
+                    lock: lock_group.lock.clone().into(),
+                    multisig_config: match &lock_group.kind {
+                        LockKind::Sighash(_) => None,
+                        LockKind::Multisig(config) => {
+                            Some(json_types::JsonBytes::from_bytes(config.to_bytes()))
+                        }
+                    },
+                    input_indices: lock_group
+                        .input_indices
+                        .iter()
+                        .map(|index| (*index as u32).into())
+                        .collect(),
+                    signatures: lock_group
+                        .signatures
+                        .iter()
+                        .map(|(_, signature)| json_types::JsonBytes::from_bytes(signature.clone()))
+                        .collect(),
+                })
+                .collect(),
+        };
+
+        serde_json::to_string_pretty(&partial_tx).expect("serialize partial transaction")
+    }
+
+    /// Load a partial transaction written by `to_json`. Nothing is trusted: the lock groups are checked against
+    /// the inputs, and the signatures against the keys of their group.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let partial_tx: PartialTxJson =
+            serde_json::from_str(json).map_err(|err| Error::InvalidPartialTx(err.to_string()))?;
+        let tx = packed::Transaction::from(partial_tx.transaction).into_view();
+        let inputs: Vec<LiveCell> = tx
+            .inputs()
+            .into_iter()
+            .zip(partial_tx.inputs)
+            .map(|(input, cell)| {
+                LiveCell::new(
+                    input.previous_output(),
+                    cell.output.into(),
+                    cell.data.into_bytes(),
+                )
+            })
+            .collect();
+        check_inputs(&tx, &inputs)?;
+
+        let mut partial = PartialTx {
+            tx,
+            inputs,
+            lock_groups: vec![],
+        };
+        for lock_group_json in partial_tx.lock_groups {
+            let lock: Script = lock_group_json.lock.into();
+            if partial
+                .lock_groups
+                .iter()
+                .any(|lock_group| lock_group.lock == lock)
+            {
+                return Err(invalid("lock group is listed twice"));
+            }
+
+            let args = lock.args().raw_data();
+            let kind = match lock_group_json.multisig_config {
+                None => {
+                    if args.len() != PUBKEY_HASH_BYTESIZE {
+                        return Err(invalid("sighash lock args are not a pubkey hash"));
+                    }
+                    let mut pubkey_hash = [0u8; PUBKEY_HASH_BYTESIZE];
+                    pubkey_hash.copy_from_slice(&args);
+                    LockKind::Sighash(pubkey_hash)
+                }
+                Some(config) => {
+                    let config = MultisigConfig::from_slice(config.as_bytes())
+                        .ok_or_else(|| invalid("invalid multisig config"))?;
+                    if args.len() < PUBKEY_HASH_BYTESIZE
+                        || config.hash()[..] != args[..PUBKEY_HASH_BYTESIZE]
+                    {
+                        return Err(invalid("multisig config doesn't match the lock"));
+                    }
+                    LockKind::Multisig(config)
+                }
+            };
+
+            let input_indices: Vec<usize> = partial
+                .inputs
+                .iter()
+                .enumerate()
+                .filter(|(_, input)| input.output.lock() == lock)
+                .map(|(index, _)| index)
+                .collect();
+            let listed_indices: Vec<usize> = lock_group_json
+                .input_indices
+                .into_iter()
+                .map(|index| index.value() as usize)
+                .collect();
+            if input_indices.is_empty() || input_indices != listed_indices {
+                return Err(invalid("lock group doesn't match the inputs"));
+            }
+
+            let lock_group = LockGroup {
+                lock,
+                kind,
+                input_indices,
+                signatures: vec![],
+            };
+            // The signing message covers the placeholder, so it has to be in place.
+            let placeholder = partial
+                .tx
+                .witnesses()
+                .get(lock_group.input_indices[0])
+                .and_then(|witness| WitnessArgs::from_slice(&witness.raw_data()).ok())
+                .and_then(|witness_args| witness_args.lock().to_opt())
+                .map(|lock| lock.raw_data());
+            if placeholder != Some(lock_group.placeholder()) {
+                return Err(invalid("witness of a lock group has no placeholder"));
+            }
+            partial.lock_groups.push(lock_group);
+
+            let group_index = partial.lock_groups.len() - 1;
+            for signature in lock_group_json.signatures {
+                partial.add_signature(group_index, signature.into_bytes())?;
+            }
+        }
+
+        Ok(partial)
+    }
+}
+
+impl fmt::Display for PartialTx {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "transaction {:#x}", self.tx.hash())?;
+
+        writeln!(f, "inputs:")?;
+        for (index, input) in self.inputs.iter().enumerate() {
+            let capacity: Capacity = input.output.capacity().unpack();
+            writeln!(
+                f,
+                "  #{} {:#x}:{}, {} shannons, lock {:#x}",
+                index,
+                input.out_point.tx_hash(),
+                Unpack::<u32>::unpack(&input.out_point.index()),
+                capacity.as_u64(),
+                input.output.lock().calc_script_hash()
+            )?;
+        }
+        writeln!(f, "outputs:")?;
+        for (index, output) in self.tx.outputs().into_iter().enumerate() {
+            let capacity: Capacity = output.capacity().unpack();
+            write!(
+                f,
+                "  #{} {} shannons, lock {:#x}",
+                index,
+                capacity.as_u64(),
+                output.lock().calc_script_hash()
+            )?;
+            match output.type_().to_opt() {
+                Some(type_script) => writeln!(f, ", type {:#x}", type_script.calc_script_hash())?,
+                None => writeln!(f)?,
+            }
+        }
+
+        for lock_group in &self.lock_groups {
+            write!(
+                f,
+                "lock {:#x} (inputs {:?}): ",
+                lock_group.lock.calc_script_hash(),
+                lock_group.input_indices
+            )?;
+            match &lock_group.kind {
+                LockKind::Sighash(pubkey_hash) => write!(f, "key {}", hex(pubkey_hash))?,
+                LockKind::Multisig(config) => write!(
+                    f,
+                    "{} of {} keys, first {} required",
+                    config.threshold,
+                    config.pubkey_hashes.len(),
+                    config.require_first_n
+                )?,
+            }
+            let signers: Vec<String> = lock_group
+                .signatures
+                .iter()
+                .map(|(signer, _)| hex(signer))
+                .collect();
+            if !signers.is_empty() {
+                write!(f, ", signed by {}", signers.join(", "))?;
+            }
+            match lock_group.missing_signatures() {
+                0 => writeln!(f)?,
+                missing => writeln!(f, ", missing {} signature(s)", missing)?,
+            }
+        }
+
+        match self.is_complete() {
+            true => writeln!(f, "ready to finalize"),
+            false => writeln!(f, "signatures missing"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PartialTxJson {
+    transaction: json_types::Transaction,
+    inputs: Vec<InputJson>,
+    lock_groups: Vec<LockGroupJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct InputJson {
+    output: json_types::CellOutput,
+    data: json_types::JsonBytes,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LockGroupJson {
+    lock: json_types::Script,
+    /// Not set for sighash locks.
+    multisig_config: Option<json_types::JsonBytes>,
+    input_indices: Vec<json_types::Uint32>,
+    signatures: Vec<json_types::JsonBytes>,
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidPartialTx(String::from(reason))
+}
+
+// The inputs have to be the cells spent by the transaction, in order.
+fn check_inputs(tx: &TransactionView, inputs: &[LiveCell]) -> Result<(), Error> {
+    let is_matching = tx.inputs().len() == inputs.len()
+        && tx
+            .inputs()
+            .into_iter()
+            .zip(inputs)
+            .all(|(input, cell)| input.previous_output() == cell.out_point);
+
+    match is_matching {
+        true => Ok(()),
+        false => Err(invalid("inputs don't match the transaction")),
+    }
+}
+
+pub(crate) fn recover_signer(message: &H256, signature: &[u8]) -> Result<PubkeyHash, Error> {
+    if signature.len() != SIGNATURE_BYTESIZE {
+        return Err(Error::InvalidSignature);
+    }
+    let pubkey = Signature::from_slice(signature)
+        .and_then(|signature| signature.recover(message))
+        .map_err(|_| Error::InvalidSignature)?;
+
+    Ok(blake160(&pubkey.serialize()))
+}
+
+fn hex(data: &[u8]) -> String {
+    let digits: String = data.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("0x{}", digits)
+}
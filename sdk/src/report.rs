@@ -0,0 +1,588 @@
+//! Vote result reports.
+//!
+//! A report is the document archived after a vote finished: its title, the tallies of every option, the result
+//! and the transaction settling the vote. It carries the settlement transaction and the transactions creating
+//! every cell it spent, each with a proof of inclusion in its block, in the format of the
+//! get_transaction_proof RPC. `VoteReport::verify` checks the proofs against block headers of a node or a
+//! snapshot and recomputes every field from the proven transactions, so nothing written by the author of the
+//! report is trusted. The Core Cell has to run the deployed dao-core, as anyone can create cells with the same
+//! data under another type script. A signature only tells who published the report.
+
+use std::fmt::Write;
+
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_hash::new_blake2b;
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{BlockView, HeaderView, TransactionView},
+    packed::{self, Byte32, Script},
+    prelude::*,
+    utilities::{merkle_root, MerkleProof, CBMT},
+    H256,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{BlockSource, ChainSnapshot};
+use crate::data::*;
+use crate::partial::{recover_signer, PubkeyHash};
+use crate::{DeployedScript, Error};
+
+/// Headers of the main chain, looked up by block hash.
+pub trait HeaderSource {
+    fn header(&self, block_hash: &Byte32) -> Result<Option<HeaderView>, Error>;
+}
+
+impl HeaderSource for ChainSnapshot {
+    fn header(&self, block_hash: &Byte32) -> Result<Option<HeaderView>, Error> {
+        Ok(self
+            .blocks()
+            .iter()
+            .find(|block| &block.hash() == block_hash)
+            .map(|block| block.header()))
+    }
+}
+
+impl HeaderSource for Vec<HeaderView> {
+    fn header(&self, block_hash: &Byte32) -> Result<Option<HeaderView>, Error> {
+        Ok(self
+            .iter()
+            .find(|header| &header.hash() == block_hash)
+            .cloned())
+    }
+}
+
+/// A transaction with the proof it was included in a block.
+#[derive(Clone, Debug)]
+pub struct IncludedTransaction {
+    pub tx: TransactionView,
+    pub block_hash: Byte32,
+    pub witnesses_root: Byte32,
+    /// Indices of the transaction hash in the CBMT of the block, and the lemmas proving it.
+    pub indices: Vec<u32>,
+    pub lemmas: Vec<Byte32>,
+}
+
+impl IncludedTransaction {
+    /// Prove the inclusion of the transaction at the index of the block.
+    pub fn new(block: &BlockView, index: usize) -> Option<Self> {
+        let tx = block.transaction(index)?;
+        let proof = CBMT::build_merkle_proof(block.tx_hashes(), &[index as u32])?;
+
+        Some(IncludedTransaction {
+            tx,
+            block_hash: block.hash(),
+            witnesses_root: block.calc_witnesses_root(),
+            indices: proof.indices().to_vec(),
+            lemmas: proof.lemmas().to_vec(),
+        })
+    }
+
+    /// Whether the proof leads from the transaction hash to the transactions root of the header.
+    pub fn is_included_in(&self, header: &HeaderView) -> bool {
+        let proof = MerkleProof::new(self.indices.clone(), self.lemmas.clone());
+
+        header.hash() == self.block_hash
+            && proof
+                .root(&[self.tx.hash()])
+                .is_some_and(|raw_transactions_root| {
+                    merkle_root(&[raw_transactions_root, self.witnesses_root.clone()])
+                        == header.transactions_root()
+                })
+    }
+}
+
+/// Votes collected by an option, over all shards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionTally {
+    pub vote_option_type: u8,
+    pub votes: u128,
+}
+
+#[derive(Clone, Debug)]
+pub struct VoteReport {
+    pub vote_title: String,
+    /// Type script of the Core Cell and Vote Cells, identifying the vote.
+    pub type_script: Script,
+    pub total_distributed_tokens: u128,
+    /// Tallies in the order of `VOTE_OPTIONS`.
+    pub tallies: Vec<OptionTally>,
+    pub vote_result_option_type: u8,
+    pub settlement_block_number: u64,
+    /// The transaction finishing the vote.
+    pub settlement: IncludedTransaction,
+    /// Transactions creating the cells spent by the settlement transaction, the Core Cell and all Vote Cells.
+    pub input_txs: Vec<IncludedTransaction>,
+    pub signature: Option<Bytes>,
+}
+
+// Fields of a report recomputed from the settlement transaction and the cells it spent.
+struct Settlement {
+    type_script: Script,
+    core_data: CoreCellData,
+    tallies: Vec<OptionTally>,
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidReport(String::from(reason))
+}
+
+fn option_name(vote_option_type: u8) -> &'static str {
+    match vote_option_type {
+        VOTE_OPTION_NO => "no",
+        VOTE_OPTION_YES => "yes",
+        _ => "unknown",
+    }
+}
+
+fn parse_option_name(name: &str) -> Result<u8, Error> {
+    VOTE_OPTIONS
+        .iter()
+        .copied()
+        .find(|vote_option_type| option_name(*vote_option_type) == name)
+        .ok_or_else(|| invalid("unknown option"))
+}
+
+// The settlement transaction spends the open Core Cell and the Vote Cells, and recreates the Core Cell finished
+// as its first output, with a type script running dao-core. Every spent cell has to be found in the input
+// transactions.
+fn settle(
+    dao_core: &DeployedScript,
+    settlement_tx: &TransactionView,
+    input_txs: &[IncludedTransaction],
+) -> Result<Settlement, Error> {
+    let (output, data) = settlement_tx
+        .output_with_data(0)
+        .ok_or_else(|| invalid("settlement transaction without outputs"))?;
+    let type_script = output
+        .type_()
+        .to_opt()
+        .ok_or_else(|| invalid("first output is not a Core Cell"))?;
+    if !dao_core.matches(&type_script) {
+        return Err(invalid("first output doesn't run dao-core"));
+    }
+    let core_data = CoreCellData::from_slice(&data)
+        .ok_or_else(|| invalid("first output is not a Core Cell"))?;
+    if core_data.is_voting_finished != VOTING_STATUS_FINISHED {
+        return Err(invalid("vote is not finished"));
+    }
+
+    let mut tallies: Vec<OptionTally> = VOTE_OPTIONS
+        .iter()
+        .map(|vote_option_type| OptionTally {
+            vote_option_type: *vote_option_type,
+            votes: 0,
+        })
+        .collect();
+    let mut has_core_cell = false;
+
+    for input in settlement_tx.inputs() {
+        let out_point = input.previous_output();
+        let index: u32 = out_point.index().unpack();
+        let (output, data) = input_txs
+            .iter()
+            .find(|input_tx| input_tx.tx.hash() == out_point.tx_hash())
+            .and_then(|input_tx| input_tx.tx.output_with_data(index as usize))
+            .ok_or_else(|| invalid("spent cell missing from the input transactions"))?;
+        if output.type_().to_opt().as_ref() != Some(&type_script) {
+            continue;
+        }
+
+        if let Some(vote_data) = VoteCellData::from_slice(&data) {
+            let tally = tallies
+                .iter_mut()
+                .find(|tally| tally.vote_option_type == vote_data.vote_option_type)
+                .ok_or_else(|| invalid("unknown option"))?;
+            tally.votes = tally
+                .votes
+                .checked_add(vote_data.total_votes_collected)
+                .ok_or(Error::AmountOverflow)?;
+        } else if CoreCellData::from_slice(&data)
+            .is_some_and(|data| data.is_voting_finished == VOTING_STATUS_OPEN)
+        {
+            has_core_cell = true;
+        }
+    }
+
+    if !has_core_cell {
+        return Err(invalid(
+            "settlement transaction doesn't spend the open Core Cell",
+        ));
+    }
+
+    Ok(Settlement {
+        type_script,
+        core_data,
+        tallies,
+    })
+}
+
+impl VoteReport {
+    /// Generate the report of the vote finished by the settlement transaction. Blocks are scanned from the tip
+    /// down to the oldest cell spent by the settlement transaction.
+    pub fn generate(
+        source: &impl BlockSource,
+        settlement_tx_hash: &Byte32,
+        dao_core: &DeployedScript,
+    ) -> Result<Self, Error> {
+        let mut settlement: Option<IncludedTransaction> = None;
+        let mut missing_tx_hashes: Vec<Byte32> = vec![];
+        let mut input_txs: Vec<IncludedTransaction> = vec![];
+
+        for number in (0..=source.tip_block_number()?).rev() {
+            let block = match source.block(number)? {
+                Some(block) => block,
+                None => continue,
+            };
+            let tx_hashes = block.tx_hashes().to_vec();
+
+            if settlement.is_none() {
+                if let Some(index) = tx_hashes.iter().position(|hash| hash == settlement_tx_hash) {
+                    let included = IncludedTransaction::new(&block, index)
+                        .ok_or_else(|| invalid("transaction not in block"))?;
+                    for input in included.tx.inputs() {
+                        let tx_hash = input.previous_output().tx_hash();
+                        if !missing_tx_hashes.contains(&tx_hash) {
+                            missing_tx_hashes.push(tx_hash);
+                        }
+                    }
+                    settlement = Some(included);
+                }
+            }
+
+            for (index, tx_hash) in tx_hashes.iter().enumerate() {
+                if missing_tx_hashes.contains(tx_hash) {
+                    missing_tx_hashes.retain(|missing| missing != tx_hash);
+                    input_txs.push(
+                        IncludedTransaction::new(&block, index)
+                            .ok_or_else(|| invalid("transaction not in block"))?,
+                    );
+                }
+            }
+
+            if settlement.is_some() && missing_tx_hashes.is_empty() {
+                break;
+            }
+        }
+
+        let settlement = settlement.ok_or(Error::VoteNotFound)?;
+        if !missing_tx_hashes.is_empty() {
+            return Err(invalid("spent cell missing from the chain"));
+        }
+        // Oldest first, like the chain.
+        input_txs.reverse();
+
+        let Settlement {
+            type_script,
+            core_data,
+            tallies,
+        } = settle(dao_core, &settlement.tx, &input_txs)?;
+
+        Ok(VoteReport {
+            vote_title: core_data.vote_title,
+            type_script,
+            total_distributed_tokens: core_data.total_distributed_tokens,
+            tallies,
+            vote_result_option_type: core_data.vote_result_option_type,
+            settlement_block_number: core_data.settlement_block_number,
+            settlement,
+            input_txs,
+            signature: None,
+        })
+    }
+
+    pub fn settlement_tx_hash(&self) -> Byte32 {
+        self.settlement.tx.hash()
+    }
+
+    pub fn settlement_block_hash(&self) -> Byte32 {
+        self.settlement.block_hash.clone()
+    }
+
+    /// The message signed by the author: every field of the report but the proven transactions, which stand for
+    /// themselves.
+    pub fn signing_message(&self) -> H256 {
+        let mut blake2b = new_blake2b();
+        blake2b.update(&self.settlement.tx.hash().raw_data());
+        blake2b.update(&self.settlement.block_hash.raw_data());
+        blake2b.update(&self.type_script.calc_script_hash().raw_data());
+        blake2b.update(&(self.vote_title.len() as u64).to_le_bytes());
+        blake2b.update(self.vote_title.as_bytes());
+        blake2b.update(&self.total_distributed_tokens.to_le_bytes());
+        for tally in &self.tallies {
+            blake2b.update(&[tally.vote_option_type]);
+            blake2b.update(&tally.votes.to_le_bytes());
+        }
+        blake2b.update(&[self.vote_result_option_type]);
+        blake2b.update(&self.settlement_block_number.to_le_bytes());
+
+        let mut message = [0u8; 32];
+        blake2b.finalize(&mut message);
+
+        H256::from(message)
+    }
+
+    pub fn sign(&mut self, key: &Privkey) -> Result<(), Error> {
+        let signature = key
+            .sign_recoverable(&self.signing_message())
+            .map_err(|_| Error::InvalidSignature)?;
+        self.signature = Some(Bytes::from(signature.serialize()));
+
+        Ok(())
+    }
+
+    /// Blake160 hash of the public key which signed the report, if signed.
+    pub fn signer(&self) -> Result<Option<PubkeyHash>, Error> {
+        match &self.signature {
+            Some(signature) => recover_signer(&self.signing_message(), signature).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Check the report against the headers of the main chain. Every transaction has to be included in a block
+    /// known to the header source, the Core Cell has to run the deployed dao-core, and every field has to match
+    /// the ones recomputed from the transactions. Returns the signer, if signed.
+    pub fn verify(
+        &self,
+        headers: &impl HeaderSource,
+        dao_core: &DeployedScript,
+    ) -> Result<Option<PubkeyHash>, Error> {
+        for included in std::iter::once(&self.settlement).chain(self.input_txs.iter()) {
+            let header = headers
+                .header(&included.block_hash)?
+                .ok_or_else(|| invalid("block not found in the headers"))?;
+            if !included.is_included_in(&header) {
+                return Err(invalid("transaction not included in its block"));
+            }
+        }
+
+        let settlement = settle(dao_core, &self.settlement.tx, &self.input_txs)?;
+        let core_data = &settlement.core_data;
+        if settlement.type_script != self.type_script {
+            return Err(invalid("type script doesn't match the Core Cell"));
+        }
+        if core_data.vote_title != self.vote_title
+            || core_data.total_distributed_tokens != self.total_distributed_tokens
+        {
+            return Err(invalid("settings don't match the Core Cell"));
+        }
+        if core_data.vote_result_option_type != self.vote_result_option_type
+            || core_data.settlement_block_number != self.settlement_block_number
+        {
+            return Err(invalid("result doesn't match the Core Cell"));
+        }
+        if settlement.tallies != self.tallies {
+            return Err(invalid("tallies don't match the Vote Cells"));
+        }
+
+        self.signer()
+    }
+
+    pub fn to_json(&self) -> String {
+        let report = VoteReportJson {
+            vote_title: self.vote_title.clone(),
+            type_script: self.type_script.clone().into(),
+            total_distributed_tokens: self.total_distributed_tokens.into(),
+            tallies: self
+                .tallies
+                .iter()
+                .map(|tally| OptionTallyJson {
+                    option: option_name(tally.vote_option_type).to_string(),
+                    votes: tally.votes.into(),
+                })
+                .collect(),
+            result: option_name(self.vote_result_option_type).to_string(),
+            settlement_block_number: self.settlement_block_number.into(),
+            settlement_tx_hash: self.settlement_tx_hash().unpack(),
+            settlement_block_hash: self.settlement_block_hash().unpack(),
+            settlement: (&self.settlement).into(),
+            input_txs: self.input_txs.iter().map(Into::into).collect(),
+            signature: self
+                .signature
+                .clone()
+                .map(json_types::JsonBytes::from_bytes),
+        };
+
+        serde_json::to_string_pretty(&report).expect("serialize report")
+    }
+
+    /// Load a report. The proofs and fields are only checked by `verify`.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let report: VoteReportJson =
+            serde_json::from_str(json).map_err(|err| Error::InvalidReport(err.to_string()))?;
+        let tallies = report
+            .tallies
+            .iter()
+            .map(|tally| {
+                Ok(OptionTally {
+                    vote_option_type: parse_option_name(&tally.option)?,
+                    votes: tally.votes.into(),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let report_fields = VoteReport {
+            vote_title: report.vote_title,
+            type_script: report.type_script.into(),
+            total_distributed_tokens: report.total_distributed_tokens.into(),
+            tallies,
+            vote_result_option_type: parse_option_name(&report.result)?,
+            settlement_block_number: report.settlement_block_number.into(),
+            settlement: report.settlement.into(),
+            input_txs: report.input_txs.into_iter().map(Into::into).collect(),
+            signature: report.signature.map(|signature| signature.into_bytes()),
+        };
+
+        // The hashes are written out for readers, and have to match the settlement transaction.
+        if report_fields.settlement_tx_hash() != report.settlement_tx_hash.pack()
+            || report_fields.settlement_block_hash() != report.settlement_block_hash.pack()
+        {
+            return Err(invalid("hashes don't match the settlement transaction"));
+        }
+
+        Ok(report_fields)
+    }
+
+    /// A summary for archiving. Only the JSON can be verified.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        let cast_votes = self
+            .tallies
+            .iter()
+            .fold(0u128, |total, tally| total.saturating_add(tally.votes));
+
+        writeln!(markdown, "# {}", self.vote_title).unwrap();
+        writeln!(markdown).unwrap();
+        writeln!(markdown, "| Option | Votes |").unwrap();
+        writeln!(markdown, "| --- | ---: |").unwrap();
+        for tally in &self.tallies {
+            writeln!(
+                markdown,
+                "| {} | {} |",
+                option_name(tally.vote_option_type),
+                tally.votes
+            )
+            .unwrap();
+        }
+        writeln!(markdown).unwrap();
+        writeln!(
+            markdown,
+            "**Result: {}**, with {} of {} distributed tokens cast. Settled at block {}.",
+            option_name(self.vote_result_option_type),
+            cast_votes,
+            self.total_distributed_tokens,
+            self.settlement_block_number
+        )
+        .unwrap();
+        writeln!(markdown).unwrap();
+        writeln!(
+            markdown,
+            "- Settlement transaction: `{:#x}`",
+            self.settlement_tx_hash()
+        )
+        .unwrap();
+        writeln!(markdown, "- Block: `{:#x}`", self.settlement_block_hash()).unwrap();
+        writeln!(
+            markdown,
+            "- Vote type script hash: `{:#x}`",
+            self.type_script.calc_script_hash()
+        )
+        .unwrap();
+        let signer = match self.signer() {
+            Ok(Some(signer)) => format!("`0x{}`", hex(&signer)),
+            Ok(None) => String::from("not signed"),
+            Err(_) => String::from("invalid signature"),
+        };
+        writeln!(markdown, "- Signed by: {}", signer).unwrap();
+        writeln!(markdown).unwrap();
+
+        writeln!(markdown, "## Inclusion proofs").unwrap();
+        writeln!(markdown).unwrap();
+        writeln!(markdown, "| Transaction | Block | CBMT index | Lemmas |").unwrap();
+        writeln!(markdown, "| --- | --- | ---: | ---: |").unwrap();
+        for included in std::iter::once(&self.settlement).chain(self.input_txs.iter()) {
+            let indices: Vec<String> = included.indices.iter().map(u32::to_string).collect();
+            writeln!(
+                markdown,
+                "| `{:#x}` | `{:#x}` | {} | {} |",
+                included.tx.hash(),
+                included.block_hash,
+                indices.join(", "),
+                included.lemmas.len()
+            )
+            .unwrap();
+        }
+
+        markdown
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct VoteReportJson {
+    vote_title: String,
+    type_script: json_types::Script,
+    total_distributed_tokens: json_types::Uint128,
+    tallies: Vec<OptionTallyJson>,
+    result: String,
+    settlement_block_number: json_types::Uint64,
+    settlement_tx_hash: H256,
+    settlement_block_hash: H256,
+    settlement: IncludedTransactionJson,
+    input_txs: Vec<IncludedTransactionJson>,
+    signature: Option<json_types::JsonBytes>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OptionTallyJson {
+    option: String,
+    votes: json_types::Uint128,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IncludedTransactionJson {
+    transaction: json_types::Transaction,
+    proof: json_types::TransactionProof,
+}
+
+impl From<&IncludedTransaction> for IncludedTransactionJson {
+    fn from(included: &IncludedTransaction) -> Self {
+        IncludedTransactionJson {
+            transaction: included.tx.data().into(),
+            proof: json_types::TransactionProof {
+                block_hash: included.block_hash.unpack(),
+                witnesses_root: included.witnesses_root.unpack(),
+                proof: json_types::MerkleProof {
+                    indices: included
+                        .indices
+                        .iter()
+                        .map(|index| (*index).into())
+                        .collect(),
+                    lemmas: included.lemmas.iter().map(|lemma| lemma.unpack()).collect(),
+                },
+            },
+        }
+    }
+}
+
+impl From<IncludedTransactionJson> for IncludedTransaction {
+    fn from(included: IncludedTransactionJson) -> Self {
+        let proof = included.proof;
+
+        IncludedTransaction {
+            tx: packed::Transaction::from(included.transaction).into_view(),
+            block_hash: proof.block_hash.pack(),
+            witnesses_root: proof.witnesses_root.pack(),
+            indices: proof.proof.indices.into_iter().map(Into::into).collect(),
+            lemmas: proof
+                .proof
+                .lemmas
+                .iter()
+                .map(|lemma| lemma.pack())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Decoding the exit codes of failed scripts.
+//!
+//! A transaction failing verification only reports the exit code of the script and the cell it ran for, in
+//! ckb-testtool as well as in the error message of the `send_transaction` RPC. The cell is resolved to find which
+//! contract the script runs, and the code is then decoded with the registry shared with the contracts.
+
+use std::fmt;
+
+use ckb_tool::ckb_types::{
+    core::TransactionView,
+    packed::{OutPoint, Script},
+};
+
+pub use ckb_voting_errors::{DaoCoreError, SudtError};
+
+use crate::{DeployedScript, LiveCell};
+
+/// An error of dao-core or sUDT.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractError {
+    DaoCore(DaoCoreError),
+    Sudt(SudtError),
+}
+
+impl ContractError {
+    /// Name of the contract returning the error.
+    pub fn script(self) -> &'static str {
+        match self {
+            ContractError::DaoCore(_) => "dao-core",
+            ContractError::Sudt(_) => "sudt",
+        }
+    }
+
+    pub fn code(self) -> i8 {
+        match self {
+            ContractError::DaoCore(err) => err.code(),
+            ContractError::Sudt(err) => err.code(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ContractError::DaoCore(err) => err.name(),
+            ContractError::Sudt(err) => err.name(),
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            ContractError::DaoCore(err) => err.message(),
+            ContractError::Sudt(err) => err.message(),
+        }
+    }
+}
+
+impl From<DaoCoreError> for ContractError {
+    fn from(err: DaoCoreError) -> Self {
+        ContractError::DaoCore(err)
+    }
+}
+
+impl From<SudtError> for ContractError {
+    fn from(err: SudtError) -> Self {
+        ContractError::Sudt(err)
+    }
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} error {} ({})",
+            self.script(),
+            self.code(),
+            self.name()
+        )
+    }
+}
+
+/// The first cell of the script group a failed script ran for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureSource {
+    InputLock(usize),
+    InputType(usize),
+    OutputType(usize),
+}
+
+impl fmt::Display for FailureSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FailureSource::InputLock(index) => write!(f, "lock script of input {}", index),
+            FailureSource::InputType(index) => write!(f, "type script of input {}", index),
+            FailureSource::OutputType(index) => write!(f, "type script of output {}", index),
+        }
+    }
+}
+
+/// A script which exited with a non-zero code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScriptFailure {
+    pub source: FailureSource,
+    pub code: i8,
+}
+
+impl ScriptFailure {
+    /// Parse the failure from the message of a verification error, such as
+    /// `TransactionScriptError { source: Inputs[0].Type, cause: ValidationFailure(25): ... }`. Returns `None` if
+    /// the message isn't about a script exiting with a non-zero code.
+    pub fn parse(message: &str) -> Option<Self> {
+        let code = field(message, "ValidationFailure(", ')')?.parse().ok()?;
+
+        let source = field(message, "source: ", ',')?;
+        let (cells, script) = source.split_once('.')?;
+        let (cells, index) = cells.split_once('[')?;
+        let index = index.strip_suffix(']')?.parse().ok()?;
+        let source = match (cells, script.trim()) {
+            ("Inputs", "Lock") => FailureSource::InputLock(index),
+            ("Inputs", "Type") => FailureSource::InputType(index),
+            ("Outputs", "Type") => FailureSource::OutputType(index),
+            _ => return None,
+        };
+
+        Some(ScriptFailure { source, code })
+    }
+
+    /// The script which failed. Inputs are looked up with `resolve`.
+    pub fn script(
+        &self,
+        tx: &TransactionView,
+        resolve: impl Fn(&OutPoint) -> Option<LiveCell>,
+    ) -> Option<Script> {
+        let input = |index: usize| {
+            tx.inputs()
+                .get(index)
+                .and_then(|input| resolve(&input.previous_output()))
+        };
+
+        match self.source {
+            FailureSource::InputLock(index) => input(index).map(|cell| cell.output.lock()),
+            FailureSource::InputType(index) => {
+                input(index).and_then(|cell| cell.output.type_().to_opt())
+            }
+            FailureSource::OutputType(index) => tx
+                .outputs()
+                .get(index)
+                .and_then(|output| output.type_().to_opt()),
+        }
+    }
+
+    /// Decode the exit code, if the failed script runs dao-core or sUDT and the code is one of their errors.
+    pub fn decode(
+        &self,
+        tx: &TransactionView,
+        dao_core: &DeployedScript,
+        sudt: &DeployedScript,
+        resolve: impl Fn(&OutPoint) -> Option<LiveCell>,
+    ) -> Option<ContractError> {
+        let script = self.script(tx, resolve)?;
+
+        if dao_core.matches(&script) {
+            DaoCoreError::from_code(self.code).map(ContractError::DaoCore)
+        } else if sudt.matches(&script) {
+            SudtError::from_code(self.code).map(ContractError::Sudt)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ScriptFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} exited with code {}", self.source, self.code)
+    }
+}
+
+/// Decode the error of a transaction which failed verification, from the message of the error. See
+/// [`ScriptFailure::decode`].
+pub fn decode_script_error(
+    message: &str,
+    tx: &TransactionView,
+    dao_core: &DeployedScript,
+    sudt: &DeployedScript,
+    resolve: impl Fn(&OutPoint) -> Option<LiveCell>,
+) -> Option<(ScriptFailure, ContractError)> {
+    let failure = ScriptFailure::parse(message)?;
+    let error = failure.decode(tx, dao_core, sudt, resolve)?;
+
+    Some((failure, error))
+}
+
+// The text following `prefix` up to `terminator`.
+fn field<'a>(message: &'a str, prefix: &str, terminator: char) -> Option<&'a str> {
+    let start = message.find(prefix)? + prefix.len();
+    let rest = &message[start..];
+    let end = rest.find(terminator)?;
+
+    Some(&rest[..end])
+}
@@ -0,0 +1,369 @@
+//! JSON-RPC API over the votes indexed by ckb-voting-indexer.
+//!
+//! Methods follow the conventions of the CKB RPC: positional params, and numbers and hashes as 0x-prefixed
+//! hex strings. Lists are paginated with an offset and a limit, which defaults to and is capped at
+//! `MAX_PAGE_SIZE`.
+
+use std::sync::{Arc, Mutex};
+
+use ckb_tool::ckb_jsonrpc_types::{Uint128, Uint64};
+use ckb_tool::ckb_types::{packed::Byte32, prelude::*, H256};
+use ckb_voting_indexer::{Ballot, Receipt, Store, Vote};
+use ckb_voting_sdk::{
+    VOTE_OPTIONS, VOTE_OPTION_NO, VOTE_OPTION_YES, VOTING_STATUS_CANCELLED, VOTING_STATUS_EXECUTED,
+    VOTING_STATUS_FINISHED, VOTING_STATUS_OPEN, VOTING_WEIGHT_MODE_SNAPSHOT,
+};
+use jsonrpc_core::{Error, IoHandler, Params, Result, Value};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+#[derive(Serialize)]
+pub struct VoteView {
+    pub instance_id: H256,
+    pub tx_hash: H256,
+    pub block_number: Uint64,
+    pub title: String,
+    pub status: &'static str,
+    pub voting_weight_mode: &'static str,
+    pub total_distributed_tokens: Uint128,
+    pub voting_end_block_number: Option<Uint64>,
+    /// Only set when finished.
+    pub result: Option<&'static str>,
+    pub settlement_block_number: Option<Uint64>,
+}
+
+#[derive(Serialize)]
+pub struct OptionTally {
+    pub option: &'static str,
+    pub votes: Uint128,
+}
+
+#[derive(Serialize)]
+pub struct ShardTally {
+    pub option: &'static str,
+    pub shard_index: Uint64,
+    pub votes: Uint128,
+}
+
+#[derive(Serialize)]
+pub struct TallyView {
+    pub instance_id: H256,
+    pub total_distributed_tokens: Uint128,
+    pub options: Vec<OptionTally>,
+    pub shards: Vec<ShardTally>,
+}
+
+#[derive(Serialize)]
+pub struct BallotView {
+    pub tx_hash: H256,
+    pub block_number: Uint64,
+    pub instance_id: H256,
+    pub option: &'static str,
+    pub votes: Uint128,
+}
+
+#[derive(Serialize)]
+pub struct ReceiptView {
+    pub tx_hash: H256,
+    pub output_index: Uint64,
+    pub block_number: Uint64,
+    pub instance_id: H256,
+    pub option: &'static str,
+    pub amount: Uint128,
+    /// Set once the receipt is spent, e.g. to refund the escrowed tokens.
+    pub spent_block_number: Option<Uint64>,
+}
+
+#[derive(Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: Uint64,
+    pub offset: Uint64,
+    pub limit: Uint64,
+}
+
+impl From<&Receipt> for ReceiptView {
+    fn from(receipt: &Receipt) -> Self {
+        let output_index: u32 = receipt.out_point.index().unpack();
+
+        ReceiptView {
+            tx_hash: to_h256(&receipt.out_point.tx_hash()),
+            output_index: u64::from(output_index).into(),
+            block_number: receipt.block_number.into(),
+            instance_id: to_h256(&receipt.instance_id),
+            option: option_name(receipt.vote_option_type),
+            amount: receipt.amount.into(),
+            spent_block_number: receipt.spent_block_number.map(Into::into),
+        }
+    }
+}
+
+fn option_name(vote_option_type: u8) -> &'static str {
+    match vote_option_type {
+        VOTE_OPTION_NO => "no",
+        VOTE_OPTION_YES => "yes",
+        _ => "unknown",
+    }
+}
+
+fn status_name(status: u8) -> &'static str {
+    match status {
+        VOTING_STATUS_OPEN => "open",
+        VOTING_STATUS_FINISHED => "finished",
+        VOTING_STATUS_CANCELLED => "cancelled",
+        VOTING_STATUS_EXECUTED => "executed",
+        _ => "unknown",
+    }
+}
+
+fn to_h256(hash: &Byte32) -> H256 {
+    hash.unpack()
+}
+
+impl From<&Vote> for VoteView {
+    fn from(vote: &Vote) -> Self {
+        let core_data = &vote.core_data;
+        let finished = core_data.is_voting_finished == VOTING_STATUS_FINISHED
+            || core_data.is_voting_finished == VOTING_STATUS_EXECUTED;
+
+        VoteView {
+            instance_id: to_h256(&vote.instance_id),
+            tx_hash: to_h256(&vote.tx_hash),
+            block_number: vote.block_number.into(),
+            title: core_data.vote_title.clone(),
+            status: status_name(core_data.is_voting_finished),
+            voting_weight_mode: match core_data.voting_weight_mode {
+                VOTING_WEIGHT_MODE_SNAPSHOT => "snapshot",
+                _ => "distributed",
+            },
+            total_distributed_tokens: core_data.total_distributed_tokens.into(),
+            voting_end_block_number: match core_data.voting_end_block_number {
+                0 => None,
+                voting_end_block_number => Some(voting_end_block_number.into()),
+            },
+            result: match finished {
+                true => Some(option_name(core_data.vote_result_option_type)),
+                false => None,
+            },
+            settlement_block_number: match finished {
+                true => Some(core_data.settlement_block_number.into()),
+                false => None,
+            },
+        }
+    }
+}
+
+impl From<&Ballot> for BallotView {
+    fn from(ballot: &Ballot) -> Self {
+        BallotView {
+            tx_hash: to_h256(&ballot.tx_hash),
+            block_number: ballot.block_number.into(),
+            instance_id: to_h256(&ballot.instance_id),
+            option: option_name(ballot.vote_option_type),
+            votes: ballot.votes.into(),
+        }
+    }
+}
+
+fn paginate<T>(items: Vec<T>, offset: Option<Uint64>, limit: Option<Uint64>) -> Page<T> {
+    let total = items.len() as u64;
+    let offset = offset.map(|offset| offset.value()).unwrap_or(0);
+    let limit = limit
+        .map(|limit| limit.value().min(MAX_PAGE_SIZE))
+        .unwrap_or(MAX_PAGE_SIZE);
+
+    Page {
+        items: items
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect(),
+        total: total.into(),
+        offset: offset.into(),
+        limit: limit.into(),
+    }
+}
+
+fn database_error(err: ckb_voting_indexer::Error) -> Error {
+    let mut error = Error::internal_error();
+    error.message = err.to_string();
+    error
+}
+
+/// Parse positional params, of which the trailing ones may be omitted.
+fn parse_params<T: DeserializeOwned>(params: Params, len: usize) -> Result<T> {
+    let mut values: Vec<Value> = match params {
+        Params::None => vec![],
+        params => params.parse()?,
+    };
+    if values.len() < len {
+        values.resize(len, Value::Null);
+    }
+
+    Params::Array(values).parse()
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|_| Error::internal_error())
+}
+
+/// The API over a store, shared with the indexer or opened read-only.
+pub struct Api {
+    store: Arc<Mutex<Store>>,
+}
+
+impl Api {
+    pub fn new(store: Store) -> Self {
+        Api {
+            store: Arc::new(Mutex::new(store)),
+        }
+    }
+
+    fn with_store<T>(&self, f: impl FnOnce(&Store) -> Result<T>) -> Result<T> {
+        let store = self.store.lock().map_err(|_| Error::internal_error())?;
+        f(&store)
+    }
+
+    pub fn get_vote(&self, instance_id: H256) -> Result<Option<VoteView>> {
+        self.with_store(|store| {
+            let vote = store.vote(&instance_id.pack()).map_err(database_error)?;
+            Ok(vote.as_ref().map(VoteView::from))
+        })
+    }
+
+    pub fn list_votes(
+        &self,
+        status: Option<String>,
+        offset: Option<Uint64>,
+        limit: Option<Uint64>,
+    ) -> Result<Page<VoteView>> {
+        self.with_store(|store| {
+            let votes: Vec<VoteView> = store
+                .votes()
+                .map_err(database_error)?
+                .iter()
+                .map(VoteView::from)
+                .filter(|vote| status.as_deref().is_none_or(|status| vote.status == status))
+                .collect();
+
+            Ok(paginate(votes, offset, limit))
+        })
+    }
+
+    pub fn get_tally(&self, instance_id: H256) -> Result<Option<TallyView>> {
+        self.with_store(|store| {
+            let instance_id = instance_id.pack();
+            let vote = match store.vote(&instance_id).map_err(database_error)? {
+                Some(vote) => vote,
+                None => return Ok(None),
+            };
+            let vote_cells = store.vote_cells(&instance_id).map_err(database_error)?;
+
+            let options = VOTE_OPTIONS
+                .iter()
+                .map(|vote_option_type| OptionTally {
+                    option: option_name(*vote_option_type),
+                    votes: vote_cells
+                        .iter()
+                        .filter(|vote_cell| vote_cell.vote_option_type == *vote_option_type)
+                        .fold(0u128, |total, vote_cell| {
+                            total.saturating_add(vote_cell.total_votes_collected)
+                        })
+                        .into(),
+                })
+                .collect();
+            let shards = vote_cells
+                .iter()
+                .map(|vote_cell| ShardTally {
+                    option: option_name(vote_cell.vote_option_type),
+                    shard_index: u64::from(vote_cell.shard_index).into(),
+                    votes: vote_cell.total_votes_collected.into(),
+                })
+                .collect();
+
+            Ok(Some(TallyView {
+                instance_id: to_h256(&instance_id),
+                total_distributed_tokens: vote.core_data.total_distributed_tokens.into(),
+                options,
+                shards,
+            }))
+        })
+    }
+
+    pub fn get_voter_history(
+        &self,
+        lock_hash: H256,
+        offset: Option<Uint64>,
+        limit: Option<Uint64>,
+    ) -> Result<Page<BallotView>> {
+        self.with_store(|store| {
+            let ballots: Vec<BallotView> = store
+                .voter_ballots(&lock_hash.pack())
+                .map_err(database_error)?
+                .iter()
+                .map(BallotView::from)
+                .collect();
+
+            Ok(paginate(ballots, offset, limit))
+        })
+    }
+
+    pub fn get_voter_receipts(
+        &self,
+        lock_hash: H256,
+        offset: Option<Uint64>,
+        limit: Option<Uint64>,
+    ) -> Result<Page<ReceiptView>> {
+        self.with_store(|store| {
+            let receipts: Vec<ReceiptView> = store
+                .voter_receipts(&lock_hash.pack())
+                .map_err(database_error)?
+                .iter()
+                .map(ReceiptView::from)
+                .collect();
+
+            Ok(paginate(receipts, offset, limit))
+        })
+    }
+
+    /// Register the methods of the API.
+    pub fn into_handler(self) -> IoHandler {
+        let api = Arc::new(self);
+        let mut io = IoHandler::new();
+
+        let get_vote = api.clone();
+        io.add_method("get_vote", move |params: Params| {
+            let (instance_id,): (H256,) = params.parse()?;
+            to_value(get_vote.get_vote(instance_id)?)
+        });
+
+        let list_votes = api.clone();
+        io.add_method("list_votes", move |params: Params| {
+            let (status, offset, limit) = parse_params(params, 3)?;
+            to_value(list_votes.list_votes(status, offset, limit)?)
+        });
+
+        let get_tally = api.clone();
+        io.add_method("get_tally", move |params: Params| {
+            let (instance_id,): (H256,) = params.parse()?;
+            to_value(get_tally.get_tally(instance_id)?)
+        });
+
+        let get_voter_history = api.clone();
+        io.add_method("get_voter_history", move |params: Params| {
+            let (lock_hash, offset, limit): (H256, Option<Uint64>, Option<Uint64>) =
+                parse_params(params, 3)?;
+            to_value(get_voter_history.get_voter_history(lock_hash, offset, limit)?)
+        });
+
+        let get_voter_receipts = api;
+        io.add_method("get_voter_receipts", move |params: Params| {
+            let (lock_hash, offset, limit): (H256, Option<Uint64>, Option<Uint64>) =
+                parse_params(params, 3)?;
+            to_value(get_voter_receipts.get_voter_receipts(lock_hash, offset, limit)?)
+        });
+
+        io
+    }
+}
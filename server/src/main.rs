@@ -0,0 +1,55 @@
+//! Serves the votes indexed by ckb-voting-indexer over JSON-RPC.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::process;
+
+use ckb_voting_indexer::Store;
+use ckb_voting_server::Api;
+use clap::{App, Arg, ArgMatches};
+use jsonrpc_http_server::ServerBuilder;
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("ckb-voting-server")
+        .about("Serve the indexed votes over JSON-RPC")
+        .arg(
+            Arg::with_name("db")
+                .long("db")
+                .takes_value(true)
+                .default_value("votes.db")
+                .help("SQLite database written by ckb-voting-indexer"),
+        )
+        .arg(
+            Arg::with_name("listen")
+                .long("listen")
+                .takes_value(true)
+                .default_value("127.0.0.1:8118")
+                .help("Address to listen on"),
+        )
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let listen = matches.value_of("listen").unwrap_or_default();
+    let address: SocketAddr = listen
+        .parse()
+        .map_err(|_| format!("invalid address {}", listen))?;
+    let store = Store::open(Path::new(matches.value_of("db").unwrap_or_default()))
+        .map_err(|err| err.to_string())?;
+
+    let server = ServerBuilder::new(Api::new(store).into_handler())
+        .start_http(&address)
+        .map_err(|err| err.to_string())?;
+    println!("listening on {}", address);
+    server.wait();
+
+    Ok(())
+}
+
+fn main() {
+    let matches = app().get_matches();
+
+    if let Err(err) = run(&matches) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
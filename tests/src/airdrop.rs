@@ -0,0 +1,682 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_hash::Blake2bBuilder;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{bytes::Bytes, core::TransactionBuilder, packed::*, prelude::*};
+use ckb_voting_errors::SudtError;
+
+const MAX_CYCLES: u64 = 10_000_000;
+const BLAKE2B256_HASH_LEN: usize = 32; // Number of bytes for a Blake2b-256 hash.
+
+// Error Codes
+const ERROR_INVALID_PROOF: i8 = 9;
+const ERROR_ALREADY_CLAIMED: i8 = 10;
+const ERROR_INVALID_OUTPUT_CELL: i8 = 12;
+const ERROR_CLAIM_AMOUNT_MISMATCH: i8 = 13;
+const ERROR_SUDT_NON_TRANSFERABLE: i8 = SudtError::NonTransferable as i8;
+
+// sUDT Flags
+const FLAG_NON_TRANSFERABLE: u8 = 0b0000_0001;
+
+// Merkle Tree Prefixes
+const MERKLE_LEAF_PREFIX: u8 = 0;
+const MERKLE_NODE_PREFIX: u8 = 1;
+
+// Amount of tokens airdropped to each voter.
+const VOTER_AMOUNTS: [u128; 4] = [10, 20, 30, 40];
+
+fn hash(parts: &[&[u8]]) -> [u8; BLAKE2B256_HASH_LEN] {
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_LEN)
+        .personal(b"ckb-default-hash")
+        .build();
+
+    for part in parts {
+        blake2b.update(part);
+    }
+
+    let mut hash: [u8; BLAKE2B256_HASH_LEN] = [0; BLAKE2B256_HASH_LEN];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+fn leaf_hash(index: u32, lock_hash: &[u8; 32], amount: u128) -> [u8; BLAKE2B256_HASH_LEN] {
+    hash(&[
+        &[MERKLE_LEAF_PREFIX],
+        &index.to_le_bytes(),
+        lock_hash,
+        &amount.to_le_bytes(),
+    ])
+}
+
+// Builds a Merkle tree over the leaves, padded with zero hashes to a power of two.
+// Returns all levels of the tree, from the leaves up to the root.
+fn build_merkle_tree(
+    leaves: Vec<[u8; BLAKE2B256_HASH_LEN]>,
+) -> Vec<Vec<[u8; BLAKE2B256_HASH_LEN]>> {
+    let mut level = leaves;
+    level.resize(level.len().next_power_of_two(), [0u8; BLAKE2B256_HASH_LEN]);
+
+    let mut tree = vec![level];
+    while tree.last().unwrap().len() > 1 {
+        let next_level = tree
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| hash(&[&[MERKLE_NODE_PREFIX], &pair[0], &pair[1]]))
+            .collect();
+        tree.push(next_level);
+    }
+
+    tree
+}
+
+fn merkle_proof(tree: &[Vec<[u8; BLAKE2B256_HASH_LEN]>], index: usize) -> Vec<u8> {
+    let mut proof = vec![];
+    let mut position = index;
+
+    for level in &tree[0..tree.len() - 1] {
+        proof.extend_from_slice(&level[position ^ 1]);
+        position >>= 1;
+    }
+
+    proof
+}
+
+fn build_airdrop_data(
+    amount: u128,
+    tree: &[Vec<[u8; BLAKE2B256_HASH_LEN]>],
+    claimed: &[usize],
+) -> Bytes {
+    let mut claimed_bitmap = 0u8;
+    for index in claimed {
+        claimed_bitmap |= 1 << index;
+    }
+
+    let mut data = amount.to_le_bytes().to_vec();
+    data.extend_from_slice(&tree.last().unwrap()[0]);
+    data.push(claimed_bitmap);
+
+    Bytes::from(data)
+}
+
+#[test]
+fn test_airdrop_claim() {
+    let mut context = Context::default();
+    let out_point_airdrop = context.deploy_cell(Loader::default().load_binary("airdrop"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0u8]))
+        .expect("script");
+    let organizer_lock_hash: [u8; 32] = organizer_lock_script.calc_script_hash().unpack();
+    let airdrop_lock_script = context
+        .build_script(&out_point_airdrop, organizer_lock_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, organizer_lock_hash.to_vec().into())
+        .expect("script");
+
+    // Every voter is allocated tokens in a leaf of the Merkle tree.
+    let mut voter_lock_scripts = vec![];
+    let mut leaves = vec![];
+    for (i, amount) in VOTER_AMOUNTS.iter().enumerate() {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![i as u8 + 1]))
+            .expect("script");
+        leaves.push(leaf_hash(
+            i as u32,
+            &voter_lock_script.calc_script_hash().unpack(),
+            *amount,
+        ));
+        voter_lock_scripts.push(voter_lock_script);
+    }
+    let tree = build_merkle_tree(leaves);
+
+    // The Airdrop Cell holds the tokens of all voters.
+    let airdrop_cell = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(airdrop_lock_script)
+        .type_(Some(sudt_type_script.clone()).pack())
+        .build();
+    let airdrop_out_point =
+        context.create_cell(airdrop_cell.clone(), build_airdrop_data(100, &tree, &[]));
+
+    // Voter 2 claims with a proof of the allocation in their leaf.
+    let voter_lock_hash: [u8; 32] = voter_lock_scripts[2].calc_script_hash().unpack();
+    let mut witness = 2u32.to_le_bytes().to_vec();
+    witness.extend_from_slice(&voter_lock_hash);
+    witness.extend_from_slice(&30u128.to_le_bytes());
+    witness.extend_from_slice(&merkle_proof(&tree, 2));
+    let witness_args = WitnessArgs::new_builder()
+        .lock(
+            BytesOpt::new_builder()
+                .set(Some(Bytes::from(witness).pack()))
+                .build(),
+        )
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(airdrop_out_point)
+                .build(),
+        )
+        .output(airdrop_cell)
+        .output_data(build_airdrop_data(70, &tree, &[2]).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(voter_lock_scripts[2].clone())
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .witness(witness_args.as_bytes().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_airdrop_cannot_claim_twice() {
+    let mut context = Context::default();
+    let out_point_airdrop = context.deploy_cell(Loader::default().load_binary("airdrop"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0u8]))
+        .expect("script");
+    let organizer_lock_hash: [u8; 32] = organizer_lock_script.calc_script_hash().unpack();
+    let airdrop_lock_script = context
+        .build_script(&out_point_airdrop, organizer_lock_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, organizer_lock_hash.to_vec().into())
+        .expect("script");
+
+    // Every voter is allocated tokens in a leaf of the Merkle tree.
+    let mut voter_lock_scripts = vec![];
+    let mut leaves = vec![];
+    for (i, amount) in VOTER_AMOUNTS.iter().enumerate() {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![i as u8 + 1]))
+            .expect("script");
+        leaves.push(leaf_hash(
+            i as u32,
+            &voter_lock_script.calc_script_hash().unpack(),
+            *amount,
+        ));
+        voter_lock_scripts.push(voter_lock_script);
+    }
+    let tree = build_merkle_tree(leaves);
+
+    // The Airdrop Cell holds the tokens of all voters, and voter 2 has claimed already.
+    let airdrop_cell = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(airdrop_lock_script)
+        .type_(Some(sudt_type_script.clone()).pack())
+        .build();
+    let airdrop_out_point =
+        context.create_cell(airdrop_cell.clone(), build_airdrop_data(70, &tree, &[2]));
+
+    // Voter 2 claims with a proof of the allocation in their leaf.
+    let voter_lock_hash: [u8; 32] = voter_lock_scripts[2].calc_script_hash().unpack();
+    let mut witness = 2u32.to_le_bytes().to_vec();
+    witness.extend_from_slice(&voter_lock_hash);
+    witness.extend_from_slice(&30u128.to_le_bytes());
+    witness.extend_from_slice(&merkle_proof(&tree, 2));
+    let witness_args = WitnessArgs::new_builder()
+        .lock(
+            BytesOpt::new_builder()
+                .set(Some(Bytes::from(witness).pack()))
+                .build(),
+        )
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(airdrop_out_point)
+                .build(),
+        )
+        .output(airdrop_cell)
+        .output_data(build_airdrop_data(40, &tree, &[2]).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(voter_lock_scripts[2].clone())
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .witness(witness_args.as_bytes().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ALREADY_CLAIMED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_airdrop_cannot_claim_more_than_allocated() {
+    let mut context = Context::default();
+    let out_point_airdrop = context.deploy_cell(Loader::default().load_binary("airdrop"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0u8]))
+        .expect("script");
+    let organizer_lock_hash: [u8; 32] = organizer_lock_script.calc_script_hash().unpack();
+    let airdrop_lock_script = context
+        .build_script(&out_point_airdrop, organizer_lock_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, organizer_lock_hash.to_vec().into())
+        .expect("script");
+
+    // Every voter is allocated tokens in a leaf of the Merkle tree.
+    let mut voter_lock_scripts = vec![];
+    let mut leaves = vec![];
+    for (i, amount) in VOTER_AMOUNTS.iter().enumerate() {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![i as u8 + 1]))
+            .expect("script");
+        leaves.push(leaf_hash(
+            i as u32,
+            &voter_lock_script.calc_script_hash().unpack(),
+            *amount,
+        ));
+        voter_lock_scripts.push(voter_lock_script);
+    }
+    let tree = build_merkle_tree(leaves);
+
+    // The Airdrop Cell holds the tokens of all voters.
+    let airdrop_cell = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(airdrop_lock_script)
+        .type_(Some(sudt_type_script.clone()).pack())
+        .build();
+    let airdrop_out_point =
+        context.create_cell(airdrop_cell.clone(), build_airdrop_data(100, &tree, &[]));
+
+    // Voter 2 claims with a proof of the allocation in their leaf.
+    let voter_lock_hash: [u8; 32] = voter_lock_scripts[2].calc_script_hash().unpack();
+    let mut witness = 2u32.to_le_bytes().to_vec();
+    witness.extend_from_slice(&voter_lock_hash);
+    witness.extend_from_slice(&31u128.to_le_bytes());
+    witness.extend_from_slice(&merkle_proof(&tree, 2));
+    let witness_args = WitnessArgs::new_builder()
+        .lock(
+            BytesOpt::new_builder()
+                .set(Some(Bytes::from(witness).pack()))
+                .build(),
+        )
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(airdrop_out_point)
+                .build(),
+        )
+        .output(airdrop_cell)
+        .output_data(build_airdrop_data(69, &tree, &[2]).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(voter_lock_scripts[2].clone())
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(31u128.to_le_bytes().to_vec()).pack())
+        .witness(witness_args.as_bytes().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_PROOF).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_airdrop_cannot_claim_to_other_lock() {
+    let mut context = Context::default();
+    let out_point_airdrop = context.deploy_cell(Loader::default().load_binary("airdrop"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0u8]))
+        .expect("script");
+    let organizer_lock_hash: [u8; 32] = organizer_lock_script.calc_script_hash().unpack();
+    let airdrop_lock_script = context
+        .build_script(&out_point_airdrop, organizer_lock_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, organizer_lock_hash.to_vec().into())
+        .expect("script");
+
+    // Every voter is allocated tokens in a leaf of the Merkle tree.
+    let mut voter_lock_scripts = vec![];
+    let mut leaves = vec![];
+    for (i, amount) in VOTER_AMOUNTS.iter().enumerate() {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![i as u8 + 1]))
+            .expect("script");
+        leaves.push(leaf_hash(
+            i as u32,
+            &voter_lock_script.calc_script_hash().unpack(),
+            *amount,
+        ));
+        voter_lock_scripts.push(voter_lock_script);
+    }
+    let tree = build_merkle_tree(leaves);
+
+    // The Airdrop Cell holds the tokens of all voters.
+    let airdrop_cell = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(airdrop_lock_script)
+        .type_(Some(sudt_type_script.clone()).pack())
+        .build();
+    let airdrop_out_point =
+        context.create_cell(airdrop_cell.clone(), build_airdrop_data(100, &tree, &[]));
+
+    // Voter 2 claims with a proof of the allocation in their leaf.
+    let voter_lock_hash: [u8; 32] = voter_lock_scripts[2].calc_script_hash().unpack();
+    let mut witness = 2u32.to_le_bytes().to_vec();
+    witness.extend_from_slice(&voter_lock_hash);
+    witness.extend_from_slice(&30u128.to_le_bytes());
+    witness.extend_from_slice(&merkle_proof(&tree, 2));
+    let witness_args = WitnessArgs::new_builder()
+        .lock(
+            BytesOpt::new_builder()
+                .set(Some(Bytes::from(witness).pack()))
+                .build(),
+        )
+        .build();
+
+    // The tokens go to the lock of voter 3.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(airdrop_out_point)
+                .build(),
+        )
+        .output(airdrop_cell)
+        .output_data(build_airdrop_data(70, &tree, &[2]).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(voter_lock_scripts[3].clone())
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .witness(witness_args.as_bytes().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_CLAIM_AMOUNT_MISMATCH).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_airdrop_cannot_claim_without_marking_claimed() {
+    let mut context = Context::default();
+    let out_point_airdrop = context.deploy_cell(Loader::default().load_binary("airdrop"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0u8]))
+        .expect("script");
+    let organizer_lock_hash: [u8; 32] = organizer_lock_script.calc_script_hash().unpack();
+    let airdrop_lock_script = context
+        .build_script(&out_point_airdrop, organizer_lock_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, organizer_lock_hash.to_vec().into())
+        .expect("script");
+
+    // Every voter is allocated tokens in a leaf of the Merkle tree.
+    let mut voter_lock_scripts = vec![];
+    let mut leaves = vec![];
+    for (i, amount) in VOTER_AMOUNTS.iter().enumerate() {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![i as u8 + 1]))
+            .expect("script");
+        leaves.push(leaf_hash(
+            i as u32,
+            &voter_lock_script.calc_script_hash().unpack(),
+            *amount,
+        ));
+        voter_lock_scripts.push(voter_lock_script);
+    }
+    let tree = build_merkle_tree(leaves);
+
+    // The Airdrop Cell holds the tokens of all voters.
+    let airdrop_cell = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(airdrop_lock_script)
+        .type_(Some(sudt_type_script.clone()).pack())
+        .build();
+    let airdrop_out_point =
+        context.create_cell(airdrop_cell.clone(), build_airdrop_data(100, &tree, &[]));
+
+    // Voter 2 claims with a proof of the allocation in their leaf.
+    let voter_lock_hash: [u8; 32] = voter_lock_scripts[2].calc_script_hash().unpack();
+    let mut witness = 2u32.to_le_bytes().to_vec();
+    witness.extend_from_slice(&voter_lock_hash);
+    witness.extend_from_slice(&30u128.to_le_bytes());
+    witness.extend_from_slice(&merkle_proof(&tree, 2));
+    let witness_args = WitnessArgs::new_builder()
+        .lock(
+            BytesOpt::new_builder()
+                .set(Some(Bytes::from(witness).pack()))
+                .build(),
+        )
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(airdrop_out_point)
+                .build(),
+        )
+        .output(airdrop_cell)
+        .output_data(build_airdrop_data(70, &tree, &[]).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(voter_lock_scripts[2].clone())
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .witness(witness_args.as_bytes().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_airdrop_cannot_claim_non_transferable_tokens() {
+    let mut context = Context::default();
+    let out_point_airdrop = context.deploy_cell(Loader::default().load_binary("airdrop"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0u8]))
+        .expect("script");
+    let organizer_lock_hash: [u8; 32] = organizer_lock_script.calc_script_hash().unpack();
+    let airdrop_lock_script = context
+        .build_script(&out_point_airdrop, organizer_lock_hash.to_vec().into())
+        .expect("script");
+
+    // The airdropped tokens are non-transferable.
+    let mut sudt_args = organizer_lock_hash.to_vec();
+    sudt_args.push(FLAG_NON_TRANSFERABLE);
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, sudt_args.into())
+        .expect("script");
+
+    // Every voter is allocated tokens in a leaf of the Merkle tree.
+    let mut voter_lock_scripts = vec![];
+    let mut leaves = vec![];
+    for (i, amount) in VOTER_AMOUNTS.iter().enumerate() {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![i as u8 + 1]))
+            .expect("script");
+        leaves.push(leaf_hash(
+            i as u32,
+            &voter_lock_script.calc_script_hash().unpack(),
+            *amount,
+        ));
+        voter_lock_scripts.push(voter_lock_script);
+    }
+    let tree = build_merkle_tree(leaves);
+
+    // The Airdrop Cell holds the tokens of all voters.
+    let airdrop_cell = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(airdrop_lock_script)
+        .type_(Some(sudt_type_script.clone()).pack())
+        .build();
+    let airdrop_out_point =
+        context.create_cell(airdrop_cell.clone(), build_airdrop_data(100, &tree, &[]));
+
+    // Voter 2 claims with a proof of the allocation in their leaf.
+    let voter_lock_hash: [u8; 32] = voter_lock_scripts[2].calc_script_hash().unpack();
+    let mut witness = 2u32.to_le_bytes().to_vec();
+    witness.extend_from_slice(&voter_lock_hash);
+    witness.extend_from_slice(&30u128.to_le_bytes());
+    witness.extend_from_slice(&merkle_proof(&tree, 2));
+    let witness_args = WitnessArgs::new_builder()
+        .lock(
+            BytesOpt::new_builder()
+                .set(Some(Bytes::from(witness).pack()))
+                .build(),
+        )
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(airdrop_out_point)
+                .build(),
+        )
+        .output(airdrop_cell)
+        .output_data(build_airdrop_data(70, &tree, &[2]).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(voter_lock_scripts[2].clone())
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .witness(witness_args.as_bytes().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_SUDT_NON_TRANSFERABLE).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_airdrop_organizer_can_reclaim() {
+    let mut context = Context::default();
+    let out_point_airdrop = context.deploy_cell(Loader::default().load_binary("airdrop"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0u8]))
+        .expect("script");
+    let organizer_lock_hash: [u8; 32] = organizer_lock_script.calc_script_hash().unpack();
+    let airdrop_lock_script = context
+        .build_script(&out_point_airdrop, organizer_lock_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, organizer_lock_hash.to_vec().into())
+        .expect("script");
+
+    // Every voter is allocated tokens in a leaf of the Merkle tree.
+    let mut voter_lock_scripts = vec![];
+    let mut leaves = vec![];
+    for (i, amount) in VOTER_AMOUNTS.iter().enumerate() {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![i as u8 + 1]))
+            .expect("script");
+        leaves.push(leaf_hash(
+            i as u32,
+            &voter_lock_script.calc_script_hash().unpack(),
+            *amount,
+        ));
+        voter_lock_scripts.push(voter_lock_script);
+    }
+    let tree = build_merkle_tree(leaves);
+
+    // The Airdrop Cell holds the tokens of all voters, and voter 2 has claimed already.
+    let airdrop_cell = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(airdrop_lock_script)
+        .type_(Some(sudt_type_script.clone()).pack())
+        .build();
+    let airdrop_out_point =
+        context.create_cell(airdrop_cell.clone(), build_airdrop_data(70, &tree, &[2]));
+
+    // The organizer spends a cell of their own lock to take back the unclaimed tokens.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(airdrop_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(organizer_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
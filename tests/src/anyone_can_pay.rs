@@ -0,0 +1,614 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{bytes::Bytes, core::TransactionBuilder, packed::*, prelude::*};
+use ckb_voting_errors::AcpError;
+
+const MAX_CYCLES: u64 = 10_000_000;
+
+// Error Codes
+const ERROR_INVALID_ARGS_LENGTH: i8 = AcpError::InvalidArgsLength as i8;
+const ERROR_OUTPUT_CELL_MISSING: i8 = AcpError::OutputCellMissing as i8;
+const ERROR_AMOUNT_DECREASED: i8 = AcpError::AmountDecreased as i8;
+const ERROR_MINIMUM_AMOUNT_NOT_REACHED: i8 = AcpError::MinimumAmountNotReached as i8;
+
+// Minimum Amount Exponents
+const MINIMUM_CKB_EXPONENT: u8 = 2; // 100 shannons
+const MINIMUM_UDT_EXPONENT: u8 = 1; // 10 tokens
+
+#[test]
+fn test_acp_owner_can_spend() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let issuer_lock_hash = [3u8; 32];
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, issuer_lock_hash.to_vec().into())
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let acp_lock_script = context
+        .build_script(&out_point_acp, owner_lock_hash.to_vec().into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let owner_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(owner_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(owner_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(2000u64.pack())
+                .lock(owner_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(10u128.to_le_bytes().to_vec()).pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_acp_can_receive_ckb() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let payer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let acp_lock_script = context
+        .build_script(&out_point_acp, owner_lock_hash.to_vec().into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let payer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(payer_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(payer_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(1500u64.pack())
+                .lock(acp_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(payer_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_acp_can_receive_udt() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let payer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let issuer_lock_hash = [3u8; 32];
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, issuer_lock_hash.to_vec().into())
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let mut acp_args = owner_lock_hash.to_vec();
+    acp_args.extend_from_slice(&[MINIMUM_CKB_EXPONENT, MINIMUM_UDT_EXPONENT]);
+    let acp_lock_script = context
+        .build_script(&out_point_acp, acp_args.into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let payer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(payer_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(20u128.to_le_bytes().to_vec()),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(payer_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(acp_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(20u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(payer_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(10u128.to_le_bytes().to_vec()).pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_acp_can_receive_ckb_above_minimum() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let payer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let issuer_lock_hash = [3u8; 32];
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, issuer_lock_hash.to_vec().into())
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let mut acp_args = owner_lock_hash.to_vec();
+    acp_args.extend_from_slice(&[MINIMUM_CKB_EXPONENT, MINIMUM_UDT_EXPONENT]);
+    let acp_lock_script = context
+        .build_script(&out_point_acp, acp_args.into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let payer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(payer_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(payer_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(1100u64.pack())
+                .lock(acp_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(10u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(900u64.pack())
+                .lock(payer_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_acp_cannot_receive_below_minimum() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let payer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let issuer_lock_hash = [3u8; 32];
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, issuer_lock_hash.to_vec().into())
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let mut acp_args = owner_lock_hash.to_vec();
+    acp_args.extend_from_slice(&[MINIMUM_CKB_EXPONENT, MINIMUM_UDT_EXPONENT]);
+    let acp_lock_script = context
+        .build_script(&out_point_acp, acp_args.into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let payer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(payer_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(20u128.to_le_bytes().to_vec()),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(payer_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(1050u64.pack())
+                .lock(acp_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(15u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(950u64.pack())
+                .lock(payer_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(15u128.to_le_bytes().to_vec()).pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_MINIMUM_AMOUNT_NOT_REACHED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_acp_cannot_take_ckb() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let payer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let acp_lock_script = context
+        .build_script(&out_point_acp, owner_lock_hash.to_vec().into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(900u64.pack())
+                .lock(acp_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(100u64.pack())
+                .lock(payer_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_AMOUNT_DECREASED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_acp_cannot_take_udt() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let payer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let issuer_lock_hash = [3u8; 32];
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, issuer_lock_hash.to_vec().into())
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let acp_lock_script = context
+        .build_script(&out_point_acp, owner_lock_hash.to_vec().into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let payer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(payer_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(payer_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(1500u64.pack())
+                .lock(acp_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(5u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(payer_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(5u128.to_le_bytes().to_vec()).pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_AMOUNT_DECREASED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_acp_cannot_spend_without_paying_back() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let payer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let issuer_lock_hash = [3u8; 32];
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, issuer_lock_hash.to_vec().into())
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let acp_lock_script = context
+        .build_script(&out_point_acp, owner_lock_hash.to_vec().into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(payer_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(10u128.to_le_bytes().to_vec()).pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_OUTPUT_CELL_MISSING).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_acp_invalid_args_length() {
+    let mut context = Context::default();
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let mut acp_args = owner_lock_hash.to_vec();
+    acp_args.extend_from_slice(&[MINIMUM_CKB_EXPONENT, MINIMUM_UDT_EXPONENT, 0]);
+    let acp_lock_script = context
+        .build_script(&out_point_acp, acp_args.into())
+        .expect("script");
+
+    let acp_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(acp_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(acp_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(acp_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_ARGS_LENGTH).input_lock_script(0)
+    );
+}
@@ -0,0 +1,206 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{BlockBuilder, BlockView, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_voting_sdk::audit::{audit_vote, ChainSnapshot, Mint};
+use ckb_voting_sdk::{CastVote, CreateVote, Error, LiveCell, MintTokens};
+
+// Constants
+const MAX_CYCLES: u64 = 100_000_000;
+
+fn create_cell(context: &mut Context, lock_script: Script) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(lock_script)
+        .build();
+    let out_point = context.create_cell(output.clone(), Bytes::new());
+
+    LiveCell::new(out_point, output, Bytes::new())
+}
+
+// Verifies the transaction and adds it to the chain in a block of its own.
+fn commit_tx(
+    context: &mut Context,
+    blocks: &mut Vec<BlockView>,
+    tx: TransactionView,
+) -> Vec<LiveCell> {
+    let tx = context.complete_tx(tx);
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+
+    let live_cells = tx
+        .outputs_with_data_iter()
+        .enumerate()
+        .map(|(i, (output, data))| {
+            let out_point = OutPoint::new(tx.hash(), i as u32);
+            context.create_cell_with_out_point(out_point.clone(), output.clone(), data.clone());
+
+            LiveCell::new(out_point, output, data)
+        })
+        .collect();
+
+    let block = BlockBuilder::default()
+        .number((blocks.len() as u64 + 1).pack())
+        .transaction(tx)
+        .build();
+    blocks.push(block);
+
+    live_cells
+}
+
+#[test]
+fn test_audit_vote() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The vote is created in block 1, and the first voter votes in block 2.
+    let mut blocks = vec![];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone());
+    commit_tx(&mut context, &mut blocks, cast_vote.build().expect("build"));
+
+    let report = audit_vote(&ChainSnapshot::new(blocks.clone()), &cells[0]).expect("audit");
+    assert_eq!(report.creation_tx_hash, blocks[0].transactions()[0].hash());
+    assert_eq!(report.creation_block_number, 1);
+    assert_eq!(report.total_distributed_tokens, 30);
+    assert_eq!(report.live_tokens, 20);
+    assert_eq!(report.burned_tokens, 10);
+    assert_eq!(report.issued_tokens(), 30);
+    assert!(report.mints.is_empty());
+    assert!(report.is_supply_consistent());
+}
+
+#[test]
+fn test_audit_vote_mint_after_creation() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script.clone());
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let mut blocks = vec![];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+
+    // The organizer mints more tokens to a new voter in block 2.
+    let owner_cell = create_cell(&mut context, lock_script);
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let mint_tokens = MintTokens::new(&sudt, owner_cell).recipient(recipient_lock_script, 100);
+    let tx = context.complete_tx(mint_tokens.build().expect("build"));
+    commit_tx(&mut context, &mut blocks, tx.clone());
+
+    let report = audit_vote(&ChainSnapshot::new(blocks), &cells[0]).expect("audit");
+    assert_eq!(report.issued_tokens(), 130);
+    assert_eq!(
+        report.mints,
+        vec![Mint {
+            tx_hash: tx.hash(),
+            block_number: 2,
+            amount: 100,
+        }]
+    );
+    assert!(!report.is_supply_consistent());
+}
+
+#[test]
+fn test_audit_vote_from_json_snapshot() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script.clone());
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let mut blocks = vec![];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+    let owner_cell = create_cell(&mut context, lock_script.clone());
+    let mint_tokens = MintTokens::new(&sudt, owner_cell).recipient(lock_script, 100);
+    commit_tx(
+        &mut context,
+        &mut blocks,
+        mint_tokens.build().expect("build"),
+    );
+
+    let json_blocks: Vec<json_types::BlockView> = blocks.iter().cloned().map(Into::into).collect();
+    let json = serde_json::to_string(&json_blocks).expect("json");
+    let snapshot = ChainSnapshot::from_json(&json).expect("snapshot");
+
+    let core_cell = snapshot.cell(&cells[0].out_point).expect("core cell");
+    let report = audit_vote(&snapshot, &core_cell).expect("audit");
+    assert_eq!(
+        report,
+        audit_vote(&ChainSnapshot::new(blocks), &cells[0]).expect("audit")
+    );
+    assert_eq!(report.mints.len(), 1);
+}
+
+#[test]
+fn test_audit_vote_not_found() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script.clone());
+    let create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).voter(lock_script, 10);
+
+    let mut blocks = vec![];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+
+    // The Core Cell isn't created within the snapshot.
+    let snapshot = ChainSnapshot::new(blocks[1..].to_vec());
+    assert_eq!(audit_vote(&snapshot, &cells[0]), Err(Error::VoteNotFound));
+}
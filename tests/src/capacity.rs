@@ -0,0 +1,212 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_voting_sdk::capacity::{transaction_fee, with_signature_placeholders, DEFAULT_FEE_RATE};
+use ckb_voting_sdk::{
+    CoreCellData, CreateVote, Error, LiveCell, MintTokens, CORE_DATA_LEN, VOTE_DATA_LEN,
+};
+
+// Constants
+const MAX_CYCLES: u64 = 100_000_000;
+const CKBYTE: u64 = 100_000_000;
+
+// Capacity of a cell with a lock with 1 byte args, a type with 32 byte args and the data. (8 bytes capacity, 33
+// + 1 bytes lock, 33 + 32 bytes type)
+const fn cell_capacity(data_len: usize) -> u64 {
+    (8 + 34 + 65 + data_len as u64) * CKBYTE
+}
+
+fn create_cell(context: &mut Context, lock_script: &Script, capacity: u64) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(lock_script.clone())
+        .build();
+    let out_point = context.create_cell(output.clone(), Bytes::new());
+
+    LiveCell::new(out_point, output, Bytes::new())
+}
+
+fn capacities(tx: &TransactionView) -> Vec<u64> {
+    tx.outputs()
+        .into_iter()
+        .map(|output| {
+            let capacity: Capacity = output.capacity().unpack();
+            capacity.as_u64()
+        })
+        .collect()
+}
+
+#[test]
+fn test_required_capacity() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script, 1_000 * CKBYTE);
+
+    // A vote of three voters with two shards, which needs more capacity than the seed cell has.
+    let mut core_data = CoreCellData::new([0u8; 32], 0);
+    core_data.vote_shard_count = 2;
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell.clone()).core_data(core_data);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+    let required = create_vote.required_capacity().expect("capacity");
+
+    assert_eq!(required.core_cell, cell_capacity(CORE_DATA_LEN));
+    assert_eq!(required.vote_cells, vec![cell_capacity(VOTE_DATA_LEN); 4]);
+    assert_eq!(required.token_cells, vec![cell_capacity(16); 3]);
+
+    // Without capacity cells the fee is the one of the seed cell spent alone.
+    let tx = create_vote.build().expect("build");
+    let signed_tx = with_signature_placeholders(&tx, &[seed_cell.output.lock()]);
+    assert_eq!(required.fee, transaction_fee(&signed_tx, DEFAULT_FEE_RATE));
+    assert!(required.fee > 0);
+
+    // Every cell gets exactly the capacity it occupies.
+    let mut expected = vec![required.core_cell];
+    expected.extend(&required.vote_cells);
+    expected.extend(&required.token_cells);
+    assert_eq!(capacities(&tx), expected);
+    assert_eq!(
+        required.total(),
+        expected.iter().sum::<u64>() + required.fee
+    );
+}
+
+#[test]
+fn test_create_vote_collects_capacity_cells() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script, 1_000 * CKBYTE);
+
+    // A vote of three voters with two shards, which needs more capacity than the seed cell has.
+    let mut core_data = CoreCellData::new([0u8; 32], 0);
+    core_data.vote_shard_count = 2;
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell.clone()).core_data(core_data);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+    for _ in 0..3 {
+        let capacity_cell = create_cell(&mut context, &lock_script, 1_000 * CKBYTE);
+        create_vote = create_vote.capacity_cell(capacity_cell);
+    }
+    let required = create_vote.required_capacity().expect("capacity");
+    let tx = create_vote.build().expect("build");
+
+    // The seed cell and a single capacity cell cover the 1,207 CKBytes of the vote.
+    assert_eq!(tx.inputs().len(), 2);
+    let outputs = capacities(&tx);
+    assert_eq!(outputs.len(), 1 + 4 + 3 + 1);
+    let change = outputs[outputs.len() - 1];
+    assert_eq!(change, 2_000 * CKBYTE - required.total());
+    assert_eq!(tx.outputs().get(8).unwrap().lock(), lock_script);
+
+    let tx = context.complete_tx(tx);
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_create_vote_insufficient_capacity() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script, 1_000 * CKBYTE);
+
+    // A vote of three voters with two shards, which needs more capacity than the seed cell has.
+    let mut core_data = CoreCellData::new([0u8; 32], 0);
+    core_data.vote_shard_count = 2;
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell.clone()).core_data(core_data);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The 1,210 CKBytes of the seed and capacity cell cover the vote and the fee, but not a change cell.
+    let required = create_vote.required_capacity().expect("capacity");
+    assert!(required.total() < 1_210 * CKBYTE);
+    let capacity_cell = create_cell(&mut context, &lock_script, 210 * CKBYTE);
+    let create_vote = create_vote.capacity_cell(capacity_cell);
+    assert!(matches!(
+        create_vote.build(),
+        Err(Error::InsufficientCapacity)
+    ));
+    assert!(matches!(
+        create_vote.required_capacity(),
+        Err(Error::InsufficientCapacity)
+    ));
+}
+
+#[test]
+fn test_mint_tokens_fee_rate() {
+    let mut context = Context::default();
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let owner_cell = create_cell(&mut context, &lock_script, 1_000 * CKBYTE);
+    let capacity_cell = create_cell(&mut context, &lock_script, 1_000 * CKBYTE);
+    let change_lock = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1]))
+        .expect("script");
+    let recipient_lock = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2]))
+        .expect("script");
+    let fee_rate = 10 * DEFAULT_FEE_RATE;
+    let tx = MintTokens::new(&sudt, owner_cell.clone())
+        .recipient(recipient_lock, 1_000)
+        .capacity_cell(capacity_cell)
+        .change_lock(change_lock.clone())
+        .fee_rate(fee_rate)
+        .build()
+        .expect("build");
+
+    // The owner cell pays for the token cell, so the capacity cell isn't spent.
+    assert_eq!(tx.inputs().len(), 1);
+    let change = tx.outputs().get(1).unwrap();
+    assert_eq!(change.lock(), change_lock);
+
+    let signed_tx = with_signature_placeholders(&tx, &[owner_cell.output.lock()]);
+    let fee = transaction_fee(&signed_tx, fee_rate);
+    assert_eq!(
+        capacities(&tx),
+        vec![cell_capacity(16), 1_000 * CKBYTE - cell_capacity(16) - fee]
+    );
+
+    let tx = context.complete_tx(tx);
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
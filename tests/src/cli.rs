@@ -0,0 +1,515 @@
+use super::*;
+use ckb_system_scripts::BUNDLED_CELL;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{
+        Capacity, HeaderBuilder, HeaderView, ScriptHashType, TransactionBuilder, TransactionView,
+    },
+    packed::*,
+    prelude::*,
+    H256,
+};
+use ckb_tool::{ckb_error::assert_error_eq, ckb_script::ScriptError};
+use ckb_voting::chain::format_out_point;
+use ckb_voting::commands::{app, run};
+use ckb_voting::deployment::Deployment;
+use ckb_voting::wallet::{lock_script, sign_tx};
+use ckb_voting::Error;
+use ckb_voting_sdk::capacity::{
+    balance_tx, transaction_fee, with_signature_placeholders, DEFAULT_FEE_RATE,
+};
+use ckb_voting_sdk::{CreateVote, DeployedScript, LiveCell, MintTokens};
+use jsonrpc_core::{IoHandler, Params};
+use jsonrpc_http_server::{Server, ServerBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+
+// Constants
+const MAX_CYCLES: u64 = 100_000_000;
+
+// Error Codes
+const ERROR_PUBKEY_BLAKE160_HASH: i8 = -31;
+
+const DEPLOYMENT: &str = r#"
+[[cells]]
+name = "dao-core"
+enable_type_id = true
+location = { file = "build/release/dao-core" }
+
+[[cells]]
+name = "sudt"
+enable_type_id = false
+location = { file = "build/release/sudt" }
+
+[[cells]]
+name = "airdrop"
+enable_type_id = false
+location = { file = "build/release/airdrop" }
+"#;
+
+const MIGRATION: &str = r#"
+{
+    "cell_recipes": [
+        {
+            "name": "dao-core",
+            "tx_hash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "index": 0,
+            "occupied_capacity": 100000000000,
+            "data_hash": "0x2222222222222222222222222222222222222222222222222222222222222222",
+            "type_id": "0x3333333333333333333333333333333333333333333333333333333333333333"
+        },
+        {
+            "name": "sudt",
+            "tx_hash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "index": 1,
+            "occupied_capacity": 100000000000,
+            "data_hash": "0x4444444444444444444444444444444444444444444444444444444444444444",
+            "type_id": null
+        }
+    ]
+}
+"#;
+
+fn create_cell(context: &mut Context, lock: &Script, capacity: u64) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(capacity.pack())
+        .lock(lock.clone())
+        .build();
+    let out_point = context.create_cell(output.clone(), Default::default());
+
+    LiveCell::new(out_point, output, Default::default())
+}
+
+fn commit_tx(
+    context: &mut Context,
+    tx: &TransactionView,
+    block_number: u64,
+) -> (Vec<LiveCell>, HeaderView) {
+    let header = HeaderBuilder::default().number(block_number.pack()).build();
+    context.insert_header(header.clone());
+
+    let live_cells = tx
+        .outputs_with_data_iter()
+        .enumerate()
+        .map(|(i, (output, data))| {
+            let out_point = OutPoint::new(tx.hash(), i as u32);
+            context.create_cell_with_out_point(out_point.clone(), output.clone(), data.clone());
+            context.link_cell_with_block(out_point.clone(), header.hash(), 0);
+
+            LiveCell::new(out_point, output, data)
+        })
+        .collect();
+
+    (live_cells, header)
+}
+
+// A node serving the live cells, and the headers of the blocks they were created in.
+fn serve_node(cells: Vec<(LiveCell, HeaderView)>) -> Server {
+    let cells = Arc::new(cells);
+    let mut io = IoHandler::new();
+
+    let live_cells = cells.clone();
+    io.add_method("get_live_cell", move |params: Params| {
+        let (out_point, _): (json_types::OutPoint, bool) = params.parse()?;
+        let out_point: OutPoint = out_point.into();
+        let cell = live_cells
+            .iter()
+            .find(|(cell, _)| cell.out_point == out_point)
+            .map(|(cell, _)| json_types::CellInfo {
+                output: cell.output.clone().into(),
+                data: Some(json_types::CellData {
+                    content: json_types::JsonBytes::from_bytes(cell.data.clone()),
+                    hash: CellOutput::calc_data_hash(&cell.data).unpack(),
+                }),
+            });
+        let status = match cell {
+            Some(_) => "live",
+            None => "unknown",
+        };
+        Ok(serde_json::to_value(json_types::CellWithStatus {
+            cell,
+            status: status.to_string(),
+        })
+        .unwrap())
+    });
+
+    let transactions = cells.clone();
+    io.add_method("get_transaction", move |params: Params| {
+        let (tx_hash,): (H256,) = params.parse()?;
+        let tx = transactions
+            .iter()
+            .find(|(cell, _)| cell.out_point.tx_hash() == tx_hash.pack())
+            .map(|(_, header)| {
+                json_types::TransactionWithStatus::with_committed(
+                    TransactionBuilder::default().build(),
+                    header.hash().unpack(),
+                )
+            });
+        Ok(serde_json::to_value(tx).unwrap())
+    });
+
+    let headers = cells;
+    io.add_method("get_header", move |params: Params| {
+        let (block_hash,): (H256,) = params.parse()?;
+        let header = headers
+            .iter()
+            .find(|(_, header)| header.hash() == block_hash.pack())
+            .map(|(_, header)| json_types::HeaderView::from(header.clone()));
+        Ok(serde_json::to_value(header).unwrap())
+    });
+
+    ServerBuilder::new(io)
+        .start_http(&"127.0.0.1:0".parse().unwrap())
+        .expect("start node")
+}
+
+// Write the deployment config and migration of the scripts to a new directory.
+fn write_deployment(name: &str, scripts: &[(&str, &DeployedScript)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ckb-voting-{}-{}", name, process::id()));
+    fs::create_dir_all(&dir).expect("create dir");
+
+    let mut deployment = String::new();
+    let mut recipes = vec![];
+    for (name, script) in scripts {
+        deployment.push_str(&format!(
+            "[[cells]]\nname = \"{}\"\nenable_type_id = false\nlocation = {{ file = \"build/release/{}\" }}\n",
+            name, name
+        ));
+        let tx_hash: H256 = script.out_point.tx_hash().unpack();
+        let index: u32 = script.out_point.index().unpack();
+        let data_hash: H256 = script.code_hash.unpack();
+        recipes.push(serde_json::json!({
+            "name": name,
+            "tx_hash": tx_hash,
+            "index": index,
+            "data_hash": data_hash,
+            "type_id": null,
+        }));
+    }
+    fs::write(dir.join("deployment.toml"), deployment).expect("write deployment");
+    fs::write(
+        dir.join("migration.json"),
+        serde_json::json!({ "cell_recipes": recipes }).to_string(),
+    )
+    .expect("write migration");
+
+    dir
+}
+
+// Run the command against the node and the deployment, and load the transaction it wrote.
+fn run_command(node: &Server, dir: &Path, args: &[String]) -> TransactionView {
+    let output = dir.join("tx.json");
+    let mut command = vec![
+        String::from("ckb-voting"),
+        format!("--rpc=http://{}", node.address()),
+        format!("--deployment={}", dir.join("deployment.toml").display()),
+        format!("--migration={}", dir.join("migration.json").display()),
+    ];
+    command.extend_from_slice(args);
+    command.push(format!("--output={}", output.display()));
+
+    let matches = app().get_matches_from_safe(command).expect("arguments");
+    run(&matches).expect("run command");
+
+    let tx: json_types::TransactionView =
+        serde_json::from_str(&fs::read_to_string(output).expect("read tx")).expect("tx");
+    Transaction::from(tx.inner).into_view()
+}
+
+#[test]
+fn test_deployment_resolves_migration() {
+    let deployment = Deployment::from_str(DEPLOYMENT, Some(MIGRATION)).expect("deployment");
+
+    let dao_core = deployment.script("dao-core").expect("dao-core");
+    assert_eq!(dao_core.code_hash.raw_data().to_vec(), vec![0x33u8; 32]);
+    assert!(matches!(dao_core.hash_type, ScriptHashType::Type));
+    let index: u32 = dao_core.out_point.index().unpack();
+    assert_eq!(index, 0);
+
+    let sudt = deployment.script("sudt").expect("sudt");
+    assert_eq!(sudt.code_hash.raw_data().to_vec(), vec![0x44u8; 32]);
+    assert!(matches!(sudt.hash_type, ScriptHashType::Data));
+    let index: u32 = sudt.out_point.index().unpack();
+    assert_eq!(index, 1);
+}
+
+#[test]
+fn test_deployment_missing_migration() {
+    let deployment = Deployment::from_str(DEPLOYMENT, Some(MIGRATION)).expect("deployment");
+    assert!(matches!(
+        deployment.script("airdrop"),
+        Err(Error::NotDeployed(_))
+    ));
+
+    let deployment = Deployment::from_str(DEPLOYMENT, None).expect("deployment");
+    assert!(matches!(
+        deployment.script("dao-core"),
+        Err(Error::NotDeployed(_))
+    ));
+}
+
+#[test]
+fn test_deployment_invalid() {
+    assert!(matches!(
+        Deployment::from_str("[[cells]]\nname = 1", None),
+        Err(Error::InvalidDeployment(_))
+    ));
+}
+
+#[test]
+fn test_sign_tx() {
+    // Mint tokens owned by a secp256k1 lock, paying with another cell of the same lock.
+    let mut context = Context::default();
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let secp256k1_data = BUNDLED_CELL.get("specs/cells/secp256k1_data").unwrap();
+    let secp256k1_sighash_all = BUNDLED_CELL
+        .get("specs/cells/secp256k1_blake160_sighash_all")
+        .unwrap();
+    let out_point_secp256k1_data = context.deploy_cell(secp256k1_data.to_vec().into());
+    let out_point_lock = context.deploy_cell(secp256k1_sighash_all.to_vec().into());
+    let sudt_code = Loader::default().load_binary("sudt");
+    let sudt = DeployedScript::from_code(context.deploy_cell(sudt_code.clone()), &sudt_code);
+
+    let key = Privkey::from_slice(&[1u8; 32]);
+    let lock_template = context
+        .build_script(&out_point_lock, Default::default())
+        .expect("script");
+    let lock_script = lock_script(Some(&lock_template), &key).expect("lock script");
+    let recipient_lock = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+
+    let owner_cell = create_cell(&mut context, &lock_script, 13_000_000_000);
+    let capacity_cell = create_cell(&mut context, &lock_script, 20_000_000_000);
+
+    let tx = MintTokens::new(&sudt, owner_cell.clone())
+        .recipient(recipient_lock, 1_000)
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(out_point_secp256k1_data)
+                .build(),
+        )
+        .build()
+        .expect("build");
+
+    // The owner cell alone doesn't cover a change cell.
+    let tx = balance_tx(
+        tx,
+        std::slice::from_ref(&owner_cell),
+        std::slice::from_ref(&capacity_cell),
+        &lock_script,
+        DEFAULT_FEE_RATE,
+    )
+    .expect("balance");
+    let input_locks = vec![lock_script.clone(), lock_script.clone()];
+    let fee = transaction_fee(
+        &with_signature_placeholders(&tx, &input_locks),
+        DEFAULT_FEE_RATE,
+    );
+    let tx = context.complete_tx(tx);
+
+    // The change returns everything but the minted tokens and the fee. The token cell occupies 122 CKBytes.
+    let change: Capacity = tx.outputs().get(1).unwrap().capacity().unpack();
+    assert_eq!(change.as_u64(), 33_000_000_000 - 12_200_000_000 - fee);
+
+    let tx = sign_tx(tx, &input_locks, &lock_script, &key).expect("sign");
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_sign_tx_other_key() {
+    // Mint tokens owned by a secp256k1 lock, paying with another cell of the same lock.
+    let mut context = Context::default();
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let secp256k1_data = BUNDLED_CELL.get("specs/cells/secp256k1_data").unwrap();
+    let secp256k1_sighash_all = BUNDLED_CELL
+        .get("specs/cells/secp256k1_blake160_sighash_all")
+        .unwrap();
+    let out_point_secp256k1_data = context.deploy_cell(secp256k1_data.to_vec().into());
+    let out_point_lock = context.deploy_cell(secp256k1_sighash_all.to_vec().into());
+    let sudt_code = Loader::default().load_binary("sudt");
+    let sudt = DeployedScript::from_code(context.deploy_cell(sudt_code.clone()), &sudt_code);
+
+    let key = Privkey::from_slice(&[1u8; 32]);
+    let lock_template = context
+        .build_script(&out_point_lock, Default::default())
+        .expect("script");
+    let lock_script = lock_script(Some(&lock_template), &key).expect("lock script");
+    let recipient_lock = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+
+    let owner_cell = create_cell(&mut context, &lock_script, 13_000_000_000);
+    let capacity_cell = create_cell(&mut context, &lock_script, 20_000_000_000);
+
+    let tx = MintTokens::new(&sudt, owner_cell.clone())
+        .recipient(recipient_lock, 1_000)
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(out_point_secp256k1_data)
+                .build(),
+        )
+        .build()
+        .expect("build");
+
+    // The owner cell alone doesn't cover a change cell.
+    let tx = balance_tx(
+        tx,
+        std::slice::from_ref(&owner_cell),
+        std::slice::from_ref(&capacity_cell),
+        &lock_script,
+        DEFAULT_FEE_RATE,
+    )
+    .expect("balance");
+    let tx = context.complete_tx(tx);
+    let input_locks = vec![lock_script.clone(), lock_script.clone()];
+
+    let other_key = Privkey::from_slice(&[2u8; 32]);
+    let tx = sign_tx(tx, &input_locks, &lock_script, &other_key).expect("sign");
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_PUBKEY_BLAKE160_HASH).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_vote_command_returns_freed_capacity() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script, 1_000_000_000_000);
+    let tx = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .voter(voter_lock_script.clone(), 10)
+        .build()
+        .expect("build");
+    let (cells, header) = commit_tx(&mut context, &tx, 100);
+
+    let node = serve_node(
+        cells
+            .iter()
+            .map(|cell| (cell.clone(), header.clone()))
+            .collect(),
+    );
+    let dir = write_deployment("vote", &[("dao-core", &dao_core), ("sudt", &sudt)]);
+
+    // All tokens are burned without capacity cells, so the fee is paid from the capacity of the token cell.
+    let tx = run_command(
+        &node,
+        &dir,
+        &[
+            String::from("vote"),
+            format!("--core={}", format_out_point(&cells[0].out_point)),
+            format!("--vote-cell={}", format_out_point(&cells[2].out_point)),
+            format!("--token-cell={}", format_out_point(&cells[3].out_point)),
+            format!("--cell-dep={}", format_out_point(&out_point_always_success)),
+        ],
+    );
+    assert_eq!(tx.inputs().len(), 2);
+    assert_eq!(tx.outputs().len(), 2);
+    let change = tx.outputs().get(1).expect("change");
+    assert_eq!(change.lock(), voter_lock_script);
+    let input_locks = [cells[2].output.lock(), cells[3].output.lock()];
+    let fee = transaction_fee(
+        &with_signature_placeholders(&tx, &input_locks),
+        DEFAULT_FEE_RATE,
+    );
+    let token_capacity: Capacity = cells[3].output.capacity().unpack();
+    let capacity: Capacity = change.capacity().unpack();
+    assert_eq!(capacity.as_u64(), token_capacity.as_u64() - fee);
+
+    let tx = context.complete_tx(tx);
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_vote_command_pays_with_capacity_cell() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script, 1_000_000_000_000);
+    let tx = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .voter(voter_lock_script, 10)
+        .build()
+        .expect("build");
+    let (cells, header) = commit_tx(&mut context, &tx, 100);
+    let capacity_cell = create_cell(&mut context, &lock_script, 20_000_000_000);
+
+    let node = serve_node(
+        cells
+            .iter()
+            .chain(std::iter::once(&capacity_cell))
+            .map(|cell| (cell.clone(), header.clone()))
+            .collect(),
+    );
+    let dir = write_deployment("vote-capacity", &[("dao-core", &dao_core), ("sudt", &sudt)]);
+
+    // The remaining tokens keep the capacity of the token cell, so the capacity cell pays the fee.
+    let tx = run_command(
+        &node,
+        &dir,
+        &[
+            String::from("vote"),
+            format!("--core={}", format_out_point(&cells[0].out_point)),
+            format!("--vote-cell={}", format_out_point(&cells[2].out_point)),
+            format!("--token-cell={}", format_out_point(&cells[3].out_point)),
+            String::from("--votes=4"),
+            format!(
+                "--capacity-cell={}",
+                format_out_point(&capacity_cell.out_point)
+            ),
+            format!("--cell-dep={}", format_out_point(&out_point_always_success)),
+        ],
+    );
+    assert_eq!(tx.inputs().len(), 3);
+    assert_eq!(
+        tx.inputs().get(2).unwrap().previous_output(),
+        capacity_cell.out_point
+    );
+
+    // A single change cell, locked with the lock of the capacity cell.
+    assert_eq!(tx.outputs().len(), 3);
+    let change = tx.outputs().get(2).expect("change");
+    assert_eq!(change.lock(), lock_script);
+    let input_locks = [
+        cells[2].output.lock(),
+        cells[3].output.lock(),
+        capacity_cell.output.lock(),
+    ];
+    let fee = transaction_fee(
+        &with_signature_placeholders(&tx, &input_locks),
+        DEFAULT_FEE_RATE,
+    );
+    let capacity: Capacity = change.capacity().unpack();
+    assert_eq!(capacity.as_u64(), 20_000_000_000 - fee);
+
+    let tx = context.complete_tx(tx);
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
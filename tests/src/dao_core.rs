@@ -1,136 +1,7175 @@
 use super::*;
 use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
-use ckb_tool::ckb_hash::Blake2bBuilder;
-use ckb_tool::ckb_types::{bytes::Bytes, core::TransactionBuilder, packed::*, prelude::*};
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_hash::blake2b_256;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{HeaderBuilder, HeaderView, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_voting_errors::{AcpError, DaoCoreError};
+use ckb_voting_sdk::capacity::{transaction_fee, with_signature_placeholders, DEFAULT_FEE_RATE};
+use ckb_voting_sdk::{
+    calculate_instance_id, CastVote, CoreCellData, CreateVote, DeployedScript, Error, FinishVote,
+    LiveCell, ReceiptCellData, VoteCellData, ORGANIZER_MODE_LOCK, ORGANIZER_MODE_MULTISIG,
+    TOKEN_ID_MODE_CODE_HASH, VOTE_OPTION_NO, VOTE_OPTION_YES, VOTING_STATUS_CANCELLED,
+    VOTING_STATUS_EXECUTED, VOTING_STATUS_FINISHED, VOTING_STATUS_OPEN,
+    VOTING_WEIGHT_MODE_SNAPSHOT,
+};
 
 const MAX_CYCLES: u64 = 10_000_000;
-const BLAKE2B256_HASH_LEN: usize = 32; // Number of bytes for a Blake2b-256 hash.
 
-fn calculate_instance_id(
-    seed_cell_outpoint: &OutPoint,
-    output_index: usize,
-) -> [u8; BLAKE2B256_HASH_LEN] {
-    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_LEN)
-        .personal(b"ckb-default-hash")
+// Error Codes
+const ERROR_INVALID_OUTPUT_CELL_DATA: i8 = DaoCoreError::InvalidOutputCellData as i8;
+const ERROR_UNAUTHORIZED: i8 = DaoCoreError::Unauthorized as i8;
+const ERROR_INVALID_INSTANCE_ID: i8 = DaoCoreError::InvalidInstanceId as i8;
+const ERROR_TOKEN_DISTRIBUTION_MISMATCH: i8 = DaoCoreError::TokenDistributionMismatch as i8;
+const ERROR_INVALID_VOTE_CELL_COUNT: i8 = DaoCoreError::InvalidVoteCellCount as i8;
+const ERROR_CORE_CELL_MISSING: i8 = DaoCoreError::CoreCellMissing as i8;
+const ERROR_VOTING_FINISHED: i8 = DaoCoreError::VotingFinished as i8;
+const ERROR_VOTE_TALLY_MISMATCH: i8 = DaoCoreError::VoteTallyMismatch as i8;
+const ERROR_SNAPSHOT_NOT_REACHED: i8 = DaoCoreError::SnapshotNotReached as i8;
+const ERROR_CELL_NOT_LIVE_AT_SNAPSHOT: i8 = DaoCoreError::CellNotLiveAtSnapshot as i8;
+const ERROR_SNAPSHOT_TOKENS_NOT_RETURNED: i8 = DaoCoreError::SnapshotTokensNotReturned as i8;
+const ERROR_INVALID_TOKEN_ID_MODE: i8 = DaoCoreError::InvalidTokenIdMode as i8;
+const ERROR_NO_MAJORITY: i8 = DaoCoreError::NoMajority as i8;
+const ERROR_SETTLEMENT_HEADER_MISSING: i8 = DaoCoreError::SettlementHeaderMissing as i8;
+const ERROR_INVALID_ORGANIZER_WITNESS: i8 = DaoCoreError::InvalidOrganizerWitness as i8;
+const ERROR_ORGANIZER_THRESHOLD_NOT_MET: i8 = DaoCoreError::OrganizerThresholdNotMet as i8;
+const ERROR_DUPLICATE_ORGANIZER_SIGNER: i8 = DaoCoreError::DuplicateOrganizerSigner as i8;
+const ERROR_RECEIPT_MISMATCH: i8 = DaoCoreError::ReceiptMismatch as i8;
+const ERROR_INVALID_EXTENSION: i8 = DaoCoreError::InvalidExtension as i8;
+const ERROR_EXTENSION_LIMIT_EXCEEDED: i8 = DaoCoreError::ExtensionLimitExceeded as i8;
+const ERROR_VOTING_NOT_ENDED: i8 = DaoCoreError::VotingNotEnded as i8;
+const ERROR_INVALID_VOTE_SHARD: i8 = DaoCoreError::InvalidVoteShard as i8;
+const ERROR_INVALID_SHARD_COUNT: i8 = DaoCoreError::InvalidShardCount as i8;
+const ERROR_VOTE_NOT_FINISHED: i8 = DaoCoreError::VoteNotFinished as i8;
+const ERROR_ESCROW_OUTSTANDING: i8 = DaoCoreError::EscrowOutstanding as i8;
+const ERROR_INVALID_ESCROW_LOCK: i8 = DaoCoreError::InvalidEscrowLock as i8;
+const ERROR_VOTING_ENDED: i8 = DaoCoreError::VotingEnded as i8;
+const ERROR_MINTABLE_TOKEN_FAMILY: i8 = DaoCoreError::MintableTokenFamily as i8;
+const ERROR_TREASURY_NOT_RELEASED: i8 = DaoCoreError::TreasuryNotReleased as i8;
+const ERROR_NON_TRANSFERABLE_ESCROW: i8 = DaoCoreError::NonTransferableEscrow as i8;
+const ERROR_ACP_AMOUNT_DECREASED: i8 = AcpError::AmountDecreased as i8;
+
+// Voting End
+const VOTING_END_BLOCK_NUMBER: u64 = 200;
+const MAX_EXTENSION: u64 = 100;
+
+// Script Hash Types
+const HASH_TYPE_DATA: u8 = 0;
+const HASH_TYPE_TYPE: u8 = 1;
+
+// sUDT Flags
+const FLAG_NON_TRANSFERABLE: u8 = 0b0000_0001;
+
+// Builds an Anyone-Can-Pay lock owned by the specified lock, without minimum amounts.
+fn build_acp_lock_script(
+    context: &mut Context,
+    out_point_acp: &OutPoint,
+    owner_lock_script: &Script,
+) -> Script {
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+
+    context
+        .build_script(out_point_acp, owner_lock_hash.to_vec().into())
+        .expect("script")
+}
+
+fn build_vote_data(vote_option_type: u8, total_votes_collected: u128) -> Bytes {
+    build_shard_vote_data(vote_option_type, 0, total_votes_collected)
+}
+
+fn build_shard_vote_data(
+    vote_option_type: u8,
+    shard_index: u8,
+    total_votes_collected: u128,
+) -> Bytes {
+    VoteCellData::new(vote_option_type, shard_index, total_votes_collected).to_bytes()
+}
+
+fn build_receipt_data(vote_option_type: u8, amount: u128, owner_lock_hash: [u8; 32]) -> Bytes {
+    let mut data = vec![vote_option_type];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&owner_lock_hash);
+
+    Bytes::from(data)
+}
+
+// Deploys a token family other than the standard sUDT, whose instances are only issued by the organizer. Its code is
+// a copy of the sUDT code, which has another code hash.
+fn deploy_organizer_token(context: &mut Context) -> DeployedScript {
+    let mut code = Loader::default().load_binary("sudt").to_vec();
+    code.push(0);
+    let code = Bytes::from(code);
+    let out_point = context.deploy_cell(code.clone());
+
+    DeployedScript::from_code(out_point, &code)
+}
+
+// Creates a live cell owned by the specified lock, to be spent as the seed of a vote.
+fn create_seed_cell(context: &mut Context, lock_script: Script) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(lock_script)
+        .build();
+    let out_point = context.create_cell(output.clone(), Bytes::new());
+
+    LiveCell::new(out_point, output, Bytes::new())
+}
+
+// Adds the outputs of a verified transaction to the context as live cells, created in a block of the given number.
+fn commit_tx(
+    context: &mut Context,
+    tx: &TransactionView,
+    block_number: u64,
+) -> (Vec<LiveCell>, HeaderView) {
+    let header = HeaderBuilder::default().number(block_number.pack()).build();
+    context.insert_header(header.clone());
+
+    let live_cells = tx
+        .outputs_with_data_iter()
+        .enumerate()
+        .map(|(i, (output, data))| {
+            let out_point = OutPoint::new(tx.hash(), i as u32);
+            context.create_cell_with_out_point(out_point.clone(), output.clone(), data.clone());
+            context.link_cell_with_block(out_point.clone(), header.hash(), 0);
+
+            LiveCell::new(out_point, output, data)
+        })
+        .collect();
+
+    (live_cells, header)
+}
+
+#[test]
+fn test_can_create_vote() {
+    // deploy contract
+    let mut context = Context::default();
+    context.set_capture_debug(true);
+    let dao_core = deploy_script(&mut context, "dao-core");
+
+    // prepare scripts
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+
+    // Voter cells are locked with ACP locks owned by the voters' own locks.
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    // build transaction
+    let tx = create_vote.build().expect("build");
+    println!("OUTPUTS DATA: {:?}", tx.outputs_data());
+    let tx = context.complete_tx(tx);
+
+    // run
+    context.verify_tx(&tx, MAX_CYCLES).unwrap();
+    println!("DEBUG MESSAGES: {:?}", context.captured_messages());
+}
+
+#[test]
+fn test_can_run_vote_lifecycle() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Lifecycle");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    context.verify_tx(&tx, MAX_CYCLES).expect("create vote");
+    let (cells, core_header) = commit_tx(&mut context, &tx, 100);
+    let (core_cell, vote_no_cell) = (cells[0].clone(), cells[1].clone());
+
+    // The first two voters vote yes with all of their tokens.
+    let mut vote_yes_cell = cells[2].clone();
+    let mut vote_yes_header = core_header.clone();
+    for (i, token_cell) in cells[3..5].iter().enumerate() {
+        let cast_vote = CastVote::new(&dao_core, &sudt, core_cell.clone(), vote_yes_cell)
+            .token_cell(token_cell.clone());
+
+        let tx = context.complete_tx(cast_vote.build().expect("build"));
+        context.verify_tx(&tx, MAX_CYCLES).expect("cast vote");
+        let (cells, header) = commit_tx(&mut context, &tx, 150 + i as u64);
+        vote_yes_cell = cells[0].clone();
+        vote_yes_header = header;
+    }
+
+    let finish_vote = FinishVote::new(&dao_core, core_cell, &core_header)
+        .vote_cell(vote_no_cell, &core_header)
+        .vote_cell(vote_yes_cell, &vote_yes_header);
+    assert_eq!(finish_vote.vote_result(), Ok(VOTE_OPTION_YES));
+
+    let tx = context.complete_tx(finish_vote.build().expect("build"));
+    context.verify_tx(&tx, MAX_CYCLES).expect("finish vote");
+}
+
+#[test]
+fn test_cast_vote_returns_freed_capacity() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .voter(voter_lock_script.clone(), 10)
+        .capacity(100_000_000_000);
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    context.verify_tx(&tx, MAX_CYCLES).expect("create vote");
+    let (cells, _) = commit_tx(&mut context, &tx, 100);
+
+    // All tokens are burned, so the capacity of the token cell goes to the voter, less the fee.
+    let tx = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .build()
+        .expect("build");
+    assert_eq!(tx.outputs().len(), 2);
+    let change = tx.outputs().get(1).expect("change");
+    assert_eq!(change.lock(), voter_lock_script);
+    let capacity: u64 = change.capacity().unpack();
+    let input_locks = [cells[2].output.lock(), cells[3].output.lock()];
+    let fee = transaction_fee(
+        &with_signature_placeholders(&tx, &input_locks),
+        DEFAULT_FEE_RATE,
+    );
+    assert!(fee > 0);
+    assert_eq!(capacity, 100_000_000_000 - fee);
+
+    let tx = context.complete_tx(tx);
+    context.verify_tx(&tx, MAX_CYCLES).expect("cast vote");
+}
+
+#[test]
+fn test_can_cast_vote_with_escrow_with_sdk() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let vote_escrow = deploy_script(&mut context, "vote-escrow");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let create_vote =
+        CreateVote::new(&dao_core, &sudt, seed_cell).voter(voter_lock_script.clone(), 10);
+    let type_hash = create_vote.type_script().calc_script_hash();
+    let escrow_lock_hash: [u8; 32] = vote_escrow
+        .script(type_hash.raw_data())
+        .calc_script_hash()
+        .unpack();
+    let create_vote = create_vote.core_data(CoreCellData {
+        escrow_lock_hash,
+        ..CoreCellData::new([0u8; 32], 0)
+    });
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    context.verify_tx(&tx, MAX_CYCLES).expect("create vote");
+    let (cells, _) = commit_tx(&mut context, &tx, 100);
+
+    // The escrow lock can't be built without the vote-escrow script.
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8);
+    assert_eq!(cast_vote.build().err(), Some(Error::InvalidEscrowLock));
+
+    // 8 tokens are escrowed with a receipt for the voter, and the other 2 are returned.
+    let tx = cast_vote.vote_escrow(&vote_escrow).build().expect("build");
+    let receipt_data: Bytes = tx.outputs_data().get(3).expect("receipt").unpack();
+    assert_eq!(
+        ReceiptCellData::from_slice(&receipt_data),
+        Some(ReceiptCellData::new(
+            VOTE_OPTION_YES,
+            8,
+            voter_lock_script.calc_script_hash().unpack()
+        ))
+    );
+
+    let tx = context.complete_tx(tx);
+    context.verify_tx(&tx, MAX_CYCLES).expect("cast vote");
+}
+
+#[test]
+fn test_can_cast_snapshot_vote_with_sdk() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+            snapshot_block_number: 100,
+            ..CoreCellData::new([0u8; 32], 10)
+        })
+        .voter(voter_lock_script, 10);
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    context.verify_tx(&tx, MAX_CYCLES).expect("create vote");
+    let (cells, header) = commit_tx(&mut context, &tx, 100);
+
+    // Snapshot votes are cast with the whole balance, which is returned.
+    let cast_vote = || {
+        CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+            .token_cell(cells[3].clone())
+            .header_dep(header.hash())
+    };
+    assert_eq!(
+        cast_vote().votes(8).build().err(),
+        Some(Error::PartialSnapshotVote)
+    );
+
+    let tx = cast_vote().build().expect("build");
+    let token_data: Bytes = tx.outputs_data().get(1).expect("tokens").unpack();
+    assert_eq!(token_data, Bytes::from(10u128.to_le_bytes().to_vec()));
+
+    let tx = context.complete_tx(tx);
+    context.verify_tx(&tx, MAX_CYCLES).expect("cast vote");
+}
+
+#[test]
+fn test_can_create_vote_with_lock_args() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+
+    // The Core Cell and Vote Cells are kept under a lock with args.
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8; 20]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .lock(vote_lock_script)
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_can_create_several_votes_in_one_transaction() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // Two more votes are created from the same Seed Cell, each with the instance id of its own output index.
+    // They count a snapshot balance of the voting token, so no more tokens have to be distributed.
+    let seed_out_point = tx.inputs().get(0).expect("input").previous_output();
+    let token_code_hash = create_vote.token_type_script().calc_script_hash().unpack();
+    let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    for _ in 0..2 {
+        let instance_id = calculate_instance_id(&seed_out_point, outputs.len());
+        let dao_core_cell = outputs[0]
+            .clone()
+            .as_builder()
+            .type_(Some(dao_core.script(Bytes::from(instance_id.to_vec()))).pack())
+            .build();
+        let core_data = CoreCellData {
+            voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+            ..CoreCellData::new(token_code_hash, 1000)
+        };
+        outputs.push(dao_core_cell.clone());
+        outputs_data.push(core_data.to_bytes().pack());
+
+        for vote_option_type in &[VOTE_OPTION_NO, VOTE_OPTION_YES] {
+            outputs.push(dao_core_cell.clone());
+            outputs_data.push(build_vote_data(*vote_option_type, 0).pack());
+        }
+    }
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs(outputs)
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_create_vote_with_instance_id_of_first_output() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script.clone());
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // The instance id was calculated for output 0, but the Core Cell is moved to output 1.
+    let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    outputs.insert(
+        0,
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script)
+            .build(),
+    );
+    outputs_data.insert(0, Bytes::new().pack());
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs(outputs)
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_INSTANCE_ID).output_type_script(1)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_instance_id_of_other_output() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // A second vote from the same Seed Cell, with the instance id of the output after its Core Cell.
+    let seed_out_point = tx.inputs().get(0).expect("input").previous_output();
+    let token_code_hash = create_vote.token_type_script().calc_script_hash().unpack();
+    let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    let core_cell_index = outputs.len();
+    let instance_id = calculate_instance_id(&seed_out_point, core_cell_index + 1);
+    let dao_core_cell = outputs[0]
+        .clone()
+        .as_builder()
+        .type_(Some(dao_core.script(Bytes::from(instance_id.to_vec()))).pack())
+        .build();
+    let core_data = CoreCellData {
+        voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+        ..CoreCellData::new(token_code_hash, 1000)
+    };
+    outputs.push(dao_core_cell.clone());
+    outputs_data.push(core_data.to_bytes().pack());
+    for vote_option_type in &[VOTE_OPTION_NO, VOTE_OPTION_YES] {
+        outputs.push(dao_core_cell.clone());
+        outputs_data.push(build_vote_data(*vote_option_type, 0).pack());
+    }
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs(outputs)
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_INSTANCE_ID)
+            .output_type_script(core_cell_index)
+    );
+}
+
+#[test]
+fn test_can_create_vote_with_vote_shards() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+
+    // The votes of every option are split across 3 Vote Cells.
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            vote_shard_count: 3,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_create_vote_with_missing_vote_shard() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            vote_shard_count: 3,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // The Vote Cell of the last shard of YES is left out.
+    let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    outputs.remove(6);
+    outputs_data.remove(6);
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs(outputs)
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_CELL_COUNT).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_duplicate_vote_shard() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            vote_shard_count: 2,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // The second shard of YES claims to be the first one.
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    outputs_data[4] = build_shard_vote_data(VOTE_OPTION_YES, 0, 0).pack();
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_CELL_COUNT).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_without_vote_shards() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            vote_shard_count: 0,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_SHARD_COUNT).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_without_vote_cells() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // The Vote Cell of YES is left out.
+    let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    outputs.remove(2);
+    outputs_data.remove(2);
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs(outputs)
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_CELL_COUNT).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_duplicate_vote_option() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // Both Vote Cells are for NO.
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    outputs_data[2] = build_vote_data(VOTE_OPTION_NO, 0).pack();
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_CELL_COUNT).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_token_distribution_mismatch() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // The last voter gets one token less than the Core Cell records.
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    outputs_data[5] = Bytes::from(9u128.to_le_bytes().to_vec()).pack();
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_TOKEN_DISTRIBUTION_MISMATCH).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_create_snapshot_vote_without_distributing_tokens() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+
+    // The voters already hold the tokens, so only the Core Cell and Vote Cells are created.
+    let create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+            snapshot_block_number: 100,
+            ..CoreCellData::new([0u8; 32], 1_000_000)
+        })
+        .capacity(500);
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_can_create_vote_with_escrow() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let vote_escrow = deploy_script(&mut context, "vote-escrow");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    // Voted tokens are held by the vote escrow lock of this vote.
+    let type_hash = create_vote.type_script().calc_script_hash();
+    let escrow_lock_hash = vote_escrow
+        .script(type_hash.raw_data())
+        .calc_script_hash()
+        .unpack();
+    let create_vote = create_vote.core_data(CoreCellData {
+        escrow_lock_hash,
+        ..CoreCellData::new([0u8; 32], 0)
+    });
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_create_vote_with_escrow_of_other_vote() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let vote_escrow = deploy_script(&mut context, "vote-escrow");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+
+    // The vote escrow lock holds the tokens of another vote.
+    let escrow_lock_hash = vote_escrow
+        .script(Bytes::from(vec![7u8; 32]))
+        .calc_script_hash()
+        .unpack();
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            escrow_lock_hash,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_ESCROW_LOCK).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_other_escrow_lock() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script.clone());
+
+    // The organizer's own lock would receive the voted tokens.
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            escrow_lock_hash: lock_script.calc_script_hash().unpack(),
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_ESCROW_LOCK).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_escrow_of_non_transferable_tokens() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let vote_escrow = deploy_script(&mut context, "vote-escrow");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    let type_hash = create_vote.type_script().calc_script_hash();
+    let escrow_lock_hash = vote_escrow
+        .script(type_hash.raw_data())
+        .calc_script_hash()
+        .unpack();
+    let create_vote = create_vote.core_data(CoreCellData {
+        escrow_lock_hash,
+        ..CoreCellData::new([0u8; 32], 0)
+    });
+    let tx = create_vote.build().expect("build");
+
+    // The tokens are issued in non-transferable mode, so votes couldn't move them to the vote escrow lock.
+    let token_type_script = create_vote.token_type_script();
+    let mut token_args: Vec<u8> = token_type_script.args().raw_data().to_vec();
+    token_args.push(FLAG_NON_TRANSFERABLE);
+    let non_transferable_type_script = token_type_script
+        .clone()
+        .as_builder()
+        .args(Bytes::from(token_args).pack())
+        .build();
+    let outputs: Vec<CellOutput> = tx
+        .outputs()
+        .into_iter()
+        .map(|output| match output.type_().to_opt() {
+            Some(type_script) if type_script == token_type_script => output
+                .as_builder()
+                .type_(Some(non_transferable_type_script.clone()).pack())
+                .build(),
+            _ => output,
+        })
+        .collect();
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    let core_data = CoreCellData::from_slice(&outputs_data[0].raw_data()).expect("core data");
+    outputs_data[0] = CoreCellData {
+        token_code_hash: non_transferable_type_script.calc_script_hash().unpack(),
+        ..core_data
+    }
+    .to_bytes()
+    .pack();
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs(outputs)
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_NON_TRANSFERABLE_ESCROW).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_finished_vote() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            is_voting_finished: VOTING_STATUS_FINISHED,
+            vote_result_option_type: VOTE_OPTION_YES,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_create_vote_with_token_code_hash() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let token = deploy_organizer_token(&mut context);
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &token, seed_cell)
+        .core_data(CoreCellData {
+            token_id_mode: TOKEN_ID_MODE_CODE_HASH,
+            token_hash_type: HASH_TYPE_DATA,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // The last voter gets another token instance of the same family.
+    let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    outputs[5] = outputs[5]
+        .clone()
+        .as_builder()
+        .type_(Some(token.script(Bytes::from(vec![7u8; 32]))).pack())
+        .build();
+    let tx = tx.as_advanced_builder().set_outputs(outputs).build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_create_vote_with_sudt_code_hash() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            token_id_mode: TOKEN_ID_MODE_CODE_HASH,
+            token_hash_type: HASH_TYPE_DATA,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    // Anyone can issue an instance of the standard sUDT, so voters could mint their own voting weight.
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_MINTABLE_TOKEN_FAMILY).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_other_token_by_type_hash() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // The last voter gets another token instance sharing the code of the voting token.
+    let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    outputs[5] = outputs[5]
+        .clone()
+        .as_builder()
+        .type_(Some(sudt.script(Bytes::from(vec![7u8; 32]))).pack())
+        .build();
+    let tx = tx.as_advanced_builder().set_outputs(outputs).build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_TOKEN_DISTRIBUTION_MISMATCH).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_token_code_hash_and_wrong_hash_type() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let token = deploy_organizer_token(&mut context);
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &token, seed_cell)
+        .core_data(CoreCellData {
+            token_id_mode: TOKEN_ID_MODE_CODE_HASH,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+    let tx = create_vote.build().expect("build");
+
+    // The token is referenced by data hash, but the Core Cell expects it by type. Code referenced by type has to be
+    // a cell dep, so its family can be checked.
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    let core_data = CoreCellData::from_slice(&outputs_data[0].raw_data()).expect("core data");
+    outputs_data[0] = CoreCellData {
+        token_hash_type: HASH_TYPE_TYPE,
+        ..core_data
+    }
+    .to_bytes()
+    .pack();
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_MINTABLE_TOKEN_FAMILY).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_create_vote_with_invalid_token_id_mode() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            token_id_mode: 2,
+            ..CoreCellData::new([0u8; 32], 0)
+        })
+        .capacity(500);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(
+            build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script),
+            10,
+        );
+    }
+
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_TOKEN_ID_MODE).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_create_vote_with_airdrop_cell() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_airdrop = context.deploy_cell(Loader::default().load_binary("airdrop"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_seed_cell(&mut context, lock_script);
+    let create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).capacity(500);
+    let tx = create_vote.build().expect("build");
+
+    // All voting tokens are held by a single Airdrop Cell instead of one cell per voter.
+    let airdrop_lock_script = context
+        .build_script(&out_point_airdrop, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let airdrop_cell = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(airdrop_lock_script)
+        .type_(Some(create_vote.token_type_script()).pack())
+        .build();
+    let mut airdrop_data = 30u128.to_le_bytes().to_vec();
+    airdrop_data.extend_from_slice(&[1u8; 32]); // Merkle root of the voter allocations.
+    airdrop_data.push(0); // No allocations claimed yet.
+
+    let mut outputs_data: Vec<_> = tx.outputs_data().into_iter().collect();
+    let core_data = CoreCellData::from_slice(&outputs_data[0].raw_data()).expect("core data");
+    outputs_data[0] = CoreCellData {
+        total_distributed_tokens: 30,
+        ..core_data
+    }
+    .to_bytes()
+    .pack();
+    outputs_data.push(Bytes::from(airdrop_data).pack());
+    let tx = tx
+        .as_advanced_builder()
+        .output(airdrop_cell)
+        .set_outputs_data(outputs_data)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_can_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30);
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_can_vote_with_token_code_hash() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let token = deploy_organizer_token(&mut context);
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = token.script(lock_script_hash_owner.to_vec().into());
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        token_id_mode: TOKEN_ID_MODE_CODE_HASH,
+        ..CoreCellData::new(sudt_type_script.code_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_vote_with_tokens_of_another_voter() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30);
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    // Without the voter's own lock in the inputs, the ACP cell can't be spent.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ACP_AMOUNT_DECREASED).input_lock_script(1)
+    );
+}
+
+#[test]
+fn test_cannot_vote_with_more_votes_than_tokens_burned() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30);
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 12).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_TALLY_MISMATCH).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_with_self_minted_tokens() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30);
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter issues another instance of the voting token's code, with the voter's own lock as the owner.
+    let voter_lock_hash: [u8; 32] = voter_lock_script.calc_script_hash().unpack();
+    let minted_type_script = context
+        .build_script(&out_point_sudt, voter_lock_hash.to_vec().into())
+        .expect("script");
+    let minted_token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script)
+        .type_(Some(minted_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        minted_token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(minted_token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_TALLY_MISMATCH).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_without_core_cell_dep() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30);
+    context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_CORE_CELL_MISSING).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_when_voting_finished() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTING_FINISHED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_vote_into_vote_cell_created_at_voting_end() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        voting_end_block_number: VOTING_END_BLOCK_NUMBER,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let vote_header = HeaderBuilder::default()
+        .number(VOTING_END_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(vote_header.clone());
+    context.link_cell_with_block(vote_out_point.clone(), vote_header.hash(), 0);
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .header_dep(vote_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_vote_into_vote_cell_created_after_voting_end() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        voting_end_block_number: VOTING_END_BLOCK_NUMBER,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let vote_header = HeaderBuilder::default()
+        .number((VOTING_END_BLOCK_NUMBER + 1).pack())
+        .build();
+    context.insert_header(vote_header.clone());
+    context.link_cell_with_block(vote_out_point.clone(), vote_header.hash(), 0);
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .header_dep(vote_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTING_ENDED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_without_vote_cell_header_dep() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        voting_end_block_number: VOTING_END_BLOCK_NUMBER,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTING_ENDED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_vote_with_snapshot_balance() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+        snapshot_block_number: 100,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 1_000)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let first_token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let first_token_header = HeaderBuilder::default().number(90u64.pack()).build();
+    context.insert_header(first_token_header.clone());
+    context.link_cell_with_block(first_token_out_point.clone(), first_token_header.hash(), 0);
+    let second_token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(20u128.to_le_bytes().to_vec()),
+    );
+    let second_token_header = HeaderBuilder::default().number(100u64.pack()).build();
+    context.insert_header(second_token_header.clone());
+    context.link_cell_with_block(
+        second_token_out_point.clone(),
+        second_token_header.hash(),
+        0,
+    );
+    let tip_header = HeaderBuilder::default().number(120u64.pack()).build();
+    context.insert_header(tip_header.clone());
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(first_token_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(second_token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 35).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .header_dep(first_token_header.hash())
+        .header_dep(second_token_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_vote_with_balance_acquired_after_snapshot() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+        snapshot_block_number: 100,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 1_000)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let token_header = HeaderBuilder::default().number(101u64.pack()).build();
+    context.insert_header(token_header.clone());
+    context.link_cell_with_block(token_out_point.clone(), token_header.hash(), 0);
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(10u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .header_dep(token_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_CELL_NOT_LIVE_AT_SNAPSHOT).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_with_snapshot_balance_without_header_dep() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+        snapshot_block_number: 100,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 1_000)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tip_header = HeaderBuilder::default().number(120u64.pack()).build();
+    context.insert_header(tip_header.clone());
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(10u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_CELL_NOT_LIVE_AT_SNAPSHOT).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_with_snapshot_balance_before_snapshot() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+        snapshot_block_number: 100,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 1_000)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let token_header = HeaderBuilder::default().number(90u64.pack()).build();
+    context.insert_header(token_header.clone());
+    context.link_cell_with_block(token_out_point.clone(), token_header.hash(), 0);
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(10u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .header_dep(token_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_SNAPSHOT_NOT_REACHED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_with_snapshot_balance_without_returning_tokens() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+        snapshot_block_number: 100,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 1_000)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let token_header = HeaderBuilder::default().number(90u64.pack()).build();
+    context.insert_header(token_header.clone());
+    context.link_cell_with_block(token_out_point.clone(), token_header.hash(), 0);
+    let tip_header = HeaderBuilder::default().number(120u64.pack()).build();
+    context.insert_header(tip_header.clone());
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .header_dep(token_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_SNAPSHOT_TOKENS_NOT_RETURNED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_move_votes_to_other_vote_shard() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        vote_shard_count: 2,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    // The votes are recorded in the second shard, while the input is the first one.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_shard_vote_data(VOTE_OPTION_NO, 1, 11).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_SHARD).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_when_voting_cancelled() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_CANCELLED,
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 15).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTING_FINISHED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_vote_with_escrow() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        escrow_lock_hash: escrow_lock_script.calc_script_hash().unpack(),
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell.clone())
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell.clone())
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .output(
+            token_cell
+                .clone()
+                .as_builder()
+                .lock(escrow_lock_script)
+                .build(),
+        )
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .output(vote_cell)
+        .output_data(
+            build_receipt_data(
+                VOTE_OPTION_NO,
+                6,
+                voter_acp_lock_script.calc_script_hash().unpack(),
+            )
+            .pack(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_vote_with_escrow_without_receipt() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        escrow_lock_hash: escrow_lock_script.calc_script_hash().unpack(),
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell)
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell.clone())
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .output(
+            token_cell
+                .clone()
+                .as_builder()
+                .lock(escrow_lock_script)
+                .build(),
+        )
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_RECEIPT_MISMATCH).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_with_escrow_with_receipt_exceeding_votes() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        escrow_lock_hash: escrow_lock_script.calc_script_hash().unpack(),
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell.clone())
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell.clone())
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .output(
+            token_cell
+                .clone()
+                .as_builder()
+                .lock(escrow_lock_script)
+                .build(),
+        )
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .output(vote_cell)
+        .output_data(
+            build_receipt_data(
+                VOTE_OPTION_NO,
+                7,
+                voter_acp_lock_script.calc_script_hash().unpack(),
+            )
+            .pack(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_RECEIPT_MISMATCH).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_vote_with_escrow_with_several_receipts() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        escrow_lock_hash: escrow_lock_script.calc_script_hash().unpack(),
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell.clone())
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell.clone())
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .output(
+            token_cell
+                .clone()
+                .as_builder()
+                .lock(escrow_lock_script)
+                .build(),
+        )
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .output(vote_cell.clone())
+        .output_data(
+            build_receipt_data(
+                VOTE_OPTION_NO,
+                2,
+                voter_acp_lock_script.calc_script_hash().unpack(),
+            )
+            .pack(),
+        )
+        .output(vote_cell)
+        .output_data(
+            build_receipt_data(
+                VOTE_OPTION_NO,
+                4,
+                voter_acp_lock_script.calc_script_hash().unpack(),
+            )
+            .pack(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_vote_with_escrow_issuing_receipt_to_other_lock() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        escrow_lock_hash: escrow_lock_script.calc_script_hash().unpack(),
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell.clone())
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell.clone())
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .output(
+            token_cell
+                .clone()
+                .as_builder()
+                .lock(escrow_lock_script)
+                .build(),
+        )
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .output(vote_cell)
+        .output_data(build_receipt_data(VOTE_OPTION_NO, 6, [5u8; 32]).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_RECEIPT_MISMATCH).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_with_escrow_by_burning_tokens() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData {
+        escrow_lock_hash: escrow_lock_script.calc_script_hash().unpack(),
+        ..CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30)
+    };
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    // The voted tokens are burned instead of escrowed.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell.clone())
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .output(vote_cell)
+        .output_data(
+            build_receipt_data(
+                VOTE_OPTION_NO,
+                6,
+                voter_acp_lock_script.calc_script_hash().unpack(),
+            )
+            .pack(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_TALLY_MISMATCH).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_issue_receipt_without_escrow() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_acp = context.deploy_cell(Loader::default().load_binary("anyone-can-pay"));
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, lock_script_hash_owner.to_vec().into())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let voter_acp_lock_script =
+        build_acp_lock_script(&mut context, &out_point_acp, &voter_lock_script);
+
+    // The Core Cell is live, and its Vote Cell for NO has 5 votes so far.
+    let core_data = CoreCellData::new(sudt_type_script.calc_script_hash().unpack(), 30);
+    let core_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(dao_core_type_script.clone()).pack())
+            .build(),
+        core_data.to_bytes(),
+    );
+    let vote_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let vote_out_point = context.create_cell(vote_cell.clone(), build_vote_data(VOTE_OPTION_NO, 5));
+
+    // The voter's own lock is spent too, which unlocks the voter's ACP cells.
+    let voter_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(voter_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let token_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(voter_acp_lock_script.clone())
+        .type_(Some(sudt_type_script).pack())
+        .build();
+    let token_out_point = context.create_cell(
+        token_cell.clone(),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(vote_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(voter_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(vote_cell.clone())
+        .output_data(build_vote_data(VOTE_OPTION_NO, 11).pack())
+        .output(token_cell)
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .output(vote_cell)
+        .output_data(
+            build_receipt_data(
+                VOTE_OPTION_NO,
+                6,
+                voter_acp_lock_script.calc_script_hash().unpack(),
+            )
+            .pack(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_RECEIPT_MISMATCH).input_type_script(0)
+    );
+}
+
+// Data of a Core Cell with an organizer. Voting ends at block 200 and can be extended by 100 blocks.
+fn build_organized_core_data(organizer_lock_hash: [u8; 32], organizer_mode: u8) -> CoreCellData {
+    CoreCellData {
+        organizer_lock_hash,
+        organizer_mode,
+        voting_end_block_number: VOTING_END_BLOCK_NUMBER,
+        max_extension: MAX_EXTENSION,
+        ..CoreCellData::new([0u8; 32], 30)
+    }
+}
+
+// Builds an M-of-N multisig config of the organizer keys.
+fn build_multisig_config(threshold: u8, keys: &[Privkey]) -> Bytes {
+    let mut config = vec![threshold, keys.len() as u8];
+
+    for key in keys {
+        let pubkey = key.pubkey().expect("pubkey");
+        config.extend_from_slice(&blake2b_256(pubkey.serialize())[..20]);
+    }
+
+    Bytes::from(config)
+}
+
+fn build_organizer_keys() -> Vec<Privkey> {
+    (1..=3u8).map(|i| Privkey::from_slice(&[i; 32])).collect()
+}
+
+// Creates a cell committed in the block with the specified number, and returns it along with the block hash.
+fn create_cell_in_block(
+    context: &mut Context,
+    cell: CellOutput,
+    data: Bytes,
+    block_number: u64,
+) -> (OutPoint, Byte32) {
+    let out_point = context.create_cell(cell, data);
+    let header = HeaderBuilder::default().number(block_number.pack()).build();
+    context.insert_header(header.clone());
+    context.link_cell_with_block(out_point.clone(), header.hash(), 0);
+
+    (out_point, header.hash())
+}
+
+// Authorizes a completed transaction as a multisig organizer, by signing its hash with the specified keys.
+// The transaction hash doesn't cover the witnesses, so the signatures are added afterwards.
+fn sign_as_organizer(
+    tx: TransactionView,
+    multisig_config: &Bytes,
+    keys: &[Privkey],
+) -> TransactionView {
+    let message = tx.hash().unpack();
+    let mut witness = multisig_config.to_vec();
+    for key in keys {
+        let signature = key.sign_recoverable(&message).expect("sign");
+        witness.extend_from_slice(&signature.serialize());
+    }
+
+    let witness_args = WitnessArgs::new_builder()
+        .input_type(
+            BytesOpt::new_builder()
+                .set(Some(Bytes::from(witness).pack()))
+                .build(),
+        )
+        .build();
+
+    tx.as_advanced_builder()
+        .set_witnesses(vec![witness_args.as_bytes().pack()])
+        .build()
+}
+
+#[test]
+fn test_can_finish_vote_with_yes() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 10),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 16),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_can_finish_vote_with_no() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 20),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 5),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_finish_vote_without_majority() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_NO_MAJORITY).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_can_finish_vote_without_majority_with_casting_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_organizer_cannot_finish_vote_with_invalid_casting_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: 2,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_cannot_override_majority() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 10),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 16),
+        200,
+    );
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_finish_vote_with_wrong_result() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 10),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 16),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_finish_vote_without_all_vote_cells() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 16),
+        200,
+    );
+    // The Vote Cell for NO is left out.
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_CELL_COUNT).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_finish_vote_with_earlier_settlement_block() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 10),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 16),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 150,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_finish_vote_without_vote_cell_headers() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 10),
+        150,
+    );
+    let (yes_out_point, _) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 16),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_SETTLEMENT_HEADER_MISSING).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_multisig_can_finish_vote_with_casting_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    // The organizer is a 2-of-3 multisig of the organizer keys.
+    let keys = build_organizer_keys();
+    let multisig_config = build_multisig_config(2, &keys);
+    let core_data =
+        build_organized_core_data(blake2b_256(&multisig_config), ORGANIZER_MODE_MULTISIG);
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+    let tx = sign_as_organizer(tx, &multisig_config, &[keys[0].clone(), keys[2].clone()]);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+// Creates a live cell committed in the block with the specified number, for the builders of the SDK.
+fn create_live_cell_in_block(
+    context: &mut Context,
+    cell: CellOutput,
+    data: Bytes,
+    block_number: u64,
+) -> (LiveCell, HeaderView) {
+    let out_point = context.create_cell(cell.clone(), data.clone());
+    let header = HeaderBuilder::default().number(block_number.pack()).build();
+    context.insert_header(header.clone());
+    context.link_cell_with_block(out_point.clone(), header.hash(), 0);
+
+    (LiveCell::new(out_point, cell, data), header)
+}
+
+#[test]
+fn test_can_finish_vote_with_casting_vote_with_sdk() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core.script(Bytes::from(vec![42u8; 32]))).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let (core_cell, core_header) = create_live_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_cell, no_header) = create_live_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_cell, yes_header) = create_live_cell_in_block(
+        &mut context,
+        dao_core_cell,
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+    let organizer_output = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(organizer_lock_script)
+        .build();
+    let organizer_out_point = context.create_cell(organizer_output.clone(), Bytes::new());
+    let organizer_cell = LiveCell::new(organizer_out_point, organizer_output.clone(), Bytes::new());
+
+    let finish_vote = FinishVote::new(&dao_core, core_cell, &core_header)
+        .vote_cell(no_cell, &no_header)
+        .vote_cell(yes_cell, &yes_header)
+        .casting_vote(VOTE_OPTION_NO);
+
+    // Without the organizer cell the casting vote isn't authorized.
+    let tx = context.complete_tx(finish_vote.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_NO_MAJORITY).input_type_script(0)
+    );
+
+    // The organizer cell is spent after the Vote Cells and recreated unchanged.
+    let tx = finish_vote
+        .organizer_input(organizer_cell)
+        .build()
+        .expect("build");
+    assert_eq!(tx.inputs().len(), 4);
+    assert_eq!(tx.outputs().get(1), Some(organizer_output));
+
+    let tx = context.complete_tx(tx);
+    context.verify_tx(&tx, MAX_CYCLES).expect("finish vote");
+}
+
+#[test]
+fn test_organizer_multisig_can_finish_vote_with_casting_vote_with_sdk() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core.script(Bytes::from(vec![42u8; 32]))).pack())
+        .build();
+
+    // The organizer is a 2-of-3 multisig of the organizer keys.
+    let keys = build_organizer_keys();
+    let multisig_config = build_multisig_config(2, &keys);
+    let core_data =
+        build_organized_core_data(blake2b_256(&multisig_config), ORGANIZER_MODE_MULTISIG);
+    let (core_cell, core_header) = create_live_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_cell, no_header) = create_live_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_cell, yes_header) = create_live_cell_in_block(
+        &mut context,
+        dao_core_cell,
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    let tx = FinishVote::new(&dao_core, core_cell, &core_header)
+        .vote_cell(no_cell, &no_header)
+        .vote_cell(yes_cell, &yes_header)
+        .casting_vote(VOTE_OPTION_NO)
+        .organizer_multisig(multisig_config)
+        .build()
+        .expect("build");
+
+    // Signing needs the config in the witness of the Core Cell.
+    let unconfigured_tx = tx.as_advanced_builder().set_witnesses(vec![]).build();
+    assert_eq!(
+        ckb_voting_sdk::sign_as_organizer(unconfigured_tx, &keys[0]).err(),
+        Some(Error::MissingOrganizerWitness)
+    );
+
+    // The organizer signs the completed transaction, one key at a time.
+    let tx = context.complete_tx(tx);
+    let tx = ckb_voting_sdk::sign_as_organizer(tx, &keys[0]).expect("sign");
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ORGANIZER_THRESHOLD_NOT_MET).input_type_script(0)
+    );
+
+    let tx = ckb_voting_sdk::sign_as_organizer(tx, &keys[2]).expect("sign");
+    context.verify_tx(&tx, MAX_CYCLES).expect("finish vote");
+}
+
+#[test]
+fn test_organizer_multisig_cannot_finish_vote_below_threshold() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    // The organizer is a 2-of-3 multisig of the organizer keys.
+    let keys = build_organizer_keys();
+    let multisig_config = build_multisig_config(2, &keys);
+    let core_data =
+        build_organized_core_data(blake2b_256(&multisig_config), ORGANIZER_MODE_MULTISIG);
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+    let tx = sign_as_organizer(tx, &multisig_config, &[keys[1].clone()]);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ORGANIZER_THRESHOLD_NOT_MET).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_multisig_cannot_finish_vote_with_duplicate_signers() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    // The organizer is a 2-of-3 multisig of the organizer keys.
+    let keys = build_organizer_keys();
+    let multisig_config = build_multisig_config(2, &keys);
+    let core_data =
+        build_organized_core_data(blake2b_256(&multisig_config), ORGANIZER_MODE_MULTISIG);
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+    let tx = sign_as_organizer(tx, &multisig_config, &[keys[1].clone(), keys[1].clone()]);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_DUPLICATE_ORGANIZER_SIGNER).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_multisig_cannot_finish_vote_with_unlisted_signer() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    // The organizer is a 2-of-2 multisig of the first two organizer keys.
+    let keys = build_organizer_keys();
+    let multisig_config = build_multisig_config(2, &keys[..2]);
+    let core_data =
+        build_organized_core_data(blake2b_256(&multisig_config), ORGANIZER_MODE_MULTISIG);
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+    let tx = sign_as_organizer(tx, &multisig_config, &[keys[0].clone(), keys[2].clone()]);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_ORGANIZER_WITNESS).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_multisig_lock_does_not_authorize() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    // The organizer is a 2-of-3 multisig of the organizer keys.
+    let keys = build_organizer_keys();
+    let multisig_config = build_multisig_config(2, &keys);
+    let core_data =
+        build_organized_core_data(blake2b_256(&multisig_config), ORGANIZER_MODE_MULTISIG);
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 15),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 15),
+        200,
+    );
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_NO,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_NO_MAJORITY).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_can_cancel_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_CANCELLED,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_organizer_multisig_can_cancel_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    // The organizer is a 2-of-3 multisig of the organizer keys.
+    let keys = build_organizer_keys();
+    let multisig_config = build_multisig_config(2, &keys);
+    let core_data =
+        build_organized_core_data(blake2b_256(&multisig_config), ORGANIZER_MODE_MULTISIG);
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_CANCELLED,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+    let tx = sign_as_organizer(tx, &multisig_config, &[keys[0].clone(), keys[1].clone()]);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_cancel_vote_without_organizer() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_CANCELLED,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_UNAUTHORIZED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_cancel_vote_with_changed_data() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_CANCELLED,
+                total_distributed_tokens: 40,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_finish_vote_before_voting_end() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        voting_end_block_number: 250,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 10),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 16),
+        200,
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTING_NOT_ENDED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_finish_vote_after_voting_end() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        voting_end_block_number: 250,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+
+    // The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_NO, 10),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        build_vote_data(VOTE_OPTION_YES, 16),
+        200,
+    );
+
+    // The chain has reached the voting end.
+    let tip_header = HeaderBuilder::default().number(250u64.pack()).build();
+    context.insert_header(tip_header.clone());
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_organizer_can_extend_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                voting_end_block_number: 250,
+                total_extension: 50,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_organizer_can_extend_vote_up_to_max_extension() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                voting_end_block_number: VOTING_END_BLOCK_NUMBER + MAX_EXTENSION,
+                total_extension: MAX_EXTENSION,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_extend_vote_without_organizer() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                voting_end_block_number: 250,
+                total_extension: 50,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_UNAUTHORIZED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_move_voting_end_back() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                voting_end_block_number: 150,
+                total_extension: 0,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_EXTENSION).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_extend_vote_beyond_max_extension() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                voting_end_block_number: VOTING_END_BLOCK_NUMBER + MAX_EXTENSION + 1,
+                total_extension: MAX_EXTENSION + 1,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_EXTENSION_LIMIT_EXCEEDED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_extend_vote_without_recording_extension() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                voting_end_block_number: 250,
+                total_extension: 0,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_extend_vote_with_changed_data() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = build_organized_core_data(
+        organizer_lock_script.calc_script_hash().unpack(),
+        ORGANIZER_MODE_LOCK,
+    );
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                voting_end_block_number: 250,
+                total_extension: 50,
+                execution_delay: 10,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_can_mark_vote_executed() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+
+    // The vote passed in block 200, and the chain is past its execution delay.
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        vote_result_option_type: VOTE_OPTION_YES,
+        execution_delay: 10,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        200,
+    );
+    let tip_header = HeaderBuilder::default().number(210u64.pack()).build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient. The payout hash covers the output, followed
+    // by the length of its data.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let mut payout_preimage = payout.as_slice().to_vec();
+    payout_preimage.extend_from_slice(&0u64.to_le_bytes());
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&blake2b_256(&payout_preimage));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_EXECUTED,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .header_dep(core_block_hash)
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_mark_open_vote_executed() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_OPEN,
+        vote_result_option_type: VOTE_OPTION_YES,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_EXECUTED,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_NOT_FINISHED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_mark_vote_executed_twice() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_EXECUTED,
+        vote_result_option_type: VOTE_OPTION_YES,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_EXECUTED,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_NOT_FINISHED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_mark_vote_executed_with_changed_data() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        vote_result_option_type: VOTE_OPTION_YES,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_EXECUTED,
+                vote_result_option_type: VOTE_OPTION_NO,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_OUTPUT_CELL_DATA).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_mark_vote_executed_without_releasing_treasury() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        vote_result_option_type: VOTE_OPTION_YES,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell.clone(), core_data.to_bytes());
+
+    // No Treasury Cell is spent.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_EXECUTED,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_TREASURY_NOT_RELEASED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_can_burn_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
         .build();
 
-    blake2b.update(&seed_cell_outpoint.tx_hash().raw_data());
-    blake2b.update(&seed_cell_outpoint.index().raw_data());
-    blake2b.update(&(output_index as u32).to_le_bytes());
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell, core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
 
-    let mut hash: [u8; BLAKE2B256_HASH_LEN] = [0; BLAKE2B256_HASH_LEN];
-    blake2b.finalize(&mut hash);
+    // The Core Cell is burned, without any outputs.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
 
-    hash
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
 }
 
 #[test]
-fn test_can_create_vote() {
-    // deploy contract
+fn test_cannot_burn_vote_without_organizer() {
     let mut context = Context::default();
-    context.set_capture_debug(true);
-    let contract_bin: Bytes = Loader::default().load_binary("dao-core");
-    let out_point = context.deploy_cell(contract_bin);
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
 
-    // prepare scripts
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell, core_data.to_bytes());
+
+    // The Core Cell is burned, without any outputs.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_UNAUTHORIZED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_can_burn_finished_vote_with_escrow() {
+    // Escrowed tokens of a finished vote can't be refunded anymore.
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
     let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
-    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
 
     let lock_script = context
         .build_script(&out_point_always_success, Default::default())
         .expect("script");
-    let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
-    let script_args: Bytes = lock_script_hash_owner.to_vec().into();
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        escrow_lock_hash: [7u8; 32],
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell, core_data.to_bytes());
 
-    let input_out_point = context.create_cell(
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
         CellOutput::new_builder()
             .capacity(1000u64.pack())
-            .lock(lock_script.clone())
+            .lock(organizer_lock_script)
             .build(),
         Bytes::new(),
     );
 
+    // The Core Cell is burned, without any outputs.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_organizer_cannot_burn_cancelled_vote_with_escrow() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
     let dao_core_type_script = context
-        .build_script(
-            &out_point,
-            Bytes::from(calculate_instance_id(&input_out_point, 0).to_vec()),
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_CANCELLED,
+        escrow_lock_hash: [7u8; 32],
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell, core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The Core Cell is burned, without any outputs.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
         )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ESCROW_OUTSTANDING).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_cannot_burn_open_vote_with_escrow() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
         .expect("script");
-    let dao_core_type_script_dep = CellDep::new_builder().out_point(out_point).build();
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
 
-    let sudt_type_script = context
-        .build_script(&out_point_sudt, script_args)
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_OPEN,
+        escrow_lock_hash: [7u8; 32],
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell, core_data.to_bytes());
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The Core Cell is burned, without any outputs.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ESCROW_OUTSTANDING).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_cannot_burn_passed_vote_without_releasing_treasury() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
         .expect("script");
-    let sudt_dep = CellDep::new_builder()
-        .out_point(out_point_sudt.clone())
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
         .build();
 
-    // prepare cells
+    // The vote passed, but isn't executed yet.
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        vote_result_option_type: VOTE_OPTION_YES,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let core_out_point = context.create_cell(dao_core_cell, core_data.to_bytes());
 
-    let voters = vec![
-        (lock_script.clone(), 10u128),
-        (lock_script.clone(), 10u128),
-        (lock_script.clone(), 10u128),
-    ];
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
 
-    let input = CellInput::new_builder()
-        .previous_output(input_out_point)
+    // The Core Cell is burned, without any outputs.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
         .build();
-    let mut outputs = vec![CellOutput::new_builder()
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_TREASURY_NOT_RELEASED).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_organizer_can_burn_passed_vote_releasing_treasury() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
         .capacity(500u64.pack())
+        .lock(lock_script)
         .type_(Some(dao_core_type_script.clone()).pack())
-        .build()];
+        .build();
+
+    // The vote passed in block 200, and the chain is past its execution delay.
+    let core_data = CoreCellData {
+        is_voting_finished: VOTING_STATUS_FINISHED,
+        vote_result_option_type: VOTE_OPTION_YES,
+        execution_delay: 10,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let (core_out_point, core_block_hash) =
+        create_cell_in_block(&mut context, dao_core_cell, core_data.to_bytes(), 200);
+    let tip_header = HeaderBuilder::default().number(210u64.pack()).build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient. The payout hash covers the output, followed
+    // by the length of its data.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let mut payout_preimage = payout.as_slice().to_vec();
+    payout_preimage.extend_from_slice(&0u64.to_le_bytes());
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&blake2b_256(&payout_preimage));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The organizer's own lock is spent too, which authorizes the transaction.
+    let organizer_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(organizer_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The Core Cell is burned, and the payout is the only output.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(organizer_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .header_dep(core_block_hash)
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
 
-    let mut core_output_data: Vec<u8> = [].to_vec();
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
 
-    let mut token_code_hash = sudt_type_script.calc_script_hash().as_bytes().to_vec();
+// Vote Cells of two shards per option, with the YES majority only reached by adding up both shards.
+fn build_sharded_vote_cells_data() -> Vec<Bytes> {
+    vec![
+        build_shard_vote_data(VOTE_OPTION_NO, 0, 5),
+        build_shard_vote_data(VOTE_OPTION_YES, 0, 10),
+        build_shard_vote_data(VOTE_OPTION_NO, 1, 5),
+        build_shard_vote_data(VOTE_OPTION_YES, 1, 6),
+    ]
+}
 
-    core_output_data.append(&mut token_code_hash);
-    let mut vote_title = String::from("Should Christmas last all year?");
+#[test]
+fn test_can_finish_vote_adding_up_vote_shards() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
 
-    while vote_title.len() < 32 {
-        vote_title += " ";
-    }
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        vote_shard_count: 2,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+    let vote_cells_data = build_sharded_vote_cells_data();
 
-    let tokens_to_distribute: u128 = voters.iter().map(|(_, amount)| *amount).sum::<u128>();
+    // The Core Cell was created in block 100, and the Vote Cells in every 50th block after it.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let mut tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .header_dep(core_block_hash);
+    for (i, data) in vote_cells_data.into_iter().enumerate() {
+        let (out_point, block_hash) = create_cell_in_block(
+            &mut context,
+            dao_core_cell.clone(),
+            data,
+            150 + 50 * i as u64,
+        );
+        tx = tx
+            .input(CellInput::new_builder().previous_output(out_point).build())
+            .header_dep(block_hash);
+    }
+    let tx = tx
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 300,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
 
-    core_output_data.append(&mut Bytes::from(vote_title).to_vec());
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
 
-    let mut total_distributed_tokens = tokens_to_distribute.to_le_bytes().to_vec();
-    core_output_data.append(&mut total_distributed_tokens);
+#[test]
+fn test_cannot_finish_vote_without_all_vote_shards() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
 
-    let mut is_voting_finished = [0u8; 1].to_vec();
-    core_output_data.append(&mut is_voting_finished);
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
 
-    let mut vote_result_option_type = [0u8; 1].to_vec();
-    core_output_data.append(&mut vote_result_option_type);
+    let core_data = CoreCellData {
+        vote_shard_count: 2,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
 
-    let mut outputs_data = vec![Bytes::from(core_output_data)];
+    // The second shard of NO is left out.
+    let mut vote_cells_data = build_sharded_vote_cells_data();
+    vote_cells_data.remove(2);
 
-    for (voter_lock_script, voter_sudt_amount) in voters {
-        outputs.push(
-            CellOutput::new_builder()
-                .capacity(500u64.pack())
-                .lock(voter_lock_script.clone())
-                .type_(Some(sudt_type_script.clone()).pack())
+    // The Core Cell was created in block 100, and the Vote Cells in every 50th block after it.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let mut tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
                 .build(),
+        )
+        .header_dep(core_block_hash);
+    for (i, data) in vote_cells_data.into_iter().enumerate() {
+        let (out_point, block_hash) = create_cell_in_block(
+            &mut context,
+            dao_core_cell.clone(),
+            data,
+            150 + 50 * i as u64,
         );
-        outputs_data.push(Bytes::from(voter_sudt_amount.to_le_bytes().to_vec()));
+        tx = tx
+            .input(CellInput::new_builder().previous_output(out_point).build())
+            .header_dep(block_hash);
     }
+    let tx = tx
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 250,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .build();
+    let tx = context.complete_tx(tx);
 
-    println!("OUTPUTS DATA: {:?}", outputs_data);
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_CELL_COUNT).input_type_script(0)
+    );
+}
 
-    // build transaction
-    let tx = TransactionBuilder::default()
-        .input(input)
-        .outputs(outputs)
-        .outputs_data(outputs_data.pack())
-        .cell_dep(dao_core_type_script_dep)
-        .cell_dep(sudt_dep)
+#[test]
+fn test_cannot_finish_vote_with_duplicate_vote_shard() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let organizer_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+
+    let core_data = CoreCellData {
+        vote_shard_count: 2,
+        ..build_organized_core_data(
+            organizer_lock_script.calc_script_hash().unpack(),
+            ORGANIZER_MODE_LOCK,
+        )
+    };
+
+    // The first shard of NO is spent twice, instead of the second one.
+    let mut vote_cells_data = build_sharded_vote_cells_data();
+    vote_cells_data[2] = build_shard_vote_data(VOTE_OPTION_NO, 0, 5);
+
+    // The Core Cell was created in block 100, and the Vote Cells in every 50th block after it.
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let mut tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .header_dep(core_block_hash);
+    for (i, data) in vote_cells_data.into_iter().enumerate() {
+        let (out_point, block_hash) = create_cell_in_block(
+            &mut context,
+            dao_core_cell.clone(),
+            data,
+            150 + 50 * i as u64,
+        );
+        tx = tx
+            .input(CellInput::new_builder().previous_output(out_point).build())
+            .header_dep(block_hash);
+    }
+    let tx = tx
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: 1,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 300,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
         .build();
     let tx = context.complete_tx(tx);
 
-    // run
-    context.verify_tx(&tx, MAX_CYCLES).unwrap();
-    println!("DEBUG MESSAGES: {:?}", context.captured_messages());
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_CELL_COUNT).input_type_script(0)
+    );
 }
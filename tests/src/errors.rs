@@ -0,0 +1,302 @@
+use super::explain::{commit_tx, create_cell};
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{bytes::Bytes, packed::OutPoint, prelude::*};
+use ckb_voting_errors::{AcpError, DaoCoreError, SudtError};
+use ckb_voting_sdk::script_error::{
+    decode_script_error, ContractError, FailureSource, ScriptFailure,
+};
+use ckb_voting_sdk::{CastVote, CreateVote, LiveCell};
+
+// Constants
+const MAX_CYCLES: u64 = 100_000_000;
+
+// The codes the contracts were released with. Codes must never change, so a failing assertion here means a variant
+// was renumbered or removed.
+const DAO_CORE_ERROR_CODES: &[(DaoCoreError, i8)] = &[
+    (DaoCoreError::IndexOutOfBound, 1),
+    (DaoCoreError::ItemMissing, 2),
+    (DaoCoreError::LengthNotEnough, 3),
+    (DaoCoreError::Encoding, 4),
+    (DaoCoreError::InvalidCounterValue, 5),
+    (DaoCoreError::InvalidTransactionStructure, 6),
+    (DaoCoreError::InvalidOutputCellData, 7),
+    (DaoCoreError::CounterValueOverflow, 8),
+    (DaoCoreError::Unauthorized, 9),
+    (DaoCoreError::InvalidDataBytesize, 10),
+    (DaoCoreError::WitnessLoadFail, 11),
+    (DaoCoreError::InvalidInputCellData, 12),
+    (DaoCoreError::InvalidArgsLength, 13),
+    (DaoCoreError::LockArgsOtherThanZero, 14),
+    (DaoCoreError::InvalidInstanceId, 15),
+    (DaoCoreError::TokenDistributionMismatch, 16),
+    (DaoCoreError::InvalidVotingWeightMode, 17),
+    (DaoCoreError::InvalidVoteOption, 18),
+    (DaoCoreError::InvalidVoteCellCount, 19),
+    (DaoCoreError::InvalidVoteTally, 20),
+    (DaoCoreError::InvalidTokenCellData, 21),
+    (DaoCoreError::AmountOverflow, 22),
+    (DaoCoreError::CoreCellMissing, 23),
+    (DaoCoreError::VotingFinished, 24),
+    (DaoCoreError::VoteTallyMismatch, 25),
+    (DaoCoreError::SnapshotNotReached, 26),
+    (DaoCoreError::CellNotLiveAtSnapshot, 27),
+    (DaoCoreError::SnapshotTokensNotReturned, 28),
+    (DaoCoreError::InvalidTokenIdMode, 29),
+    (DaoCoreError::InvalidTokenHashType, 30),
+    (DaoCoreError::NoMajority, 31),
+    (DaoCoreError::SettlementHeaderMissing, 32),
+    (DaoCoreError::InvalidOrganizerMode, 33),
+    (DaoCoreError::InvalidOrganizerWitness, 34),
+    (DaoCoreError::OrganizerThresholdNotMet, 35),
+    (DaoCoreError::DuplicateOrganizerSigner, 36),
+    (DaoCoreError::VoteNotCancelled, 37),
+    (DaoCoreError::ReceiptMismatch, 38),
+    (DaoCoreError::RefundMismatch, 39),
+    (DaoCoreError::InvalidExtension, 40),
+    (DaoCoreError::ExtensionLimitExceeded, 41),
+    (DaoCoreError::VotingNotEnded, 42),
+    (DaoCoreError::InvalidVoteShard, 43),
+    (DaoCoreError::InvalidShardCount, 44),
+    (DaoCoreError::InvalidVoteIntent, 45),
+    (DaoCoreError::VoteIntentMismatch, 46),
+    (DaoCoreError::VoteNotFinished, 47),
+    (DaoCoreError::EscrowOutstanding, 48),
+    (DaoCoreError::InvalidEscrowLock, 49),
+    (DaoCoreError::VotingEnded, 50),
+    (DaoCoreError::MintableTokenFamily, 51),
+    (DaoCoreError::TreasuryNotReleased, 52),
+    (DaoCoreError::NonTransferableEscrow, 53),
+];
+
+const SUDT_ERROR_CODES: &[(SudtError, i8)] = &[
+    (SudtError::IndexOutOfBound, 1),
+    (SudtError::ItemMissing, 2),
+    (SudtError::LengthNotEnough, 3),
+    (SudtError::Encoding, 4),
+    (SudtError::Amount, 5),
+    (SudtError::ArgsLength, 6),
+    (SudtError::NonTransferable, 7),
+];
+
+const ACP_ERROR_CODES: &[(AcpError, i8)] = &[
+    (AcpError::IndexOutOfBound, 1),
+    (AcpError::ItemMissing, 2),
+    (AcpError::LengthNotEnough, 3),
+    (AcpError::Encoding, 4),
+    (AcpError::InvalidArgsLength, 5),
+    (AcpError::InvalidMinimumAmount, 6),
+    (AcpError::InvalidUdtCellData, 7),
+    (AcpError::OutputCellMissing, 8),
+    (AcpError::AmountDecreased, 9),
+    (AcpError::MinimumAmountNotReached, 10),
+    (AcpError::AmountOverflow, 11),
+];
+
+#[test]
+fn test_dao_core_error_codes_are_stable() {
+    for (error, code) in DAO_CORE_ERROR_CODES {
+        assert_eq!(error.code(), *code, "{}", error.name());
+        assert_eq!(DaoCoreError::from_code(*code), Some(*error));
+        assert!(!error.message().is_empty());
+    }
+    let errors: Vec<DaoCoreError> = DAO_CORE_ERROR_CODES
+        .iter()
+        .map(|(error, _)| *error)
+        .collect();
+    assert_eq!(DaoCoreError::ALL, errors.as_slice());
+
+    assert_eq!(DaoCoreError::from_code(0), None);
+    assert_eq!(DaoCoreError::from_code(54), None);
+}
+
+#[test]
+fn test_sudt_error_codes_are_stable() {
+    for (error, code) in SUDT_ERROR_CODES {
+        assert_eq!(error.code(), *code, "{}", error.name());
+        assert_eq!(SudtError::from_code(*code), Some(*error));
+        assert!(!error.message().is_empty());
+    }
+    let errors: Vec<SudtError> = SUDT_ERROR_CODES.iter().map(|(error, _)| *error).collect();
+    assert_eq!(SudtError::ALL, errors.as_slice());
+
+    assert_eq!(SudtError::from_code(0), None);
+    assert_eq!(SudtError::from_code(8), None);
+}
+
+#[test]
+fn test_acp_error_codes_are_stable() {
+    for (error, code) in ACP_ERROR_CODES {
+        assert_eq!(error.code(), *code, "{}", error.name());
+        assert_eq!(AcpError::from_code(*code), Some(*error));
+        assert!(!error.message().is_empty());
+    }
+    let errors: Vec<AcpError> = ACP_ERROR_CODES.iter().map(|(error, _)| *error).collect();
+    assert_eq!(AcpError::ALL, errors.as_slice());
+
+    assert_eq!(AcpError::from_code(0), None);
+    assert_eq!(AcpError::from_code(12), None);
+}
+
+#[test]
+fn test_parse_script_failure() {
+    let message = ScriptError::ValidationFailure(25)
+        .input_type_script(1)
+        .to_string();
+    assert_eq!(
+        ScriptFailure::parse(&message),
+        Some(ScriptFailure {
+            source: FailureSource::InputType(1),
+            code: 25,
+        })
+    );
+
+    // As reported by the send_transaction RPC of a node.
+    let message = format!(
+        "TransactionFailedToVerify: Verification failed Script({})",
+        ScriptError::ValidationFailure(-1).input_lock_script(3)
+    );
+    assert_eq!(
+        ScriptFailure::parse(&message),
+        Some(ScriptFailure {
+            source: FailureSource::InputLock(3),
+            code: -1,
+        })
+    );
+
+    let message = ScriptError::ExceededMaximumCycles(MAX_CYCLES)
+        .output_type_script(0)
+        .to_string();
+    assert_eq!(ScriptFailure::parse(&message), None);
+}
+
+#[test]
+fn test_decode_script_error() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    // The seed cell's lock owns the token, so the vote is kept under another lock.
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The first voter casts 8 votes for YES.
+    let cells = commit_tx(&mut context, create_vote.build().expect("build"));
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8);
+    let tx = context.complete_tx(cast_vote.build().expect("build"));
+    let resolve = |out_point: &OutPoint| {
+        context
+            .get_cell(out_point)
+            .map(|(output, data)| LiveCell::new(out_point.clone(), output, data))
+    };
+
+    // The Vote Cell is the first input.
+    let message = ScriptError::ValidationFailure(25)
+        .input_type_script(0)
+        .to_string();
+    let (failure, error) =
+        decode_script_error(&message, &tx, &dao_core, &sudt, resolve).expect("decode");
+    assert_eq!(failure.source, FailureSource::InputType(0));
+    assert_eq!(
+        error,
+        ContractError::DaoCore(DaoCoreError::VoteTallyMismatch)
+    );
+    assert_eq!(error.to_string(), "dao-core error 25 (VoteTallyMismatch)");
+    assert_eq!(
+        error.message(),
+        "The votes added to the Vote Cells don't match the tokens burned."
+    );
+
+    // The sUDT cell of the voter is the second input.
+    let message = ScriptError::ValidationFailure(5)
+        .input_type_script(1)
+        .to_string();
+    let (_, error) = decode_script_error(&message, &tx, &dao_core, &sudt, resolve).expect("decode");
+    assert_eq!(error, ContractError::Sudt(SudtError::Amount));
+
+    // Neither the lock of the voter nor unknown codes are decoded.
+    let message = ScriptError::ValidationFailure(5)
+        .input_lock_script(0)
+        .to_string();
+    assert_eq!(
+        decode_script_error(&message, &tx, &dao_core, &sudt, resolve),
+        None
+    );
+    let message = ScriptError::ValidationFailure(100)
+        .input_type_script(0)
+        .to_string();
+    assert_eq!(
+        decode_script_error(&message, &tx, &dao_core, &sudt, resolve),
+        None
+    );
+}
+
+#[test]
+fn test_decode_verification_failure() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    // The seed cell's lock owns the token, so the vote is kept under another lock.
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let tx = create_vote.build().expect("build");
+    // The last voter receives 9 tokens instead of 10.
+    let mut outputs_data: Vec<Bytes> = tx
+        .outputs_data()
+        .into_iter()
+        .map(|data| data.unpack())
+        .collect();
+    outputs_data[5] = Bytes::from(9u128.to_le_bytes().to_vec());
+    let tx = context.complete_tx(
+        tx.as_advanced_builder()
+            .set_outputs_data(outputs_data.pack().into_iter().collect())
+            .build(),
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    let (failure, error) =
+        decode_script_error(&err.to_string(), &tx, &dao_core, &sudt, |out_point| {
+            context
+                .get_cell(out_point)
+                .map(|(output, data)| LiveCell::new(out_point.clone(), output, data))
+        })
+        .expect("decode");
+    assert_eq!(failure.source, FailureSource::OutputType(0));
+    assert_eq!(
+        error,
+        ContractError::DaoCore(DaoCoreError::TokenDistributionMismatch)
+    );
+}
@@ -0,0 +1,410 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use ckb_tool::{ckb_error::assert_error_eq, ckb_script::ScriptError};
+use ckb_voting_sdk::explain::{explain_tx, CellRole, DecodedData, Explanation, Mode};
+use ckb_voting_sdk::script_error::{ContractError, DaoCoreError, SudtError};
+use ckb_voting_sdk::{CastVote, CreateVote, DeployedScript, LiveCell, VOTE_OPTION_YES};
+
+// Constants
+const MAX_CYCLES: u64 = 100_000_000;
+
+pub(crate) fn create_cell(context: &mut Context, lock_script: Script) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(lock_script)
+        .build();
+    let out_point = context.create_cell(output.clone(), Bytes::new());
+
+    LiveCell::new(out_point, output, Bytes::new())
+}
+
+// Verifies the transaction and creates its outputs.
+pub(crate) fn commit_tx(context: &mut Context, tx: TransactionView) -> Vec<LiveCell> {
+    let tx = context.complete_tx(tx);
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+
+    tx.outputs_with_data_iter()
+        .enumerate()
+        .map(|(i, (output, data))| {
+            let out_point = OutPoint::new(tx.hash(), i as u32);
+            context.create_cell_with_out_point(out_point.clone(), output.clone(), data.clone());
+
+            LiveCell::new(out_point, output, data)
+        })
+        .collect()
+}
+
+fn explain(
+    context: &Context,
+    dao_core: &DeployedScript,
+    sudt: &DeployedScript,
+    tx: &TransactionView,
+) -> Explanation {
+    explain_tx(tx, dao_core, sudt, |out_point| {
+        context
+            .get_cell(out_point)
+            .map(|(output, data)| LiveCell::new(out_point.clone(), output, data))
+    })
+}
+
+fn roles(explanation: &Explanation) -> Vec<CellRole> {
+    explanation.outputs.iter().map(|cell| cell.role).collect()
+}
+
+#[test]
+fn test_explain_create_vote() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    // The seed cell's lock owns the token, so the vote is kept under another lock.
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .title("Explained")
+        .lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+    let tx = context.complete_tx(create_vote.build().expect("build"));
+
+    let explanation = explain(&context, &dao_core, &sudt, &tx);
+    assert!(explanation.is_complete());
+    assert_eq!(explanation.inputs[0].role, CellRole::Seed);
+    assert_eq!(
+        roles(&explanation),
+        vec![
+            CellRole::Core,
+            CellRole::Vote,
+            CellRole::Vote,
+            CellRole::Token,
+            CellRole::Token,
+            CellRole::Token,
+        ]
+    );
+    match &explanation.outputs[0].data {
+        Some(DecodedData::Core(core_data)) => {
+            assert_eq!(core_data.vote_title, "Explained");
+            assert_eq!(core_data.total_distributed_tokens, 30);
+        }
+        _ => panic!("Core Cell not decoded"),
+    }
+    assert_eq!(explanation.outputs[3].data, Some(DecodedData::Token(10)));
+
+    assert_eq!(explanation.votes.len(), 1);
+    assert_eq!(explanation.votes[0].mode, Some(Mode::Create));
+    assert_eq!(explanation.tokens.len(), 1);
+    assert_eq!(explanation.tokens[0].output_amount, 30);
+    assert_eq!(explanation.predicted_error(), None);
+
+    commit_tx(&mut context, tx);
+}
+
+#[test]
+fn test_explain_predicts_token_distribution_mismatch() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .title("Explained")
+        .lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let tx = create_vote.build().expect("build");
+    let mut outputs_data: Vec<Bytes> = tx
+        .outputs_data()
+        .into_iter()
+        .map(|data| data.unpack())
+        .collect();
+    outputs_data[5] = Bytes::from(9u128.to_le_bytes().to_vec());
+    let tx = context.complete_tx(
+        tx.as_advanced_builder()
+            .set_outputs_data(outputs_data.pack().into_iter().collect())
+            .build(),
+    );
+
+    let explanation = explain(&context, &dao_core, &sudt, &tx);
+    assert_eq!(explanation.votes[0].mode, Some(Mode::Create));
+    let error = explanation.predicted_error().expect("predicted error");
+    assert_eq!(
+        error,
+        ContractError::DaoCore(DaoCoreError::TokenDistributionMismatch)
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(error.code()).output_type_script(0)
+    );
+}
+
+#[test]
+fn test_explain_cast_vote() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .title("Explained")
+        .lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The first voter casts 8 votes for YES.
+    let cells = commit_tx(&mut context, create_vote.build().expect("build"));
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8);
+    let tx = context.complete_tx(cast_vote.build().expect("build"));
+
+    let explanation = explain(&context, &dao_core, &sudt, &tx);
+    assert_eq!(explanation.inputs[0].role, CellRole::Vote);
+    assert_eq!(explanation.inputs[1].role, CellRole::Token);
+    assert!(explanation
+        .cell_deps
+        .iter()
+        .any(|cell_dep| cell_dep.role == CellRole::Core));
+    assert_eq!(roles(&explanation), vec![CellRole::Vote, CellRole::Token]);
+    match &explanation.outputs[0].data {
+        Some(DecodedData::Vote(vote_data)) => {
+            assert_eq!(vote_data.vote_option_type, VOTE_OPTION_YES);
+            assert_eq!(vote_data.total_votes_collected, 8);
+        }
+        _ => panic!("Vote Cell not decoded"),
+    }
+
+    assert_eq!(explanation.votes[0].mode, Some(Mode::Vote));
+    assert_eq!(explanation.predicted_error(), None);
+
+    commit_tx(&mut context, tx);
+}
+
+#[test]
+fn test_explain_predicts_vote_tally_mismatch() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .title("Explained")
+        .lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The first voter casts 8 votes for YES.
+    let cells = commit_tx(&mut context, create_vote.build().expect("build"));
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8);
+    let tx = context.complete_tx(cast_vote.build().expect("build"));
+    // Keep 3 tokens instead of 2, which still leaves the tally at 8.
+    let mut outputs_data: Vec<Bytes> = tx
+        .outputs_data()
+        .into_iter()
+        .map(|data| data.unpack())
+        .collect();
+    outputs_data[1] = Bytes::from(3u128.to_le_bytes().to_vec());
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs_data(outputs_data.pack().into_iter().collect())
+        .build();
+
+    let explanation = explain(&context, &dao_core, &sudt, &tx);
+    assert_eq!(explanation.votes[0].mode, Some(Mode::Vote));
+    let error = explanation.predicted_error().expect("predicted error");
+    assert_eq!(
+        error,
+        ContractError::DaoCore(DaoCoreError::VoteTallyMismatch)
+    );
+    assert_eq!(explanation.tokens[0].error, None);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(error.code()).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_explain_predicts_core_cell_missing() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .title("Explained")
+        .lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The first voter casts 8 votes for YES.
+    let cells = commit_tx(&mut context, create_vote.build().expect("build"));
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8);
+    let tx = context.complete_tx(cast_vote.build().expect("build"));
+    // Only code cells are kept, which have no type script.
+    let cell_deps: Vec<CellDep> = tx
+        .cell_deps_iter()
+        .filter(|cell_dep| match context.get_cell(&cell_dep.out_point()) {
+            Some((output, _)) => output.type_().is_none(),
+            None => true,
+        })
+        .collect();
+    let tx = tx.as_advanced_builder().set_cell_deps(cell_deps).build();
+
+    let explanation = explain(&context, &dao_core, &sudt, &tx);
+    let error = explanation.predicted_error().expect("predicted error");
+    assert_eq!(error, ContractError::DaoCore(DaoCoreError::CoreCellMissing));
+}
+
+#[test]
+fn test_explain_predicts_sudt_amount() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .title("Explained")
+        .lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The first voter casts 8 votes for YES.
+    let cells = commit_tx(&mut context, create_vote.build().expect("build"));
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8);
+    let tx = context.complete_tx(cast_vote.build().expect("build"));
+    // The change cell holds more tokens than the voter had.
+    let mut outputs_data: Vec<Bytes> = tx
+        .outputs_data()
+        .into_iter()
+        .map(|data| data.unpack())
+        .collect();
+    outputs_data[1] = Bytes::from(20u128.to_le_bytes().to_vec());
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs_data(outputs_data.pack().into_iter().collect())
+        .build();
+
+    let explanation = explain(&context, &dao_core, &sudt, &tx);
+    let error = explanation.tokens[0].error.expect("predicted error");
+    assert_eq!(error, ContractError::Sudt(SudtError::Amount));
+}
+
+#[test]
+fn test_explain_with_unresolved_inputs() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let vote_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![0]))
+        .expect("script");
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .title("Explained")
+        .lock(vote_lock_script);
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let tx = create_vote.build().expect("build");
+
+    let explanation = explain_tx(&tx, &dao_core, &sudt, |_| None);
+    assert!(!explanation.is_complete());
+    assert_eq!(explanation.inputs[0].role, CellRole::Unresolved);
+    assert_eq!(explanation.outputs[0].role, CellRole::Core);
+    assert!(explanation.votes.is_empty());
+    assert_eq!(explanation.predicted_error(), None);
+    assert!(explanation.to_string().contains("no errors predicted"));
+}
@@ -0,0 +1,590 @@
+use super::*;
+use aggregator::{Aggregator, VoteCellInfo, VoteIntent};
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_jsonrpc_types as json_types;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{BlockBuilder, BlockView, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_voting_indexer::{Indexer, Store};
+use ckb_voting_sdk::audit::ChainSnapshot;
+use ckb_voting_sdk::{
+    CastVote, CoreCellData, CreateVote, FinishVote, LiveCell, VoteCellData, VOTE_OPTION_NO,
+    VOTE_OPTION_YES, VOTING_STATUS_FINISHED, VOTING_WEIGHT_MODE_SNAPSHOT,
+};
+
+// Constants
+const MAX_CYCLES: u64 = 100_000_000;
+
+fn create_cell(context: &mut Context, lock_script: Script) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(lock_script)
+        .build();
+    let out_point = context.create_cell(output.clone(), Bytes::new());
+
+    LiveCell::new(out_point, output, Bytes::new())
+}
+
+// Adds a block with the transactions on top of the chain.
+fn push_block(blocks: &mut Vec<BlockView>, txs: Vec<TransactionView>) {
+    let parent = blocks.last().expect("genesis block");
+    let block = BlockBuilder::default()
+        .number((parent.number() + 1).pack())
+        .parent_hash(parent.hash())
+        .transactions(txs)
+        .build();
+    blocks.push(block);
+}
+
+// Verifies the transaction and adds it to the chain in a block of its own, whose header can be
+// loaded by later transactions.
+pub(crate) fn commit_tx(
+    context: &mut Context,
+    blocks: &mut Vec<BlockView>,
+    tx: TransactionView,
+) -> Vec<LiveCell> {
+    let tx = context.complete_tx(tx);
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    push_block(blocks, vec![tx.clone()]);
+    let header = blocks.last().expect("block").header();
+    context.insert_header(header.clone());
+
+    tx.outputs_with_data_iter()
+        .enumerate()
+        .map(|(i, (output, data))| {
+            let out_point = OutPoint::new(tx.hash(), i as u32);
+            context.create_cell_with_out_point(out_point.clone(), output.clone(), data.clone());
+            context.link_cell_with_block(out_point.clone(), header.hash(), 0);
+
+            LiveCell::new(out_point, output, data)
+        })
+        .collect()
+}
+
+pub(crate) fn instance_id(core_cell: &LiveCell) -> Byte32 {
+    let type_script = core_cell.output.type_().to_opt().expect("type script");
+    Byte32::from_slice(&type_script.args().raw_data()).expect("instance id")
+}
+
+fn tallies(store: &Store, instance_id: &Byte32) -> Vec<(u8, u128)> {
+    store
+        .vote_cells(instance_id)
+        .expect("vote cells")
+        .iter()
+        .map(|vote_cell| (vote_cell.vote_option_type, vote_cell.total_votes_collected))
+        .collect()
+}
+
+#[test]
+fn test_index_vote_lifecycle() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Indexed");
+    let mut voter_lock_hashes = vec![];
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        voter_lock_hashes.push(voter_lock_script.calc_script_hash());
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The vote is created in block 1, after an empty genesis block.
+    let mut blocks = vec![BlockBuilder::default().build()];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+    let (core_cell, mut vote_cells) = (cells[0].clone(), cells[1..3].to_vec());
+
+    // The first two voters vote yes and the third votes no, with 8 of their 10 tokens each.
+    for (voter, vote_option_type) in [
+        (0, VOTE_OPTION_YES),
+        (1, VOTE_OPTION_YES),
+        (2, VOTE_OPTION_NO),
+    ] {
+        let cast_vote = CastVote::new(
+            &dao_core,
+            &sudt,
+            core_cell.clone(),
+            vote_cells[vote_option_type as usize].clone(),
+        )
+        .token_cell(cells[3 + voter].clone())
+        .votes(8);
+        let tx = cast_vote.build().expect("build");
+        vote_cells[vote_option_type as usize] = commit_tx(&mut context, &mut blocks, tx)[0].clone();
+    }
+
+    let finish_vote = FinishVote::new(&dao_core, core_cell.clone(), &blocks[1].header())
+        .vote_cell(vote_cells[0].clone(), &blocks[4].header())
+        .vote_cell(vote_cells[1].clone(), &blocks[3].header())
+        .casting_vote(VOTE_OPTION_YES);
+    let tx = finish_vote.build().expect("build");
+    commit_tx(&mut context, &mut blocks, tx);
+
+    let mut store = Store::open_in_memory().expect("store");
+    let tip = Indexer::new(&dao_core, &sudt)
+        .sync(&mut store, &ChainSnapshot::new(blocks))
+        .expect("sync");
+    assert_eq!(tip, Some(5));
+
+    let instance_id = instance_id(&core_cell);
+    let vote = store
+        .vote(&instance_id)
+        .expect("vote")
+        .expect("indexed vote");
+    assert_eq!(vote.block_number, 1);
+    assert_eq!(vote.core_data.vote_title, "Indexed");
+    assert_eq!(vote.core_data.total_distributed_tokens, 30);
+    assert_eq!(vote.core_data.is_voting_finished, VOTING_STATUS_FINISHED);
+    assert_eq!(vote.core_data.vote_result_option_type, VOTE_OPTION_YES);
+
+    // Tallies are kept after the Vote Cells were collected.
+    assert_eq!(
+        tallies(&store, &instance_id),
+        vec![(VOTE_OPTION_NO, 8), (VOTE_OPTION_YES, 16)]
+    );
+
+    let ballots = store.ballots(&instance_id).expect("ballots");
+    let ballots: Vec<(Byte32, u8, u128, u64)> = ballots
+        .into_iter()
+        .map(|ballot| {
+            (
+                ballot.voter_lock_hash,
+                ballot.vote_option_type,
+                ballot.votes,
+                ballot.block_number,
+            )
+        })
+        .collect();
+    assert_eq!(
+        ballots,
+        vec![
+            (voter_lock_hashes[0].clone(), VOTE_OPTION_YES, 8, 2),
+            (voter_lock_hashes[1].clone(), VOTE_OPTION_YES, 8, 3),
+            (voter_lock_hashes[2].clone(), VOTE_OPTION_NO, 8, 4),
+        ]
+    );
+}
+
+#[test]
+fn test_index_batched_vote() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Indexed");
+    let mut voter_lock_hashes = vec![];
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        voter_lock_hashes.push(voter_lock_script.calc_script_hash());
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let mut blocks = vec![BlockBuilder::default().build()];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+
+    let vote_cells = cells[1..3]
+        .iter()
+        .map(|vote_cell| {
+            let vote_data = VoteCellData::from_slice(&vote_cell.data).expect("vote cell");
+            VoteCellInfo {
+                out_point: vote_cell.out_point.clone(),
+                output: vote_cell.output.clone(),
+                vote_option_type: vote_data.vote_option_type,
+                shard_index: vote_data.shard_index,
+                total_votes_collected: vote_data.total_votes_collected,
+            }
+        })
+        .collect();
+    let mut aggregator = Aggregator::new(vote_cells);
+    for (voter, vote_option_type, amount) in [(0, VOTE_OPTION_YES, 10), (1, VOTE_OPTION_NO, 6)] {
+        let token_cell = &cells[3 + voter];
+        let intent = VoteIntent::new(
+            vote_option_type,
+            amount,
+            token_cell.out_point.clone(),
+            token_cell.output.lock().calc_script_hash().unpack(),
+        )
+        .sign(&Privkey::from_slice(&[voter as u8 + 1; 32]))
+        .expect("sign");
+        aggregator
+            .add_intent(intent, token_cell.output.clone())
+            .expect("add intent");
+    }
+
+    // Only the indexer reads the batch, so the tokens can stay with the always success lock.
+    push_block(&mut blocks, vec![aggregator.build().expect("build")]);
+
+    let mut store = Store::open_in_memory().expect("store");
+    Indexer::new(&dao_core, &sudt)
+        .sync(&mut store, &ChainSnapshot::new(blocks))
+        .expect("sync");
+
+    let ballots: Vec<(Byte32, u8, u128)> = store
+        .ballots(&instance_id(&cells[0]))
+        .expect("ballots")
+        .into_iter()
+        .map(|ballot| {
+            (
+                ballot.voter_lock_hash,
+                ballot.vote_option_type,
+                ballot.votes,
+            )
+        })
+        .collect();
+    assert_eq!(
+        ballots,
+        vec![
+            (voter_lock_hashes[0].clone(), VOTE_OPTION_YES, 10),
+            (voter_lock_hashes[1].clone(), VOTE_OPTION_NO, 6),
+        ]
+    );
+}
+
+#[test]
+fn test_index_snapshot_vote() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+    let create_vote = CreateVote::new(&dao_core, &sudt, seed_cell)
+        .core_data(CoreCellData {
+            voting_weight_mode: VOTING_WEIGHT_MODE_SNAPSHOT,
+            snapshot_block_number: 1,
+            ..CoreCellData::new([0u8; 32], 10)
+        })
+        .voter(voter_lock_script.clone(), 10);
+
+    let mut blocks = vec![BlockBuilder::default().build()];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+
+    // The voter gets all 10 tokens back, but still votes with all of them.
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .header_dep(blocks[1].hash());
+    let tx = cast_vote.build().expect("build");
+    commit_tx(&mut context, &mut blocks, tx);
+
+    let mut store = Store::open_in_memory().expect("store");
+    Indexer::new(&dao_core, &sudt)
+        .sync(&mut store, &ChainSnapshot::new(blocks))
+        .expect("sync");
+
+    let instance_id = instance_id(&cells[0]);
+    assert_eq!(
+        tallies(&store, &instance_id),
+        vec![(VOTE_OPTION_NO, 0), (VOTE_OPTION_YES, 10)]
+    );
+    let ballots = store
+        .voter_ballots(&voter_lock_script.calc_script_hash())
+        .expect("ballots");
+    assert_eq!(ballots.len(), 1);
+    assert_eq!(ballots[0].instance_id, instance_id);
+    assert_eq!(ballots[0].vote_option_type, VOTE_OPTION_YES);
+    assert_eq!(ballots[0].votes, 10);
+    assert_eq!(ballots[0].block_number, 2);
+}
+
+#[test]
+fn test_index_escrow_receipts() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let vote_escrow = deploy_script(&mut context, "vote-escrow");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+    let create_vote =
+        CreateVote::new(&dao_core, &sudt, seed_cell).voter(voter_lock_script.clone(), 10);
+    let type_hash = create_vote.type_script().calc_script_hash();
+    let escrow_lock_hash: [u8; 32] = vote_escrow
+        .script(type_hash.raw_data())
+        .calc_script_hash()
+        .unpack();
+    let create_vote = create_vote.core_data(CoreCellData {
+        escrow_lock_hash,
+        ..CoreCellData::new([0u8; 32], 0)
+    });
+
+    let mut blocks = vec![BlockBuilder::default().build()];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+
+    // 8 tokens are escrowed with a Receipt Cell for the voter, and the other 2 are returned.
+    let cast_vote = CastVote::new(&dao_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8)
+        .vote_escrow(&vote_escrow);
+    let tx = cast_vote.build().expect("build");
+    let cast_cells = commit_tx(&mut context, &mut blocks, tx);
+
+    let mut store = Store::open_in_memory().expect("store");
+    let indexer = Indexer::new(&dao_core, &sudt);
+    indexer
+        .sync(&mut store, &ChainSnapshot::new(blocks.clone()))
+        .expect("sync");
+
+    let instance_id = instance_id(&cells[0]);
+    let voter_lock_hash = voter_lock_script.calc_script_hash();
+    let ballots = store.voter_ballots(&voter_lock_hash).expect("ballots");
+    assert_eq!(ballots.len(), 1);
+    assert_eq!(ballots[0].votes, 8);
+
+    let receipts = store.voter_receipts(&voter_lock_hash).expect("receipts");
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].out_point, cast_cells[3].out_point);
+    assert_eq!(receipts[0].block_number, 2);
+    assert_eq!(receipts[0].instance_id, instance_id);
+    assert_eq!(receipts[0].vote_option_type, VOTE_OPTION_YES);
+    assert_eq!(receipts[0].amount, 8);
+    assert_eq!(receipts[0].spent_block_number, None);
+
+    // The receipt is dropped with the block it was issued in.
+    blocks.truncate(2);
+    indexer
+        .sync(&mut store, &ChainSnapshot::new(blocks))
+        .expect("sync");
+    assert!(store
+        .voter_receipts(&voter_lock_hash)
+        .expect("receipts")
+        .is_empty());
+}
+
+#[test]
+fn test_index_rollback_on_reorg() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Indexed");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let mut blocks = vec![BlockBuilder::default().build()];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+    let core_cell = cells[0].clone();
+
+    // The first voter votes yes in block 2 and the second voter in block 3.
+    let tx = CastVote::new(&dao_core, &sudt, core_cell.clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8)
+        .build()
+        .expect("build");
+    let vote_yes_cell = commit_tx(&mut context, &mut blocks, tx)[0].clone();
+    let fork_blocks = blocks.clone();
+    let tx = CastVote::new(&dao_core, &sudt, core_cell.clone(), vote_yes_cell)
+        .token_cell(cells[4].clone())
+        .votes(8)
+        .build()
+        .expect("build");
+    commit_tx(&mut context, &mut blocks, tx);
+
+    let indexer = Indexer::new(&dao_core, &sudt);
+    let mut store = Store::open_in_memory().expect("store");
+    assert_eq!(
+        indexer
+            .sync(&mut store, &ChainSnapshot::new(blocks))
+            .expect("sync"),
+        Some(3)
+    );
+
+    // A fork from block 2, where the second voter votes no instead, wins.
+    let mut blocks = fork_blocks;
+    let tx = CastVote::new(&dao_core, &sudt, core_cell.clone(), cells[1].clone())
+        .token_cell(cells[4].clone())
+        .votes(8)
+        .build()
+        .expect("build");
+    commit_tx(&mut context, &mut blocks, tx);
+    push_block(&mut blocks, vec![]);
+    assert_eq!(
+        indexer
+            .sync(&mut store, &ChainSnapshot::new(blocks.clone()))
+            .expect("sync"),
+        Some(4)
+    );
+
+    let instance_id = instance_id(&core_cell);
+    assert_eq!(
+        tallies(&store, &instance_id),
+        vec![(VOTE_OPTION_NO, 8), (VOTE_OPTION_YES, 8)]
+    );
+    let ballots = store.ballots(&instance_id).expect("ballots");
+    assert_eq!(ballots.len(), 2);
+    assert_eq!(
+        ballots[1].voter_lock_hash,
+        cells[4].output.lock().calc_script_hash()
+    );
+    assert_eq!(ballots[1].vote_option_type, VOTE_OPTION_NO);
+    assert_eq!(ballots[1].tx_hash, blocks[3].transactions()[0].hash());
+}
+
+#[test]
+fn test_index_rollback_on_shorter_fork() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Indexed");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let mut blocks = vec![BlockBuilder::default().build()];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+    let core_cell = cells[0].clone();
+
+    let tx = CastVote::new(&dao_core, &sudt, core_cell.clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .votes(8)
+        .build()
+        .expect("build");
+    commit_tx(&mut context, &mut blocks, tx);
+
+    let indexer = Indexer::new(&dao_core, &sudt);
+    let mut store = Store::open_in_memory().expect("store");
+    assert_eq!(
+        indexer
+            .sync(&mut store, &ChainSnapshot::new(blocks.clone()))
+            .expect("sync"),
+        Some(2)
+    );
+
+    // The chain without the vote, e.g. after the node switched to a fork.
+    blocks.truncate(2);
+    assert_eq!(
+        indexer
+            .sync(&mut store, &ChainSnapshot::new(blocks.clone()))
+            .expect("sync"),
+        Some(1)
+    );
+
+    let instance_id = instance_id(&core_cell);
+    assert_eq!(
+        tallies(&store, &instance_id),
+        vec![(VOTE_OPTION_NO, 0), (VOTE_OPTION_YES, 0)]
+    );
+    assert!(store.ballots(&instance_id).expect("ballots").is_empty());
+
+    // Rolling back the creation removes the vote.
+    blocks.truncate(1);
+    assert_eq!(
+        indexer
+            .sync(&mut store, &ChainSnapshot::new(blocks))
+            .expect("sync"),
+        Some(0)
+    );
+    assert_eq!(store.vote(&instance_id).expect("vote"), None);
+}
+
+#[test]
+fn test_index_block_fixtures() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Indexed");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    let mut blocks = vec![BlockBuilder::default().build()];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+    let core_cell = cells[0].clone();
+
+    // The first voter votes yes and the second voter votes no.
+    for (vote_cell, token_cell) in [(&cells[2], &cells[3]), (&cells[1], &cells[4])] {
+        let tx = CastVote::new(&dao_core, &sudt, core_cell.clone(), vote_cell.clone())
+            .token_cell(token_cell.clone())
+            .votes(8)
+            .build()
+            .expect("build");
+        commit_tx(&mut context, &mut blocks, tx);
+    }
+
+    // Blocks recorded from a node, in the format of the get_block_by_number RPC.
+    let blocks: Vec<json_types::BlockView> = blocks.into_iter().map(Into::into).collect();
+    let json = serde_json::to_string(&blocks).expect("json");
+    let snapshot = ChainSnapshot::from_json(&json).expect("snapshot");
+
+    let indexer = Indexer::new(&dao_core, &sudt);
+    let mut store = Store::open_in_memory().expect("store");
+    assert_eq!(indexer.sync(&mut store, &snapshot).expect("sync"), Some(3));
+    let instance_id = instance_id(&core_cell);
+    assert_eq!(
+        tallies(&store, &instance_id),
+        vec![(VOTE_OPTION_NO, 8), (VOTE_OPTION_YES, 8)]
+    );
+
+    // Syncing again doesn't change anything.
+    assert_eq!(indexer.sync(&mut store, &snapshot).expect("sync"), Some(3));
+    assert_eq!(store.ballots(&instance_id).expect("ballots").len(), 2);
+}
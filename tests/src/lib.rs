@@ -1,15 +1,59 @@
+use ckb_testtool::context::Context;
 use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_voting_sdk::DeployedScript;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+#[cfg(test)]
+mod airdrop;
+
+#[cfg(test)]
+mod anyone_can_pay;
+
+#[cfg(test)]
+mod audit;
+
+#[cfg(test)]
+mod capacity;
+
+#[cfg(test)]
+mod cli;
+
 #[cfg(test)]
 mod dao_core;
 
+#[cfg(test)]
+mod errors;
+
+#[cfg(test)]
+mod explain;
+
+#[cfg(test)]
+mod indexer;
+
+#[cfg(test)]
+mod partial;
+
+#[cfg(test)]
+mod report;
+
+#[cfg(test)]
+mod server;
+
 #[cfg(test)]
 mod sudt;
 
+#[cfg(test)]
+mod treasury;
+
+#[cfg(test)]
+mod vote_escrow;
+
+#[cfg(test)]
+mod vote_intent;
+
 const TEST_ENV_VAR: &str = "CAPSULE_TEST_ENV";
 
 pub enum TestEnv {
@@ -62,3 +106,11 @@ impl Loader {
         fs::read(path).expect("binary").into()
     }
 }
+
+// Deploys the specified contract, so transactions can be built with the SDK.
+pub fn deploy_script(context: &mut Context, name: &str) -> DeployedScript {
+    let code = Loader::default().load_binary(name);
+    let out_point = context.deploy_cell(code.clone());
+
+    DeployedScript::from_code(out_point, &code)
+}
@@ -0,0 +1,371 @@
+use super::*;
+use ckb_system_scripts::BUNDLED_CELL;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_hash::blake2b_256;
+use ckb_tool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use ckb_voting_sdk::partial::{MultisigConfig, PartialTx, PubkeyHash};
+use ckb_voting_sdk::{CreateVote, DeployedScript, Error, LiveCell};
+
+// Constants
+const MAX_CYCLES: u64 = 100_000_000;
+
+fn bundled_cell(name: &str) -> Bytes {
+    let path = format!("specs/cells/{}", name);
+    BUNDLED_CELL.get(&path).unwrap().to_vec().into()
+}
+
+// Deploys a script bundled with the CKB system scripts.
+fn deploy_bundled_script(context: &mut Context, name: &str) -> DeployedScript {
+    let code = bundled_cell(name);
+    let out_point = context.deploy_cell(code.clone());
+
+    DeployedScript::from_code(out_point, &code)
+}
+
+// Builds a vote created from the first input, with capacity from the other inputs.
+fn create_vote_tx(
+    context: &mut Context,
+    dao_core: &DeployedScript,
+    sudt: &DeployedScript,
+    inputs: &[LiveCell],
+) -> TransactionView {
+    let out_point_secp256k1_data = context.deploy_cell(bundled_cell("secp256k1_data"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let mut create_vote = CreateVote::new(dao_core, sudt, inputs[0].clone());
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+    let tx = create_vote
+        .build()
+        .expect("build")
+        .as_advanced_builder()
+        .inputs(inputs[1..].iter().map(|cell| {
+            CellInput::new_builder()
+                .previous_output(cell.out_point.clone())
+                .build()
+        }))
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(out_point_secp256k1_data)
+                .build(),
+        )
+        .build();
+
+    context.complete_tx(tx)
+}
+
+fn create_cell(context: &mut Context, lock: &Script) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(100_000_000_000u64.pack())
+        .lock(lock.clone())
+        .build();
+    let out_point = context.create_cell(output.clone(), Bytes::new());
+
+    LiveCell::new(out_point, output, Bytes::new())
+}
+
+fn pubkey_hash(key: &Privkey) -> PubkeyHash {
+    let pubkey = key.pubkey().expect("pubkey");
+    let mut pubkey_hash = [0u8; 20];
+    pubkey_hash.copy_from_slice(&blake2b_256(pubkey.serialize())[..20]);
+
+    pubkey_hash
+}
+
+// Loads the partial transaction, signs it with the key and writes it back.
+fn sign_offline(json: &str, key: &Privkey) -> String {
+    let mut partial = PartialTx::from_json(json).expect("from json");
+    assert_eq!(partial.sign(key).expect("sign"), 1);
+
+    partial.to_json()
+}
+
+#[test]
+fn test_partial_tx_signed_by_every_party() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let sighash = deploy_bundled_script(&mut context, "secp256k1_blake160_sighash_all");
+    let multisig = deploy_bundled_script(&mut context, "secp256k1_blake160_multisig_all");
+
+    // The organizer spends the seed cell, and two cells of a funder and a cell of a 2-of-3 multisig of committee
+    // members add capacity.
+    let organizer_key = Privkey::from_slice(&[1u8; 32]);
+    let funder_key = Privkey::from_slice(&[2u8; 32]);
+    let member_keys: Vec<Privkey> = (3..6u8).map(|i| Privkey::from_slice(&[i; 32])).collect();
+    let multisig_config = MultisigConfig::new(0, 2, member_keys.iter().map(pubkey_hash).collect());
+    let organizer_lock = sighash.script(Bytes::from(pubkey_hash(&organizer_key).to_vec()));
+    let funder_lock = sighash.script(Bytes::from(pubkey_hash(&funder_key).to_vec()));
+    let multisig_lock = multisig.script(Bytes::from(multisig_config.hash().to_vec()));
+    let inputs = vec![
+        create_cell(&mut context, &organizer_lock),
+        create_cell(&mut context, &funder_lock),
+        create_cell(&mut context, &multisig_lock),
+        create_cell(&mut context, &funder_lock),
+    ];
+    let tx = create_vote_tx(&mut context, &dao_core, &sudt, &inputs);
+
+    let partial = PartialTx::new(
+        tx.clone(),
+        inputs.clone(),
+        &sighash,
+        &multisig,
+        std::slice::from_ref(&multisig_config),
+    )
+    .expect("partial tx");
+    assert_eq!(partial.lock_groups().len(), 3);
+    assert_eq!(partial.lock_groups()[1].input_indices, vec![1, 3]);
+    assert!(!partial.is_complete());
+
+    let json = partial.to_json();
+    let copies = vec![
+        sign_offline(&json, &organizer_key),
+        sign_offline(&json, &funder_key),
+        sign_offline(&json, &member_keys[0]),
+        sign_offline(&json, &member_keys[2]),
+    ];
+
+    let mut combined = PartialTx::from_json(&json).expect("from json");
+    for copy in &copies {
+        let copy = PartialTx::from_json(copy).expect("from json");
+        combined.combine(&copy).expect("combine");
+    }
+    assert!(combined.is_complete());
+    assert!(combined.to_string().ends_with("ready to finalize\n"));
+
+    let signed_tx = combined.finalize().expect("finalize");
+    assert_eq!(signed_tx.hash(), tx.hash());
+    context
+        .verify_tx(&signed_tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_partial_tx_missing_signatures() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let sighash = deploy_bundled_script(&mut context, "secp256k1_blake160_sighash_all");
+    let multisig = deploy_bundled_script(&mut context, "secp256k1_blake160_multisig_all");
+
+    // The organizer spends the seed cell, and two cells of a funder and a cell of a 2-of-3 multisig of committee
+    // members add capacity.
+    let organizer_key = Privkey::from_slice(&[1u8; 32]);
+    let funder_key = Privkey::from_slice(&[2u8; 32]);
+    let member_keys: Vec<Privkey> = (3..6u8).map(|i| Privkey::from_slice(&[i; 32])).collect();
+    let multisig_config = MultisigConfig::new(0, 2, member_keys.iter().map(pubkey_hash).collect());
+    let organizer_lock = sighash.script(Bytes::from(pubkey_hash(&organizer_key).to_vec()));
+    let funder_lock = sighash.script(Bytes::from(pubkey_hash(&funder_key).to_vec()));
+    let multisig_lock = multisig.script(Bytes::from(multisig_config.hash().to_vec()));
+    let inputs = vec![
+        create_cell(&mut context, &organizer_lock),
+        create_cell(&mut context, &funder_lock),
+        create_cell(&mut context, &multisig_lock),
+        create_cell(&mut context, &funder_lock),
+    ];
+    let tx = create_vote_tx(&mut context, &dao_core, &sudt, &inputs);
+
+    let mut partial = PartialTx::new(
+        tx.clone(),
+        inputs.clone(),
+        &sighash,
+        &multisig,
+        std::slice::from_ref(&multisig_config),
+    )
+    .expect("partial tx");
+    partial.sign(&organizer_key).expect("sign");
+    partial.sign(&funder_key).expect("sign");
+    partial.sign(&member_keys[1]).expect("sign");
+
+    assert!(!partial.is_complete());
+    assert_eq!(partial.lock_groups()[2].missing_signatures(), 1);
+    assert!(partial
+        .to_string()
+        .contains("2 of 3 keys, first 0 required, signed by"));
+    assert!(matches!(partial.finalize(), Err(Error::MissingSignatures)));
+
+    // Signing twice with the same key doesn't count twice.
+    partial.sign(&member_keys[1]).expect("sign");
+    assert_eq!(partial.lock_groups()[2].missing_signatures(), 1);
+}
+
+#[test]
+fn test_partial_tx_required_first_key() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let sighash = deploy_bundled_script(&mut context, "secp256k1_blake160_sighash_all");
+    let multisig = deploy_bundled_script(&mut context, "secp256k1_blake160_multisig_all");
+
+    // The organizer spends the seed cell, and two cells of a funder and a cell of a 2-of-3 multisig of committee
+    // members add capacity.
+    let organizer_key = Privkey::from_slice(&[1u8; 32]);
+    let funder_key = Privkey::from_slice(&[2u8; 32]);
+    let member_keys: Vec<Privkey> = (3..6u8).map(|i| Privkey::from_slice(&[i; 32])).collect();
+    let multisig_config = MultisigConfig::new(1, 2, member_keys.iter().map(pubkey_hash).collect());
+    let organizer_lock = sighash.script(Bytes::from(pubkey_hash(&organizer_key).to_vec()));
+    let funder_lock = sighash.script(Bytes::from(pubkey_hash(&funder_key).to_vec()));
+    let multisig_lock = multisig.script(Bytes::from(multisig_config.hash().to_vec()));
+    let inputs = vec![
+        create_cell(&mut context, &organizer_lock),
+        create_cell(&mut context, &funder_lock),
+        create_cell(&mut context, &multisig_lock),
+        create_cell(&mut context, &funder_lock),
+    ];
+    let tx = create_vote_tx(&mut context, &dao_core, &sudt, &inputs);
+
+    let mut partial = PartialTx::new(
+        tx.clone(),
+        inputs.clone(),
+        &sighash,
+        &multisig,
+        std::slice::from_ref(&multisig_config),
+    )
+    .expect("partial tx");
+    partial.sign(&organizer_key).expect("sign");
+    partial.sign(&funder_key).expect("sign");
+    partial.sign(&member_keys[1]).expect("sign");
+    partial.sign(&member_keys[2]).expect("sign");
+
+    // The threshold is reached, but the first member hasn't signed.
+    assert_eq!(partial.lock_groups()[2].missing_signatures(), 1);
+    assert!(!partial.is_complete());
+
+    partial.sign(&member_keys[0]).expect("sign");
+    let tx = partial.finalize().expect("finalize");
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_partial_tx_rejects_foreign_signatures() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let sighash = deploy_bundled_script(&mut context, "secp256k1_blake160_sighash_all");
+    let multisig = deploy_bundled_script(&mut context, "secp256k1_blake160_multisig_all");
+
+    // The organizer spends the seed cell, and two cells of a funder and a cell of a 2-of-3 multisig of committee
+    // members add capacity.
+    let organizer_key = Privkey::from_slice(&[1u8; 32]);
+    let funder_key = Privkey::from_slice(&[2u8; 32]);
+    let member_keys: Vec<Privkey> = (3..6u8).map(|i| Privkey::from_slice(&[i; 32])).collect();
+    let multisig_config = MultisigConfig::new(0, 2, member_keys.iter().map(pubkey_hash).collect());
+    let organizer_lock = sighash.script(Bytes::from(pubkey_hash(&organizer_key).to_vec()));
+    let funder_lock = sighash.script(Bytes::from(pubkey_hash(&funder_key).to_vec()));
+    let multisig_lock = multisig.script(Bytes::from(multisig_config.hash().to_vec()));
+    let inputs = vec![
+        create_cell(&mut context, &organizer_lock),
+        create_cell(&mut context, &funder_lock),
+        create_cell(&mut context, &multisig_lock),
+        create_cell(&mut context, &funder_lock),
+    ];
+    let tx = create_vote_tx(&mut context, &dao_core, &sudt, &inputs);
+
+    let mut partial = PartialTx::new(
+        tx.clone(),
+        inputs.clone(),
+        &sighash,
+        &multisig,
+        std::slice::from_ref(&multisig_config),
+    )
+    .expect("partial tx");
+
+    // A key without cells in the transaction has nothing to sign.
+    let stranger_key = Privkey::from_slice(&[9u8; 32]);
+    assert_eq!(partial.sign(&stranger_key).expect("sign"), 0);
+
+    // A signature of the organizer doesn't count for the funder.
+    let message = partial.signing_message(&partial.lock_groups()[1]);
+    let signature = organizer_key.sign_recoverable(&message).expect("sign");
+    assert!(matches!(
+        partial.add_signature(1, Bytes::from(signature.serialize())),
+        Err(Error::InvalidSignature)
+    ));
+
+    // Neither does a signature of another transaction.
+    let mut other = PartialTx::new(
+        tx.as_advanced_builder()
+            .output_data(Bytes::new().pack())
+            .build(),
+        inputs.clone(),
+        &sighash,
+        &multisig,
+        std::slice::from_ref(&multisig_config),
+    )
+    .expect("partial tx");
+    other.sign(&funder_key).expect("sign");
+    let signature = other.lock_groups()[1].signatures[0].1.clone();
+    assert!(matches!(
+        partial.add_signature(1, signature),
+        Err(Error::InvalidSignature)
+    ));
+    assert!(matches!(
+        partial.combine(&other),
+        Err(Error::PartialTxMismatch)
+    ));
+}
+
+#[test]
+fn test_partial_tx_from_invalid_json() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let sighash = deploy_bundled_script(&mut context, "secp256k1_blake160_sighash_all");
+    let multisig = deploy_bundled_script(&mut context, "secp256k1_blake160_multisig_all");
+
+    // The organizer spends the seed cell, and two cells of a funder and a cell of a 2-of-3 multisig of committee
+    // members add capacity.
+    let organizer_key = Privkey::from_slice(&[1u8; 32]);
+    let funder_key = Privkey::from_slice(&[2u8; 32]);
+    let member_keys: Vec<Privkey> = (3..6u8).map(|i| Privkey::from_slice(&[i; 32])).collect();
+    let multisig_config = MultisigConfig::new(0, 2, member_keys.iter().map(pubkey_hash).collect());
+    let organizer_lock = sighash.script(Bytes::from(pubkey_hash(&organizer_key).to_vec()));
+    let funder_lock = sighash.script(Bytes::from(pubkey_hash(&funder_key).to_vec()));
+    let multisig_lock = multisig.script(Bytes::from(multisig_config.hash().to_vec()));
+    let inputs = vec![
+        create_cell(&mut context, &organizer_lock),
+        create_cell(&mut context, &funder_lock),
+        create_cell(&mut context, &multisig_lock),
+        create_cell(&mut context, &funder_lock),
+    ];
+    let tx = create_vote_tx(&mut context, &dao_core, &sudt, &inputs);
+
+    let mut partial = PartialTx::new(
+        tx.clone(),
+        inputs.clone(),
+        &sighash,
+        &multisig,
+        std::slice::from_ref(&multisig_config),
+    )
+    .expect("partial tx");
+    partial.sign(&funder_key).expect("sign");
+    let json = partial.to_json();
+
+    // Moving the signature of the funder to the organizer is detected.
+    let mut value: serde_json::Value = serde_json::from_str(&json).expect("json");
+    value["lock_groups"][0]["signatures"] = value["lock_groups"][1]["signatures"].clone();
+    assert!(matches!(
+        PartialTx::from_json(&value.to_string()),
+        Err(Error::InvalidSignature)
+    ));
+
+    // So is leaving an input out of its lock group.
+    let mut value: serde_json::Value = serde_json::from_str(&json).expect("json");
+    value["lock_groups"][1]["input_indices"] = serde_json::json!(["0x1"]);
+    assert!(matches!(
+        PartialTx::from_json(&value.to_string()),
+        Err(Error::InvalidPartialTx(_))
+    ));
+
+    assert!(matches!(
+        PartialTx::from_json("{}"),
+        Err(Error::InvalidPartialTx(_))
+    ));
+}
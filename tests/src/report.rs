@@ -0,0 +1,529 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_hash::blake2b_256;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{BlockBuilder, BlockView, HeaderView, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_voting_sdk::audit::ChainSnapshot;
+use ckb_voting_sdk::report::{OptionTally, VoteReport};
+use ckb_voting_sdk::{
+    CastVote, CreateVote, DeployedScript, Error, FinishVote, LiveCell, VOTE_OPTION_NO,
+    VOTE_OPTION_YES,
+};
+
+// Constants
+const MAX_CYCLES: u64 = 100_000_000;
+
+// Completes and verifies the transaction.
+fn verify_tx(context: &mut Context, tx: TransactionView) -> TransactionView {
+    let tx = context.complete_tx(tx);
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+
+    tx
+}
+
+// Adds the transactions to the chain in a new block, and their outputs to the context.
+fn commit_block(
+    context: &mut Context,
+    blocks: &mut Vec<BlockView>,
+    txs: Vec<TransactionView>,
+) -> (Vec<Vec<LiveCell>>, HeaderView) {
+    let block = BlockBuilder::default()
+        .number((blocks.len() as u64 + 1).pack())
+        .transactions(txs.clone())
+        .build();
+    context.insert_header(block.header());
+
+    let live_cells = txs
+        .iter()
+        .map(|tx| {
+            tx.outputs_with_data_iter()
+                .enumerate()
+                .map(|(i, (output, data))| {
+                    let out_point = OutPoint::new(tx.hash(), i as u32);
+                    context.create_cell_with_out_point(
+                        out_point.clone(),
+                        output.clone(),
+                        data.clone(),
+                    );
+                    context.link_cell_with_block(out_point.clone(), block.hash(), 0);
+
+                    LiveCell::new(out_point, output, data)
+                })
+                .collect()
+        })
+        .collect();
+    blocks.push(block.clone());
+
+    (live_cells, block.header())
+}
+
+fn create_cell(context: &mut Context, lock_script: &Script) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(lock_script.clone())
+        .build();
+    let out_point = context.create_cell(output.clone(), Bytes::new());
+
+    LiveCell::new(out_point, output, Bytes::new())
+}
+
+fn assert_invalid_report(result: Result<Option<[u8; 20]>, Error>, expected_reason: &str) {
+    match result {
+        Err(Error::InvalidReport(reason)) => assert_eq!(reason, expected_reason),
+        result => panic!("expected an invalid report, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_report_of_finished_vote() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Report");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The vote is created in block 1, two voters vote no and yes in block 2, and the third voter votes yes in
+    // block 3.
+    let mut blocks = vec![];
+    let tx = verify_tx(&mut context, create_vote.build().expect("build"));
+    let (cells, core_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+    let cells = &cells[0];
+    let cast_vote = |vote_cell: &LiveCell, token_cell: &LiveCell| {
+        CastVote::new(&dao_core, &sudt, cells[0].clone(), vote_cell.clone())
+            .token_cell(token_cell.clone())
+            .build()
+            .expect("build")
+    };
+    let vote_no_tx = verify_tx(&mut context, cast_vote(&cells[1], &cells[4]));
+    let vote_yes_tx = verify_tx(&mut context, cast_vote(&cells[2], &cells[3]));
+    let (cast_cells, cast_header) =
+        commit_block(&mut context, &mut blocks, vec![vote_no_tx, vote_yes_tx]);
+    let tx = verify_tx(&mut context, cast_vote(&cast_cells[1][0], &cells[5]));
+    let (last_cells, last_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+
+    // It is finished in block 4, after a transaction which has nothing to do with the vote.
+    let cell = create_cell(&mut context, &lock_script);
+    let other_tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(cell.out_point)
+                .build(),
+        )
+        .output(cell.output)
+        .output_data(Bytes::new().pack())
+        .build();
+    let other_tx = verify_tx(&mut context, other_tx);
+    let finish_vote = FinishVote::new(&dao_core, cells[0].clone(), &core_header)
+        .vote_cell(cast_cells[0][0].clone(), &cast_header)
+        .vote_cell(last_cells[0][0].clone(), &last_header);
+    let settlement_tx = verify_tx(&mut context, finish_vote.build().expect("build"));
+    commit_block(
+        &mut context,
+        &mut blocks,
+        vec![other_tx, settlement_tx.clone()],
+    );
+    let snapshot = ChainSnapshot::new(blocks.clone());
+
+    let report = VoteReport::generate(&snapshot, &settlement_tx.hash(), &dao_core).expect("report");
+
+    assert_eq!(report.vote_title, "Report");
+    assert_eq!(report.total_distributed_tokens, 30);
+    assert_eq!(
+        report.tallies,
+        vec![
+            OptionTally {
+                vote_option_type: VOTE_OPTION_NO,
+                votes: 10,
+            },
+            OptionTally {
+                vote_option_type: VOTE_OPTION_YES,
+                votes: 20,
+            },
+        ]
+    );
+    assert_eq!(report.vote_result_option_type, VOTE_OPTION_YES);
+    assert_eq!(report.settlement_block_number, 3);
+    assert_eq!(report.settlement_tx_hash(), settlement_tx.hash());
+    assert_eq!(report.settlement_block_hash(), blocks[3].hash());
+
+    // The Core Cell was created in block 1, and the Vote Cells in blocks 2 and 3.
+    let input_tx_hashes: Vec<Byte32> = report.input_txs.iter().map(|tx| tx.tx.hash()).collect();
+    assert_eq!(
+        input_tx_hashes,
+        vec![
+            blocks[0].tx_hashes()[0].clone(),
+            blocks[1].tx_hashes()[0].clone(),
+            blocks[2].tx_hashes()[0].clone(),
+        ]
+    );
+
+    // Checked against a snapshot, or only the headers of the chain.
+    assert_eq!(report.verify(&snapshot, &dao_core), Ok(None));
+    let headers: Vec<HeaderView> = blocks.iter().map(|block| block.header()).collect();
+    assert_eq!(report.verify(&headers, &dao_core), Ok(None));
+
+    assert!(matches!(
+        VoteReport::generate(&snapshot, &Byte32::default(), &dao_core),
+        Err(Error::VoteNotFound)
+    ));
+}
+
+#[test]
+fn test_report_signed_json() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Report");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The vote is created in block 1, two voters vote no and yes in block 2, and the third voter votes yes in
+    // block 3.
+    let mut blocks = vec![];
+    let tx = verify_tx(&mut context, create_vote.build().expect("build"));
+    let (cells, core_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+    let cells = &cells[0];
+    let cast_vote = |vote_cell: &LiveCell, token_cell: &LiveCell| {
+        CastVote::new(&dao_core, &sudt, cells[0].clone(), vote_cell.clone())
+            .token_cell(token_cell.clone())
+            .build()
+            .expect("build")
+    };
+    let vote_no_tx = verify_tx(&mut context, cast_vote(&cells[1], &cells[4]));
+    let vote_yes_tx = verify_tx(&mut context, cast_vote(&cells[2], &cells[3]));
+    let (cast_cells, cast_header) =
+        commit_block(&mut context, &mut blocks, vec![vote_no_tx, vote_yes_tx]);
+    let tx = verify_tx(&mut context, cast_vote(&cast_cells[1][0], &cells[5]));
+    let (last_cells, last_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+
+    // It is finished in block 4, after a transaction which has nothing to do with the vote.
+    let cell = create_cell(&mut context, &lock_script);
+    let other_tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(cell.out_point)
+                .build(),
+        )
+        .output(cell.output)
+        .output_data(Bytes::new().pack())
+        .build();
+    let other_tx = verify_tx(&mut context, other_tx);
+    let finish_vote = FinishVote::new(&dao_core, cells[0].clone(), &core_header)
+        .vote_cell(cast_cells[0][0].clone(), &cast_header)
+        .vote_cell(last_cells[0][0].clone(), &last_header);
+    let settlement_tx = verify_tx(&mut context, finish_vote.build().expect("build"));
+    commit_block(
+        &mut context,
+        &mut blocks,
+        vec![other_tx, settlement_tx.clone()],
+    );
+    let snapshot = ChainSnapshot::new(blocks.clone());
+
+    let key = Privkey::from_slice(&[1u8; 32]);
+    let mut report =
+        VoteReport::generate(&snapshot, &settlement_tx.hash(), &dao_core).expect("report");
+    report.sign(&key).expect("sign");
+
+    let report = VoteReport::from_json(&report.to_json()).expect("from json");
+    let pubkey = key.pubkey().expect("pubkey");
+    let mut pubkey_hash = [0u8; 20];
+    pubkey_hash.copy_from_slice(&blake2b_256(pubkey.serialize())[..20]);
+    assert_eq!(report.verify(&snapshot, &dao_core), Ok(Some(pubkey_hash)));
+
+    let markdown = report.to_markdown();
+    assert!(markdown.starts_with("# Report\n"));
+    assert!(markdown.contains("| no | 10 |\n| yes | 20 |\n"));
+    assert!(markdown.contains("**Result: yes**, with 30 of 30 distributed tokens cast."));
+    assert!(markdown.contains(&format!("{:#x}", settlement_tx.hash())));
+    assert!(markdown.contains(&format!("- Signed by: `0x{}`", hex(&pubkey_hash))));
+}
+
+#[test]
+fn test_report_rejects_edited_fields() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Report");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The vote is created in block 1, two voters vote no and yes in block 2, and the third voter votes yes in
+    // block 3.
+    let mut blocks = vec![];
+    let tx = verify_tx(&mut context, create_vote.build().expect("build"));
+    let (cells, core_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+    let cells = &cells[0];
+    let cast_vote = |vote_cell: &LiveCell, token_cell: &LiveCell| {
+        CastVote::new(&dao_core, &sudt, cells[0].clone(), vote_cell.clone())
+            .token_cell(token_cell.clone())
+            .build()
+            .expect("build")
+    };
+    let vote_no_tx = verify_tx(&mut context, cast_vote(&cells[1], &cells[4]));
+    let vote_yes_tx = verify_tx(&mut context, cast_vote(&cells[2], &cells[3]));
+    let (cast_cells, cast_header) =
+        commit_block(&mut context, &mut blocks, vec![vote_no_tx, vote_yes_tx]);
+    let tx = verify_tx(&mut context, cast_vote(&cast_cells[1][0], &cells[5]));
+    let (last_cells, last_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+
+    // It is finished in block 4, after a transaction which has nothing to do with the vote.
+    let cell = create_cell(&mut context, &lock_script);
+    let other_tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(cell.out_point)
+                .build(),
+        )
+        .output(cell.output)
+        .output_data(Bytes::new().pack())
+        .build();
+    let other_tx = verify_tx(&mut context, other_tx);
+    let finish_vote = FinishVote::new(&dao_core, cells[0].clone(), &core_header)
+        .vote_cell(cast_cells[0][0].clone(), &cast_header)
+        .vote_cell(last_cells[0][0].clone(), &last_header);
+    let settlement_tx = verify_tx(&mut context, finish_vote.build().expect("build"));
+    commit_block(
+        &mut context,
+        &mut blocks,
+        vec![other_tx, settlement_tx.clone()],
+    );
+    let snapshot = ChainSnapshot::new(blocks.clone());
+
+    let json = VoteReport::generate(&snapshot, &settlement_tx.hash(), &dao_core)
+        .expect("report")
+        .to_json();
+    let edit = |field: &str, value: serde_json::Value| {
+        let mut report: serde_json::Value = serde_json::from_str(&json).expect("json");
+        report[field] = value;
+        VoteReport::from_json(&report.to_string()).expect("from json")
+    };
+
+    let report = edit(
+        "tallies",
+        serde_json::json!([{ "option": "no", "votes": "0x14" }, { "option": "yes", "votes": "0xa" }]),
+    );
+    assert_invalid_report(
+        report.verify(&snapshot, &dao_core),
+        "tallies don't match the Vote Cells",
+    );
+
+    let report = edit("result", serde_json::json!("no"));
+    assert_invalid_report(
+        report.verify(&snapshot, &dao_core),
+        "result doesn't match the Core Cell",
+    );
+
+    let report = edit("vote_title", serde_json::json!("Another vote"));
+    assert_invalid_report(
+        report.verify(&snapshot, &dao_core),
+        "settings don't match the Core Cell",
+    );
+
+    // The hashes written out for readers have to match the settlement transaction.
+    let mut report: serde_json::Value = serde_json::from_str(&json).expect("json");
+    report["settlement_tx_hash"] = serde_json::json!(format!("{:#x}", Byte32::default()));
+    assert!(matches!(
+        VoteReport::from_json(&report.to_string()),
+        Err(Error::InvalidReport(_))
+    ));
+}
+
+#[test]
+fn test_report_rejects_unproven_transactions() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script);
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Report");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The vote is created in block 1, two voters vote no and yes in block 2, and the third voter votes yes in
+    // block 3.
+    let mut blocks = vec![];
+    let tx = verify_tx(&mut context, create_vote.build().expect("build"));
+    let (cells, core_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+    let cells = &cells[0];
+    let cast_vote = |vote_cell: &LiveCell, token_cell: &LiveCell| {
+        CastVote::new(&dao_core, &sudt, cells[0].clone(), vote_cell.clone())
+            .token_cell(token_cell.clone())
+            .build()
+            .expect("build")
+    };
+    let vote_no_tx = verify_tx(&mut context, cast_vote(&cells[1], &cells[4]));
+    let vote_yes_tx = verify_tx(&mut context, cast_vote(&cells[2], &cells[3]));
+    let (cast_cells, cast_header) =
+        commit_block(&mut context, &mut blocks, vec![vote_no_tx, vote_yes_tx]);
+    let tx = verify_tx(&mut context, cast_vote(&cast_cells[1][0], &cells[5]));
+    let (last_cells, last_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+
+    // It is finished in block 4, after a transaction which has nothing to do with the vote.
+    let cell = create_cell(&mut context, &lock_script);
+    let other_tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(cell.out_point)
+                .build(),
+        )
+        .output(cell.output)
+        .output_data(Bytes::new().pack())
+        .build();
+    let other_tx = verify_tx(&mut context, other_tx);
+    let finish_vote = FinishVote::new(&dao_core, cells[0].clone(), &core_header)
+        .vote_cell(cast_cells[0][0].clone(), &cast_header)
+        .vote_cell(last_cells[0][0].clone(), &last_header);
+    let settlement_tx = verify_tx(&mut context, finish_vote.build().expect("build"));
+    commit_block(
+        &mut context,
+        &mut blocks,
+        vec![other_tx, settlement_tx.clone()],
+    );
+    let snapshot = ChainSnapshot::new(blocks.clone());
+
+    let report = VoteReport::generate(&snapshot, &settlement_tx.hash(), &dao_core).expect("report");
+
+    // A Vote Cell with more votes than it had on chain.
+    let mut edited = report.clone();
+    let input_tx = &mut edited.input_txs[2];
+    let mut vote_data = input_tx
+        .tx
+        .outputs_data()
+        .get(0)
+        .expect("data")
+        .raw_data()
+        .to_vec();
+    vote_data[1] = 100;
+    input_tx.tx = input_tx
+        .tx
+        .as_advanced_builder()
+        .set_outputs_data(vec![Bytes::from(vote_data).pack()])
+        .build();
+    assert_invalid_report(
+        edited.verify(&snapshot, &dao_core),
+        "transaction not included in its block",
+    );
+
+    // Leaving out a Vote Cell.
+    let mut edited = report.clone();
+    edited.input_txs.remove(1);
+    assert_invalid_report(
+        edited.verify(&snapshot, &dao_core),
+        "spent cell missing from the input transactions",
+    );
+
+    // Headers of another chain.
+    let headers: Vec<HeaderView> = blocks[..3].iter().map(|block| block.header()).collect();
+    assert_invalid_report(
+        report.verify(&headers, &dao_core),
+        "block not found in the headers",
+    );
+}
+
+#[test]
+fn test_report_rejects_other_core_script() {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    // A look-alike vote, whose Core Cell runs a script accepting anything. The trailing byte gives it another code
+    // hash than any contract.
+    let other_code = Bytes::from([&ALWAYS_SUCCESS[..], &[0]].concat());
+    let other_core =
+        DeployedScript::from_code(context.deploy_cell(other_code.clone()), &other_code);
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let seed_cell = create_cell(&mut context, &lock_script);
+    let voter_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1]))
+        .expect("script");
+    let create_vote = CreateVote::new(&other_core, &sudt, seed_cell)
+        .title("Report")
+        .voter(voter_lock_script, 10);
+
+    let mut blocks = vec![];
+    let tx = verify_tx(&mut context, create_vote.build().expect("build"));
+    let (cells, core_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+    let cells = &cells[0];
+    let cast_vote = CastVote::new(&other_core, &sudt, cells[0].clone(), cells[2].clone())
+        .token_cell(cells[3].clone())
+        .build()
+        .expect("build");
+    let tx = verify_tx(&mut context, cast_vote);
+    let (cast_cells, cast_header) = commit_block(&mut context, &mut blocks, vec![tx]);
+    let finish_vote = FinishVote::new(&other_core, cells[0].clone(), &core_header)
+        .vote_cell(cast_cells[0][0].clone(), &cast_header);
+    let settlement_tx = verify_tx(&mut context, finish_vote.build().expect("build"));
+    commit_block(&mut context, &mut blocks, vec![settlement_tx.clone()]);
+    let snapshot = ChainSnapshot::new(blocks);
+
+    assert_invalid_report(
+        VoteReport::generate(&snapshot, &settlement_tx.hash(), &dao_core).map(|_| None),
+        "first output doesn't run dao-core",
+    );
+
+    // A report of the look-alike is rejected when checked against the deployed dao-core.
+    let report =
+        VoteReport::generate(&snapshot, &settlement_tx.hash(), &other_core).expect("report");
+    assert_eq!(report.verify(&snapshot, &other_core), Ok(None));
+    assert_invalid_report(
+        report.verify(&snapshot, &dao_core),
+        "first output doesn't run dao-core",
+    );
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,213 @@
+use super::indexer::{commit_tx, instance_id};
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_types::{bytes::Bytes, core::BlockBuilder, packed::*, prelude::*, H256};
+use ckb_voting_indexer::{Indexer, Store};
+use ckb_voting_sdk::audit::ChainSnapshot;
+use ckb_voting_sdk::{CastVote, CreateVote, FinishVote, LiveCell, VOTE_OPTION_NO, VOTE_OPTION_YES};
+use ckb_voting_server::Api;
+use jsonrpc_core::IoHandler;
+use serde_json::{json, Value};
+
+// A finished vote with two YES ballots and one NO ballot, served from an in-memory store. Returns the instance id
+// and the lock hashes of the voters.
+fn finished_vote_handler() -> (IoHandler, Byte32, Vec<Byte32>) {
+    let mut context = Context::default();
+    let dao_core = deploy_script(&mut context, "dao-core");
+    let sudt = deploy_script(&mut context, "sudt");
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let output = CellOutput::new_builder()
+        .capacity(1000u64.pack())
+        .lock(lock_script)
+        .build();
+    let out_point = context.create_cell(output.clone(), Bytes::new());
+    let seed_cell = LiveCell::new(out_point, output, Bytes::new());
+
+    let mut create_vote = CreateVote::new(&dao_core, &sudt, seed_cell).title("Indexed");
+    for voter in 1..=3u8 {
+        let voter_lock_script = context
+            .build_script(&out_point_always_success, Bytes::from(vec![voter]))
+            .expect("script");
+        create_vote = create_vote.voter(voter_lock_script, 10);
+    }
+
+    // The vote is created in block 1, after an empty genesis block.
+    let mut blocks = vec![BlockBuilder::default().build()];
+    let tx = create_vote.build().expect("build");
+    let cells = commit_tx(&mut context, &mut blocks, tx);
+    let (core_cell, mut vote_cells) = (cells[0].clone(), cells[1..3].to_vec());
+
+    for (voter, vote_option_type) in [
+        (0, VOTE_OPTION_YES),
+        (1, VOTE_OPTION_YES),
+        (2, VOTE_OPTION_NO),
+    ] {
+        let option = vote_option_type as usize;
+        let cast_vote = CastVote::new(
+            &dao_core,
+            &sudt,
+            core_cell.clone(),
+            vote_cells[option].clone(),
+        )
+        .token_cell(cells[3 + voter].clone())
+        .votes(8);
+        let tx = cast_vote.build().expect("build");
+        vote_cells[option] = commit_tx(&mut context, &mut blocks, tx)[0].clone();
+    }
+
+    let finish_vote = FinishVote::new(&dao_core, core_cell.clone(), &blocks[1].header())
+        .vote_cell(vote_cells[0].clone(), &blocks[4].header())
+        .vote_cell(vote_cells[1].clone(), &blocks[3].header())
+        .casting_vote(VOTE_OPTION_YES);
+    commit_tx(
+        &mut context,
+        &mut blocks,
+        finish_vote.build().expect("build"),
+    );
+
+    let mut store = Store::open_in_memory().expect("store");
+    Indexer::new(&dao_core, &sudt)
+        .sync(&mut store, &ChainSnapshot::new(blocks))
+        .expect("sync");
+
+    let voter_lock_hashes = cells[3..6]
+        .iter()
+        .map(|token_cell| token_cell.output.lock().calc_script_hash())
+        .collect();
+    (
+        Api::new(store).into_handler(),
+        instance_id(&core_cell),
+        voter_lock_hashes,
+    )
+}
+
+fn call(io: &IoHandler, method: &str, params: Value) -> Value {
+    let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+    let response = io
+        .handle_request_sync(&request.to_string())
+        .expect("response");
+
+    serde_json::from_str(&response).expect("json response")
+}
+
+fn hex(hash: &Byte32) -> String {
+    let hash: H256 = hash.unpack();
+    format!("{:#x}", hash)
+}
+
+#[test]
+fn test_get_vote() {
+    let (io, instance_id, _) = finished_vote_handler();
+    let instance_id = hex(&instance_id);
+
+    let vote = &call(&io, "get_vote", json!([instance_id]))["result"];
+    assert_eq!(vote["instance_id"], json!(instance_id));
+    assert_eq!(vote["block_number"], json!("0x1"));
+    assert_eq!(vote["title"], json!("Indexed"));
+    assert_eq!(vote["status"], json!("finished"));
+    assert_eq!(vote["total_distributed_tokens"], json!("0x1e"));
+    assert_eq!(vote["result"], json!("yes"));
+    assert_eq!(vote["voting_end_block_number"], Value::Null);
+
+    let unknown = hex(&Byte32::zero());
+    assert_eq!(
+        call(&io, "get_vote", json!([unknown]))["result"],
+        Value::Null
+    );
+}
+
+#[test]
+fn test_list_votes_by_status() {
+    let (io, instance_id, _) = finished_vote_handler();
+
+    let page = &call(&io, "list_votes", json!(["finished"]))["result"];
+    assert_eq!(page["total"], json!("0x1"));
+    assert_eq!(page["limit"], json!("0x64"));
+    assert_eq!(page["items"][0]["instance_id"], json!(hex(&instance_id)));
+
+    let page = &call(&io, "list_votes", json!(["open"]))["result"];
+    assert_eq!(page["total"], json!("0x0"));
+    assert_eq!(page["items"], json!([]));
+
+    // Without params, votes of every status are listed.
+    let response = call(&io, "list_votes", Value::Null);
+    assert_eq!(response["result"]["total"], json!("0x1"));
+
+    let page = &call(&io, "list_votes", json!([null, "0x1", "0x10"]))["result"];
+    assert_eq!(page["total"], json!("0x1"));
+    assert_eq!(page["offset"], json!("0x1"));
+    assert_eq!(page["limit"], json!("0x10"));
+    assert_eq!(page["items"], json!([]));
+}
+
+#[test]
+fn test_get_tally() {
+    let (io, instance_id, _) = finished_vote_handler();
+
+    let tally = &call(&io, "get_tally", json!([hex(&instance_id)]))["result"];
+    assert_eq!(tally["total_distributed_tokens"], json!("0x1e"));
+    assert_eq!(
+        tally["options"],
+        json!([
+            {"option": "no", "votes": "0x8"},
+            {"option": "yes", "votes": "0x10"},
+        ])
+    );
+    assert_eq!(
+        tally["shards"],
+        json!([
+            {"option": "no", "shard_index": "0x0", "votes": "0x8"},
+            {"option": "yes", "shard_index": "0x0", "votes": "0x10"},
+        ])
+    );
+}
+
+#[test]
+fn test_get_voter_history() {
+    let (io, instance_id, voter_lock_hashes) = finished_vote_handler();
+    let lock_hash = hex(&voter_lock_hashes[2]);
+
+    let page = &call(&io, "get_voter_history", json!([lock_hash]))["result"];
+    assert_eq!(page["total"], json!("0x1"));
+    let ballot = &page["items"][0];
+    assert_eq!(ballot["instance_id"], json!(hex(&instance_id)));
+    assert_eq!(ballot["block_number"], json!("0x4"));
+    assert_eq!(ballot["option"], json!("no"));
+    assert_eq!(ballot["votes"], json!("0x8"));
+
+    let page = &call(&io, "get_voter_history", json!([lock_hash, "0x0", "0x0"]))["result"];
+    assert_eq!(page["total"], json!("0x1"));
+    assert_eq!(page["items"], json!([]));
+}
+
+#[test]
+fn test_get_voter_receipts() {
+    let (io, _, voter_lock_hashes) = finished_vote_handler();
+
+    // The vote doesn't escrow tokens, so no receipts are issued.
+    let lock_hash = hex(&voter_lock_hashes[0]);
+    let page = &call(&io, "get_voter_receipts", json!([lock_hash]))["result"];
+    assert_eq!(page["total"], json!("0x0"));
+    assert_eq!(page["items"], json!([]));
+
+    let response = call(&io, "get_voter_receipts", json!(["0x1234"]));
+    assert_eq!(response["error"]["code"], json!(-32602));
+}
+
+#[test]
+fn test_invalid_requests() {
+    let (io, _, _) = finished_vote_handler();
+
+    let response = call(&io, "get_votes", json!([]));
+    assert_eq!(response["error"]["code"], json!(-32601));
+
+    let response = call(&io, "get_vote", json!(["0x1234"]));
+    assert_eq!(response["error"]["code"], json!(-32602));
+
+    let response = call(&io, "get_voter_history", json!([]));
+    assert_eq!(response["error"]["code"], json!(-32602));
+}
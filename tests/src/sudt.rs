@@ -3,14 +3,20 @@ use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
 use ckb_tool::{ckb_error::assert_error_eq, ckb_script::ScriptError};
 use ckb_tool::ckb_types::{bytes::Bytes, packed::*, prelude::*};
 use ckb_tool::ckb_types::core::{TransactionBuilder};
+use ckb_voting_errors::SudtError;
+use ckb_voting_sdk::{DeployedScript, LiveCell, MintTokens};
 
 // Constants
 const MAX_CYCLES: u64 = 100_000_000;
 
 // Error Codes
-const ERROR_SUDT_ENCODING: i8 = 4;
-const ERROR_SUDT_AMOUNT: i8 = 5;
-const ERROR_SUDT_ARGS_LENGTH: i8 = 6;
+const ERROR_SUDT_ENCODING: i8 = SudtError::Encoding as i8;
+const ERROR_SUDT_AMOUNT: i8 = SudtError::Amount as i8;
+const ERROR_SUDT_ARGS_LENGTH: i8 = SudtError::ArgsLength as i8;
+const ERROR_SUDT_NON_TRANSFERABLE: i8 = SudtError::NonTransferable as i8;
+
+// Script Args Flags
+const FLAG_NON_TRANSFERABLE: u8 = 0b0000_0001;
 
 #[test]
 fn test_sudt_burn()
@@ -260,6 +266,38 @@ fn test_sudt_create()
 	// println!("consume cycles: {}", cycles);
 }
 
+#[test]
+fn test_sudt_create_with_mint_tokens()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let sudt_bin: Bytes = Loader::default().load_binary("sudt");
+	let out_point_sudt = context.deploy_cell(sudt_bin.clone());
+	let sudt = DeployedScript::from_code(out_point_sudt, &sudt_bin);
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+
+	// Prepare Input Cells
+	let owner_cell = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).build();
+	let input_out_point = context.create_cell(owner_cell.clone(), Bytes::new());
+
+	// Build Transaction
+	let tx = MintTokens::new(&sudt, LiveCell::new(input_out_point, owner_cell, Bytes::new()))
+		.recipient(lock_script, 9_000)
+		.capacity(10_000_000_000)
+		.build()
+		.expect("build");
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let _cycles = context.verify_tx(&tx, MAX_CYCLES).expect("pass verification");
+	// println!("consume cycles: {}", cycles);
+}
+
 #[test]
 fn test_sudt_create_no_owner()
 {
@@ -909,3 +947,449 @@ fn test_sudt_transfer_invalid_output_data()
 	let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
 	assert_error_eq!(err, ScriptError::ValidationFailure(ERROR_SUDT_ENCODING).input_type_script(0));
 }
+
+#[test]
+fn test_sudt_non_transferable_create()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+	// Prepare Cell Deps
+	let always_success_dep = CellDep::new_builder().out_point(out_point_always_success.clone()).build();
+	let sudt_dep = CellDep::new_builder().out_point(out_point_sudt.clone()).build();
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+	let lock_script_other = context.build_script(&out_point_always_success, Bytes::from(vec![1u8])).expect("script");
+	let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+	let mut script_args = lock_script_hash_owner.to_vec();
+	script_args.push(FLAG_NON_TRANSFERABLE);
+	let script_args: Bytes = script_args.into();
+	let type_script = context.build_script(&out_point_sudt, script_args).expect("script");
+
+	// Prepare Input Cells
+	let mut inputs = vec![];
+	let data = vec![];
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+
+	// Prepare Output Cells
+	let mut outputs = vec![];
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script_other.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+
+	// Prepare Output Data
+	let mut outputs_data: Vec<Bytes> = vec![];
+	let data = 9_000u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+
+	// Build Transaction
+	let tx = TransactionBuilder::default()
+		.inputs(inputs)
+		.outputs(outputs)
+		.outputs_data(outputs_data.pack())
+		.cell_dep(always_success_dep)
+		.cell_dep(sudt_dep)
+		.build();
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let _cycles = context.verify_tx(&tx, MAX_CYCLES).expect("pass verification");
+	// println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sudt_non_transferable_transfer_same_lock()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+	// Prepare Cell Deps
+	let always_success_dep = CellDep::new_builder().out_point(out_point_always_success.clone()).build();
+	let sudt_dep = CellDep::new_builder().out_point(out_point_sudt.clone()).build();
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+	let lock_script_hash_zero = [0u8; 32];
+	let mut script_args = lock_script_hash_zero.to_vec();
+	script_args.push(FLAG_NON_TRANSFERABLE);
+	let script_args: Bytes = script_args.into();
+	let type_script = context.build_script(&out_point_sudt, script_args).expect("script");
+
+	// Prepare Input Cells
+	let mut inputs = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+
+	// Prepare Output Cells
+	let mut outputs = vec![];
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+
+	// Prepare Output Data
+	let mut outputs_data: Vec<Bytes> = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+
+	// Build Transaction
+	let tx = TransactionBuilder::default()
+		.inputs(inputs)
+		.outputs(outputs)
+		.outputs_data(outputs_data.pack())
+		.cell_dep(always_success_dep)
+		.cell_dep(sudt_dep)
+		.build();
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let _cycles = context.verify_tx(&tx, MAX_CYCLES).expect("pass verification");
+	// println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sudt_non_transferable_transfer_partial_burn()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+	// Prepare Cell Deps
+	let always_success_dep = CellDep::new_builder().out_point(out_point_always_success.clone()).build();
+	let sudt_dep = CellDep::new_builder().out_point(out_point_sudt.clone()).build();
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+	let lock_script_hash_zero = [0u8; 32];
+	let mut script_args = lock_script_hash_zero.to_vec();
+	script_args.push(FLAG_NON_TRANSFERABLE);
+	let script_args: Bytes = script_args.into();
+	let type_script = context.build_script(&out_point_sudt, script_args).expect("script");
+
+	// Prepare Input Cells
+	let mut inputs = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+
+	// Prepare Output Cells
+	let mut outputs = vec![];
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+
+	// Prepare Output Data
+	let mut outputs_data: Vec<Bytes> = vec![];
+	let data = 400u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+
+	// Build Transaction
+	let tx = TransactionBuilder::default()
+		.inputs(inputs)
+		.outputs(outputs)
+		.outputs_data(outputs_data.pack())
+		.cell_dep(always_success_dep)
+		.cell_dep(sudt_dep)
+		.build();
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let _cycles = context.verify_tx(&tx, MAX_CYCLES).expect("pass verification");
+	// println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sudt_non_transferable_transfer_other_lock()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+	// Prepare Cell Deps
+	let always_success_dep = CellDep::new_builder().out_point(out_point_always_success.clone()).build();
+	let sudt_dep = CellDep::new_builder().out_point(out_point_sudt.clone()).build();
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+	let lock_script_other = context.build_script(&out_point_always_success, Bytes::from(vec![1u8])).expect("script");
+	let lock_script_hash_zero = [0u8; 32];
+	let mut script_args = lock_script_hash_zero.to_vec();
+	script_args.push(FLAG_NON_TRANSFERABLE);
+	let script_args: Bytes = script_args.into();
+	let type_script = context.build_script(&out_point_sudt, script_args).expect("script");
+
+	// Prepare Input Cells
+	let mut inputs = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+
+	// Prepare Output Cells
+	let mut outputs = vec![];
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script_other.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+
+	// Prepare Output Data
+	let mut outputs_data: Vec<Bytes> = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+
+	// Build Transaction
+	let tx = TransactionBuilder::default()
+		.inputs(inputs)
+		.outputs(outputs)
+		.outputs_data(outputs_data.pack())
+		.cell_dep(always_success_dep)
+		.cell_dep(sudt_dep)
+		.build();
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+	assert_error_eq!(err, ScriptError::ValidationFailure(ERROR_SUDT_NON_TRANSFERABLE).input_type_script(0));
+}
+
+#[test]
+fn test_sudt_non_transferable_transfer_split_to_other_lock()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+	// Prepare Cell Deps
+	let always_success_dep = CellDep::new_builder().out_point(out_point_always_success.clone()).build();
+	let sudt_dep = CellDep::new_builder().out_point(out_point_sudt.clone()).build();
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+	let lock_script_other = context.build_script(&out_point_always_success, Bytes::from(vec![1u8])).expect("script");
+	let lock_script_hash_zero = [0u8; 32];
+	let mut script_args = lock_script_hash_zero.to_vec();
+	script_args.push(FLAG_NON_TRANSFERABLE);
+	let script_args: Bytes = script_args.into();
+	let type_script = context.build_script(&out_point_sudt, script_args).expect("script");
+
+	// Prepare Input Cells
+	let mut inputs = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+
+	// Prepare Output Cells
+	let mut outputs = vec![];
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script_other.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+
+	// Prepare Output Data
+	let mut outputs_data: Vec<Bytes> = vec![];
+	let data = 600u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+	let data = 400u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+
+	// Build Transaction
+	let tx = TransactionBuilder::default()
+		.inputs(inputs)
+		.outputs(outputs)
+		.outputs_data(outputs_data.pack())
+		.cell_dep(always_success_dep)
+		.cell_dep(sudt_dep)
+		.build();
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+	assert_error_eq!(err, ScriptError::ValidationFailure(ERROR_SUDT_NON_TRANSFERABLE).input_type_script(0));
+}
+
+#[test]
+fn test_sudt_non_transferable_transfer_merge_from_other_lock()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+	// Prepare Cell Deps
+	let always_success_dep = CellDep::new_builder().out_point(out_point_always_success.clone()).build();
+	let sudt_dep = CellDep::new_builder().out_point(out_point_sudt.clone()).build();
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+	let lock_script_other = context.build_script(&out_point_always_success, Bytes::from(vec![1u8])).expect("script");
+	let lock_script_hash_zero = [0u8; 32];
+	let mut script_args = lock_script_hash_zero.to_vec();
+	script_args.push(FLAG_NON_TRANSFERABLE);
+	let script_args: Bytes = script_args.into();
+	let type_script = context.build_script(&out_point_sudt, script_args).expect("script");
+
+	// Prepare Input Cells
+	let mut inputs = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+	let data = 1_000u128.to_le_bytes().to_vec();
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script_other.clone()).type_(Some(type_script.clone()).pack()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+
+	// Prepare Output Cells
+	let mut outputs = vec![];
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+
+	// Prepare Output Data
+	let mut outputs_data: Vec<Bytes> = vec![];
+	let data = 2_000u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+
+	// Build Transaction
+	let tx = TransactionBuilder::default()
+		.inputs(inputs)
+		.outputs(outputs)
+		.outputs_data(outputs_data.pack())
+		.cell_dep(always_success_dep)
+		.cell_dep(sudt_dep)
+		.build();
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+	assert_error_eq!(err, ScriptError::ValidationFailure(ERROR_SUDT_NON_TRANSFERABLE).input_type_script(0));
+}
+
+#[test]
+fn test_sudt_transferable_flags_transfer_other_lock()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+	// Prepare Cell Deps
+	let always_success_dep = CellDep::new_builder().out_point(out_point_always_success.clone()).build();
+	let sudt_dep = CellDep::new_builder().out_point(out_point_sudt.clone()).build();
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+	let lock_script_other = context.build_script(&out_point_always_success, Bytes::from(vec![1u8])).expect("script");
+	let lock_script_hash_zero = [0u8; 32];
+	let mut script_args = lock_script_hash_zero.to_vec();
+	script_args.push(0u8);
+	let script_args: Bytes = script_args.into();
+	let type_script = context.build_script(&out_point_sudt, script_args).expect("script");
+
+	// Prepare Input Cells
+	let mut inputs = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+
+	// Prepare Output Cells
+	let mut outputs = vec![];
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script_other.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+
+	// Prepare Output Data
+	let mut outputs_data: Vec<Bytes> = vec![];
+	let data = 1_000u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+
+	// Build Transaction
+	let tx = TransactionBuilder::default()
+		.inputs(inputs)
+		.outputs(outputs)
+		.outputs_data(outputs_data.pack())
+		.cell_dep(always_success_dep)
+		.cell_dep(sudt_dep)
+		.build();
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let _cycles = context.verify_tx(&tx, MAX_CYCLES).expect("pass verification");
+	// println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_sudt_create_invalid_flags_args_length()
+{
+	// Create Context
+	let mut context = Context::default();
+
+	// Deploy Contracts
+	let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+	let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+	// Prepare Cell Deps
+	let always_success_dep = CellDep::new_builder().out_point(out_point_always_success.clone()).build();
+	let sudt_dep = CellDep::new_builder().out_point(out_point_sudt.clone()).build();
+
+	// Prepare Scripts
+	let lock_script = context.build_script(&out_point_always_success, Default::default()).expect("script");
+	let lock_script_hash_owner: [u8; 32] = lock_script.calc_script_hash().unpack();
+	let mut script_args = lock_script_hash_owner.to_vec();
+	script_args.push(FLAG_NON_TRANSFERABLE);
+	script_args.push(0u8);
+	let script_args: Bytes = script_args.into();
+	let type_script = context.build_script(&out_point_sudt, script_args).expect("script");
+
+	// Prepare Input Cells
+	let mut inputs = vec![];
+	let data = vec![];
+	let input_out_point = context.create_cell(CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).build(), Bytes::from(data));
+	let input = CellInput::new_builder().previous_output(input_out_point).build();
+	inputs.push(input);
+
+	// Prepare Output Cells
+	let mut outputs = vec![];
+	let output = CellOutput::new_builder().capacity(10_000_000_000_u64.pack()).lock(lock_script.clone()).type_(Some(type_script.clone()).pack()).build();
+	outputs.push(output);
+
+	// Prepare Output Data
+	let mut outputs_data: Vec<Bytes> = vec![];
+	let data = 9_000u128.to_le_bytes().to_vec();
+	outputs_data.push(Bytes::from(data));
+
+	// Build Transaction
+	let tx = TransactionBuilder::default()
+		.inputs(inputs)
+		.outputs(outputs)
+		.outputs_data(outputs_data.pack())
+		.cell_dep(always_success_dep)
+		.cell_dep(sudt_dep)
+		.build();
+	let tx = context.complete_tx(tx);
+
+	// Run
+	let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+	assert_error_eq!(err, ScriptError::ValidationFailure(ERROR_SUDT_ARGS_LENGTH).output_type_script(0));
+}
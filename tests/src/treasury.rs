@@ -0,0 +1,2208 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_hash::Blake2bBuilder;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{HeaderBuilder, TransactionBuilder},
+    packed::*,
+    prelude::*,
+};
+use ckb_voting_layout::{
+    ACTION_HASH_OFFSET, CORE_DATA_LEN, EXECUTION_DELAY_OFFSET, IS_VOTING_FINISHED_OFFSET,
+    ORGANIZER_LOCK_HASH_OFFSET, SETTLEMENT_BLOCK_NUMBER_OFFSET, VOTE_OPTION_NO, VOTE_OPTION_YES,
+    VOTE_RESULT_OPTION_TYPE_OFFSET, VOTING_STATUS_EXECUTED, VOTING_STATUS_FINISHED,
+};
+
+const MAX_CYCLES: u64 = 10_000_000;
+const BLAKE2B256_HASH_LEN: usize = 32; // Number of bytes for a Blake2b-256 hash.
+
+// Error Codes
+const ERROR_CORE_CELL_MISSING: i8 = 6;
+const ERROR_VOTE_NOT_PASSED: i8 = 7;
+const ERROR_PAYOUT_MISMATCH: i8 = 8;
+const ERROR_ACTION_HASH_MISMATCH: i8 = 10;
+const ERROR_ACTION_MISMATCH: i8 = 11;
+const ERROR_EXECUTION_DELAY_NOT_PASSED: i8 = 12;
+const ERROR_CORE_CELL_HEADER_MISSING: i8 = 13;
+
+// Execution Delay
+const EXECUTION_DELAY: u64 = 100;
+const SETTLEMENT_BLOCK_NUMBER: u64 = 200;
+const EXECUTABLE_BLOCK_NUMBER: u64 = SETTLEMENT_BLOCK_NUMBER + EXECUTION_DELAY;
+
+// Action Kinds
+const ACTION_KIND_CKB_TRANSFER: u8 = 0;
+const ACTION_KIND_SUDT_TRANSFER: u8 = 1;
+const ACTION_KIND_CONTRACT_UPGRADE: u8 = 2;
+
+fn hash(data: &[u8]) -> [u8; BLAKE2B256_HASH_LEN] {
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_LEN)
+        .personal(b"ckb-default-hash")
+        .build();
+    blake2b.update(data);
+
+    let mut hash: [u8; BLAKE2B256_HASH_LEN] = [0; BLAKE2B256_HASH_LEN];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+fn calculate_payout_hash(
+    outputs: &[CellOutput],
+    outputs_data: &[Bytes],
+) -> [u8; BLAKE2B256_HASH_LEN] {
+    let mut blake2b = Blake2bBuilder::new(BLAKE2B256_HASH_LEN)
+        .personal(b"ckb-default-hash")
+        .build();
+
+    for (output, data) in outputs.iter().zip(outputs_data.iter()) {
+        blake2b.update(output.as_slice());
+        blake2b.update(&(data.len() as u64).to_le_bytes());
+        blake2b.update(data);
+    }
+
+    let mut hash: [u8; BLAKE2B256_HASH_LEN] = [0; BLAKE2B256_HASH_LEN];
+    blake2b.finalize(&mut hash);
+
+    hash
+}
+
+fn build_core_data(
+    is_voting_finished: u8,
+    vote_result_option_type: u8,
+    action_hash: [u8; BLAKE2B256_HASH_LEN],
+) -> Bytes {
+    let mut data = vec![0u8; CORE_DATA_LEN];
+    data[IS_VOTING_FINISHED_OFFSET] = is_voting_finished;
+    data[VOTE_RESULT_OPTION_TYPE_OFFSET] = vote_result_option_type;
+    data[ACTION_HASH_OFFSET..EXECUTION_DELAY_OFFSET].copy_from_slice(&action_hash);
+    data[EXECUTION_DELAY_OFFSET..SETTLEMENT_BLOCK_NUMBER_OFFSET]
+        .copy_from_slice(&EXECUTION_DELAY.to_le_bytes());
+    data[SETTLEMENT_BLOCK_NUMBER_OFFSET..ORGANIZER_LOCK_HASH_OFFSET]
+        .copy_from_slice(&SETTLEMENT_BLOCK_NUMBER.to_le_bytes());
+
+    Bytes::from(data)
+}
+
+#[test]
+fn test_treasury_release_on_passed_vote() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(1, VOTE_OPTION_YES, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell, which isn't part of the payout hash.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, [0u8; 32]).pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_treasury_cannot_release_on_rejected_vote() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(1, VOTE_OPTION_NO, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell, which isn't part of the payout hash.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_NO, [0u8; 32]).pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_NOT_PASSED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_release_before_vote_finished() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(0, VOTE_OPTION_YES, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell, which isn't part of the payout hash.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, [0u8; 32]).pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_NOT_PASSED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_release_without_spending_core_cell() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(1, VOTE_OPTION_YES, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The Core Cell is only a cell dep.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_CORE_CELL_MISSING).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_release_twice() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Another Treasury Cell with the same payout, released against the Core Cell of the first release.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell, which isn't part of the payout hash.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, [0u8; 32]).pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_NOT_PASSED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_release_to_other_outputs() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(1, VOTE_OPTION_YES, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell, which isn't part of the payout hash.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(payout.as_builder().capacity(9000u64.pack()).build())
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, [0u8; 32]).pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_PAYOUT_MISMATCH).input_lock_script(0)
+    );
+}
+
+struct Action {
+    kind: u8,
+    lock_hash: [u8; 32],
+    type_hash: [u8; 32],
+    amount: u128,
+    data_hash: [u8; 32],
+}
+
+#[test]
+fn test_treasury_cannot_release_before_execution_delay() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(1, VOTE_OPTION_YES, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number((EXECUTABLE_BLOCK_NUMBER - 1).pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell, which isn't part of the payout hash.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, [0u8; 32]).pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_EXECUTION_DELAY_NOT_PASSED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_execution_delay_counts_from_finishing_block() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // The vote was finished 50 blocks after the recorded settlement block, so the delay isn't over yet.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(1, VOTE_OPTION_YES, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number((SETTLEMENT_BLOCK_NUMBER + 50).pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell, which isn't part of the payout hash.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, [0u8; 32]).pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_EXECUTION_DELAY_NOT_PASSED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_release_without_core_cell_header() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script.clone()).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(1, VOTE_OPTION_YES, [0u8; 32]),
+    );
+
+    // The vote was finished in the block of the Core Cell, and the chain is past the execution delay.
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The treasury cell is committed to paying out to the recipient.
+    let payout = CellOutput::new_builder()
+        .capacity(5000u64.pack())
+        .lock(recipient_lock_script)
+        .build();
+    let core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let mut treasury_args = core_type_hash.to_vec();
+    treasury_args.extend_from_slice(&calculate_payout_hash(
+        std::slice::from_ref(&payout),
+        &[Bytes::new()],
+    ));
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, treasury_args.into())
+        .expect("script");
+    let treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+
+    // The payout, followed by the executed Core Cell, which isn't part of the payout hash.
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(payout)
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, [0u8; 32]).pack())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_CORE_CELL_HEADER_MISSING).input_lock_script(0)
+    );
+}
+
+// Serialize actions as a molecule ActionVec, see `schemas/actions.mol`.
+fn build_actions(actions: &[Action]) -> Bytes {
+    let mut data = (actions.len() as u32).to_le_bytes().to_vec();
+
+    for action in actions {
+        data.push(action.kind);
+        data.extend_from_slice(&action.lock_hash);
+        data.extend_from_slice(&action.type_hash);
+        data.extend_from_slice(&action.amount.to_le_bytes());
+        data.extend_from_slice(&action.data_hash);
+    }
+
+    Bytes::from(data)
+}
+
+// Transfer 5000 CKB and 30 sUDT to the recipient, and upgrade a contract.
+fn build_executor_actions(
+    recipient_lock_script: &Script,
+    sudt_type_script: &Script,
+    contract_type_script: &Script,
+    contract_code: &[u8],
+) -> Bytes {
+    let recipient_lock_hash: [u8; 32] = recipient_lock_script.calc_script_hash().unpack();
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+    let contract_type_hash: [u8; 32] = contract_type_script.calc_script_hash().unpack();
+
+    build_actions(&[
+        Action {
+            kind: ACTION_KIND_CKB_TRANSFER,
+            lock_hash: recipient_lock_hash,
+            type_hash: [0u8; 32],
+            amount: 5000,
+            data_hash: [0u8; 32],
+        },
+        Action {
+            kind: ACTION_KIND_SUDT_TRANSFER,
+            lock_hash: recipient_lock_hash,
+            type_hash: sudt_type_hash,
+            amount: 30,
+            data_hash: [0u8; 32],
+        },
+        Action {
+            kind: ACTION_KIND_CONTRACT_UPGRADE,
+            lock_hash: recipient_lock_hash,
+            type_hash: contract_type_hash,
+            amount: 0,
+            data_hash: hash(contract_code),
+        },
+    ])
+}
+
+#[test]
+fn test_treasury_execute_actions() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script.clone())
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_FINISHED, VOTE_OPTION_YES, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(BytesOpt::new_builder().set(Some(actions.pack())).build())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"new contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(core_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .output(core_cell)
+        .output_data(
+            build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, hash(&actions)).pack(),
+        )
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_treasury_cannot_execute_actions_on_rejected_vote() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script.clone())
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_FINISHED, VOTE_OPTION_NO, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(BytesOpt::new_builder().set(Some(actions.pack())).build())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"new contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(core_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .output(core_cell)
+        .output_data(build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_NO, hash(&actions)).pack())
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_NOT_PASSED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_execute_uncommitted_actions() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+    let other_actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"malicious contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script.clone())
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_FINISHED, VOTE_OPTION_YES, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(
+            BytesOpt::new_builder()
+                .set(Some(other_actions.pack()))
+                .build(),
+        )
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"malicious contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(core_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .output(core_cell)
+        .output_data(
+            build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, hash(&actions)).pack(),
+        )
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ACTION_HASH_MISMATCH).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_execute_actions_with_wrong_outputs() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script.clone())
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_FINISHED, VOTE_OPTION_YES, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(BytesOpt::new_builder().set(Some(actions.pack())).build())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"malicious contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(core_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .output(core_cell)
+        .output_data(
+            build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, hash(&actions)).pack(),
+        )
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ACTION_MISMATCH).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_send_change_elsewhere() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script.clone())
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_FINISHED, VOTE_OPTION_YES, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // Some of the change goes to the recipient.
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(BytesOpt::new_builder().set(Some(actions.pack())).build())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"new contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(core_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(core_cell)
+        .output_data(
+            build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, hash(&actions)).pack(),
+        )
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ACTION_MISMATCH).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_execute_actions_before_execution_delay() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script.clone())
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_FINISHED, VOTE_OPTION_YES, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number((EXECUTABLE_BLOCK_NUMBER - 1).pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(BytesOpt::new_builder().set(Some(actions.pack())).build())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"new contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(core_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .output(core_cell)
+        .output_data(
+            build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, hash(&actions)).pack(),
+        )
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_EXECUTION_DELAY_NOT_PASSED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_execute_actions_twice() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script.clone())
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(BytesOpt::new_builder().set(Some(actions.pack())).build())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"new contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(core_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .output(core_cell)
+        .output_data(
+            build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, hash(&actions)).pack(),
+        )
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_NOT_PASSED).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_execute_actions_with_core_cell_dep() {
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script)
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script.clone())
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell,
+        build_core_data(VOTING_STATUS_FINISHED, VOTE_OPTION_YES, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(BytesOpt::new_builder().set(Some(actions.pack())).build())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"new contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(core_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_CORE_CELL_MISSING).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_treasury_cannot_keep_change_after_execution() {
+    // Once the vote is executed the treasury can't be spent again, so change left there would be lost.
+    let mut context = Context::default();
+    let out_point_treasury = context.deploy_cell(Loader::default().load_binary("treasury"));
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+
+    let recipient_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let contract_type_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+    let core_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![4u8]))
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![3u8; 32]))
+        .expect("script");
+    let core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+
+    // Treasury cells holding CKB and sUDT, locked in executor mode.
+    let core_type_hash: [u8; 32] = core_type_script.calc_script_hash().unpack();
+    let treasury_lock_script = context
+        .build_script(&out_point_treasury, core_type_hash.to_vec().into())
+        .expect("script");
+    let ckb_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let sudt_treasury_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(treasury_lock_script.clone())
+            .type_(Some(sudt_type_script.clone()).pack())
+            .build(),
+        Bytes::from(100u128.to_le_bytes().to_vec()),
+    );
+
+    let actions = build_executor_actions(
+        &recipient_lock_script,
+        &sudt_type_script,
+        &contract_type_script,
+        b"new contract",
+    );
+
+    // The Core Cell is committed to the actions, and was finished in the settlement block. The chain is past the
+    // execution delay.
+    let core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(core_lock_script)
+        .type_(Some(core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        core_cell.clone(),
+        build_core_data(VOTING_STATUS_FINISHED, VOTE_OPTION_YES, hash(&actions)),
+    );
+    let core_header = HeaderBuilder::default()
+        .number(SETTLEMENT_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(core_header.clone());
+    context.link_cell_with_block(core_out_point.clone(), core_header.hash(), 0);
+    let tip_header = HeaderBuilder::default()
+        .number(EXECUTABLE_BLOCK_NUMBER.pack())
+        .build();
+    context.insert_header(tip_header.clone());
+
+    // The change is left in the treasury.
+    // The outputs carrying out the actions, followed by the change, which goes to the owner of the Core Cell.
+    let witness_args = WitnessArgs::new_builder()
+        .lock(BytesOpt::new_builder().set(Some(actions.pack())).build())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(ckb_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(sudt_treasury_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(5000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script.clone())
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(recipient_lock_script)
+                .type_(Some(contract_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(b"new contract".to_vec()).pack())
+        .output(
+            CellOutput::new_builder()
+                .capacity(9000u64.pack())
+                .lock(treasury_lock_script)
+                .type_(Some(sudt_type_script).pack())
+                .build(),
+        )
+        .output_data(Bytes::from(70u128.to_le_bytes().to_vec()).pack())
+        .output(core_cell)
+        .output_data(
+            build_core_data(VOTING_STATUS_EXECUTED, VOTE_OPTION_YES, hash(&actions)).pack(),
+        )
+        .witness(witness_args.as_bytes().pack())
+        .header_dep(core_header.hash())
+        .header_dep(tip_header.hash())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_ACTION_MISMATCH).input_lock_script(0)
+    );
+}
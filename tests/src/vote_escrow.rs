@@ -0,0 +1,440 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{bytes::Bytes, core::TransactionBuilder, packed::*, prelude::*};
+use ckb_voting_errors::DaoCoreError;
+use ckb_voting_layout::{
+    CORE_DATA_LEN, IS_VOTING_FINISHED_OFFSET, VOTE_OPTION_NO, VOTING_STATUS_CANCELLED,
+    VOTING_STATUS_OPEN,
+};
+
+const MAX_CYCLES: u64 = 10_000_000;
+
+// Error Codes
+const ERROR_VOTE_NOT_CANCELLED: i8 = 7;
+const ERROR_REFUND_EXCEEDS_RECEIPTS: i8 = 9;
+const ERROR_REFUND_MISMATCH: i8 = DaoCoreError::RefundMismatch as i8;
+
+// Builds the data of a Core Cell counting votes with the specified token. All other fields are left empty.
+fn build_core_data(token_type_hash: [u8; 32], voting_status: u8) -> Bytes {
+    let mut data = vec![0u8; CORE_DATA_LEN];
+    data[0..32].copy_from_slice(&token_type_hash);
+    data[IS_VOTING_FINISHED_OFFSET] = voting_status;
+
+    Bytes::from(data)
+}
+
+fn build_receipt_data(vote_option_type: u8, amount: u128, owner_lock_hash: [u8; 32]) -> Bytes {
+    let mut data = vec![vote_option_type];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&owner_lock_hash);
+
+    Bytes::from(data)
+}
+
+fn build_token_cell(lock_script: &Script, sudt_type_script: &Script) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script.clone())
+        .type_(Some(sudt_type_script.clone()).pack())
+        .build()
+}
+
+#[test]
+fn test_can_refund_cancelled_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // The vote was cancelled. A receipt of 6 votes is consumed, 6 of the 10 escrowed tokens are returned to its
+    // owner and the change is kept in escrow.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, VOTING_STATUS_CANCELLED),
+    );
+    let receipt_out_point = context.create_cell(
+        dao_core_cell,
+        build_receipt_data(VOTE_OPTION_NO, 6, owner_lock_hash),
+    );
+    let escrow_out_point = context.create_cell(
+        build_token_cell(&escrow_lock_script, &sudt_type_script),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(receipt_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(escrow_out_point)
+                .build(),
+        )
+        .output(build_token_cell(&owner_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .output(build_token_cell(&escrow_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(out_point_dao_core).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_escrow).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_sudt).build())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(out_point_always_success)
+                .build(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_refund_open_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // The vote is still open. A receipt of 6 votes is consumed, 6 of the 10 escrowed tokens are returned to its
+    // owner and the change is kept in escrow.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, VOTING_STATUS_OPEN),
+    );
+    let receipt_out_point = context.create_cell(
+        dao_core_cell,
+        build_receipt_data(VOTE_OPTION_NO, 6, owner_lock_hash),
+    );
+    let escrow_out_point = context.create_cell(
+        build_token_cell(&escrow_lock_script, &sudt_type_script),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(receipt_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(escrow_out_point)
+                .build(),
+        )
+        .output(build_token_cell(&owner_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .output(build_token_cell(&escrow_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(out_point_dao_core).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_escrow).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_sudt).build())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(out_point_always_success)
+                .build(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_NOT_CANCELLED).input_lock_script(1)
+    );
+}
+
+#[test]
+fn test_cannot_refund_more_than_receipts() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // A receipt of 6 votes for 10 escrowed tokens, of which 7 are refunded.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, VOTING_STATUS_CANCELLED),
+    );
+    let receipt_out_point = context.create_cell(
+        dao_core_cell,
+        build_receipt_data(VOTE_OPTION_NO, 6, owner_lock_hash),
+    );
+    let escrow_out_point = context.create_cell(
+        build_token_cell(&escrow_lock_script, &sudt_type_script),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(receipt_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(escrow_out_point)
+                .build(),
+        )
+        .output(build_token_cell(&owner_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(7u128.to_le_bytes().to_vec()).pack())
+        .output(build_token_cell(&escrow_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(3u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(out_point_dao_core).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_escrow).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_sudt).build())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(out_point_always_success)
+                .build(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_REFUND_EXCEEDS_RECEIPTS).input_lock_script(1)
+    );
+}
+
+#[test]
+fn test_cannot_refund_without_receipt() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // 10 escrowed tokens are refunded without a receipt.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell,
+        build_core_data(sudt_type_hash, VOTING_STATUS_CANCELLED),
+    );
+    let escrow_out_point = context.create_cell(
+        build_token_cell(&escrow_lock_script, &sudt_type_script),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(escrow_out_point)
+                .build(),
+        )
+        .output(build_token_cell(&owner_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .output(build_token_cell(&escrow_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(out_point_dao_core).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_escrow).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_sudt).build())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(out_point_always_success)
+                .build(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_REFUND_EXCEEDS_RECEIPTS).input_lock_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_refund_to_other_owner() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let owner_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![1u8]))
+        .expect("script");
+    let owner_lock_hash: [u8; 32] = owner_lock_script.calc_script_hash().unpack();
+    let other_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![2u8]))
+        .expect("script");
+
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // A receipt of 6 votes, refunded to a lock other than its owner.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, VOTING_STATUS_CANCELLED),
+    );
+    let receipt_out_point = context.create_cell(
+        dao_core_cell,
+        build_receipt_data(VOTE_OPTION_NO, 6, owner_lock_hash),
+    );
+    let escrow_out_point = context.create_cell(
+        build_token_cell(&escrow_lock_script, &sudt_type_script),
+        Bytes::from(10u128.to_le_bytes().to_vec()),
+    );
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(receipt_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(escrow_out_point)
+                .build(),
+        )
+        .output(build_token_cell(&other_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(6u128.to_le_bytes().to_vec()).pack())
+        .output(build_token_cell(&escrow_lock_script, &sudt_type_script))
+        .output_data(Bytes::from(4u128.to_le_bytes().to_vec()).pack())
+        .cell_dep(CellDep::new_builder().out_point(out_point_dao_core).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_escrow).build())
+        .cell_dep(CellDep::new_builder().out_point(out_point_sudt).build())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(out_point_always_success)
+                .build(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(core_out_point).build())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_REFUND_MISMATCH).input_type_script(0)
+    );
+}
@@ -0,0 +1,1104 @@
+use super::*;
+use aggregator::{Aggregator, VoteCellInfo, VoteIntent};
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_crypto::secp::Privkey;
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_hash::blake2b_256;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{HeaderBuilder, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_voting_errors::DaoCoreError;
+use ckb_voting_layout::{
+    CORE_DATA_LEN, ESCROW_LOCK_HASH_OFFSET, INTENT_BYTESIZE, VOTE_OPTION_NO, VOTE_OPTION_YES,
+    VOTING_STATUS_FINISHED,
+};
+use ckb_voting_sdk::{CoreCellData, VoteCellData};
+
+const MAX_CYCLES: u64 = 10_000_000;
+
+// Error Codes
+const ERROR_INVALID_SIGNATURE: i8 = 9;
+const ERROR_CORE_CELL_MISSING: i8 = 10;
+const ERROR_INTENT_NOT_COUNTED: i8 = 13;
+const ERROR_INVALID_VOTE_INTENT: i8 = DaoCoreError::InvalidVoteIntent as i8;
+const ERROR_VOTE_INTENT_MISMATCH: i8 = DaoCoreError::VoteIntentMismatch as i8;
+const ERROR_RECEIPT_MISMATCH: i8 = DaoCoreError::ReceiptMismatch as i8;
+
+// The Vote Cells come first, followed by the token cells of the voters.
+const VOTE_CELL_COUNT: usize = 2;
+const VOTER_TOKEN_AMOUNT: u128 = 10;
+
+// Builds the data of an open Core Cell counting votes with the specified token, which holds voted tokens in
+// escrow with the specified lock. All other fields are left empty.
+fn build_core_data(token_type_hash: [u8; 32], escrow_lock_hash: [u8; 32]) -> Bytes {
+    let mut data = vec![0u8; CORE_DATA_LEN];
+    data[0..32].copy_from_slice(&token_type_hash);
+    data[ESCROW_LOCK_HASH_OFFSET..ESCROW_LOCK_HASH_OFFSET + 32].copy_from_slice(&escrow_lock_hash);
+
+    Bytes::from(data)
+}
+
+fn build_receipt_data(vote_option_type: u8, amount: u128, owner_lock_hash: [u8; 32]) -> Bytes {
+    let mut data = vec![vote_option_type];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&owner_lock_hash);
+
+    Bytes::from(data)
+}
+
+// The lock hash the voter signs receipts to be issued to.
+fn build_owner_lock_hash(voter: usize) -> [u8; 32] {
+    [voter as u8 + 1; 32]
+}
+
+// Replaces the tallies of the Vote Cells built by the aggregator.
+fn set_tallies(tx: TransactionView, no_votes: u128, yes_votes: u128) -> TransactionView {
+    let outputs_data: Vec<Bytes> = tx
+        .outputs_data()
+        .into_iter()
+        .zip([no_votes, yes_votes].iter())
+        .map(|(data, total_votes_collected)| {
+            let mut data = data.raw_data().to_vec();
+            data[1..17].copy_from_slice(&total_votes_collected.to_le_bytes());
+            Bytes::from(data)
+        })
+        .collect();
+
+    tx.as_advanced_builder()
+        .set_outputs_data(outputs_data.pack().into_iter().collect())
+        .build()
+}
+
+fn build_voter_keys() -> Vec<Privkey> {
+    (1..=3u8).map(|i| Privkey::from_slice(&[i; 32])).collect()
+}
+
+// Creates a cell of 10 tokens for every voter, locked with the vote intent lock of the voter's key.
+fn create_intent_cells(
+    context: &mut Context,
+    out_point_intent: &OutPoint,
+    dao_core_type_hash: &[u8; 32],
+    sudt_type_script: &Script,
+) -> Vec<(OutPoint, CellOutput)> {
+    build_voter_keys()
+        .iter()
+        .map(|key| {
+            let pubkey_hash = blake2b_256(key.pubkey().expect("pubkey").serialize());
+            let mut args = dao_core_type_hash.to_vec();
+            args.extend_from_slice(&pubkey_hash[..20]);
+
+            let intent_lock_script = context
+                .build_script(out_point_intent, Bytes::from(args))
+                .expect("script");
+            let token_cell = CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(intent_lock_script)
+                .type_(Some(sudt_type_script.clone()).pack())
+                .build();
+            let out_point = context.create_cell(
+                token_cell.clone(),
+                Bytes::from(VOTER_TOKEN_AMOUNT.to_le_bytes().to_vec()),
+            );
+
+            (out_point, token_cell)
+        })
+        .collect()
+}
+
+// Adds the intent of the voter to the batch, signed with the specified key.
+fn add_intent(
+    aggregator: &mut Aggregator,
+    token_cells: &[(OutPoint, CellOutput)],
+    voter: usize,
+    vote_option_type: u8,
+    amount: u128,
+    key: &Privkey,
+) {
+    let (out_point, token_cell) = token_cells[voter].clone();
+    let intent = VoteIntent::new(
+        vote_option_type,
+        amount,
+        out_point,
+        build_owner_lock_hash(voter),
+    )
+    .sign(key)
+    .expect("sign");
+
+    aggregator
+        .add_intent(intent, token_cell)
+        .expect("add intent");
+}
+
+// Moves the tokens of all three voters to the escrow, and issues a receipt for the vote of every voter to the
+// specified owner. The first two voted yes and the last one voted no.
+fn add_escrow_outputs(
+    tx: TransactionView,
+    token_cell: &CellOutput,
+    escrow_lock_script: &Script,
+    receipt_owners: [[u8; 32]; 3],
+) -> TransactionView {
+    let vote_cell = tx.outputs().get(0).expect("vote cell");
+    let mut builder = tx
+        .as_advanced_builder()
+        .output(
+            token_cell
+                .clone()
+                .as_builder()
+                .lock(escrow_lock_script.clone())
+                .build(),
+        )
+        .output_data(Bytes::from(30u128.to_le_bytes().to_vec()).pack());
+
+    let vote_option_types = [VOTE_OPTION_YES, VOTE_OPTION_YES, VOTE_OPTION_NO];
+    for (vote_option_type, owner_lock_hash) in vote_option_types.iter().zip(receipt_owners.iter()) {
+        builder = builder.output(vote_cell.clone()).output_data(
+            build_receipt_data(*vote_option_type, VOTER_TOKEN_AMOUNT, *owner_lock_hash).pack(),
+        );
+    }
+
+    builder.build()
+}
+
+// Every vote intent lock checks the whole transaction, and lock groups aren't verified in a fixed order, so the
+// error can be reported by the cell of any voter.
+fn assert_intent_lock_error(err: ckb_tool::ckb_error::Error, error_code: i8) {
+    let expected: Vec<String> = (0..3)
+        .map(|voter| {
+            let expected: ckb_tool::ckb_error::Error = ScriptError::ValidationFailure(error_code)
+                .input_lock_script(VOTE_CELL_COUNT + voter)
+                .into();
+            expected.to_string()
+        })
+        .collect();
+
+    assert!(expected.contains(&err.to_string()), "{}", err);
+}
+
+// Creates a cell committed in the block with the specified number, and returns it along with the block hash.
+fn create_cell_in_block(
+    context: &mut Context,
+    cell: CellOutput,
+    data: Bytes,
+    block_number: u64,
+) -> (OutPoint, Byte32) {
+    let out_point = context.create_cell(cell, data);
+    let header = HeaderBuilder::default().number(block_number.pack()).build();
+    context.insert_header(header.clone());
+    context.link_cell_with_block(out_point.clone(), header.hash(), 0);
+
+    (out_point, header.hash())
+}
+
+#[test]
+fn test_batched_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // An open vote with empty Vote Cells for both options and three voters, who each hold a cell of 10 tokens
+    // locked with the vote intent lock.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, [0u8; 32]),
+    );
+    let vote_cells = [VOTE_OPTION_NO, VOTE_OPTION_YES]
+        .iter()
+        .map(|vote_option_type| {
+            let mut vote_cell = VoteCellInfo {
+                out_point: OutPoint::default(),
+                output: dao_core_cell.clone(),
+                vote_option_type: *vote_option_type,
+                shard_index: 0,
+                total_votes_collected: 0,
+            };
+            vote_cell.out_point = context.create_cell(dao_core_cell.clone(), vote_cell.data());
+            vote_cell
+        })
+        .collect();
+
+    let mut aggregator = Aggregator::new(vote_cells);
+    for out_point in [
+        out_point_dao_core,
+        out_point_intent.clone(),
+        out_point_sudt,
+        out_point_always_success,
+        core_out_point,
+    ] {
+        aggregator.add_cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+
+    // Every voter votes with all of their tokens. The first two vote yes and the last one votes no.
+    for (voter, key) in keys.iter().enumerate() {
+        let vote_option_type = if voter < 2 {
+            VOTE_OPTION_YES
+        } else {
+            VOTE_OPTION_NO
+        };
+        add_intent(
+            &mut aggregator,
+            &token_cells,
+            voter,
+            vote_option_type,
+            VOTER_TOKEN_AMOUNT,
+            key,
+        );
+    }
+
+    let tx = context.complete_tx(aggregator.build().expect("build"));
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_vote_with_intent_signed_by_other_key() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // An open vote with empty Vote Cells for both options and three voters, who each hold a cell of 10 tokens
+    // locked with the vote intent lock.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, [0u8; 32]),
+    );
+    let vote_cells = [VOTE_OPTION_NO, VOTE_OPTION_YES]
+        .iter()
+        .map(|vote_option_type| {
+            let mut vote_cell = VoteCellInfo {
+                out_point: OutPoint::default(),
+                output: dao_core_cell.clone(),
+                vote_option_type: *vote_option_type,
+                shard_index: 0,
+                total_votes_collected: 0,
+            };
+            vote_cell.out_point = context.create_cell(dao_core_cell.clone(), vote_cell.data());
+            vote_cell
+        })
+        .collect();
+
+    let mut aggregator = Aggregator::new(vote_cells);
+    for out_point in [
+        out_point_dao_core,
+        out_point_intent.clone(),
+        out_point_sudt,
+        out_point_always_success,
+        core_out_point,
+    ] {
+        aggregator.add_cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+
+    // The second voter's intent is signed with the key of the first voter.
+    add_intent(
+        &mut aggregator,
+        &token_cells,
+        0,
+        VOTE_OPTION_YES,
+        VOTER_TOKEN_AMOUNT,
+        &keys[0],
+    );
+    add_intent(
+        &mut aggregator,
+        &token_cells,
+        1,
+        VOTE_OPTION_YES,
+        VOTER_TOKEN_AMOUNT,
+        &keys[0],
+    );
+
+    let tx = context.complete_tx(aggregator.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_SIGNATURE)
+            .input_lock_script(VOTE_CELL_COUNT + 1)
+    );
+}
+
+#[test]
+fn test_cannot_change_option_of_signed_intent() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // An open vote with empty Vote Cells for both options and three voters, who each hold a cell of 10 tokens
+    // locked with the vote intent lock.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, [0u8; 32]),
+    );
+    let vote_cells = [VOTE_OPTION_NO, VOTE_OPTION_YES]
+        .iter()
+        .map(|vote_option_type| {
+            let mut vote_cell = VoteCellInfo {
+                out_point: OutPoint::default(),
+                output: dao_core_cell.clone(),
+                vote_option_type: *vote_option_type,
+                shard_index: 0,
+                total_votes_collected: 0,
+            };
+            vote_cell.out_point = context.create_cell(dao_core_cell.clone(), vote_cell.data());
+            vote_cell
+        })
+        .collect();
+
+    let mut aggregator = Aggregator::new(vote_cells);
+    for out_point in [
+        out_point_dao_core,
+        out_point_intent.clone(),
+        out_point_sudt,
+        out_point_always_success,
+        core_out_point,
+    ] {
+        aggregator.add_cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+
+    // Every voter votes with all of their tokens. The first two vote yes and the last one votes no.
+    for (voter, key) in keys.iter().enumerate() {
+        let vote_option_type = if voter < 2 {
+            VOTE_OPTION_YES
+        } else {
+            VOTE_OPTION_NO
+        };
+        add_intent(
+            &mut aggregator,
+            &token_cells,
+            voter,
+            vote_option_type,
+            VOTER_TOKEN_AMOUNT,
+            key,
+        );
+    }
+
+    // The aggregator counts the last vote for yes, although the voter signed a vote for no.
+    let tx = context.complete_tx(aggregator.build().expect("build"));
+    let witness = WitnessArgs::from_slice(&tx.witnesses().get(0).expect("witness").raw_data())
+        .expect("witness args");
+    let mut intents = witness
+        .input_type()
+        .to_opt()
+        .expect("intents")
+        .raw_data()
+        .to_vec();
+    intents[2 * INTENT_BYTESIZE] = VOTE_OPTION_YES;
+
+    let tx = set_tallies(tx, 0, 30);
+
+    let witness = witness
+        .as_builder()
+        .input_type(
+            BytesOpt::new_builder()
+                .set(Some(Bytes::from(intents).pack()))
+                .build(),
+        )
+        .build();
+    let tx = tx
+        .as_advanced_builder()
+        .set_witnesses(vec![witness.as_bytes().pack()])
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_SIGNATURE)
+            .input_lock_script(VOTE_CELL_COUNT + 2)
+    );
+}
+
+#[test]
+fn test_cannot_move_votes_between_options() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // An open vote with empty Vote Cells for both options and three voters, who each hold a cell of 10 tokens
+    // locked with the vote intent lock.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, [0u8; 32]),
+    );
+    let vote_cells = [VOTE_OPTION_NO, VOTE_OPTION_YES]
+        .iter()
+        .map(|vote_option_type| {
+            let mut vote_cell = VoteCellInfo {
+                out_point: OutPoint::default(),
+                output: dao_core_cell.clone(),
+                vote_option_type: *vote_option_type,
+                shard_index: 0,
+                total_votes_collected: 0,
+            };
+            vote_cell.out_point = context.create_cell(dao_core_cell.clone(), vote_cell.data());
+            vote_cell
+        })
+        .collect();
+
+    let mut aggregator = Aggregator::new(vote_cells);
+    for out_point in [
+        out_point_dao_core,
+        out_point_intent.clone(),
+        out_point_sudt,
+        out_point_always_success,
+        core_out_point,
+    ] {
+        aggregator.add_cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+
+    // Every voter votes with all of their tokens. The first two vote yes and the last one votes no.
+    for (voter, key) in keys.iter().enumerate() {
+        let vote_option_type = if voter < 2 {
+            VOTE_OPTION_YES
+        } else {
+            VOTE_OPTION_NO
+        };
+        add_intent(
+            &mut aggregator,
+            &token_cells,
+            voter,
+            vote_option_type,
+            VOTER_TOKEN_AMOUNT,
+            key,
+        );
+    }
+
+    // The total matches the tokens spent, but one yes vote is counted for no.
+    let tx = context.complete_tx(aggregator.build().expect("build"));
+    let tx = set_tallies(tx, 20, 10);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_intent_lock_error(err, ERROR_INTENT_NOT_COUNTED);
+}
+
+#[test]
+fn test_cannot_count_more_votes_than_intents() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // An open vote with empty Vote Cells for both options and three voters, who each hold a cell of 10 tokens
+    // locked with the vote intent lock.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, [0u8; 32]),
+    );
+    let vote_cells = [VOTE_OPTION_NO, VOTE_OPTION_YES]
+        .iter()
+        .map(|vote_option_type| {
+            let mut vote_cell = VoteCellInfo {
+                out_point: OutPoint::default(),
+                output: dao_core_cell.clone(),
+                vote_option_type: *vote_option_type,
+                shard_index: 0,
+                total_votes_collected: 0,
+            };
+            vote_cell.out_point = context.create_cell(dao_core_cell.clone(), vote_cell.data());
+            vote_cell
+        })
+        .collect();
+
+    let mut aggregator = Aggregator::new(vote_cells);
+    for out_point in [
+        out_point_dao_core,
+        out_point_intent.clone(),
+        out_point_sudt,
+        out_point_always_success,
+        core_out_point,
+    ] {
+        aggregator.add_cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+
+    // Every voter votes with all of their tokens. The first two vote yes and the last one votes no.
+    for (voter, key) in keys.iter().enumerate() {
+        let vote_option_type = if voter < 2 {
+            VOTE_OPTION_YES
+        } else {
+            VOTE_OPTION_NO
+        };
+        add_intent(
+            &mut aggregator,
+            &token_cells,
+            voter,
+            vote_option_type,
+            VOTER_TOKEN_AMOUNT,
+            key,
+        );
+    }
+
+    // Every option is credited with its intents, but no is credited with 5 votes more.
+    let tx = context.complete_tx(aggregator.build().expect("build"));
+    let tx = set_tallies(tx, 15, 20);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_VOTE_INTENT_MISMATCH).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_vote_with_more_than_token_cell_amount() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // An open vote with empty Vote Cells for both options and three voters, who each hold a cell of 10 tokens
+    // locked with the vote intent lock.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(sudt_type_hash, [0u8; 32]),
+    );
+    let vote_cells = [VOTE_OPTION_NO, VOTE_OPTION_YES]
+        .iter()
+        .map(|vote_option_type| {
+            let mut vote_cell = VoteCellInfo {
+                out_point: OutPoint::default(),
+                output: dao_core_cell.clone(),
+                vote_option_type: *vote_option_type,
+                shard_index: 0,
+                total_votes_collected: 0,
+            };
+            vote_cell.out_point = context.create_cell(dao_core_cell.clone(), vote_cell.data());
+            vote_cell
+        })
+        .collect();
+
+    let mut aggregator = Aggregator::new(vote_cells);
+    for out_point in [
+        out_point_dao_core,
+        out_point_intent.clone(),
+        out_point_sudt,
+        out_point_always_success,
+        core_out_point,
+    ] {
+        aggregator.add_cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+
+    // The second voter signs a vote for more tokens than the cell holds.
+    add_intent(
+        &mut aggregator,
+        &token_cells,
+        0,
+        VOTE_OPTION_YES,
+        VOTER_TOKEN_AMOUNT,
+        &keys[0],
+    );
+    add_intent(
+        &mut aggregator,
+        &token_cells,
+        1,
+        VOTE_OPTION_NO,
+        VOTER_TOKEN_AMOUNT + 5,
+        &keys[1],
+    );
+
+    let tx = context.complete_tx(aggregator.build().expect("build"));
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_INVALID_VOTE_INTENT).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_batched_vote_with_escrow() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+
+    // The vote holds voted tokens in the vote escrow lock. Both options have empty Vote Cells, and three voters
+    // each hold a cell of 10 tokens locked with the vote intent lock.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(
+            sudt_type_hash,
+            escrow_lock_script.calc_script_hash().unpack(),
+        ),
+    );
+    let vote_cells = [VOTE_OPTION_NO, VOTE_OPTION_YES]
+        .iter()
+        .map(|vote_option_type| {
+            let mut vote_cell = VoteCellInfo {
+                out_point: OutPoint::default(),
+                output: dao_core_cell.clone(),
+                vote_option_type: *vote_option_type,
+                shard_index: 0,
+                total_votes_collected: 0,
+            };
+            vote_cell.out_point = context.create_cell(dao_core_cell.clone(), vote_cell.data());
+            vote_cell
+        })
+        .collect();
+
+    let mut aggregator = Aggregator::new(vote_cells);
+    for out_point in [
+        out_point_dao_core,
+        out_point_intent.clone(),
+        out_point_sudt,
+        out_point_always_success,
+        core_out_point,
+    ] {
+        aggregator.add_cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+
+    // Every voter votes with all of their tokens. The first two vote yes and the last one votes no.
+    for (voter, key) in keys.iter().enumerate() {
+        let vote_option_type = if voter < 2 {
+            VOTE_OPTION_YES
+        } else {
+            VOTE_OPTION_NO
+        };
+        add_intent(
+            &mut aggregator,
+            &token_cells,
+            voter,
+            vote_option_type,
+            VOTER_TOKEN_AMOUNT,
+            key,
+        );
+    }
+
+    let tx = context.complete_tx(aggregator.build().expect("build"));
+    let tx = add_escrow_outputs(
+        tx,
+        &token_cells[0].1,
+        &escrow_lock_script,
+        [
+            build_owner_lock_hash(0),
+            build_owner_lock_hash(1),
+            build_owner_lock_hash(2),
+        ],
+    );
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_cannot_redirect_receipts_of_batched_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_escrow = context.deploy_cell(Loader::default().load_binary("vote-escrow"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+    let escrow_lock_script = context
+        .build_script(&out_point_escrow, dao_core_type_hash.to_vec().into())
+        .expect("script");
+
+    // The vote holds voted tokens in the vote escrow lock. Both options have empty Vote Cells, and three voters
+    // each hold a cell of 10 tokens locked with the vote intent lock.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_out_point = context.create_cell(
+        dao_core_cell.clone(),
+        build_core_data(
+            sudt_type_hash,
+            escrow_lock_script.calc_script_hash().unpack(),
+        ),
+    );
+    let vote_cells = [VOTE_OPTION_NO, VOTE_OPTION_YES]
+        .iter()
+        .map(|vote_option_type| {
+            let mut vote_cell = VoteCellInfo {
+                out_point: OutPoint::default(),
+                output: dao_core_cell.clone(),
+                vote_option_type: *vote_option_type,
+                shard_index: 0,
+                total_votes_collected: 0,
+            };
+            vote_cell.out_point = context.create_cell(dao_core_cell.clone(), vote_cell.data());
+            vote_cell
+        })
+        .collect();
+
+    let mut aggregator = Aggregator::new(vote_cells);
+    for out_point in [
+        out_point_dao_core,
+        out_point_intent.clone(),
+        out_point_sudt,
+        out_point_always_success,
+        core_out_point,
+    ] {
+        aggregator.add_cell_dep(CellDep::new_builder().out_point(out_point).build());
+    }
+
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+
+    // Every voter votes with all of their tokens. The first two vote yes and the last one votes no.
+    for (voter, key) in keys.iter().enumerate() {
+        let vote_option_type = if voter < 2 {
+            VOTE_OPTION_YES
+        } else {
+            VOTE_OPTION_NO
+        };
+        add_intent(
+            &mut aggregator,
+            &token_cells,
+            voter,
+            vote_option_type,
+            VOTER_TOKEN_AMOUNT,
+            key,
+        );
+    }
+
+    // The receipts add up for every option, but the aggregator keeps the receipt of the second voter.
+    let tx = context.complete_tx(aggregator.build().expect("build"));
+    let tx = add_escrow_outputs(
+        tx,
+        &token_cells[0].1,
+        &escrow_lock_script,
+        [
+            build_owner_lock_hash(0),
+            [9u8; 32],
+            build_owner_lock_hash(2),
+        ],
+    );
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_RECEIPT_MISMATCH).input_type_script(0)
+    );
+}
+
+#[test]
+fn test_cannot_spend_intent_cell_finishing_vote() {
+    let mut context = Context::default();
+    let out_point_dao_core = context.deploy_cell(Loader::default().load_binary("dao-core"));
+    let out_point_intent = context.deploy_cell(Loader::default().load_binary("vote-intent"));
+    let out_point_sudt = context.deploy_cell(Loader::default().load_binary("sudt"));
+    let out_point_always_success = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&out_point_always_success, Default::default())
+        .expect("script");
+    let other_lock_script = context
+        .build_script(&out_point_always_success, Bytes::from(vec![9u8]))
+        .expect("script");
+    let dao_core_type_script = context
+        .build_script(&out_point_dao_core, Bytes::from(vec![42u8; 32]))
+        .expect("script");
+    let dao_core_type_hash: [u8; 32] = dao_core_type_script.calc_script_hash().unpack();
+    let sudt_type_script = context
+        .build_script(&out_point_sudt, Bytes::from(vec![0u8; 32]))
+        .expect("script");
+    let sudt_type_hash: [u8; 32] = sudt_type_script.calc_script_hash().unpack();
+
+    // Yes has a majority. The Core Cell was created in block 100, and the Vote Cells in blocks 150 and 200.
+    let dao_core_cell = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(dao_core_type_script).pack())
+        .build();
+    let core_data = CoreCellData::new(sudt_type_hash, 30);
+    let (core_out_point, core_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        core_data.to_bytes(),
+        100,
+    );
+    let (no_out_point, no_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        VoteCellData::new(VOTE_OPTION_NO, 0, 0).to_bytes(),
+        150,
+    );
+    let (yes_out_point, yes_block_hash) = create_cell_in_block(
+        &mut context,
+        dao_core_cell.clone(),
+        VoteCellData::new(VOTE_OPTION_YES, 0, 30).to_bytes(),
+        200,
+    );
+
+    // The first voter signed an intent, which is passed along with the Vote Cells of a transaction finishing the
+    // vote. The tokens are sent to another lock instead of being voted with.
+    let keys = build_voter_keys();
+    let token_cells = create_intent_cells(
+        &mut context,
+        &out_point_intent,
+        &dao_core_type_hash,
+        &sudt_type_script,
+    );
+    let (token_out_point, token_cell) = token_cells[0].clone();
+    let intent = VoteIntent::new(
+        VOTE_OPTION_YES,
+        VOTER_TOKEN_AMOUNT,
+        token_out_point.clone(),
+        build_owner_lock_hash(0),
+    )
+    .sign(&keys[0])
+    .expect("sign");
+    let witness = WitnessArgs::new_builder()
+        .input_type(
+            BytesOpt::new_builder()
+                .set(Some(intent.to_bytes().pack()))
+                .build(),
+        )
+        .build();
+
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(core_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(no_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(yes_out_point)
+                .build(),
+        )
+        .input(
+            CellInput::new_builder()
+                .previous_output(token_out_point)
+                .build(),
+        )
+        .output(dao_core_cell)
+        .output_data(
+            CoreCellData {
+                is_voting_finished: VOTING_STATUS_FINISHED,
+                vote_result_option_type: VOTE_OPTION_YES,
+                settlement_block_number: 200,
+                ..core_data
+            }
+            .to_bytes()
+            .pack(),
+        )
+        .output(token_cell.as_builder().lock(other_lock_script).build())
+        .output_data(Bytes::from(VOTER_TOKEN_AMOUNT.to_le_bytes().to_vec()).pack())
+        .header_dep(core_block_hash)
+        .header_dep(no_block_hash)
+        .header_dep(yes_block_hash)
+        .witness(Bytes::new().pack())
+        .witness(witness.as_bytes().pack())
+        .build();
+    let tx = context.complete_tx(tx);
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ERROR_CORE_CELL_MISSING).input_lock_script(3)
+    );
+}